@@ -0,0 +1,229 @@
+//! Microbenchmarks for the memory pool allocator, the mock transport,
+//! cache key hashing and concurrent PUTs
+//!
+//! Run with `cargo bench --bench micro`. These isolate the pieces `kv-bench`
+//! measures end to end, so allocator or copy-path regressions show up on their own.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use dashmap::DashMap;
+use kv_rdma_poc::core::KvCore;
+use kv_rdma_poc::keys::{CacheKey, KeyHasherConfig};
+use kv_rdma_poc::memory::{MemoryPool, MemoryPoolConfig, SizeClass};
+use kv_rdma_poc::server::ServerConfig;
+use kv_rdma_poc::transport::{DomainRouting, TransferRequest};
+use kv_rdma_poc::{RdmaTransport, TransportConfig};
+use std::time::{Duration, Instant};
+
+const POOL_SIZE: usize = 256 * 1024 * 1024;
+
+fn pool(size_classes: Vec<SizeClass>) -> MemoryPool {
+    let config = MemoryPoolConfig {
+        size: POOL_SIZE,
+        alignment: 4096,
+        size_classes,
+        ..Default::default()
+    };
+    MemoryPool::new(config, 0, None).unwrap()
+}
+
+/// Allocate and immediately free one block: the steady state of PUT overwrites
+fn bench_allocate_free_cycle(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pool_allocate_free");
+    for size in [4 * 1024, 64 * 1024, 1024 * 1024] {
+        let pool = pool(Vec::new());
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| {
+                let allocation = pool.allocate(black_box(size)).unwrap();
+                pool.deallocate(&allocation);
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Allocate into a pool whose free list has `holes` scattered gaps
+///
+/// Every other block of a filled region is freed, so each allocation has to
+/// search the free list; the cost should not grow badly with its length.
+fn bench_allocate_fragmented(c: &mut Criterion) {
+    const BLOCK: usize = 4096;
+    let mut group = c.benchmark_group("pool_allocate_fragmented");
+    for holes in [16, 256, 4096] {
+        let pool = pool(Vec::new());
+        let blocks: Vec<_> = (0..holes * 2)
+            .map(|_| pool.allocate(BLOCK).unwrap())
+            .collect();
+        for allocation in blocks.iter().step_by(2) {
+            pool.deallocate(allocation);
+        }
+
+        group.bench_with_input(BenchmarkId::from_parameter(holes), &holes, |b, _| {
+            b.iter(|| {
+                // Larger than any hole, so it walks the whole free list and bumps
+                let large = pool.allocate(black_box(BLOCK * 2)).unwrap();
+                let small = pool.allocate(black_box(BLOCK)).unwrap();
+                pool.deallocate(&small);
+                pool.deallocate(&large);
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Mixed small and large values across size classes, as a real keyspace sees
+fn bench_allocate_mixed_sizes(c: &mut Criterion) {
+    let sizes = [512, 4 * 1024, 700, 256 * 1024, 2 * 1024, 1024 * 1024];
+    let classes = vec![
+        SizeClass {
+            max_size: 4 * 1024,
+            capacity: 32 * 1024 * 1024,
+        },
+        SizeClass {
+            max_size: 256 * 1024,
+            capacity: 64 * 1024 * 1024,
+        },
+    ];
+
+    let mut group = c.benchmark_group("pool_allocate_mixed");
+    for (name, classes) in [("single_class", Vec::new()), ("size_classes", classes)] {
+        let pool = pool(classes);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let allocations: Vec<_> = sizes
+                    .iter()
+                    .map(|&size| pool.allocate(black_box(size)).unwrap())
+                    .collect();
+                for allocation in allocations.iter().rev() {
+                    pool.deallocate(allocation);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Mock transport copy throughput per transfer size
+fn bench_mock_transfer(c: &mut Criterion) {
+    let transport = RdmaTransport::new(TransportConfig {
+        use_mock: true,
+        ..Default::default()
+    })
+    .unwrap();
+
+    let mut group = c.benchmark_group("mock_transfer");
+    for size in [4 * 1024, 64 * 1024, 1024 * 1024, 16 * 1024 * 1024] {
+        let mut src = vec![1u8; size];
+        let mut dst = vec![0u8; size];
+        let (src_handle, _) = transport.register_memory(src.as_mut_ptr(), size).unwrap();
+        let (_, dst_descriptor) = transport.register_memory(dst.as_mut_ptr(), size).unwrap();
+
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| {
+                transport
+                    .submit_transfer(TransferRequest {
+                        src_handle,
+                        src_offset: 0,
+                        length: size as u64,
+                        imm_data: None,
+                        dst_descriptor: dst_descriptor.clone(),
+                        dst_offset: 0,
+                        routing: DomainRouting::default(),
+                    })
+                    .unwrap();
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Cache map lookups of short fixed-format keys under each key hasher
+///
+/// The map is small enough to stay in cache, so hashing dominates.
+fn bench_key_lookup(c: &mut Criterion) {
+    const KEYS: usize = 1024;
+    let keys: Vec<Vec<u8>> = (0..KEYS)
+        .map(|i| format!("session-{:08}", i).into_bytes())
+        .collect();
+
+    let mut group = c.benchmark_group("key_lookup");
+    for (name, hasher) in [
+        ("std", KeyHasherConfig::Std),
+        ("ahash", KeyHasherConfig::Ahash),
+        ("keyed_sip", KeyHasherConfig::KeyedSip { k0: 1, k1: 2 }),
+    ] {
+        let map = DashMap::with_capacity_and_hasher(KEYS, hasher.build());
+        for (i, key) in keys.iter().enumerate() {
+            map.insert(CacheKey::Owned(key.clone()), i);
+        }
+
+        let mut next = 0;
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                next = (next + 7919) % KEYS;
+                black_box(*map.get(keys[next].as_slice()).unwrap())
+            })
+        });
+    }
+    group.finish();
+}
+
+/// A fixed number of 256KB PUTs split across threads, each cycling through its
+/// own keys
+///
+/// Writers fill their own pool regions under the pool's read lock, so time per
+/// batch should fall close to 1/threads while there are cores to spare.
+fn bench_parallel_put(c: &mut Criterion) {
+    const SIZE: usize = 256 * 1024;
+    const KEYS_PER_THREAD: usize = 8;
+    const PUTS: usize = 64;
+
+    let core = KvCore::new(&ServerConfig {
+        memory_pool_size: 64 * 1024 * 1024,
+        ..Default::default()
+    })
+    .unwrap();
+    let value = vec![0xAB; SIZE];
+
+    let mut group = c.benchmark_group("pool_parallel_put");
+    group.throughput(Throughput::Bytes((PUTS * SIZE) as u64));
+    for threads in [1, 2, 4] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads),
+            &threads,
+            |b, &threads| {
+                b.iter_custom(|iters| {
+                    let mut elapsed = Duration::ZERO;
+                    for _ in 0..iters {
+                        let start = Instant::now();
+                        std::thread::scope(|scope| {
+                            for t in 0..threads {
+                                let (core, value) = (&core, &value);
+                                scope.spawn(move || {
+                                    for i in 0..PUTS / threads {
+                                        let key = format!("t{}-k{}", t, i % KEYS_PER_THREAD);
+                                        core.put(key.as_bytes(), value.clone(), 0).unwrap();
+                                    }
+                                });
+                            }
+                        });
+                        elapsed += start.elapsed();
+                    }
+                    elapsed
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_key_lookup,
+    bench_allocate_free_cycle,
+    bench_allocate_fragmented,
+    bench_allocate_mixed_sizes,
+    bench_mock_transfer,
+    bench_parallel_put
+);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+//! Admission control for GETs under overload
+//!
+//! The server estimates how long a newly arriving GET would wait behind the
+//! ones already in flight and sheds it when that exceeds the configured budget.
+//! Fast-failing a read the caller can retry is cheaper than letting every
+//! request's latency balloon.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Weight of the newest sample in the service time average (1/8)
+const EWMA_SHIFT: u32 = 3;
+
+/// Tracks in-flight GETs and their recent service times
+pub struct AdmissionController {
+    budget: Duration,
+    in_flight: AtomicUsize,
+    /// Exponentially weighted moving average of completed GET latency
+    avg_service_nanos: AtomicU64,
+}
+
+impl AdmissionController {
+    /// Create a controller that sheds once the estimated queue delay exceeds `budget`
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            in_flight: AtomicUsize::new(0),
+            avg_service_nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Estimated wait for a request arriving now
+    ///
+    /// Treats the in-flight GETs as a single queue. This overestimates when
+    /// transfers overlap across NICs, which errs on the side of shedding.
+    pub fn estimated_queue_delay(&self) -> Duration {
+        let avg = self.avg_service_nanos.load(Ordering::Relaxed);
+        let in_flight = self.in_flight.load(Ordering::Relaxed) as u64;
+        Duration::from_nanos(avg.saturating_mul(in_flight))
+    }
+
+    /// Number of admitted GETs that have not completed yet
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Admit a request, or return the estimated queue delay if it should be shed
+    ///
+    /// The returned guard must be held until the request completes.
+    pub fn try_admit(&self) -> Result<AdmissionGuard<'_>, Duration> {
+        let delay = self.estimated_queue_delay();
+        if delay > self.budget {
+            return Err(delay);
+        }
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        Ok(AdmissionGuard {
+            controller: self,
+            started: Instant::now(),
+        })
+    }
+
+    fn record(&self, elapsed: Duration) {
+        let sample = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+        let _ = self
+            .avg_service_nanos
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |avg| {
+                Some(if avg == 0 {
+                    sample
+                } else {
+                    avg - (avg >> EWMA_SHIFT) + (sample >> EWMA_SHIFT)
+                })
+            });
+    }
+}
+
+/// Marks one admitted GET; records its latency when dropped
+pub struct AdmissionGuard<'a> {
+    controller: &'a AdmissionController,
+    started: Instant,
+}
+
+impl Drop for AdmissionGuard<'_> {
+    fn drop(&mut self) {
+        self.controller.record(self.started.elapsed());
+        self.controller.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,66 @@
+//! CPU affinity for keeping control-plane threads off the data-plane cores
+//!
+//! `FabricTransport` pins its worker and UVM threads to CPUs it picks from the
+//! topology; the server's tokio runtime can be pinned to a disjoint set with
+//! `ServerConfig::runtime_cpus`.
+
+use anyhow::Result;
+
+/// Restrict the calling thread to the given CPUs
+#[cfg(target_os = "linux")]
+pub fn pin_current_thread(cpus: &[usize]) -> Result<()> {
+    use anyhow::anyhow;
+
+    if cpus.is_empty() {
+        return Err(anyhow!("Empty CPU set"));
+    }
+
+    // SAFETY: cpu_set_t is plain data; CPU_SET bounds-checks against its capacity
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus {
+            if cpu >= libc::CPU_SETSIZE as usize {
+                return Err(anyhow!("CPU {} is beyond CPU_SETSIZE", cpu));
+            }
+            libc::CPU_SET(cpu, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(anyhow!(
+                "sched_setaffinity({:?}) failed: {}",
+                cpus,
+                std::io::Error::last_os_error()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// CPUs the calling thread may run on
+#[cfg(target_os = "linux")]
+pub fn current_thread_cpus() -> Result<Vec<usize>> {
+    use anyhow::anyhow;
+
+    // SAFETY: as above; the kernel fills `set`
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return Err(anyhow!(
+                "sched_getaffinity failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok((0..libc::CPU_SETSIZE as usize)
+            .filter(|&cpu| libc::CPU_ISSET(cpu, &set))
+            .collect())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_thread(_cpus: &[usize]) -> Result<()> {
+    Err(anyhow::anyhow!("CPU pinning is only supported on Linux"))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn current_thread_cpus() -> Result<Vec<usize>> {
+    Err(anyhow::anyhow!("CPU affinity is only supported on Linux"))
+}
@@ -1,15 +1,18 @@
 //! KV Cache Read Throughput Benchmark
 //!
 //! This benchmark tests the read throughput of the KV cache server.
-//! It first writes random keys with a specified value size using a single thread,
+//! It first writes random keys with a specified value size using a single thread
+//! (or, with `--concurrent-writes`, spread across the workers),
 //! then reads those keys using multiple threads to measure read throughput.
 //!
 //! Run with: cargo run --bin kv-bench -- --help
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::Parser;
-use kv_rdma_poc::client::{ClientConfig, KvCacheClient};
+use kv_rdma_poc::client::{ClientConfig, GET_BUFFER_SIZE};
+use kv_rdma_poc::sharded::ShardedClient;
 use kv_rdma_poc::transport::TransportConfig;
+use kv_rdma_poc::util::parse_size;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -19,7 +22,7 @@ use tokio::task::JoinSet;
 #[command(name = "kv-bench")]
 #[command(about = "KV Cache Read Throughput Benchmark")]
 struct Args {
-    /// Server address (gRPC endpoint)
+    /// Server address (gRPC endpoint); comma-separated to shard keys across servers
     #[arg(long, default_value = "http://[::1]:50051")]
     server_addr: String,
 
@@ -27,8 +30,8 @@ struct Args {
     #[arg(long, default_value = "1000")]
     num_keys: usize,
 
-    /// Value size in bytes (supports suffixes: KB, MB, e.g., 16KB, 1MB)
-    #[arg(long, default_value = "64KB")]
+    /// Value size in bytes (supports decimal and binary suffixes, e.g., 16KiB, 1.5MB)
+    #[arg(long, default_value = "64KiB")]
     value_size: String,
 
     /// Number of concurrent workers (tokio tasks, not OS threads)
@@ -63,35 +66,35 @@ struct Args {
     #[arg(long, default_value = "100")]
     warmup: usize,
 
+    /// Also issue `--warmup` GETs on the latency-analysis client before sampling it
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    warm_latency_client: bool,
+
     /// Number of times each worker repeats reading its assigned keys
     #[arg(long, default_value = "100")]
     repeat_reads: usize,
-}
 
-/// Parse value size string like "16KB", "1MB", etc.
-fn parse_size(s: &str) -> Result<usize> {
-    let s = s.trim().to_uppercase();
-
-    if let Some(stripped) = s.strip_suffix("KB") {
-        Ok(stripped.parse::<usize>()? * 1024)
-    } else if let Some(stripped) = s.strip_suffix("MB") {
-        Ok(stripped.parse::<usize>()? * 1024 * 1024)
-    } else if let Some(stripped) = s.strip_suffix("GB") {
-        Ok(stripped.parse::<usize>()? * 1024 * 1024 * 1024)
-    } else if let Some(stripped) = s.strip_suffix('B') {
-        Ok(stripped.parse::<usize>()?)
-    } else {
-        // Assume bytes if no suffix
-        Ok(s.parse::<usize>()?)
-    }
+    /// Spread the write phase across the workers and client pool, like reads,
+    /// to measure concurrent PUT throughput
+    #[arg(long, default_value_t = false)]
+    concurrent_writes: bool,
+
+    /// Each server's memory pool in MB (the server's `--memory-mb`), to check
+    /// that the working set fits (0 = unknown, not checked)
+    #[arg(long, default_value = "0")]
+    server_memory_mb: usize,
+
+    /// Print the plan and check the sizing, then exit without connecting
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
 }
 
 /// Format size in human-readable form
 fn format_size(bytes: usize) -> String {
     if bytes >= 1024 * 1024 {
-        format!("{:.2} MB", bytes as f64 / (1024.0 * 1024.0))
+        format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
     } else if bytes >= 1024 {
-        format!("{:.2} KB", bytes as f64 / 1024.0)
+        format!("{:.2} KiB", bytes as f64 / 1024.0)
     } else {
         format!("{} B", bytes)
     }
@@ -110,42 +113,160 @@ fn format_throughput(bytes_per_sec: f64) -> String {
     }
 }
 
-/// Create a client with the given ID
-async fn create_client(args: &Args, client_id: u32) -> Result<KvCacheClient> {
-    let config = ClientConfig {
-        client_id,
-        server_addr: args.server_addr.clone(),
-        receive_buffer_size: args.buffer_mb * 1024 * 1024,
-        transport: TransportConfig {
-            node_id: client_id,
-            num_domains: 1,
-            use_mock: args.mock,
-        },
-    };
+/// Pool space a value occupies once aligned (the server's pool aligns to 4KiB)
+fn pool_footprint(value_size: usize) -> usize {
+    value_size.max(1).div_ceil(4096) * 4096
+}
 
-    let client = KvCacheClient::new(config)?;
-    client.connect().await?;
+/// Print what a run would do and check that it fits, without connecting
+///
+/// Returns an error listing every sizing problem found.
+fn dry_run(args: &Args, value_size: usize) -> Result<()> {
+    let num_servers = server_addrs(args).len();
+    let workers_per_client = args.num_workers.div_ceil(args.num_clients.max(1));
+    let buffer_bytes = args.buffer_mb * 1024 * 1024;
+    // A GET first reserves GET_BUFFER_SIZE, then the exact size if larger
+    let per_get = value_size.max(GET_BUFFER_SIZE);
+    let buffer_needed = workers_per_client * per_get;
+    let total_bytes = args.num_keys * value_size;
+    let per_server_bytes =
+        (args.num_keys * pool_footprint(value_size)).div_ceil(num_servers.max(1));
+
+    println!("\n=== Dry Run ===");
+    println!("Servers:            {}", num_servers);
+    println!(
+        "Keys:               {} ({} per server)",
+        args.num_keys,
+        args.num_keys.div_ceil(num_servers.max(1))
+    );
+    println!(
+        "Working set:        {} ({} of pool per server)",
+        format_size(total_bytes),
+        format_size(per_server_bytes)
+    );
+    println!("Workers/client:     {}", workers_per_client);
+    println!(
+        "Buffer/client:      {} needed of {}",
+        format_size(buffer_needed),
+        format_size(buffer_bytes)
+    );
+
+    let mut problems = Vec::new();
+    if num_servers == 0 {
+        problems.push("--server-addr names no servers".to_string());
+    }
+    if args.num_workers == 0 || args.num_clients == 0 {
+        problems.push("--num-workers and --num-clients must be at least 1".to_string());
+    }
+    if buffer_needed > buffer_bytes {
+        problems.push(format!(
+            "{} workers per client each reserve {} per GET, {} in all, but --buffer-mb is {}; \
+             raise --buffer-mb or --num-clients",
+            workers_per_client,
+            format_size(per_get),
+            format_size(buffer_needed),
+            args.buffer_mb
+        ));
+    }
+    if args.server_memory_mb > 0 && per_server_bytes > args.server_memory_mb * 1024 * 1024 {
+        problems.push(format!(
+            "working set needs {} of pool per server but --server-memory-mb is {}; \
+             reduce --num-keys or --value-size, or add servers",
+            format_size(per_server_bytes),
+            args.server_memory_mb
+        ));
+    }
+
+    if problems.is_empty() {
+        println!("Plan OK");
+        return Ok(());
+    }
+    bail!(
+        "Dry run found {} problem(s):\n  - {}",
+        problems.len(),
+        problems.join("\n  - ")
+    )
+}
+
+/// Server addresses from the comma-separated `--server-addr`
+fn server_addrs(args: &Args) -> Vec<String> {
+    args.server_addr
+        .split(',')
+        .map(|addr| addr.trim().to_string())
+        .filter(|addr| !addr.is_empty())
+        .collect()
+}
+
+/// How long a client waits for servers that aren't reachable yet
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Create a client with the given ID, with one connection per server
+async fn create_client(args: &Args, client_id: u32) -> Result<ShardedClient> {
+    let configs = server_addrs(args)
+        .into_iter()
+        .map(|server_addr| ClientConfig {
+            client_id,
+            server_addr,
+            receive_buffer_size: args.buffer_mb * 1024 * 1024,
+            transport: TransportConfig {
+                node_id: client_id,
+                num_domains: 1,
+                use_mock: args.mock,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .collect();
+
+    let client = ShardedClient::new(configs)?;
+    client.connect_with_retry(CONNECT_TIMEOUT).await?;
     Ok(client)
 }
 
+/// Outcome of a timed phase
+struct PhaseResult {
+    duration: Duration,
+    /// Successful operations per server, in `--server-addr` order
+    per_server_ops: Vec<u64>,
+}
+
+/// Print per-server throughput when sharding across more than one server
+fn print_per_server(args: &Args, label: &str, result: &PhaseResult) {
+    let addrs = server_addrs(args);
+    if addrs.len() < 2 {
+        return;
+    }
+    for (addr, ops) in addrs.iter().zip(&result.per_server_ops) {
+        println!(
+            "  {} {}: {} ops, {:.0} ops/sec",
+            label,
+            addr,
+            ops,
+            *ops as f64 / result.duration.as_secs_f64()
+        );
+    }
+}
+
 /// Write phase: single thread writes all keys
-async fn write_phase(
-    args: &Args,
-    value_size: usize,
-    keys: &[String],
-) -> Result<Duration> {
+async fn write_phase(args: &Args, value_size: usize, keys: &[String]) -> Result<PhaseResult> {
     println!("\n=== Write Phase ===");
-    println!("Writing {} keys with {} values...", args.num_keys, format_size(value_size));
+    println!(
+        "Writing {} keys with {} values...",
+        args.num_keys,
+        format_size(value_size)
+    );
 
     let client = create_client(args, args.base_client_id).await?;
 
     // Create random value
     let value: Vec<u8> = (0..value_size).map(|i| (i % 256) as u8).collect();
 
+    let mut per_server_ops = vec![0u64; client.shards().len()];
     let start = Instant::now();
 
     for (i, key) in keys.iter().enumerate() {
         client.put(key.as_bytes(), &value, args.ttl).await?;
+        per_server_ops[client.shard_index(key.as_bytes())] += 1;
 
         if (i + 1) % 100 == 0 {
             print!("\rWrote {}/{} keys...", i + 1, args.num_keys);
@@ -160,19 +281,144 @@ async fn write_phase(
     let bytes_per_sec = (args.num_keys * value_size) as f64 / duration.as_secs_f64();
 
     println!("Write completed in {:.2}s", duration.as_secs_f64());
-    println!("Write throughput: {:.0} ops/sec, {}", ops_per_sec, format_throughput(bytes_per_sec));
+    println!(
+        "Write throughput: {:.0} ops/sec, {}",
+        ops_per_sec,
+        format_throughput(bytes_per_sec)
+    );
 
-    Ok(duration)
+    let result = PhaseResult {
+        duration,
+        per_server_ops,
+    };
+    print_per_server(args, "Write", &result);
+
+    Ok(result)
+}
+
+/// Concurrent write phase: workers PUT disjoint slices of the keys using a pool of clients
+async fn concurrent_write_phase(
+    args: &Args,
+    value_size: usize,
+    keys: &[String],
+    clients: &[Arc<ShardedClient>],
+) -> Result<PhaseResult> {
+    println!("\n=== Write Phase (concurrent) ===");
+    println!(
+        "Writing {} keys with {} values using {} workers and {} clients...",
+        args.num_keys,
+        format_size(value_size),
+        args.num_workers,
+        clients.len()
+    );
+
+    let keys = Arc::new(keys.to_vec());
+    let value: Arc<Vec<u8>> = Arc::new((0..value_size).map(|i| (i % 256) as u8).collect());
+    let per_server_ops: Arc<Vec<AtomicU64>> = Arc::new(
+        (0..clients[0].shards().len())
+            .map(|_| AtomicU64::new(0))
+            .collect(),
+    );
+    let mut tasks = JoinSet::new();
+
+    let start = Instant::now();
+
+    for worker_id in 0..args.num_workers {
+        let client = Arc::clone(&clients[worker_id % clients.len()]);
+        let keys = Arc::clone(&keys);
+        let value = Arc::clone(&value);
+        let per_server_ops = Arc::clone(&per_server_ops);
+        let num_workers = args.num_workers;
+        let num_keys = args.num_keys;
+        let ttl = args.ttl;
+
+        tasks.spawn(async move {
+            // Each key belongs to exactly one worker, so no two PUTs race on a key
+            let keys_per_worker = num_keys / num_workers;
+            let start_idx = worker_id * keys_per_worker;
+            let end_idx = if worker_id == num_workers - 1 {
+                num_keys // Last worker handles remainder
+            } else {
+                start_idx + keys_per_worker
+            };
+
+            let mut latencies = Vec::with_capacity(end_idx - start_idx);
+            for key in &keys[start_idx..end_idx] {
+                let put_start = Instant::now();
+                client.put(key.as_bytes(), &value, ttl).await?;
+                latencies.push(put_start.elapsed());
+                per_server_ops[client.shard_index(key.as_bytes())].fetch_add(1, Ordering::Relaxed);
+            }
+            Ok::<_, anyhow::Error>(latencies)
+        });
+    }
+
+    let mut latencies = Vec::with_capacity(args.num_keys);
+    while let Some(result) = tasks.join_next().await {
+        latencies.extend(result??);
+    }
+
+    let duration = start.elapsed();
+    latencies.sort();
+
+    let ops_per_sec = latencies.len() as f64 / duration.as_secs_f64();
+    let bytes_per_sec = (latencies.len() * value_size) as f64 / duration.as_secs_f64();
+
+    println!(
+        "Wrote {}/{} keys in {:.2}s",
+        latencies.len(),
+        args.num_keys,
+        duration.as_secs_f64()
+    );
+    println!(
+        "Write throughput: {:.0} ops/sec, {}",
+        ops_per_sec,
+        format_throughput(bytes_per_sec)
+    );
+    if !latencies.is_empty() {
+        println!("PUT latency (microseconds):");
+        println!(
+            "  Median: {:8.2} µs",
+            latencies[latencies.len() / 2].as_micros()
+        );
+        println!(
+            "  P95:    {:8.2} µs",
+            latencies[(latencies.len() * 95) / 100].as_micros()
+        );
+        println!(
+            "  P99:    {:8.2} µs",
+            latencies[(latencies.len() * 99) / 100].as_micros()
+        );
+        println!(
+            "  Max:    {:8.2} µs",
+            latencies[latencies.len() - 1].as_micros()
+        );
+    }
+
+    let result = PhaseResult {
+        duration,
+        per_server_ops: per_server_ops
+            .iter()
+            .map(|ops| ops.load(Ordering::Relaxed))
+            .collect(),
+    };
+    print_per_server(args, "Write", &result);
+
+    Ok(result)
 }
 
 /// Warmup phase: warm up client connections
-async fn warmup_phase(args: &Args, keys: &[String], clients: &[Arc<KvCacheClient>]) -> Result<()> {
+async fn warmup_phase(args: &Args, keys: &[String], clients: &[Arc<ShardedClient>]) -> Result<()> {
     if args.warmup == 0 {
         return Ok(());
     }
 
     println!("\n=== Warmup Phase ===");
-    println!("Running {} warmup iterations with {} clients...", args.warmup, clients.len());
+    println!(
+        "Running {} warmup iterations with {} clients...",
+        args.warmup,
+        clients.len()
+    );
 
     let keys = Arc::new(keys.to_vec());
     let mut tasks = JoinSet::new();
@@ -205,8 +451,8 @@ async fn read_phase(
     args: &Args,
     value_size: usize,
     keys: &[String],
-    clients: &[Arc<KvCacheClient>],
-) -> Result<Duration> {
+    clients: &[Arc<ShardedClient>],
+) -> Result<PhaseResult> {
     let total_operations = args.num_keys * args.repeat_reads;
 
     println!("\n=== Read Phase ===");
@@ -214,13 +460,22 @@ async fn read_phase(
         println!("Reading {} keys {} times each ({} total operations) with {} workers using {} clients...",
             args.num_keys, args.repeat_reads, total_operations, args.num_workers, clients.len());
     } else {
-        println!("Reading {} keys with {} workers using {} clients...",
-            args.num_keys, args.num_workers, clients.len());
+        println!(
+            "Reading {} keys with {} workers using {} clients...",
+            args.num_keys,
+            args.num_workers,
+            clients.len()
+        );
     }
 
     let keys = Arc::new(keys.to_vec());
     let mut tasks = JoinSet::new();
     let errors = Arc::new(AtomicU64::new(0));
+    let per_server_ops: Arc<Vec<AtomicU64>> = Arc::new(
+        (0..clients[0].shards().len())
+            .map(|_| AtomicU64::new(0))
+            .collect(),
+    );
 
     let start = Instant::now();
 
@@ -229,6 +484,7 @@ async fn read_phase(
         let client = Arc::clone(&clients[worker_id % clients.len()]);
         let keys = Arc::clone(&keys);
         let errors = Arc::clone(&errors);
+        let per_server_ops = Arc::clone(&per_server_ops);
         let num_workers = args.num_workers;
         let num_keys = args.num_keys;
         let repeat_reads = args.repeat_reads;
@@ -260,6 +516,8 @@ async fn read_phase(
                                 );
                             }
                             worker_ops += 1;
+                            per_server_ops[client.shard_index(keys[idx].as_bytes())]
+                                .fetch_add(1, Ordering::Relaxed);
                         }
                         Err(e) => {
                             tracing::error!("Worker {}: GET error: {}", worker_id, e);
@@ -297,26 +555,48 @@ async fn read_phase(
     let ops_per_sec = total_ops as f64 / duration.as_secs_f64();
     let bytes_per_sec = (total_ops * value_size as u64) as f64 / duration.as_secs_f64();
 
-    println!("Read completed: {} operations ({} keys × {} repeats) in {:.2}s",
-        total_ops, args.num_keys, args.repeat_reads, duration.as_secs_f64());
-    println!("Read throughput: {:.0} ops/sec, {}", ops_per_sec, format_throughput(bytes_per_sec));
+    println!(
+        "Read completed: {} operations ({} keys × {} repeats) in {:.2}s",
+        total_ops,
+        args.num_keys,
+        args.repeat_reads,
+        duration.as_secs_f64()
+    );
+    println!(
+        "Read throughput: {:.0} ops/sec, {}",
+        ops_per_sec,
+        format_throughput(bytes_per_sec)
+    );
 
     if total_errors > 0 {
         println!("Errors: {}", total_errors);
     }
 
-    Ok(duration)
+    let result = PhaseResult {
+        duration,
+        per_server_ops: per_server_ops
+            .iter()
+            .map(|ops| ops.load(Ordering::Relaxed))
+            .collect(),
+    };
+    print_per_server(args, "Read", &result);
+
+    Ok(result)
 }
 
 /// Delete phase: delete all keys created during write phase
 async fn delete_phase(
     args: &Args,
     keys: &[String],
-    clients: &[Arc<KvCacheClient>],
+    clients: &[Arc<ShardedClient>],
 ) -> Result<Duration> {
     println!("\n=== Delete Phase ===");
-    println!("Deleting {} keys with {} workers using {} clients...",
-        args.num_keys, args.num_workers, clients.len());
+    println!(
+        "Deleting {} keys with {} workers using {} clients...",
+        args.num_keys,
+        args.num_workers,
+        clients.len()
+    );
 
     let keys = Arc::new(keys.to_vec());
     let mut tasks = JoinSet::new();
@@ -404,17 +684,38 @@ async fn delete_phase(
     Ok(duration)
 }
 
+/// Latency-analysis samples, and the warmup GETs that preceded them
+struct LatencyResult {
+    warmup_ops: usize,
+    /// Sorted ascending
+    latencies: Vec<Duration>,
+}
+
 /// Run latency analysis: measure individual operation latencies
 async fn latency_analysis(
     args: &Args,
     _value_size: usize,
     keys: &[String],
     num_samples: usize,
-) -> Result<()> {
+) -> Result<LatencyResult> {
     println!("\n=== Latency Analysis ===");
-    println!("Measuring latency for {} random GET operations...", num_samples);
 
+    // A dedicated client, so it must be warmed itself or its first samples pay
+    // for connection setup
     let client = create_client(args, args.base_client_id + 1000).await?;
+    let warmup_ops = if args.warm_latency_client {
+        args.warmup
+    } else {
+        0
+    };
+    for i in 0..warmup_ops {
+        client.get(keys[i % keys.len()].as_bytes()).await?;
+    }
+
+    println!(
+        "Measuring latency for {} random GET operations...",
+        num_samples
+    );
     let mut latencies = Vec::with_capacity(num_samples);
 
     for i in 0..num_samples {
@@ -442,7 +743,10 @@ async fn latency_analysis(
     println!("  P99:    {:8.2} µs", p99.as_micros());
     println!("  Max:    {:8.2} µs", max.as_micros());
 
-    Ok(())
+    Ok(LatencyResult {
+        warmup_ops,
+        latencies,
+    })
 }
 
 #[tokio::main(worker_threads = 4)]
@@ -465,13 +769,36 @@ async fn main() -> Result<()> {
     println!("Server:             {}", args.server_addr);
     println!("Keys:               {}", args.num_keys);
     println!("Value size:         {}", format_size(value_size));
-    println!("Repeat reads:       {} (total ops: {})", args.repeat_reads, args.num_keys * args.repeat_reads);
+    println!(
+        "Repeat reads:       {} (total ops: {})",
+        args.repeat_reads,
+        args.num_keys * args.repeat_reads
+    );
     println!("Concurrent workers: {}", args.num_workers);
     println!("RDMA clients:       {} (OS threads: 4)", args.num_clients);
     println!("Buffer/client:      {} MB", args.buffer_mb);
-    println!("Transport:          {}", if args.mock { "Mock (same process only)" } else { "Real RDMA" });
+    println!(
+        "Transport:          {}",
+        if args.mock {
+            "Mock (same process only)"
+        } else {
+            "Real RDMA"
+        }
+    );
+    println!(
+        "Writes:             {}",
+        if args.concurrent_writes {
+            "Concurrent"
+        } else {
+            "Single-threaded"
+        }
+    );
     println!("==============================================");
 
+    if args.dry_run {
+        return dry_run(&args, value_size);
+    }
+
     if args.mock {
         println!("\nWARNING: Mock transport only works when server runs in same process.");
         println!("For separate server process, use --mock false (requires RDMA hardware)");
@@ -479,7 +806,10 @@ async fn main() -> Result<()> {
     }
 
     if args.num_clients > 8 {
-        println!("\nWARNING: High client count ({}) may exhaust RDMA endpoint resources.", args.num_clients);
+        println!(
+            "\nWARNING: High client count ({}) may exhaust RDMA endpoint resources.",
+            args.num_clients
+        );
         println!("If you see 'Cannot allocate memory' errors, reduce --num-clients");
         println!("Recommended: 2-4 clients for RDMA workloads\n");
     }
@@ -489,29 +819,44 @@ async fn main() -> Result<()> {
         .map(|i| format!("bench_key_{:08}", i))
         .collect();
 
-    // Phase 1: Write all keys
-    let write_duration = write_phase(&args, value_size, &keys).await?;
-
-    // Phase 2: Create client pool
+    // Phase 1: Create client pool
     println!("\n=== Creating Client Pool ===");
     println!("Creating {} RDMA clients...", args.num_clients);
-    let mut clients: Vec<Arc<KvCacheClient>> = Vec::with_capacity(args.num_clients);
+    let mut clients: Vec<Arc<ShardedClient>> = Vec::with_capacity(args.num_clients);
     for client_id in 0..args.num_clients {
         let client = create_client(&args, args.base_client_id + client_id as u32 + 1).await?;
         clients.push(Arc::new(client));
-        print!("\rCreated {}/{} clients...", client_id + 1, args.num_clients);
+        print!(
+            "\rCreated {}/{} clients...",
+            client_id + 1,
+            args.num_clients
+        );
         std::io::Write::flush(&mut std::io::stdout())?;
     }
-    println!("\rCreated {}/{} clients.", args.num_clients, args.num_clients);
+    println!(
+        "\rCreated {}/{} clients.",
+        args.num_clients, args.num_clients
+    );
+
+    // Phase 2: Write all keys
+    let write_duration = if args.concurrent_writes {
+        concurrent_write_phase(&args, value_size, &keys, &clients)
+            .await?
+            .duration
+    } else {
+        write_phase(&args, value_size, &keys).await?.duration
+    };
 
     // Phase 3: Warmup
     warmup_phase(&args, &keys, &clients).await?;
 
     // Phase 4: Read all keys with multiple workers
-    let read_duration = read_phase(&args, value_size, &keys, &clients).await?;
+    let read_duration = read_phase(&args, value_size, &keys, &clients)
+        .await?
+        .duration;
 
     // Phase 5: Latency analysis
-    latency_analysis(&args, value_size, &keys, 100.min(args.num_keys)).await?;
+    let latency = latency_analysis(&args, value_size, &keys, 100.min(args.num_keys)).await?;
 
     // Phase 6: Delete all keys
     let delete_duration = delete_phase(&args, &keys, &clients).await?;
@@ -521,25 +866,35 @@ async fn main() -> Result<()> {
     let read_ops_per_sec = total_read_ops / read_duration.as_secs_f64();
 
     println!("\n=== Summary ===");
-    println!("Write:  {:.2}s, {:.0} ops/sec",
+    println!(
+        "Write:  {:.2}s, {:.0} ops/sec",
         write_duration.as_secs_f64(),
         args.num_keys as f64 / write_duration.as_secs_f64()
     );
 
     if args.repeat_reads > 1 {
-        println!("Read:   {:.2}s, {:.0} ops/sec ({} keys × {} repeats = {:.0} total ops)",
+        println!(
+            "Read:   {:.2}s, {:.0} ops/sec ({} keys × {} repeats = {:.0} total ops)",
             read_duration.as_secs_f64(),
             read_ops_per_sec,
             args.num_keys,
             args.repeat_reads,
             total_read_ops
         );
-        println!("        Speedup: {:.1}x vs single-threaded write ({} workers)",
-            (total_read_ops / read_duration.as_secs_f64()) / (args.num_keys as f64 / write_duration.as_secs_f64()),
+        println!(
+            "        Speedup: {:.1}x vs {} write ({} workers)",
+            (total_read_ops / read_duration.as_secs_f64())
+                / (args.num_keys as f64 / write_duration.as_secs_f64()),
+            if args.concurrent_writes {
+                "concurrent"
+            } else {
+                "single-threaded"
+            },
             args.num_workers
         );
     } else {
-        println!("Read:   {:.2}s, {:.0} ops/sec ({:.1}x speedup with {} workers)",
+        println!(
+            "Read:   {:.2}s, {:.0} ops/sec ({:.1}x speedup with {} workers)",
             read_duration.as_secs_f64(),
             read_ops_per_sec,
             write_duration.as_secs_f64() / read_duration.as_secs_f64(),
@@ -547,14 +902,210 @@ async fn main() -> Result<()> {
         );
     }
 
-    println!("Delete: {:.2}s, {:.0} ops/sec",
+    println!(
+        "Delete: {:.2}s, {:.0} ops/sec",
         delete_duration.as_secs_f64(),
         args.num_keys as f64 / delete_duration.as_secs_f64()
     );
 
     let total_data = (args.num_keys * args.repeat_reads * value_size) as f64;
     println!("Total data read: {}", format_size(total_data as usize));
-    println!("Read throughput: {}", format_throughput(total_data / read_duration.as_secs_f64()));
+    println!(
+        "Read throughput: {}",
+        format_throughput(total_data / read_duration.as_secs_f64())
+    );
+    println!(
+        "GET latency:     {} µs median ({} warmup GETs on the latency client)",
+        latency.latencies[latency.latencies.len() / 2].as_micros(),
+        latency.warmup_ops
+    );
+
+    for (addr, shard) in server_addrs(&args).iter().zip(clients[0].shards()) {
+        match shard.server_memory_stats().await {
+            Ok(stats) => println!("Server {} memory: {}", addr, stats),
+            Err(e) => println!("Server {} memory: unavailable ({})", addr, e),
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kv_rdma_poc::server::{KvCacheServer, ServerConfig};
+
+    /// Start an in-process mock server and return its client-facing address
+    async fn spawn_mock_server() -> (String, tokio::task::JoinHandle<()>) {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let listen_addr = format!("127.0.0.1:{}", port);
+        let server = KvCacheServer::new(ServerConfig {
+            listen_addr: listen_addr.clone(),
+            memory_pool_size: 16 * 1024 * 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        let service = server.into_service();
+
+        let handle = tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve(listen_addr.parse().unwrap())
+                .await
+                .unwrap();
+        });
+
+        (format!("http://127.0.0.1:{}", port), handle)
+    }
+
+    #[tokio::test]
+    async fn test_write_phase_shards_across_servers() {
+        let (addr_a, server_a) = spawn_mock_server().await;
+        let (addr_b, server_b) = spawn_mock_server().await;
+
+        let args = Args::parse_from([
+            "kv-bench",
+            "--server-addr",
+            &format!("{},{}", addr_a, addr_b),
+            "--num-keys",
+            "200",
+            "--buffer-mb",
+            "4",
+            "--mock",
+        ]);
+        let keys: Vec<String> = (0..args.num_keys)
+            .map(|i| format!("bench_key_{:08}", i))
+            .collect();
+
+        let result = write_phase(&args, 128, &keys).await.unwrap();
+        assert_eq!(result.per_server_ops.len(), 2);
+        assert!(result.per_server_ops.iter().all(|&ops| ops > 0));
+        assert_eq!(result.per_server_ops.iter().sum::<u64>(), 200);
+
+        // Each key landed on the server the ring assigns it to, and nowhere else
+        let client = create_client(&args, 1).await.unwrap();
+        for key in &keys {
+            let owner = client.shard_index(key.as_bytes());
+            assert!(client.shards()[owner].get(key.as_bytes()).await.is_ok());
+            assert!(client.shards()[1 - owner]
+                .get(key.as_bytes())
+                .await
+                .is_err());
+        }
+
+        server_a.abort();
+        server_b.abort();
+    }
+
+    #[test]
+    fn test_dry_run_reports_oversized_working_set() {
+        let args = Args::parse_from([
+            "kv-bench",
+            "--num-keys",
+            "1000",
+            "--value-size",
+            "64KiB",
+            "--server-memory-mb",
+            "16",
+            "--dry-run",
+        ]);
+        let err = dry_run(&args, 64 * 1024).unwrap_err().to_string();
+        assert!(err.contains("1 problem(s)"), "{}", err);
+        assert!(
+            err.contains("working set needs 62.50 MiB of pool per server"),
+            "{}",
+            err
+        );
+
+        let args = Args::parse_from(["kv-bench", "--server-memory-mb", "1024", "--dry-run"]);
+        dry_run(&args, 64 * 1024).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_write_phase_stores_every_key() {
+        let (addr, server) = spawn_mock_server().await;
+
+        let args = Args::parse_from([
+            "kv-bench",
+            "--server-addr",
+            &addr,
+            "--num-keys",
+            "203",
+            "--num-workers",
+            "8",
+            "--num-clients",
+            "2",
+            "--buffer-mb",
+            "4",
+            "--mock",
+            "--concurrent-writes",
+        ]);
+        let keys: Vec<String> = (0..args.num_keys)
+            .map(|i| format!("bench_key_{:08}", i))
+            .collect();
+        let mut clients = Vec::new();
+        for i in 0..args.num_clients {
+            clients.push(Arc::new(create_client(&args, 10 + i as u32).await.unwrap()));
+        }
+
+        let result = concurrent_write_phase(&args, 128, &keys, &clients)
+            .await
+            .unwrap();
+        assert_eq!(result.per_server_ops, vec![203]);
+
+        let client = create_client(&args, 1).await.unwrap();
+        for key in &keys {
+            assert_eq!(client.get(key.as_bytes()).await.unwrap().len(), 128);
+        }
+        assert_eq!(client.shards()[0].stats().await.unwrap().num_entries, 203);
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_latency_client_is_warmed_before_sampling() {
+        let (addr, server) = spawn_mock_server().await;
+
+        let args = Args::parse_from([
+            "kv-bench",
+            "--server-addr",
+            &addr,
+            "--num-keys",
+            "20",
+            "--warmup",
+            "25",
+            "--buffer-mb",
+            "4",
+            "--mock",
+        ]);
+        let keys: Vec<String> = (0..args.num_keys)
+            .map(|i| format!("bench_key_{:08}", i))
+            .collect();
+        write_phase(&args, 128, &keys).await.unwrap();
+
+        let result = latency_analysis(&args, 128, &keys, 10).await.unwrap();
+        assert_eq!(result.warmup_ops, 25);
+        assert_eq!(result.latencies.len(), 10);
+        // The server saw the warmup GETs on top of the measured ones
+        let client = create_client(&args, 1).await.unwrap();
+        assert_eq!(client.shards()[0].stats().await.unwrap().gets, 25 + 10);
+
+        let args = Args {
+            warm_latency_client: false,
+            ..args
+        };
+        assert_eq!(
+            latency_analysis(&args, 128, &keys, 10)
+                .await
+                .unwrap()
+                .warmup_ops,
+            0
+        );
+
+        server.abort();
+    }
+}
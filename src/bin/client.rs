@@ -3,14 +3,22 @@
 //! Run with: cargo run --bin kv-client -- --help
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
 use kv_rdma_poc::client::{ClientConfig, KvCacheClient};
-use kv_rdma_poc::transport::TransportConfig;
+use kv_rdma_poc::config::load_toml;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[command(name = "kv-client")]
 #[command(about = "Distributed KV Cache Client with RDMA support")]
 struct Args {
+    /// TOML file with `ClientConfig` settings; flags given explicitly override it
+    #[arg(long)]
+    config: Option<PathBuf>,
+
     /// Client node ID
     #[arg(long, default_value = "1")]
     client_id: u32,
@@ -19,6 +27,10 @@ struct Args {
     #[arg(long, default_value = "http://[::1]:50051")]
     server_addr: String,
 
+    /// Fallback server address, tried after --server-addr (repeatable)
+    #[arg(long = "seed-addr")]
+    seed_addrs: Vec<String>,
+
     /// Receive buffer size in MB
     #[arg(long, default_value = "64")]
     buffer_mb: usize,
@@ -27,6 +39,10 @@ struct Args {
     #[arg(long, default_value_t = false)]
     mock: bool,
 
+    /// Keep retrying for this many seconds while the server isn't reachable yet
+    #[arg(long, default_value = "5")]
+    connect_timeout_secs: u64,
+
     /// Log level (trace, debug, info, warn, error)
     #[arg(long, default_value = "info")]
     log_level: String,
@@ -67,34 +83,58 @@ enum Commands {
         /// Value size in bytes
         #[arg(long, default_value = "1024")]
         value_size: usize,
+        /// TTL in seconds for written keys (0 = no expiration)
+        #[arg(long, default_value = "0")]
+        ttl: u64,
     },
 }
 
-async fn run_client(args: &Args) -> Result<KvCacheClient> {
-    let config = ClientConfig {
-        client_id: args.client_id,
-        server_addr: args.server_addr.clone(),
-        receive_buffer_size: args.buffer_mb * 1024 * 1024,
-        transport: TransportConfig {
-            node_id: args.client_id,
-            num_domains: 1,
-            use_mock: args.mock,
-        },
+/// Build the client config from `--config` (if any) plus CLI flags
+///
+/// Without a config file every flag applies, defaults included. With one, only
+/// flags given on the command line override the file.
+fn build_config(args: &Args, matches: &ArgMatches) -> Result<ClientConfig> {
+    let mut config: ClientConfig = match &args.config {
+        Some(path) => load_toml(path)?,
+        None => ClientConfig::default(),
     };
+    let apply = |id: &str| {
+        args.config.is_none() || matches.value_source(id) == Some(ValueSource::CommandLine)
+    };
+
+    if apply("client_id") {
+        config.client_id = args.client_id;
+        config.transport.node_id = args.client_id;
+    }
+    if apply("server_addr") {
+        config.server_addr = args.server_addr.clone();
+    }
+    if apply("seed_addrs") {
+        config.seed_addrs = args.seed_addrs.clone();
+    }
+    if apply("buffer_mb") {
+        config.receive_buffer_size = args.buffer_mb * 1024 * 1024;
+    }
+    if apply("mock") {
+        config.transport.use_mock = args.mock;
+    }
+
+    config.validate()?;
+    Ok(config)
+}
 
+async fn run_client(config: ClientConfig, connect_timeout: Duration) -> Result<KvCacheClient> {
     let client = KvCacheClient::new(config)?;
-    client.connect().await?;
+    client.connect_with_retry(connect_timeout).await?;
     Ok(client)
 }
 
-async fn cmd_get(client: &KvCacheClient, key: &str) -> Result<()> {
+async fn cmd_get(client: &KvCacheClient, key: &str, out: &mut impl Write) -> Result<()> {
     match client.get(key.as_bytes()).await {
-        Ok(value) => {
-            match String::from_utf8(value.clone()) {
-                Ok(s) => println!("{}", s),
-                Err(_) => println!("{:?}", value),
-            }
-        }
+        Ok(value) => match String::from_utf8(value.clone()) {
+            Ok(s) => writeln!(out, "{}", s)?,
+            Err(_) => writeln!(out, "{:?}", value)?,
+        },
         Err(e) => {
             eprintln!("Error: {}", e);
         }
@@ -102,21 +142,27 @@ async fn cmd_get(client: &KvCacheClient, key: &str) -> Result<()> {
     Ok(())
 }
 
-async fn cmd_put(client: &KvCacheClient, key: &str, value: &str, ttl: u64) -> Result<()> {
+async fn cmd_put(
+    client: &KvCacheClient,
+    key: &str,
+    value: &str,
+    ttl: u64,
+    out: &mut impl Write,
+) -> Result<()> {
     match client.put(key.as_bytes(), value.as_bytes(), ttl).await {
-        Ok(()) => println!("OK"),
+        Ok(()) => writeln!(out, "OK")?,
         Err(e) => eprintln!("Error: {}", e),
     }
     Ok(())
 }
 
-async fn cmd_delete(client: &KvCacheClient, key: &str) -> Result<()> {
+async fn cmd_delete(client: &KvCacheClient, key: &str, out: &mut impl Write) -> Result<()> {
     match client.delete(key.as_bytes()).await {
         Ok(existed) => {
             if existed {
-                println!("Deleted");
+                writeln!(out, "Deleted")?;
             } else {
-                println!("Key not found");
+                writeln!(out, "Key not found")?;
             }
         }
         Err(e) => eprintln!("Error: {}", e),
@@ -124,23 +170,51 @@ async fn cmd_delete(client: &KvCacheClient, key: &str) -> Result<()> {
     Ok(())
 }
 
+async fn cmd_ttl(client: &KvCacheClient, key: &str, out: &mut impl Write) -> Result<()> {
+    match client.get_detailed(key.as_bytes()).await {
+        Ok(outcome) if outcome.remaining_ttl == 0 => writeln!(out, "No expiration")?,
+        Ok(outcome) => writeln!(out, "{}s", outcome.remaining_ttl)?,
+        Err(e) => eprintln!("Error: {}", e),
+    }
+    Ok(())
+}
+
+async fn cmd_touch(
+    client: &KvCacheClient,
+    key: &str,
+    ttl: u64,
+    out: &mut impl Write,
+) -> Result<()> {
+    match client.touch(key.as_bytes(), ttl).await {
+        Ok(true) => writeln!(out, "OK")?,
+        Ok(false) => writeln!(out, "Key not found")?,
+        Err(e) => eprintln!("Error: {}", e),
+    }
+    Ok(())
+}
+
 async fn cmd_repl(client: &KvCacheClient) -> Result<()> {
-    use std::io::{self, BufRead, Write};
+    repl(client, io::stdin().lock(), &mut io::stdout()).await
+}
 
-    println!("KV Cache REPL - Commands: get <key>, put <key> <value>, delete <key>, quit");
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
+/// Run REPL commands read from `input` until it ends or says quit
+async fn repl(client: &KvCacheClient, mut input: impl BufRead, out: &mut impl Write) -> Result<()> {
+    writeln!(
+        out,
+        "KV Cache REPL - Commands: get <key>, put <key> <value>, putttl <key> <value> <seconds>, \
+         ttl <key>, touch <key> <seconds>, delete <key>, quit"
+    )?;
 
     loop {
-        print!("> ");
-        stdout.flush()?;
+        write!(out, "> ")?;
+        out.flush()?;
 
         let mut line = String::new();
-        if stdin.lock().read_line(&mut line)? == 0 {
+        if input.read_line(&mut line)? == 0 {
             break;
         }
 
-        let parts: Vec<&str> = line.trim().split_whitespace().collect();
+        let parts: Vec<&str> = line.split_whitespace().collect();
         if parts.is_empty() {
             continue;
         }
@@ -148,40 +222,59 @@ async fn cmd_repl(client: &KvCacheClient) -> Result<()> {
         match parts[0] {
             "get" => {
                 if parts.len() < 2 {
-                    println!("Usage: get <key>");
+                    writeln!(out, "Usage: get <key>")?;
                     continue;
                 }
-                cmd_get(client, parts[1]).await?;
+                cmd_get(client, parts[1], out).await?;
             }
             "put" => {
                 if parts.len() < 3 {
-                    println!("Usage: put <key> <value>");
+                    writeln!(out, "Usage: put <key> <value>")?;
+                    continue;
+                }
+                cmd_put(client, parts[1], parts[2], 0, out).await?;
+            }
+            "putttl" => {
+                let Some(ttl) = parts.get(3).and_then(|ttl| ttl.parse().ok()) else {
+                    writeln!(out, "Usage: putttl <key> <value> <seconds>")?;
+                    continue;
+                };
+                cmd_put(client, parts[1], parts[2], ttl, out).await?;
+            }
+            "ttl" => {
+                if parts.len() < 2 {
+                    writeln!(out, "Usage: ttl <key>")?;
                     continue;
                 }
-                cmd_put(client, parts[1], parts[2], 0).await?;
+                cmd_ttl(client, parts[1], out).await?;
+            }
+            "touch" => {
+                let Some(ttl) = parts.get(2).and_then(|ttl| ttl.parse().ok()) else {
+                    writeln!(out, "Usage: touch <key> <seconds>")?;
+                    continue;
+                };
+                cmd_touch(client, parts[1], ttl, out).await?;
             }
             "delete" | "del" => {
                 if parts.len() < 2 {
-                    println!("Usage: delete <key>");
+                    writeln!(out, "Usage: delete <key>")?;
                     continue;
                 }
-                cmd_delete(client, parts[1]).await?;
+                cmd_delete(client, parts[1], out).await?;
             }
             "stats" => {
-                let stats = client.memory_stats();
-                println!(
-                    "Memory: total={} MB, used={} MB, available={} MB",
-                    stats.total / 1024 / 1024,
-                    stats.used / 1024 / 1024,
-                    stats.available / 1024 / 1024
-                );
+                writeln!(out, "Memory: {}", client.memory_stats())?;
+                match client.server_memory_stats().await {
+                    Ok(stats) => writeln!(out, "Server memory: {}", stats)?,
+                    Err(e) => writeln!(out, "Server memory: unavailable ({})", e)?,
+                }
             }
             "quit" | "exit" | "q" => {
-                println!("Bye!");
+                writeln!(out, "Bye!")?;
                 break;
             }
             _ => {
-                println!("Unknown command: {}", parts[0]);
+                writeln!(out, "Unknown command: {}", parts[0])?;
             }
         }
     }
@@ -189,21 +282,21 @@ async fn cmd_repl(client: &KvCacheClient) -> Result<()> {
     Ok(())
 }
 
-async fn cmd_bench(client: &KvCacheClient, ops: usize, value_size: usize) -> Result<()> {
+async fn cmd_bench(client: &KvCacheClient, ops: usize, value_size: usize, ttl: u64) -> Result<()> {
     use std::time::Instant;
 
     let value = vec![b'x'; value_size];
 
     println!(
-        "Running benchmark: {} ops, {} byte values",
-        ops, value_size
+        "Running benchmark: {} ops, {} byte values, ttl={}s",
+        ops, value_size, ttl
     );
 
     // PUT benchmark
     let start = Instant::now();
     for i in 0..ops {
         let key = format!("bench_key_{}", i);
-        client.put(key.as_bytes(), &value, 0).await?;
+        client.put(key.as_bytes(), &value, ttl).await?;
     }
     let put_duration = start.elapsed();
     let put_ops_per_sec = ops as f64 / put_duration.as_secs_f64();
@@ -249,9 +342,13 @@ async fn cmd_bench(client: &KvCacheClient, ops: usize, value_size: usize) -> Res
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches)?;
 
     // Initialize logging
+    #[cfg(feature = "otel")]
+    let _telemetry = kv_rdma_poc::telemetry::init_tracing(&args.log_level, "kv-client")?;
+    #[cfg(not(feature = "otel"))]
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::try_from_default_env()
@@ -259,15 +356,150 @@ async fn main() -> Result<()> {
         )
         .init();
 
-    let client = run_client(&args).await?;
+    let connect_timeout = Duration::from_secs(args.connect_timeout_secs);
+    let client = run_client(build_config(&args, &matches)?, connect_timeout).await?;
 
     match &args.command {
-        Commands::Get { key } => cmd_get(&client, key).await?,
-        Commands::Put { key, value, ttl } => cmd_put(&client, key, value, *ttl).await?,
-        Commands::Delete { key } => cmd_delete(&client, key).await?,
+        Commands::Get { key } => cmd_get(&client, key, &mut io::stdout()).await?,
+        Commands::Put { key, value, ttl } => {
+            cmd_put(&client, key, value, *ttl, &mut io::stdout()).await?
+        }
+        Commands::Delete { key } => cmd_delete(&client, key, &mut io::stdout()).await?,
         Commands::Repl => cmd_repl(&client).await?,
-        Commands::Bench { ops, value_size } => cmd_bench(&client, *ops, *value_size).await?,
+        Commands::Bench {
+            ops,
+            value_size,
+            ttl,
+        } => cmd_bench(&client, *ops, *value_size, *ttl).await?,
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kv_rdma_poc::server::{KvCacheServer, ServerConfig};
+
+    #[tokio::test]
+    async fn test_bench_with_short_ttl_has_no_misses() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let listen_addr = format!("127.0.0.1:{}", port);
+        let server = KvCacheServer::new(ServerConfig {
+            listen_addr: listen_addr.clone(),
+            memory_pool_size: 16 * 1024 * 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        let service = server.into_service();
+        let server_handle = tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve(listen_addr.parse().unwrap())
+                .await
+                .unwrap();
+        });
+        let matches = Args::command().get_matches_from([
+            "kv-client",
+            "--server-addr",
+            &format!("http://127.0.0.1:{}", port),
+            "--buffer-mb",
+            "4",
+            "--mock",
+            "bench",
+            "--ops",
+            "50",
+            "--value-size",
+            "256",
+            "--ttl",
+            "5",
+        ]);
+        let args = Args::from_arg_matches(&matches).unwrap();
+        let Commands::Bench {
+            ops,
+            value_size,
+            ttl,
+        } = args.command
+        else {
+            panic!("expected bench subcommand");
+        };
+        assert_eq!(ttl, 5);
+
+        // cmd_bench propagates any GET miss as an error
+        let client = run_client(
+            build_config(&args, &matches).unwrap(),
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+        cmd_bench(&client, ops, value_size, ttl).await.unwrap();
+
+        server_handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_repl_ttl_counts_down_and_touch_extends_it() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let listen_addr = format!("127.0.0.1:{}", port);
+        let server = KvCacheServer::new(ServerConfig {
+            listen_addr: listen_addr.clone(),
+            memory_pool_size: 16 * 1024 * 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        let service = server.into_service();
+        let server_handle = tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve(listen_addr.parse().unwrap())
+                .await
+                .unwrap();
+        });
+        let client = run_client(
+            ClientConfig {
+                server_addr: format!("http://127.0.0.1:{}", port),
+                receive_buffer_size: 4 * 1024 * 1024,
+                ..Default::default()
+            },
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+        let run = |script: &'static str| {
+            let client = &client;
+            async move {
+                let mut out = Vec::new();
+                repl(client, script.as_bytes(), &mut out).await.unwrap();
+                // Drop the banner line; each command's output follows its prompt
+                let out = String::from_utf8(out).unwrap();
+                let (_banner, prompts) = out.split_once('\n').unwrap();
+                prompts
+                    .split("> ")
+                    .skip(1)
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            run("putttl session token 10\nttl session\n").await,
+            ["OK\n", "10s\n", ""]
+        );
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        assert_eq!(run("ttl session\n").await, ["9s\n", ""]);
+        assert_eq!(
+            run("touch session 60\nttl session\ntouch missing 60\nquit\n").await,
+            ["OK\n", "60s\n", "Key not found\n", "Bye!\n"]
+        );
+
+        server_handle.abort();
+    }
+}
@@ -2,20 +2,29 @@
 //!
 //! Run with: cargo run --bin kv-server -- --help
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{anyhow, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser};
+use kv_rdma_poc::affinity::pin_current_thread;
+use kv_rdma_poc::bloom::BloomFilterConfig;
+use kv_rdma_poc::config::load_toml;
 use kv_rdma_poc::server::{run_server, ServerConfig};
-use kv_rdma_poc::transport::TransportConfig;
+use kv_rdma_poc::transport::RdmaTransport;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "kv-server")]
 #[command(about = "Distributed KV Cache Server with RDMA support")]
 struct Args {
+    /// TOML file with `ServerConfig` settings; flags given explicitly override it
+    #[arg(long)]
+    config: Option<PathBuf>,
+
     /// Server node ID
     #[arg(long, default_value = "0")]
     node_id: u32,
 
-    /// gRPC listen address
+    /// gRPC listen address (host:port, or unix:/path/to/sock for a Unix domain socket)
     #[arg(long, default_value = "[::1]:50051")]
     listen_addr: String,
 
@@ -23,6 +32,10 @@ struct Args {
     #[arg(long, default_value = "1024")]
     memory_mb: usize,
 
+    /// Reject GETs, PUTs and DELETEs of keys longer than this many bytes (0 = unlimited)
+    #[arg(long, default_value = "65536")]
+    max_key_size: usize,
+
     /// Number of RDMA domains/NICs to use
     #[arg(long, default_value = "1")]
     num_domains: usize,
@@ -38,10 +51,98 @@ struct Args {
     /// Number of worker threads for processing requests
     #[arg(long, default_value = "4")]
     worker_threads: usize,
+
+    /// Keep retrying the bind for this many seconds if the address is in use
+    #[arg(long, default_value = "0")]
+    bind_retry_secs: u64,
+
+    /// Size a Bloom filter for this many keys to short-circuit misses (0 = disabled)
+    #[arg(long, default_value = "0")]
+    bloom_filter_items: usize,
+
+    /// Shed GETs whose estimated queue delay exceeds this many milliseconds (0 = never shed)
+    #[arg(long, default_value = "0")]
+    get_latency_budget_ms: u64,
+
+    /// Write-ahead log file; replayed on startup, fsynced on Flush
+    #[arg(long)]
+    wal_path: Option<PathBuf>,
+
+    /// Pin runtime worker threads to these CPUs (comma-separated, e.g. "8,9,10,11")
+    #[arg(long, value_delimiter = ',')]
+    runtime_cpus: Option<Vec<usize>>,
+
+    /// Check the transport by moving data between two buffers on every domain,
+    /// print per-domain latency and bandwidth, and exit instead of serving
+    #[arg(long, default_value_t = false)]
+    selftest: bool,
+}
+
+/// Build the server config from `--config` (if any) plus CLI flags
+///
+/// Without a config file every flag applies, defaults included. With one, only
+/// flags given on the command line override the file.
+fn build_config(args: &Args, matches: &ArgMatches) -> Result<ServerConfig> {
+    let mut config: ServerConfig = match &args.config {
+        Some(path) => load_toml(path)?,
+        None => ServerConfig::default(),
+    };
+    let apply = |id: &str| {
+        args.config.is_none() || matches.value_source(id) == Some(ValueSource::CommandLine)
+    };
+
+    if apply("node_id") {
+        config.node_id = args.node_id;
+        config.transport.node_id = args.node_id;
+    }
+    if apply("listen_addr") {
+        config.listen_addr = args.listen_addr.clone();
+    }
+    if apply("memory_mb") {
+        config.memory_pool_size = args.memory_mb * 1024 * 1024;
+    }
+    if apply("max_key_size") {
+        config.max_key_size = args.max_key_size;
+    }
+    if apply("num_domains") {
+        config.transport.num_domains = args.num_domains;
+    }
+    if apply("mock") {
+        config.transport.use_mock = args.mock;
+    }
+    if apply("bind_retry_secs") {
+        config.bind_retry_timeout = std::time::Duration::from_secs(args.bind_retry_secs);
+    }
+    if apply("bloom_filter_items") {
+        config.bloom_filter = (args.bloom_filter_items > 0).then(|| BloomFilterConfig {
+            expected_items: args.bloom_filter_items,
+            ..Default::default()
+        });
+    }
+
+    if apply("get_latency_budget_ms") {
+        config.get_latency_budget = (args.get_latency_budget_ms > 0)
+            .then(|| std::time::Duration::from_millis(args.get_latency_budget_ms));
+    }
+
+    if apply("wal_path") {
+        config.wal_path = args.wal_path.clone();
+    }
+    if apply("runtime_cpus") {
+        config.runtime_cpus = args.runtime_cpus.clone();
+    }
+
+    config.validate()?;
+    Ok(config)
 }
 
-async fn run_with_config(args: Args) -> Result<()> {
+async fn run_with_config(args: Args, config: ServerConfig) -> Result<()> {
     // Initialize logging
+    #[cfg(feature = "otel")]
+    let _telemetry = kv_rdma_poc::telemetry::init_tracing(&args.log_level, "kv-server")?;
+    #[cfg(all(feature = "console", not(feature = "otel")))]
+    kv_rdma_poc::console::init_tracing(&args.log_level);
+    #[cfg(not(any(feature = "otel", feature = "console")))]
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::try_from_default_env()
@@ -49,36 +150,176 @@ async fn run_with_config(args: Args) -> Result<()> {
         )
         .init();
 
-    let config = ServerConfig {
-        node_id: args.node_id,
-        listen_addr: args.listen_addr.clone(),
-        memory_pool_size: args.memory_mb * 1024 * 1024,
-        transport: TransportConfig {
-            node_id: args.node_id,
-            num_domains: args.num_domains,
-            use_mock: args.mock,
-        },
-    };
-
     tracing::info!("=== KV Cache Server Configuration ===");
+    if let Some(path) = &args.config {
+        tracing::info!("Config file: {}", path.display());
+    }
     tracing::info!("Worker threads: {}", args.worker_threads);
-    tracing::info!("Listen address: {}", args.listen_addr);
-    tracing::info!("Memory pool: {} MB", args.memory_mb);
-    tracing::info!("Node ID: {}", args.node_id);
-    tracing::info!("Transport: {}", if args.mock { "Mock" } else { "RDMA" });
+    tracing::info!("Listen address: {}", config.listen_addr);
+    tracing::info!("Memory pool: {} MB", config.memory_pool_size / 1024 / 1024);
+    tracing::info!("Node ID: {}", config.node_id);
+    tracing::info!(
+        "Transport: {}",
+        if config.transport.use_mock {
+            "Mock"
+        } else {
+            "RDMA"
+        }
+    );
     tracing::info!("======================================");
 
     run_server(config).await
 }
 
+/// Run `RdmaTransport::self_test` with the configured transport and print the results
+async fn run_selftest(config: ServerConfig) -> Result<()> {
+    let mut transport_config = config.transport;
+    transport_config.node_id = config.node_id;
+    let transport = RdmaTransport::new(transport_config)?;
+    println!(
+        "Transport self-test: {} transport, {} domains",
+        if transport.is_mock() { "mock" } else { "RDMA" },
+        transport.domain_addresses().len()
+    );
+
+    for result in transport.self_test().await? {
+        println!(
+            "  domain {}: {:>8} bytes  {:>10.1?}  {:>10.1} MB/s",
+            result.domain_idx,
+            result.size,
+            result.latency,
+            result.bandwidth() / 1e6
+        );
+    }
+    println!("✓ All transfers delivered the right bytes");
+    Ok(())
+}
+
 fn main() -> Result<()> {
-    let args = Args::parse();
-    let worker_threads = args.worker_threads;
-
-    // Build tokio runtime with specified number of worker threads
-    tokio::runtime::Builder::new_multi_thread()
-        .worker_threads(worker_threads)
-        .enable_all()
-        .build()?
-        .block_on(run_with_config(args))
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches)?;
+    let config = build_config(&args, &matches)?;
+    let runtime = build_runtime(args.worker_threads, config.runtime_cpus.clone())?;
+    if args.selftest {
+        return runtime.block_on(run_selftest(config));
+    }
+    runtime.block_on(run_with_config(args, config))
+}
+
+/// Build the tokio runtime, pinning its threads to `cpus` if given
+///
+/// A throwaway thread is pinned first, so CPUs the process can't use fail
+/// startup instead of leaving the runtime unpinned.
+fn build_runtime(
+    worker_threads: usize,
+    cpus: Option<Vec<usize>>,
+) -> Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.worker_threads(worker_threads).enable_all();
+    if let Some(cpus) = cpus {
+        let probe_cpus = cpus.clone();
+        std::thread::spawn(move || pin_current_thread(&probe_cpus))
+            .join()
+            .map_err(|_| anyhow!("CPU pinning check panicked"))?
+            .map_err(|e| anyhow!("Cannot pin runtime threads to CPUs {:?}: {}", cpus, e))?;
+        builder.on_thread_start(move || {
+            if let Err(e) = pin_current_thread(&cpus) {
+                tracing::warn!("Failed to pin runtime thread: {}", e);
+            }
+        });
+    }
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unusable_runtime_cpus_fail_the_build() {
+        let err = build_runtime(2, Some(vec![100_000])).err().unwrap();
+        assert!(
+            err.to_string()
+                .starts_with("Cannot pin runtime threads to CPUs [100000]"),
+            "{}",
+            err
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_runtime_threads_are_pinned() {
+        use kv_rdma_poc::affinity::current_thread_cpus;
+
+        let allowed = current_thread_cpus().unwrap();
+        let target = vec![*allowed.last().unwrap()];
+        let runtime = build_runtime(2, Some(target.clone())).unwrap();
+
+        let observed: Vec<Vec<usize>> = runtime.block_on(async {
+            let handles: Vec<_> = (0..8)
+                .map(|_| tokio::spawn(async { current_thread_cpus().unwrap() }))
+                .collect();
+            let mut observed = Vec::new();
+            for handle in handles {
+                observed.push(handle.await.unwrap());
+            }
+            observed
+        });
+        for cpus in observed {
+            assert_eq!(cpus, target);
+        }
+    }
+
+    #[test]
+    fn test_config_file_with_cli_override() {
+        let path = std::env::temp_dir().join(format!("kv-server-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+node_id = 3
+listen_addr = "0.0.0.0:6000"
+memory_pool_size = 8388608
+bind_retry_timeout = "5s"
+wal_path = "/var/lib/kv/cache.wal"
+runtime_cpus = [2, 3]
+
+[transport]
+node_id = 3
+num_domains = 2
+use_mock = true
+
+[bloom_filter]
+expected_items = 1000
+"#,
+        )
+        .unwrap();
+
+        let argv = [
+            "kv-server",
+            "--config",
+            path.to_str().unwrap(),
+            "--listen-addr",
+            "127.0.0.1:7000",
+            "--runtime-cpus",
+            "4,5",
+        ];
+        let matches = Args::command().get_matches_from(argv);
+        let args = Args::from_arg_matches(&matches).unwrap();
+        let config = build_config(&args, &matches).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Explicit flag wins; everything else comes from the file, not flag defaults
+        assert_eq!(config.listen_addr, "127.0.0.1:7000");
+        assert_eq!(config.node_id, 3);
+        assert_eq!(config.memory_pool_size, 8 * 1024 * 1024);
+        assert_eq!(config.bind_retry_timeout, std::time::Duration::from_secs(5));
+        assert_eq!(config.transport.num_domains, 2);
+        assert!(config.transport.use_mock);
+        assert_eq!(config.bloom_filter.unwrap().expected_items, 1000);
+        assert_eq!(
+            config.wal_path,
+            Some(PathBuf::from("/var/lib/kv/cache.wal"))
+        );
+        assert_eq!(config.runtime_cpus, Some(vec![4, 5]));
+    }
 }
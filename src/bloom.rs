@@ -0,0 +1,139 @@
+//! Counting Bloom filter for fast negative key lookups
+//!
+//! The server consults this before touching the cache map so that misses on
+//! large, sparsely-hit caches return without a DashMap lookup. Counters (rather
+//! than bits) allow keys to be removed again on DELETE.
+
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Sizing parameters for the Bloom filter
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BloomFilterConfig {
+    /// Number of distinct keys the filter is sized for
+    pub expected_items: usize,
+    /// Target false-positive rate at `expected_items` (e.g. 0.01)
+    pub false_positive_rate: f64,
+}
+
+impl Default for BloomFilterConfig {
+    fn default() -> Self {
+        Self {
+            expected_items: 1_000_000,
+            false_positive_rate: 0.01,
+        }
+    }
+}
+
+/// A Bloom filter with 8-bit saturating counters
+///
+/// A counter that saturates at `u8::MAX` is never decremented again, so it
+/// can only cause false positives, never false negatives.
+pub struct CountingBloomFilter {
+    counters: Vec<AtomicU8>,
+    num_hashes: u32,
+}
+
+impl CountingBloomFilter {
+    /// Create a filter sized for the given item count and false-positive rate
+    pub fn new(config: &BloomFilterConfig) -> Self {
+        let n = config.expected_items.max(1) as f64;
+        let p = config.false_positive_rate.clamp(1e-9, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+
+        let num_counters = ((-n * p.ln()) / (ln2 * ln2)).ceil().max(64.0) as usize;
+        let num_hashes = ((num_counters as f64 / n) * ln2).round().clamp(1.0, 16.0) as u32;
+
+        Self {
+            counters: (0..num_counters).map(|_| AtomicU8::new(0)).collect(),
+            num_hashes,
+        }
+    }
+
+    /// Record a key
+    pub fn insert(&self, key: &[u8]) {
+        for idx in self.indexes(key) {
+            let _ = self.counters[idx]
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |c| c.checked_add(1));
+        }
+    }
+
+    /// Remove a previously inserted key
+    pub fn remove(&self, key: &[u8]) {
+        for idx in self.indexes(key) {
+            let _ =
+                self.counters[idx].fetch_update(Ordering::AcqRel, Ordering::Acquire, |c| match c {
+                    0 | u8::MAX => None,
+                    c => Some(c - 1),
+                });
+        }
+    }
+
+    /// Returns false only if the key was definitely never inserted (or was removed)
+    pub fn may_contain(&self, key: &[u8]) -> bool {
+        self.indexes(key)
+            .all(|idx| self.counters[idx].load(Ordering::Acquire) > 0)
+    }
+
+    /// Number of counters in the filter
+    pub fn num_counters(&self) -> usize {
+        self.counters.len()
+    }
+
+    /// Number of hash functions applied per key
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Counter indexes for a key, via double hashing
+    fn indexes(&self, key: &[u8]) -> impl Iterator<Item = usize> + '_ {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let h1 = hasher.finish();
+        0x9e37_79b9_7f4a_7c15u64.hash(&mut hasher);
+        let h2 = hasher.finish() | 1;
+
+        let m = self.counters.len() as u64;
+        (0..self.num_hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % m) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_no_false_negatives_under_insert_delete_mix() {
+        let filter = CountingBloomFilter::new(&BloomFilterConfig {
+            expected_items: 2_000,
+            false_positive_rate: 0.01,
+        });
+        let mut present = HashSet::new();
+
+        for i in 0..20_000u32 {
+            let key = format!("key_{}", i % 5_000).into_bytes();
+            // Deterministic mix: roughly one in three operations is a delete
+            if i % 3 == 2 {
+                if present.remove(&key) {
+                    filter.remove(&key);
+                }
+            } else if present.insert(key.clone()) {
+                filter.insert(&key);
+            }
+
+            if i % 97 == 0 {
+                for key in &present {
+                    assert!(filter.may_contain(key), "false negative for {:?}", key);
+                }
+            }
+        }
+
+        for key in &present {
+            assert!(filter.may_contain(key));
+        }
+    }
+}
@@ -4,31 +4,101 @@
 //! GET/PUT requests via RPC. For GET requests, the server RDMA writes
 //! the value directly to the client's registered buffer.
 
-use crate::memory::{MemoryPool, MemoryPoolConfig, PoolAllocation};
+use crate::keys::KeyLogPolicy;
+use crate::memory::{MemoryPool, MemoryPoolConfig, PoolAllocation, PoolReadGuard};
+use crate::metrics::{ClientMetrics, OpRecorder};
 use crate::pb::kv_cache_service_client::KvCacheServiceClient;
 use crate::pb::{
-    DeleteRequest, GetRequest, HeartbeatRequest, PutRequest, RegisterClientRequest,
+    BatchPutRequest, CompareAndSwapRequest, CopyRequest, CountRequest, CountResponse,
+    DeletePrefixRequest, DeleteRequest, DeregisterClientRequest, DumpRequest, ExistsRequest,
+    FlushRequest, GetManyItem, GetManyRequest, GetRequest, GetResponse, GetSizeRequest, GetSource,
+    HeartbeatRequest, ImportRecord, ImportResponse, KeyspaceEvent, PutRequest, PutResponse,
+    RegisterClientRequest, RenameRequest, StatsRequest, StatsResponse, TouchRequest,
+    WatchEventsRequest,
 };
-use crate::protocol::{DomainAddress, ValueLocation};
+use crate::protocol::{DomainAddress, MemoryRegionDescriptor, MemoryRegionHandle, ValueLocation};
 use crate::transport::{RdmaTransport, TransportConfig};
 use anyhow::{anyhow, Result};
+use futures::{Stream, StreamExt, TryStreamExt};
 use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
 use tonic::transport::Channel;
+use tonic::{Code, Status};
 
 /// Client configuration
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ClientConfig {
     /// Client node ID
     pub client_id: u32,
     /// Server address (gRPC endpoint)
     pub server_addr: String,
+    /// Further servers to fail over to, tried in order after `server_addr`
+    pub seed_addrs: Vec<String>,
+    /// Times `connect` tries again while no server is reachable (0 = fail fast)
+    pub connect_retries: u32,
+    /// Wait before the first connect retry; doubles after each further one
+    #[serde(with = "humantime_serde")]
+    pub connect_backoff: Duration,
     /// Receive buffer size for RDMA transfers
     pub receive_buffer_size: usize,
     /// Transport configuration
     pub transport: TransportConfig,
+    /// Maximum number of in-flight GETs; further calls wait for a free slot
+    pub max_pending: usize,
+    /// GET priority registered with the server (higher is served first under load)
+    pub priority: u8,
+    /// Carve one receive region at construction for `get_reuse`, which then
+    /// never touches the allocator; for single-threaded consumers only
+    pub single_buffer_mode: bool,
+    /// Size each GET's receive buffer from the value sizes seen under the
+    /// key's prefix instead of always reserving the 1MB maximum
+    pub adaptive_buffer: Option<AdaptiveBufferConfig>,
+    /// Ask for each value's length with GetSize before a GET and reserve
+    /// exactly that, instead of retrying values that overflow the first guess
+    pub size_gets_first: bool,
+    /// Time every GET, PUT and DELETE for `metrics`
+    pub record_metrics: bool,
+    /// How keys are written in log messages
+    pub log_keys: KeyLogPolicy,
+}
+
+/// How GET receive buffers are sized from observed value sizes
+///
+/// The first GET under a prefix reserves the maximum; later ones reserve the
+/// running average plus `headroom`. A guess that turns out too small costs
+/// one retry with a buffer of the exact size.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdaptiveBufferConfig {
+    /// Keys sharing their first `prefix_len` bytes share a size estimate
+    pub prefix_len: usize,
+    /// Weight of each new value size in the running average, in (0, 1]
+    pub alpha: f64,
+    /// Room reserved beyond the estimate, as a fraction of it
+    pub headroom: f64,
+    /// Prefixes tracked at most; GETs under further ones reserve the maximum
+    pub max_prefixes: usize,
+}
+
+impl Default for AdaptiveBufferConfig {
+    fn default() -> Self {
+        Self {
+            prefix_len: 8,
+            alpha: 0.25,
+            headroom: 0.125,
+            max_prefixes: 1024,
+        }
+    }
 }
 
 impl Default for ClientConfig {
@@ -36,10 +106,144 @@ impl Default for ClientConfig {
         Self {
             client_id: 1,
             server_addr: "http://[::1]:50051".to_string(),
+            seed_addrs: Vec::new(),
+            connect_retries: 0,
+            connect_backoff: Duration::from_millis(100),
             receive_buffer_size: 64 * 1024 * 1024, // 64MB default
             transport: TransportConfig::default(),
+            max_pending: 256,
+            priority: 0,
+            single_buffer_mode: false,
+            adaptive_buffer: None,
+            size_gets_first: false,
+            record_metrics: true,
+            log_keys: KeyLogPolicy::default(),
+        }
+    }
+}
+
+/// Receive space first reserved per GET; a larger value is fetched again
+/// into a buffer of the length the server reported
+pub const GET_BUFFER_SIZE: usize = 1024 * 1024; // 1MB
+
+/// Adaptive GET buffers are rounded up to a multiple of this (the pool's alignment)
+const BUFFER_GRANULARITY: usize = 4096;
+
+/// Entries sent per `BatchPut` when loading a dump
+const LOAD_BATCH_SIZE: usize = 256;
+
+/// A key/value pair with its TTL, as produced by `dump` and consumed by `batch_put`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KvEntry {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    /// Remaining TTL in seconds (0 = no expiration)
+    pub ttl_seconds: u64,
+}
+
+/// Caller-owned buffer registered with a client's transport, for `get_many_into`
+/// and `put_from_buffer`
+///
+/// Registering is costly, so allocate these once and reuse them. Dropping one
+/// deregisters it; no transfer may target it after that.
+pub struct RegisteredBuffer {
+    data: Box<[u8]>,
+    descriptor: MemoryRegionDescriptor,
+    handle: MemoryRegionHandle,
+    transport: Arc<RdmaTransport>,
+}
+
+impl Drop for RegisteredBuffer {
+    fn drop(&mut self) {
+        if let Err(e) = self.transport.deregister_memory(&self.handle) {
+            tracing::warn!(
+                "Failed to deregister {}-byte buffer: {}",
+                self.data.len(),
+                e
+            );
+        }
+    }
+}
+
+impl Deref for RegisteredBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl DerefMut for RegisteredBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+}
+
+/// GET receive buffers reserved so far, from `KvCacheClient::get_buffer_stats`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GetBufferStats {
+    pub allocations: u64,
+    pub allocated_bytes: u64,
+    /// GETs resent with a larger buffer after the first one was too small
+    pub retries: u64,
+}
+
+/// Per-prefix value size estimates behind `adaptive_buffer`
+#[derive(Default)]
+struct BufferSizing {
+    estimates: Mutex<HashMap<Vec<u8>, f64>>,
+    allocations: AtomicU64,
+    allocated_bytes: AtomicU64,
+    retries: AtomicU64,
+}
+
+impl BufferSizing {
+    /// Receive buffer to reserve for a GET of `key`
+    fn buffer_size(&self, config: Option<&AdaptiveBufferConfig>, key: &[u8]) -> usize {
+        let Some(config) = config else {
+            return GET_BUFFER_SIZE;
+        };
+        let prefix = &key[..key.len().min(config.prefix_len)];
+        match self.estimates.lock().get(prefix) {
+            Some(&estimate) => {
+                let size = (estimate * (1.0 + config.headroom)).ceil() as usize;
+                size.next_multiple_of(BUFFER_GRANULARITY)
+                    .clamp(BUFFER_GRANULARITY, GET_BUFFER_SIZE)
+            }
+            None => GET_BUFFER_SIZE,
         }
     }
+
+    /// Fold a value size seen for `key` into its prefix's average
+    fn observe(&self, config: Option<&AdaptiveBufferConfig>, key: &[u8], value_len: usize) {
+        let Some(config) = config else {
+            return;
+        };
+        let prefix = &key[..key.len().min(config.prefix_len)];
+        let mut estimates = self.estimates.lock();
+        let tracked = estimates.len();
+        match estimates.get_mut(prefix) {
+            Some(estimate) => *estimate += config.alpha * (value_len as f64 - *estimate),
+            None if tracked < config.max_prefixes => {
+                estimates.insert(prefix.to_vec(), value_len as f64);
+            }
+            None => {}
+        }
+    }
+
+    fn record_allocation(&self, size: usize) {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        self.allocated_bytes
+            .fetch_add(size as u64, Ordering::Relaxed);
+    }
+}
+
+/// Outcome of one GET attempt
+enum Fetched {
+    /// The value (`None` if not modified) and the response it came with
+    Value(Option<Vec<u8>>, GetResponse),
+    /// The receive buffer was smaller than the value, which has this length
+    BufferTooSmall(u64),
 }
 
 /// Allocation tracking for pending requests
@@ -48,26 +252,140 @@ struct PendingAllocation {
     expected_length: u64,
 }
 
+/// Control-plane connection to one server
+#[derive(Clone)]
+struct Connection {
+    addr: String,
+    client: KvCacheServiceClient<Channel>,
+}
+
 /// KV Cache Client
 pub struct KvCacheClient {
     config: ClientConfig,
-    /// gRPC client for control plane
-    grpc_client: Mutex<Option<KvCacheServiceClient<Channel>>>,
+    /// gRPC connection to the current server
+    connection: Mutex<Option<Connection>>,
+    /// Serializes connecting, so concurrent RPCs that lose the server fail over once
+    connect_lock: tokio::sync::Mutex<()>,
     /// RDMA transport
     transport: Arc<RdmaTransport>,
     /// Memory pool for receive buffer
     memory_pool: Arc<RwLock<MemoryPool>>,
     /// Pending allocations for in-flight requests
     pending: Arc<Mutex<HashMap<u64, PendingAllocation>>>,
+    /// Slots bounding the number of pending requests
+    pending_slots: Arc<Semaphore>,
     /// Request ID counter
     request_counter: AtomicU64,
     /// Server information after registration
     server_info: RwLock<Option<ServerInfo>>,
+    /// Receive region reused by every `get_reuse` in `single_buffer_mode`
+    fixed_region: Option<PoolAllocation>,
+    /// GET buffer size estimates and counters
+    buffer_sizing: BufferSizing,
+    /// Per-operation counts and latencies, when `record_metrics` is set
+    metrics: OpRecorders,
+}
+
+/// Recorders behind `KvCacheClient::metrics`
+#[derive(Default)]
+struct OpRecorders {
+    get: OpRecorder,
+    put: OpRecorder,
+    delete: OpRecorder,
+}
+
+/// What the server reported about itself when the client registered
+#[derive(Clone, Debug)]
+pub struct ServerInfo {
+    pub server_id: u32,
+    pub domain_addresses: Vec<DomainAddress>,
+    /// Server crate version
+    pub server_version: String,
+    /// Server uptime at registration
+    pub uptime_seconds: u64,
+    /// Control-plane protocol revision (0 for servers that predate it)
+    pub protocol_version: u32,
+}
+
+/// A value from `get_detailed`, with what the server said about it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GetOutcome {
+    pub value: Vec<u8>,
+    pub source: GetSource,
+    pub version: u64,
+    /// Seconds until the value expires (0 = no expiration)
+    pub remaining_ttl: u64,
+    /// CRC-32C the server verified the value against when it was PUT, if the
+    /// writer sent one
+    pub crc32c: Option<u32>,
+}
+
+/// Error returned by GETs when the key is missing or expired
+///
+/// Other failures are plain errors; test with `err.is::<KeyNotFound>()`.
+#[derive(Debug)]
+pub struct KeyNotFound {
+    pub message: String,
+}
+
+impl std::fmt::Display for KeyNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "GET failed: {}", self.message)
+    }
+}
+
+impl std::error::Error for KeyNotFound {}
+
+/// Error from connecting when a reachable server refused to register the client
+///
+/// Unlike an unreachable server, retrying won't change the answer, so
+/// `connect_with_retry` returns it at once.
+#[derive(Debug)]
+pub struct RegistrationRejected {
+    pub message: String,
 }
 
-struct ServerInfo {
-    server_id: u32,
-    domain_addresses: Vec<DomainAddress>,
+impl std::fmt::Display for RegistrationRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to register with server: {}", self.message)
+    }
+}
+
+impl std::error::Error for RegistrationRejected {}
+
+/// How `get_with_retry` keeps asking for a key that isn't there yet
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// GETs to send in total, including the first
+    pub max_attempts: u32,
+    /// Wait after the first miss; doubles after each further miss
+    pub initial_backoff: Duration,
+    /// Cap on the wait between attempts
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before attempt `attempt + 1`: the doubled backoff, jittered down
+    /// by up to half so retrying clients spread out
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1u32 << attempt.saturating_sub(1).min(31))
+            .min(self.max_backoff);
+        let random = RandomState::new().build_hasher().finish();
+        let fraction = 0.5 + (random >> 11) as f64 / (1u64 << 53) as f64 / 2.0;
+        backoff.mul_f64(fraction)
+    }
 }
 
 impl KvCacheClient {
@@ -80,6 +398,7 @@ impl KvCacheClient {
         let pool_config = MemoryPoolConfig {
             size: config.receive_buffer_size,
             alignment: 4096,
+            ..Default::default()
         };
         let memory_pool = Arc::new(RwLock::new(MemoryPool::new(
             pool_config,
@@ -87,24 +406,154 @@ impl KvCacheClient {
             Some(&transport),
         )?));
 
+        let pending_slots = Arc::new(Semaphore::new(config.max_pending.max(1)));
+        let fixed_region = if config.single_buffer_mode {
+            Some(memory_pool.read().allocate(GET_BUFFER_SIZE)?)
+        } else {
+            None
+        };
+
         Ok(Self {
             config,
-            grpc_client: Mutex::new(None),
+            connection: Mutex::new(None),
+            connect_lock: tokio::sync::Mutex::new(()),
             transport,
             memory_pool,
             pending: Arc::new(Mutex::new(HashMap::new())),
+            pending_slots,
             request_counter: AtomicU64::new(0),
             server_info: RwLock::new(None),
+            fixed_region,
+            buffer_sizing: BufferSizing::default(),
+            metrics: OpRecorders::default(),
         })
     }
 
-    /// Connect to the server
+    /// Connect to the first reachable server, trying `server_addr` then
+    /// `seed_addrs` in order
+    ///
+    /// While no server is reachable, tries again up to `connect_retries`
+    /// times, backing off from `connect_backoff`. A `RegistrationRejected` is
+    /// returned at once.
     pub async fn connect(&self) -> Result<()> {
-        tracing::info!("Connecting to server at {}", self.config.server_addr);
+        let mut retries = 0;
+        loop {
+            match self.connect_once().await {
+                Err(err)
+                    if !err.is::<RegistrationRejected>()
+                        && retries < self.config.connect_retries =>
+                {
+                    let backoff = self
+                        .config
+                        .connect_backoff
+                        .saturating_mul(2u32.saturating_pow(retries));
+                    retries += 1;
+                    tracing::warn!(
+                        "Connect: {:#}; retry {}/{} in {:?}",
+                        err,
+                        retries,
+                        self.config.connect_retries,
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                }
+                result => return result,
+            }
+        }
+    }
 
-        let channel = Channel::from_shared(self.config.server_addr.clone())?
-            .connect()
-            .await?;
+    /// One pass over the seeds, without `connect_retries`
+    async fn connect_once(&self) -> Result<()> {
+        let _connecting = self.connect_lock.lock().await;
+        self.connect_from(0).await
+    }
+
+    /// Like `connect`, retrying for up to `timeout` while no server is reachable
+    ///
+    /// For starting alongside a server that may not be listening yet. Attempts
+    /// back off like a default `RetryPolicy`; once `timeout` has passed the last
+    /// error is returned. A `RegistrationRejected` is returned at once.
+    pub async fn connect_with_retry(&self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let policy = RetryPolicy::default();
+        let mut attempt = 1;
+        loop {
+            match self.connect_once().await {
+                Err(err) if !err.is::<RegistrationRejected>() && Instant::now() < deadline => {
+                    let backoff = policy
+                        .backoff(attempt)
+                        .min(deadline.saturating_duration_since(Instant::now()));
+                    tracing::debug!(
+                        "Connect: no server reachable yet, retrying in {:?}",
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// `server_addr` followed by `seed_addrs`
+    fn seeds(&self) -> Vec<&str> {
+        std::iter::once(&self.config.server_addr)
+            .chain(&self.config.seed_addrs)
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Try every seed in turn, starting at index `start` and wrapping around
+    async fn connect_from(&self, start: usize) -> Result<()> {
+        let seeds = self.seeds();
+        let mut errors = Vec::new();
+        for i in 0..seeds.len() {
+            let addr = seeds[(start + i) % seeds.len()];
+            match self.connect_to(addr).await {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    tracing::warn!("Failed to connect to {}: {:#}", addr, e);
+                    errors.push((addr, e));
+                }
+            }
+        }
+
+        if errors.len() == 1 {
+            return Err(errors.pop().unwrap().1);
+        }
+        // Only give up for good if every server turned the client away
+        if errors.iter().all(|(_, e)| e.is::<RegistrationRejected>()) {
+            return Err(errors.swap_remove(0).1);
+        }
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|(addr, e)| format!("{}: {:#}", addr, e))
+            .collect();
+        Err(anyhow!("No server reachable ({})", errors.join("; ")))
+    }
+
+    /// Move to the next healthy seed after losing `failed`
+    ///
+    /// A no-op if another RPC has already failed over.
+    async fn fail_over(&self, failed: &str) -> Result<()> {
+        let _connecting = self.connect_lock.lock().await;
+        if self.current_server().as_deref() != Some(failed) {
+            return Ok(());
+        }
+
+        let seeds = self.seeds();
+        let next = seeds
+            .iter()
+            .position(|&addr| addr == failed)
+            .map_or(0, |i| i + 1);
+        self.connect_from(next).await
+    }
+
+    /// Connect and register with one server
+    async fn connect_to(&self, addr: &str) -> Result<()> {
+        tracing::info!("Connecting to server at {}", addr);
+
+        let channel = open_channel(addr).await?;
 
         // Configure gRPC client to handle large messages (up to 128MB)
         let mut client = KvCacheServiceClient::new(channel)
@@ -119,58 +568,278 @@ impl KvCacheClient {
             .map(|a| a.0.clone())
             .collect();
 
-        let response = client
+        let response = match client
             .register_client(RegisterClientRequest {
                 client_id: self.config.client_id,
                 domain_addresses,
                 receive_buffer_size: self.config.receive_buffer_size as u64,
+                priority: self.config.priority as u32,
             })
-            .await?
-            .into_inner();
+            .await
+        {
+            Ok(response) => response.into_inner(),
+            Err(status) if status.code() == Code::Unavailable => return Err(status.into()),
+            // Answered, but not as a KV server willing to take the client
+            Err(status) => {
+                return Err(RegistrationRejected {
+                    message: format!("{:?}: {}", status.code(), status.message()),
+                }
+                .into())
+            }
+        };
 
         if !response.success {
-            return Err(anyhow!("Failed to register with server"));
+            return Err(RegistrationRejected {
+                message: "server reported failure".to_string(),
+            }
+            .into());
         }
 
+        let server_domains: Vec<DomainAddress> = response
+            .server_domain_addresses
+            .into_iter()
+            .map(DomainAddress::new)
+            .collect();
         tracing::info!(
-            "Registered with server {}, got {} domain addresses",
+            "Registered with server {}, domains=[{}]",
             response.server_id,
-            response.server_domain_addresses.len()
+            server_domains
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
         );
 
         *self.server_info.write() = Some(ServerInfo {
             server_id: response.server_id,
-            domain_addresses: response
-                .server_domain_addresses
-                .into_iter()
-                .map(DomainAddress::new)
-                .collect(),
+            domain_addresses: server_domains,
+            server_version: response.server_version,
+            uptime_seconds: response.uptime_seconds,
+            protocol_version: response.protocol_version,
         });
 
-        *self.grpc_client.lock() = Some(client);
+        *self.connection.lock() = Some(Connection {
+            addr: addr.to_string(),
+            client,
+        });
 
         Ok(())
     }
 
+    /// Address of the server the client is currently talking to
+    pub fn current_server(&self) -> Option<String> {
+        self.connection.lock().as_ref().map(|c| c.addr.clone())
+    }
+
+    /// Run an RPC against the current server
+    ///
+    /// If the server can't be reached, fails over to the next healthy seed
+    /// (re-registering there) and retries once.
+    async fn call<T, F, Fut>(&self, mut rpc: F) -> Result<T>
+    where
+        F: FnMut(KvCacheServiceClient<Channel>) -> Fut,
+        Fut: Future<Output = Result<tonic::Response<T>, Status>>,
+    {
+        let connection = self
+            .connection
+            .lock()
+            .clone()
+            .ok_or_else(|| anyhow!("Not connected"))?;
+
+        match rpc(connection.client).await {
+            Ok(response) => Ok(response.into_inner()),
+            Err(status) if is_connection_loss(&status) => {
+                tracing::warn!("Lost server {}: {}", connection.addr, status);
+                self.fail_over(&connection.addr).await?;
+                let client = self
+                    .connection
+                    .lock()
+                    .as_ref()
+                    .map(|c| c.client.clone())
+                    .ok_or_else(|| anyhow!("Not connected"))?;
+                Ok(rpc(client).await?.into_inner())
+            }
+            Err(status) => Err(status.into()),
+        }
+    }
+
+    /// Server details from the last successful `connect`
+    pub fn server_info(&self) -> Option<ServerInfo> {
+        self.server_info.read().clone()
+    }
+
     /// Get a value from the server
     ///
     /// The server will RDMA write the value directly to our receive buffer.
     /// Returns the value data.
     pub async fn get(&self, key: &[u8]) -> Result<Vec<u8>> {
-        tracing::debug!("GET: Starting request for key (len={})", key.len());
+        let (value, _response) = self.fetch(key, None).await?;
+        value.ok_or_else(|| anyhow!("GET failed: unexpected not-modified response"))
+    }
 
-        let mut client = self
-            .grpc_client
-            .lock()
-            .clone()
-            .ok_or_else(|| anyhow!("Not connected"))?;
+    /// Get a value along with where it came from, its version and its
+    /// remaining TTL, e.g. to decide whether to refresh it
+    pub async fn get_detailed(&self, key: &[u8]) -> Result<GetOutcome> {
+        let (value, response) = self.fetch(key, None).await?;
+        Ok(GetOutcome {
+            value: value.ok_or_else(|| anyhow!("GET failed: unexpected not-modified response"))?,
+            source: response.source(),
+            version: response.version,
+            remaining_ttl: response.remaining_ttl_seconds,
+            crc32c: response.crc32c,
+        })
+    }
 
-        let request_id = self.request_counter.fetch_add(1, Ordering::Relaxed);
+    /// Get a value only if the server's copy is newer than `known_version`
+    ///
+    /// Returns `None` when `known_version` is current; the server then skips the
+    /// RDMA write entirely. Otherwise returns the value and its version. Versions
+    /// start at 1, so `known_version = 0` always fetches.
+    pub async fn get_if_newer(
+        &self,
+        key: &[u8],
+        known_version: u64,
+    ) -> Result<Option<(Vec<u8>, u64)>> {
+        let (value, response) = self.fetch(key, Some(known_version)).await?;
+        Ok(value.map(|value| (value, response.version)))
+    }
+
+    /// Get a value, retrying misses per `policy`
+    ///
+    /// For reading your own writes through a replica that may not have the
+    /// key yet. Only misses are retried; any other error is returned at once,
+    /// and the last miss is returned once the attempts run out.
+    pub async fn get_with_retry(&self, key: &[u8], policy: &RetryPolicy) -> Result<Vec<u8>> {
+        let mut attempt = 1;
+        loop {
+            match self.get(key).await {
+                Err(err) if err.is::<KeyNotFound>() && attempt < policy.max_attempts => {
+                    let backoff = policy.backoff(attempt);
+                    tracing::debug!(
+                        "GET: miss on attempt {}, retrying in {:?}",
+                        attempt,
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Shared GET path, returning the value with the server's response; the
+    /// value is `None` if the server reported not-modified
+    #[tracing::instrument(name = "kv.client.get", skip_all, fields(key_len = key.len()))]
+    async fn fetch(
+        &self,
+        key: &[u8],
+        if_version_gt: Option<u64>,
+    ) -> Result<(Option<Vec<u8>>, GetResponse)> {
+        self.timed(&self.metrics.get, self.fetch_resizing(key, if_version_gt))
+            .await
+    }
+
+    /// Send GETs until the receive buffer is large enough for the value
+    async fn fetch_resizing(
+        &self,
+        key: &[u8],
+        if_version_gt: Option<u64>,
+    ) -> Result<(Option<Vec<u8>>, GetResponse)> {
+        tracing::debug!(
+            "GET: Starting request for key {}",
+            self.config.log_keys.display(key)
+        );
+
+        // Wait for an in-flight slot before taking any pool space
+        let _slot = self
+            .pending_slots
+            .acquire()
+            .await
+            .map_err(|_| anyhow!("Client is shutting down"))?;
+
+        let sizing = self.config.adaptive_buffer.as_ref();
+        // A miss falls through to a regular GET, which may still be loaded
+        let exact_size = match self.config.size_gets_first {
+            true => self.value_len(key).await?,
+            false => None,
+        };
+        let mut buffer_size = match exact_size {
+            Some(value_len) => (value_len as usize).max(1),
+            None => self.buffer_sizing.buffer_size(sizing, key),
+        };
+        loop {
+            match self.fetch_once(key, if_version_gt, buffer_size).await? {
+                Fetched::Value(value, response) => {
+                    if let Some(value) = &value {
+                        self.buffer_sizing.observe(sizing, key, value.len());
+                    }
+                    return Ok((value, response));
+                }
+                Fetched::BufferTooSmall(value_len) => {
+                    let value_len = value_len as usize;
+                    if value_len <= buffer_size {
+                        return Err(anyhow!(
+                            "GET failed: server reported a {}-byte value too large for a {}-byte buffer",
+                            value_len,
+                            buffer_size
+                        ));
+                    }
+                    let available = self.memory_pool.read().stats().available;
+                    if value_len > available {
+                        return Err(anyhow!(
+                            "GET failed: value of {} bytes exceeds the {} bytes free in the receive pool",
+                            value_len,
+                            available
+                        ));
+                    }
+                    tracing::debug!(
+                        "GET: {}-byte buffer too small, retrying with {}",
+                        buffer_size,
+                        value_len
+                    );
+                    self.buffer_sizing.observe(sizing, key, value_len);
+                    self.buffer_sizing.retries.fetch_add(1, Ordering::Relaxed);
+                    buffer_size = value_len;
+                }
+            }
+        }
+    }
 
-        // Allocate receive buffer
-        // For simplicity, we allocate a reasonable max size
-        // In production, you might want to query the value size first
-        let max_value_size = 1024 * 1024; // 1MB max value
+    /// Run an operation, recording it in `recorder` if `record_metrics` is set
+    async fn timed<T>(
+        &self,
+        recorder: &OpRecorder,
+        op: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        if !self.config.record_metrics {
+            return op.await;
+        }
+        let start = Instant::now();
+        let result = op.await;
+        recorder.record(start.elapsed(), result.is_ok());
+        result
+    }
+
+    /// Counts and latencies of the GETs, PUTs and DELETEs made so far
+    ///
+    /// All zero unless `record_metrics` is set.
+    pub fn metrics(&self) -> ClientMetrics {
+        ClientMetrics {
+            get: self.metrics.get.snapshot(),
+            put: self.metrics.put.snapshot(),
+            delete: self.metrics.delete.snapshot(),
+        }
+    }
+
+    /// One GET attempt into a receive buffer of `max_value_size` bytes
+    async fn fetch_once(
+        &self,
+        key: &[u8],
+        if_version_gt: Option<u64>,
+        max_value_size: usize,
+    ) -> Result<Fetched> {
+        let request_id = self.request_counter.fetch_add(1, Ordering::Relaxed);
 
         tracing::debug!("GET: Allocating receive buffer, size={}", max_value_size);
 
@@ -178,6 +847,7 @@ impl KvCacheClient {
             let pool = self.memory_pool.read();
             pool.allocate(max_value_size)?
         };
+        self.buffer_sizing.record_allocation(max_value_size);
 
         tracing::debug!("GET: Allocated buffer at offset={}", allocation.offset);
 
@@ -194,8 +864,11 @@ impl KvCacheClient {
             location
         };
 
-        tracing::debug!("GET: Created response location, ptr={:#x}, offset={}",
-            response_location.mr_descriptor.ptr, response_location.offset);
+        tracing::debug!(
+            "GET: Created response location, ptr={:#x}, offset={}",
+            response_location.mr_descriptor.ptr,
+            response_location.offset
+        );
 
         // Track pending allocation
         self.pending.lock().insert(
@@ -211,17 +884,41 @@ impl KvCacheClient {
 
         tracing::debug!("GET: Sending gRPC request, request_id={}", request_id);
 
-        let response = client
-            .get(GetRequest {
-                key: key.to_vec(),
-                response_location: Some(pb_response_location),
-                request_id,
+        let get = GetRequest {
+            key: key.to_vec(),
+            response_location: Some(pb_response_location),
+            request_id,
+            if_version_gt,
+            client_id: self.config.client_id,
+            ..Default::default()
+        };
+        let response = self
+            .call(|mut client| {
+                #[allow(unused_mut)] // only written with the otel feature
+                let mut request = tonic::Request::new(get.clone());
+                #[cfg(feature = "otel")]
+                crate::telemetry::inject_context(request.metadata_mut());
+                async move { client.get(request).await }
             })
-            .await?
-            .into_inner();
+            .await;
+        let mut response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                if let Some(pending) = self.pending.lock().remove(&request_id) {
+                    self.memory_pool.write().deallocate(&pending.allocation);
+                }
+                return match buffer_too_small(&e) {
+                    Some(value_len) => Ok(Fetched::BufferTooSmall(value_len)),
+                    None => Err(e),
+                };
+            }
+        };
 
-        tracing::debug!("GET: Received gRPC response, success={}, length={}",
-            response.success, response.value_length);
+        tracing::debug!(
+            "GET: Received gRPC response, success={}, length={}",
+            response.success,
+            response.value_length
+        );
 
         // Get the pending allocation
         let pending = self
@@ -233,9 +930,27 @@ impl KvCacheClient {
         if !response.success {
             // Deallocate the buffer
             self.memory_pool.write().deallocate(&pending.allocation);
+            if response.not_found {
+                return Err(KeyNotFound {
+                    message: response.error_message,
+                }
+                .into());
+            }
             return Err(anyhow!("GET failed: {}", response.error_message));
         }
 
+        if response.not_modified {
+            self.memory_pool.write().deallocate(&pending.allocation);
+            tracing::debug!("GET: Not modified since version {:?}", if_version_gt);
+            return Ok(Fetched::Value(None, response));
+        }
+
+        if let Some(value) = response.inline_value.take() {
+            self.memory_pool.write().deallocate(&pending.allocation);
+            tracing::debug!("GET: Value returned inline, length={}", value.len());
+            return Ok(Fetched::Value(Some(value), response));
+        }
+
         tracing::debug!("GET: Reading value from receive buffer");
         tracing::debug!("GET: About to acquire read lock on memory pool");
 
@@ -243,8 +958,11 @@ impl KvCacheClient {
         let value = {
             tracing::debug!("GET: Acquiring read lock...");
             let pool = self.memory_pool.read();
-            tracing::debug!("GET: Read lock acquired, reading at offset={}, len={}",
-                pending.allocation.offset, response.value_length);
+            tracing::debug!(
+                "GET: Read lock acquired, reading at offset={}, len={}",
+                pending.allocation.offset,
+                response.value_length
+            );
 
             let slice = pool.read(pending.allocation.offset, response.value_length as usize)?;
             tracing::debug!("GET: Slice obtained, converting to vec...");
@@ -258,20 +976,331 @@ impl KvCacheClient {
         self.memory_pool.write().deallocate(&pending.allocation);
 
         tracing::info!("GET: Successfully retrieved value, length={}", value.len());
-        Ok(value)
+        Ok(Fetched::Value(Some(value), response))
+    }
+
+    /// GET receive buffers reserved so far, for checking `adaptive_buffer`
+    pub fn get_buffer_stats(&self) -> GetBufferStats {
+        GetBufferStats {
+            allocations: self.buffer_sizing.allocations.load(Ordering::Relaxed),
+            allocated_bytes: self.buffer_sizing.allocated_bytes.load(Ordering::Relaxed),
+            retries: self.buffer_sizing.retries.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Get a value into the fixed receive region (`single_buffer_mode`)
+    ///
+    /// Returns a view of the region that holds the pool's read lock; drop it
+    /// before the next GET. Not safe for concurrent GETs: every call transfers
+    /// into the same region, so overlapping calls overwrite each other's values.
+    pub async fn get_reuse(&self, key: &[u8]) -> Result<PoolReadGuard<'_>> {
+        let region = self
+            .fixed_region
+            .as_ref()
+            .ok_or_else(|| anyhow!("get_reuse requires single_buffer_mode"))?;
+
+        let response_location = ValueLocation::new(
+            self.config.client_id,
+            self.memory_pool.read().descriptor().clone(),
+            region.offset as u64,
+            GET_BUFFER_SIZE as u64,
+        );
+
+        let request_id = self.request_counter.fetch_add(1, Ordering::Relaxed);
+        let request = GetRequest {
+            key: key.to_vec(),
+            response_location: Some((&response_location).into()),
+            request_id,
+            client_id: self.config.client_id,
+            ..Default::default()
+        };
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.get(request).await }
+            })
+            .await?;
+
+        if !response.success {
+            return Err(anyhow!("GET failed: {}", response.error_message));
+        }
+
+        if let Some(value) = &response.inline_value {
+            // Keep the returned view pointing at the region
+            self.memory_pool.write().write(region.offset, value)?;
+        }
+
+        MemoryPool::read_guard(
+            &self.memory_pool,
+            region.offset,
+            response.value_length as usize,
+        )
+    }
+
+    /// Make keys resident on the server without transferring their values
+    ///
+    /// Misses go through the server's read-through loader, if it has one.
+    /// Returns how many of the keys are now resident.
+    pub async fn warm<K: AsRef<[u8]>>(&self, keys: &[K]) -> Result<usize> {
+        let mut resident = 0;
+        for key in keys {
+            let request_id = self.request_counter.fetch_add(1, Ordering::Relaxed);
+            let request = GetRequest {
+                key: key.as_ref().to_vec(),
+                request_id,
+                warm_only: true,
+                client_id: self.config.client_id,
+                ..Default::default()
+            };
+            let response = self
+                .call(|mut client| {
+                    let request = request.clone();
+                    async move { client.get(request).await }
+                })
+                .await?;
+            if response.success {
+                resident += 1;
+            } else {
+                tracing::debug!("WARM: {}", response.error_message);
+            }
+        }
+
+        Ok(resident)
+    }
+
+    /// Allocate and register a buffer the server can RDMA write into or read from
+    pub fn register_buffer(&self, len: usize) -> Result<RegisteredBuffer> {
+        let mut data = vec![0u8; len].into_boxed_slice();
+        let (handle, descriptor) = self
+            .transport
+            .register_memory(data.as_mut_ptr(), data.len())?;
+        Ok(RegisteredBuffer {
+            data,
+            descriptor,
+            handle,
+            transport: self.transport.clone(),
+        })
+    }
+
+    /// Get several values, each written straight to its offset in `buf`
+    ///
+    /// A value may use the space up to the next offset (or the end of `buf`);
+    /// if any value doesn't fit, the call fails. Returns each key's value length,
+    /// `None` for misses.
+    pub async fn get_many_into<K: AsRef<[u8]>>(
+        &self,
+        keys: &[K],
+        buf: &mut RegisteredBuffer,
+        offsets: &[usize],
+    ) -> Result<Vec<Option<usize>>> {
+        if keys.len() != offsets.len() {
+            return Err(anyhow!(
+                "GET_MANY: {} keys but {} offsets",
+                keys.len(),
+                offsets.len()
+            ));
+        }
+
+        let mut sorted = offsets.to_vec();
+        sorted.sort_unstable();
+        if let Some(pair) = sorted.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(anyhow!("GET_MANY: offset {} given twice", pair[0]));
+        }
+        if let Some(&last) = sorted.last().filter(|&&last| last > buf.len()) {
+            return Err(anyhow!(
+                "GET_MANY: offset {} is past the {}-byte buffer",
+                last,
+                buf.len()
+            ));
+        }
+
+        let items = keys
+            .iter()
+            .zip(offsets)
+            .map(|(key, &offset)| {
+                let next = sorted.partition_point(|&other| other <= offset);
+                let end = sorted.get(next).copied().unwrap_or(buf.len());
+                GetManyItem {
+                    key: key.as_ref().to_vec(),
+                    offset: offset as u64,
+                    capacity: (end - offset) as u64,
+                }
+            })
+            .collect();
+
+        let request_id = self.request_counter.fetch_add(1, Ordering::Relaxed);
+        let request = GetManyRequest {
+            items,
+            buffer: Some((&buf.descriptor).into()),
+            request_id,
+            client_id: self.config.client_id,
+        };
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.get_many(request).await }
+            })
+            .await?;
+
+        if !response.success {
+            return Err(anyhow!("GET_MANY failed: {}", response.error_message));
+        }
+
+        Ok(response
+            .results
+            .into_iter()
+            .map(|result| result.found.then_some(result.value_length as usize))
+            .collect())
+    }
+
+    /// Get several values with one GetMany round trip, `None` for misses
+    ///
+    /// Each key gets a receive region of its own, sized like a GET's, and the
+    /// server RDMA writes them all in one batch. Keys whose regions don't fit
+    /// the receive pool together go in further batches. If a value is larger
+    /// than its region, that batch falls back to one GET per key.
+    pub async fn mget(&self, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>> {
+        let sizing = self.config.adaptive_buffer.as_ref();
+        let mut values = Vec::with_capacity(keys.len());
+        let mut next = 0;
+        while next < keys.len() {
+            let mut batch = Vec::new();
+            while let Some(&key) = keys.get(next) {
+                let size = self.buffer_sizing.buffer_size(sizing, key);
+                let allocation = match self.memory_pool.read().allocate(size) {
+                    Ok(allocation) => allocation,
+                    Err(_) if !batch.is_empty() => break,
+                    Err(e) => return Err(e),
+                };
+                self.buffer_sizing.record_allocation(size);
+                batch.push((key, allocation));
+                next += 1;
+            }
+            values.extend(self.mget_batch(batch).await?);
+        }
+        Ok(values)
+    }
+
+    /// One GetMany into the regions allocated for `batch`, freeing them after
+    async fn mget_batch(
+        &self,
+        batch: Vec<(&[u8], PoolAllocation)>,
+    ) -> Result<Vec<Option<Vec<u8>>>> {
+        let request = GetManyRequest {
+            items: batch
+                .iter()
+                .map(|(key, allocation)| GetManyItem {
+                    key: key.to_vec(),
+                    offset: allocation.offset as u64,
+                    capacity: allocation.size as u64,
+                })
+                .collect(),
+            buffer: Some(self.memory_pool.read().descriptor().into()),
+            request_id: self.request_counter.fetch_add(1, Ordering::Relaxed),
+            client_id: self.config.client_id,
+        };
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.get_many(request).await }
+            })
+            .await;
+
+        let sizing = self.config.adaptive_buffer.as_ref();
+        let received = response.and_then(|response| {
+            if !response.success {
+                tracing::debug!(
+                    "MGET: {}; falling back to single GETs",
+                    response.error_message
+                );
+                return Ok(None);
+            }
+            let pool = self.memory_pool.read();
+            batch
+                .iter()
+                .zip(&response.results)
+                .map(|((key, allocation), result)| {
+                    if !result.found {
+                        return Ok(None);
+                    }
+                    let len = result.value_length as usize;
+                    self.buffer_sizing.observe(sizing, key, len);
+                    Ok(Some(pool.read(allocation.offset, len)?.to_vec()))
+                })
+                .collect::<Result<Vec<_>>>()
+                .map(Some)
+        });
+        {
+            let pool = self.memory_pool.write();
+            for (_, allocation) in &batch {
+                pool.deallocate(allocation);
+            }
+        }
+
+        if let Some(values) = received? {
+            return Ok(values);
+        }
+        let mut values = Vec::with_capacity(batch.len());
+        for (key, _) in batch {
+            values.push(match self.get(key).await {
+                Ok(value) => Some(value),
+                Err(e) if e.is::<KeyNotFound>() => None,
+                Err(e) => return Err(e),
+            });
+        }
+        Ok(values)
     }
 
     /// Put a value into the server's cache
     ///
     /// Supports values up to 64MB sent inline via gRPC.
-    /// For larger values, consider implementing RDMA-based PUT.
+    /// For larger values, use `put_from_buffer`.
     pub async fn put(&self, key: &[u8], value: &[u8], ttl_seconds: u64) -> Result<()> {
-        let mut client = self
-            .grpc_client
-            .lock()
-            .clone()
-            .ok_or_else(|| anyhow!("Not connected"))?;
+        self.put_ms(key, value, ttl_seconds.saturating_mul(1000))
+            .await
+    }
+
+    /// Like `put`, with the TTL in milliseconds (0 = no expiration)
+    pub async fn put_ms(&self, key: &[u8], value: &[u8], ttl_millis: u64) -> Result<()> {
+        self.put_inline(key, value, ttl_millis, false)
+            .await
+            .map(|_| ())
+    }
 
+    /// Put a value only if the key has no live value (SETNX)
+    ///
+    /// Returns whether it was stored. Racing callers see exactly one `true`,
+    /// which makes this usable as a lock: hold it for `ttl_seconds`, or
+    /// release it with `delete`.
+    pub async fn put_if_absent(&self, key: &[u8], value: &[u8], ttl_seconds: u64) -> Result<bool> {
+        let response = self
+            .put_inline(key, value, ttl_seconds.saturating_mul(1000), true)
+            .await?;
+        Ok(!response.key_existed)
+    }
+
+    /// Send a PUT with the value inline
+    async fn put_inline(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        ttl_millis: u64,
+        put_if_absent: bool,
+    ) -> Result<PutResponse> {
+        self.timed(
+            &self.metrics.put,
+            self.send_inline_put(key, value, ttl_millis, put_if_absent),
+        )
+        .await
+    }
+
+    async fn send_inline_put(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        ttl_millis: u64,
+        put_if_absent: bool,
+    ) -> Result<PutResponse> {
         // Check maximum value size (64MB limit for gRPC inline)
         const MAX_VALUE_SIZE: usize = 64 * 1024 * 1024; // 64MB
         if value.len() > MAX_VALUE_SIZE {
@@ -285,59 +1314,488 @@ impl KvCacheClient {
         // Send value inline via gRPC
         let value_source = crate::pb::put_request::ValueSource::InlineValue(value.to_vec());
 
-        let response = client
-            .put(PutRequest {
-                key: key.to_vec(),
-                value_source: Some(value_source),
-                ttl_seconds,
+        let request = PutRequest {
+            key: key.to_vec(),
+            value_source: Some(value_source),
+            ttl_millis,
+            put_if_absent,
+            ..Default::default()
+        };
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.put(request).await }
             })
-            .await?
-            .into_inner();
+            .await?;
 
         if !response.success {
             return Err(anyhow!("PUT failed: {}", response.error_message));
         }
 
-        Ok(())
+        Ok(response)
+    }
+
+    /// Put the first `len` bytes of `buf` without copying them into the RPC
+    ///
+    /// The server RDMA reads the value from `buf`, which must not be modified
+    /// until this returns. Only the mock transport implements RDMA read so
+    /// far; a server on the fabric transport answers `UNIMPLEMENTED`.
+    pub async fn put_from_buffer(
+        &self,
+        key: &[u8],
+        buf: &RegisteredBuffer,
+        len: usize,
+        ttl_seconds: u64,
+    ) -> Result<()> {
+        if len > buf.len() {
+            return Err(anyhow!(
+                "PUT length {} exceeds the {}-byte buffer",
+                len,
+                buf.len()
+            ));
+        }
+
+        let location =
+            ValueLocation::new(self.config.client_id, buf.descriptor.clone(), 0, len as u64);
+        let value_source = crate::pb::put_request::ValueSource::RdmaLocation((&location).into());
+
+        let request = PutRequest {
+            key: key.to_vec(),
+            value_source: Some(value_source),
+            ttl_seconds,
+            ..Default::default()
+        };
+        let put = async {
+            let response = self
+                .call(|mut client| {
+                    let request = request.clone();
+                    async move { client.put(request).await }
+                })
+                .await?;
+
+            if !response.success {
+                return Err(anyhow!("PUT failed: {}", response.error_message));
+            }
+            Ok(())
+        };
+        self.timed(&self.metrics.put, put).await
     }
 
     /// Delete a value from the server's cache
     pub async fn delete(&self, key: &[u8]) -> Result<bool> {
-        let mut client = self
-            .grpc_client
-            .lock()
-            .clone()
-            .ok_or_else(|| anyhow!("Not connected"))?;
+        let delete = self.call(|mut client| {
+            let request = DeleteRequest {
+                key: key.to_vec(),
+                ..Default::default()
+            };
+            async move { client.delete(request).await }
+        });
+        let response = self.timed(&self.metrics.delete, delete).await?;
+
+        Ok(response.key_existed)
+    }
 
-        let response = client
-            .delete(DeleteRequest { key: key.to_vec() })
-            .await?
-            .into_inner();
+    /// Move `src`'s value to `dst`, replacing any value there
+    ///
+    /// The value isn't copied and keeps its TTL. Returns false if `src` had
+    /// no live value.
+    pub async fn rename(&self, src: &[u8], dst: &[u8]) -> Result<bool> {
+        let response = self
+            .call(|mut client| {
+                let request = RenameRequest {
+                    src_key: src.to_vec(),
+                    dst_key: dst.to_vec(),
+                };
+                async move { client.rename(request).await }
+            })
+            .await?;
 
         Ok(response.key_existed)
     }
 
-    /// Send a heartbeat to the server
-    pub async fn heartbeat(&self) -> Result<bool> {
-        let mut client = self
-            .grpc_client
-            .lock()
-            .clone()
-            .ok_or_else(|| anyhow!("Not connected"))?;
+    /// Reset `key`'s TTL to `ttl_seconds` from now (0 = no expiration)
+    ///
+    /// Returns false if `key` had no live value.
+    pub async fn touch(&self, key: &[u8], ttl_seconds: u64) -> Result<bool> {
+        let response = self
+            .call(|mut client| {
+                let request = TouchRequest {
+                    key: key.to_vec(),
+                    ttl_seconds,
+                };
+                async move { client.touch(request).await }
+            })
+            .await?;
 
-        let response = client
-            .heartbeat(HeartbeatRequest {
-                client_id: self.config.client_id,
+        Ok(response.key_existed)
+    }
+
+    /// Length of `key`'s value, or `None` if it has none, without fetching it
+    ///
+    /// Nothing is allocated from either pool and misses don't go to the
+    /// server's loader. With server-side interceptors this is the stored
+    /// (transformed) length.
+    pub async fn value_len(&self, key: &[u8]) -> Result<Option<u64>> {
+        let response = self
+            .call(|mut client| {
+                let request = GetSizeRequest { key: key.to_vec() };
+                async move { client.get_size(request).await }
             })
-            .await?
-            .into_inner();
+            .await?;
+
+        Ok(response.found.then_some(response.value_length))
+    }
+
+    /// Whether `key` has a live value, checked without fetching it
+    ///
+    /// Like `value_len`, nothing is allocated from either pool and misses
+    /// don't go to the server's loader.
+    pub async fn exists(&self, key: &[u8]) -> Result<bool> {
+        let response = self
+            .call(|mut client| {
+                let request = ExistsRequest { key: key.to_vec() };
+                async move { client.exists(request).await }
+            })
+            .await?;
+
+        Ok(response.exists)
+    }
+
+    /// Store a copy of `src`'s value under `dst`
+    ///
+    /// The copy gets `ttl_seconds` if given (0 = no expiration), otherwise the
+    /// source's remaining TTL. Returns false if `src` had no live value.
+    pub async fn copy(&self, src: &[u8], dst: &[u8], ttl_seconds: Option<u64>) -> Result<bool> {
+        let response = self
+            .call(|mut client| {
+                let request = CopyRequest {
+                    src_key: src.to_vec(),
+                    dst_key: dst.to_vec(),
+                    ttl_seconds,
+                };
+                async move { client.copy(request).await }
+            })
+            .await?;
+
+        if !response.success {
+            return Err(anyhow!("COPY failed: {}", response.error_message));
+        }
+
+        Ok(response.key_existed)
+    }
+
+    /// Store `new` under `key` only if its current value is `expected`
+    ///
+    /// `None` expects the key to have no live value. The server compares and
+    /// swaps atomically, so this can build locks and counters shared between
+    /// clients. A swapped-in value keeps the old one's remaining TTL; one
+    /// stored under an absent key doesn't expire. Returns whether it was stored.
+    pub async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool> {
+        let response = self
+            .call(|mut client| {
+                let request = CompareAndSwapRequest {
+                    key: key.to_vec(),
+                    expected: expected.map(<[u8]>::to_vec),
+                    value: new.to_vec(),
+                };
+                async move { client.compare_and_swap(request).await }
+            })
+            .await?;
+
+        if !response.success {
+            return Err(anyhow!("CAS failed: {}", response.error_message));
+        }
+
+        Ok(response.swapped)
+    }
+
+    /// Put `(key, value, ttl_seconds)` entries in one BatchPut request
+    ///
+    /// Unlike `batch_put`, every entry is tried: the result has one outcome
+    /// per entry, in order, so a value that didn't fit (for example) doesn't
+    /// stop the ones after it. The outer error is for the request as a whole.
+    pub async fn mset(&self, entries: &[(&[u8], &[u8], u64)]) -> Result<Vec<Result<()>>> {
+        let request = BatchPutRequest {
+            entries: entries
+                .iter()
+                .map(|&(key, value, ttl_seconds)| PutRequest {
+                    key: key.to_vec(),
+                    value_source: Some(crate::pb::put_request::ValueSource::InlineValue(
+                        value.to_vec(),
+                    )),
+                    ttl_seconds,
+                    ..Default::default()
+                })
+                .collect(),
+            continue_on_error: true,
+        };
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.batch_put(request).await }
+            })
+            .await?;
+
+        if response.results.len() != entries.len() {
+            return Err(anyhow!(
+                "MSET: {} results for {} entries: {}",
+                response.results.len(),
+                entries.len(),
+                response.error_message
+            ));
+        }
+        Ok(response
+            .results
+            .into_iter()
+            .map(|result| match result.stored {
+                true => Ok(()),
+                false => Err(anyhow!("PUT failed: {}", result.error_message)),
+            })
+            .collect())
+    }
+
+    /// Delete every key starting with `prefix`; returns how many were removed
+    pub async fn delete_prefix(&self, prefix: &[u8]) -> Result<u64> {
+        let response = self
+            .call(|mut client| {
+                let request = DeletePrefixRequest {
+                    prefix: prefix.to_vec(),
+                };
+                async move { client.delete_prefix(request).await }
+            })
+            .await?;
+
+        Ok(response.deleted)
+    }
+
+    /// Put many values in one RPC
+    ///
+    /// Returns the number stored; on failure, entries before the failing one are kept.
+    pub async fn batch_put(&self, entries: &[KvEntry]) -> Result<usize> {
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|entry| PutRequest {
+                key: entry.key.clone(),
+                value_source: Some(crate::pb::put_request::ValueSource::InlineValue(
+                    entry.value.clone(),
+                )),
+                ttl_seconds: entry.ttl_seconds,
+                ..Default::default()
+            })
+            .collect();
+
+        let response = self
+            .call(|mut client| {
+                let request = BatchPutRequest {
+                    entries: entries.clone(),
+                    ..Default::default()
+                };
+                async move { client.batch_put(request).await }
+            })
+            .await?;
+
+        if !response.success {
+            return Err(anyhow!(
+                "BATCH_PUT failed after {} entries: {}",
+                response.stored,
+                response.error_message
+            ));
+        }
+
+        Ok(response.stored as usize)
+    }
+
+    /// Store a stream of entries with one streaming RPC
+    ///
+    /// Unlike `batch_put`, a failed entry (e.g. once the server's pool is full)
+    /// doesn't stop the rest; the response counts both. Not retried on
+    /// failover, since the stream can't be replayed.
+    pub async fn import<S>(&self, entries: S) -> Result<ImportResponse>
+    where
+        S: Stream<Item = KvEntry> + Send + 'static,
+    {
+        let records = entries.map(|entry| ImportRecord {
+            key: entry.key,
+            value: entry.value,
+            ttl_seconds: entry.ttl_seconds,
+        });
+        let mut records = Some(records);
+        self.call(|mut client| {
+            let records = records.take();
+            async move {
+                match records {
+                    Some(records) => client.import(records).await,
+                    None => Err(Status::unavailable("Import stream was already sent")),
+                }
+            }
+        })
+        .await
+    }
+
+    /// Stream every live entry on the server
+    ///
+    /// Small values arrive inline; larger ones are fetched with a regular RDMA GET
+    /// as the stream is polled, so the receive buffer only holds one at a time.
+    pub async fn dump(&self) -> Result<impl Stream<Item = Result<KvEntry>> + '_> {
+        let entries = self
+            .call(|mut client| async move { client.dump(DumpRequest::default()).await })
+            .await?;
+
+        Ok(entries
+            .map_err(anyhow::Error::from)
+            .and_then(move |entry| async move {
+                let value = match entry.inline_value {
+                    Some(value) => value,
+                    None => self.get(&entry.key).await?,
+                };
+                Ok(KvEntry {
+                    key: entry.key,
+                    value,
+                    ttl_seconds: entry.ttl_seconds,
+                })
+            }))
+    }
+
+    /// Tail the server's keyspace events, starting from this call
+    ///
+    /// A watcher that falls too far behind loses the oldest events; the next
+    /// event it receives says how many in `missed`.
+    pub async fn watch_events(&self) -> Result<impl Stream<Item = Result<KeyspaceEvent>>> {
+        let events = self
+            .call(|mut client| async move { client.watch_events(WatchEventsRequest {}).await })
+            .await?;
+
+        Ok(events.map_err(anyhow::Error::from))
+    }
+
+    /// Bulk load entries from a dump (typically another server's) into this server
+    ///
+    /// Returns the number of entries loaded.
+    pub async fn load_from_dump<S>(&self, dump: S) -> Result<usize>
+    where
+        S: Stream<Item = Result<KvEntry>>,
+    {
+        let mut dump = std::pin::pin!(dump.try_chunks(LOAD_BATCH_SIZE));
+        let mut loaded = 0;
+        while let Some(batch) = dump.next().await {
+            let batch = batch.map_err(|e| e.1)?;
+            loaded += self.batch_put(&batch).await?;
+        }
+        Ok(loaded)
+    }
+
+    /// Wait until every write this server has acknowledged is durable
+    ///
+    /// A no-op on servers without a WAL. Returns the durable WAL sequence.
+    pub async fn flush(&self) -> Result<u64> {
+        let response = self
+            .call(|mut client| async move { client.flush(FlushRequest {}).await })
+            .await?;
+        if !response.success {
+            return Err(anyhow!("FLUSH failed: {}", response.error_message));
+        }
+
+        Ok(response.durable_sequence)
+    }
+
+    /// Fetch the server's counters
+    pub async fn stats(&self) -> Result<StatsResponse> {
+        self.call(|mut client| async move { client.stats(StatsRequest {}).await })
+            .await
+    }
+
+    /// The server's entry count and stored value bytes, without the work of `stats`
+    pub async fn count(&self) -> Result<CountResponse> {
+        self.call(|mut client| async move { client.count(CountRequest {}).await })
+            .await
+    }
+
+    /// The server's memory pool statistics, with per-shard detail when it is sharded
+    pub async fn server_memory_stats(&self) -> Result<crate::memory::PoolStats> {
+        let stats = self.stats().await?;
+        let shards: Vec<_> = stats
+            .pool_shards
+            .iter()
+            .map(|shard| crate::memory::ShardStats {
+                max_size: usize::try_from(shard.max_size).unwrap_or(usize::MAX),
+                total: shard.total_bytes as usize,
+                used: shard.used_bytes as usize,
+                available: shard.available_bytes as usize,
+            })
+            .collect();
+        Ok(crate::memory::PoolStats {
+            total: stats.pool_total_bytes as usize,
+            used: stats.pool_used_bytes as usize,
+            available: stats.pool_available_bytes as usize,
+            // Not reported by the server
+            allocations: 0,
+            shards: (!shards.is_empty()).then_some(shards),
+        })
+    }
+
+    /// Send a heartbeat to the server
+    pub async fn heartbeat(&self) -> Result<bool> {
+        let client_id = self.config.client_id;
+        let response = self
+            .call(
+                |mut client| async move { client.heartbeat(HeartbeatRequest { client_id }).await },
+            )
+            .await?;
 
         Ok(response.alive)
     }
 
+    /// Deregister from the server, release the receive pool's RDMA
+    /// registration and close the connection
+    ///
+    /// The pool is released even if the server can't be reached; that error
+    /// is returned afterwards.
+    pub async fn shutdown(self) -> Result<()> {
+        let client_id = self.config.client_id;
+        let connection = self.connection.lock().take();
+        let deregistered = match connection {
+            Some(mut connection) => connection
+                .client
+                .deregister_client(DeregisterClientRequest { client_id })
+                .await
+                .map(|response| {
+                    if !response.into_inner().was_registered {
+                        tracing::debug!(
+                            "Server {} had no registration for client {}",
+                            connection.addr,
+                            client_id
+                        );
+                    }
+                })
+                .map_err(|status| {
+                    anyhow!(
+                        "Deregister from {} failed: {}",
+                        connection.addr,
+                        status.message()
+                    )
+                }),
+            None => Ok(()),
+        };
+
+        let handle = self.memory_pool.read().handle();
+        self.transport.deregister_memory(&handle)?;
+        tracing::info!("Client {} shut down", client_id);
+        deregistered
+    }
+
+    /// Number of GETs currently holding a receive buffer allocation
+    pub fn pending_requests(&self) -> usize {
+        self.pending.lock().len()
+    }
+
     /// Check if connected to server
     pub fn is_connected(&self) -> bool {
-        self.grpc_client.lock().is_some()
+        self.connection.lock().is_some()
     }
 
     /// Get memory pool statistics
@@ -346,10 +1804,130 @@ impl KvCacheClient {
     }
 }
 
+/// Connect to a gRPC endpoint, or to a Unix domain socket for `unix:<path>`
+async fn open_channel(addr: &str) -> Result<Channel> {
+    match addr.strip_prefix("unix:") {
+        #[cfg(unix)]
+        Some(path) => {
+            let path = std::path::PathBuf::from(path);
+            // The URI is required but unused; the connector picks the socket
+            let channel = tonic::transport::Endpoint::try_from("http://[::]:50051")?
+                .connect_with_connector(tower::service_fn(move |_: tonic::transport::Uri| {
+                    let path = path.clone();
+                    async move {
+                        let stream = tokio::net::UnixStream::connect(path).await?;
+                        Ok::<_, std::io::Error>(hyper_util::rt::TokioIo::new(stream))
+                    }
+                }))
+                .await?;
+            Ok(channel)
+        }
+        #[cfg(not(unix))]
+        Some(_) => Err(anyhow!("unix: server addresses require a Unix platform")),
+        None => Ok(Channel::from_shared(addr.to_string())?.connect().await?),
+    }
+}
+
+/// The value length an `OUT_OF_RANGE` GET failure reports, if `e` is one
+fn buffer_too_small(e: &anyhow::Error) -> Option<u64> {
+    let status = e
+        .downcast_ref::<Status>()
+        .filter(|status| status.code() == Code::OutOfRange)?;
+    status
+        .metadata()
+        .get("value-length")?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Whether an RPC failed because the server couldn't be reached, as opposed
+/// to the server answering with an error
+///
+/// Transport failures carry the underlying connection error as their source;
+/// statuses sent by the server (a failed loader, say) don't.
+fn is_connection_loss(status: &Status) -> bool {
+    status.code() == Code::Unavailable && std::error::Error::source(status).is_some()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_shutdown_deregisters_client_and_releases_pool() {
+        use crate::server::{KvCacheServer, ServerConfig};
+
+        let port = std::net::TcpListener::bind("[::1]:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let server = KvCacheServer::new(ServerConfig {
+            listen_addr: format!("[::1]:{}", port),
+            memory_pool_size: 4 * 1024 * 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        let server_handle = tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(server.into_service())
+                .serve(format!("[::1]:{}", port).parse().unwrap())
+                .await
+        });
+        let config = |client_id| ClientConfig {
+            client_id,
+            server_addr: format!("http://[::1]:{}", port),
+            receive_buffer_size: 4 * 1024 * 1024,
+            ..Default::default()
+        };
+        let observer = KvCacheClient::new(config(1)).unwrap();
+        observer
+            .connect_with_retry(Duration::from_secs(5))
+            .await
+            .unwrap();
+        let client = KvCacheClient::new(config(2)).unwrap();
+        client.connect().await.unwrap();
+        client.put(b"key", b"value", 0).await.unwrap();
+        assert_eq!(observer.stats().await.unwrap().registered_clients, 2);
+
+        let transport = client.transport.clone();
+        assert_eq!(transport.registration_count(), 1);
+        client.shutdown().await.unwrap();
+
+        assert_eq!(observer.stats().await.unwrap().registered_clients, 1);
+        assert_eq!(transport.registration_count(), 0);
+        assert_eq!(transport.registered_bytes(), 0);
+        assert_eq!(Arc::strong_count(&transport), 1);
+
+        server_handle.abort();
+    }
+
+    #[test]
+    fn test_dropping_registered_buffer_deregisters_it() {
+        let client = KvCacheClient::new(ClientConfig {
+            receive_buffer_size: 1024 * 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        let before = (
+            client.transport.registration_count(),
+            client.transport.registered_bytes(),
+        );
+
+        let buf = client.register_buffer(8192).unwrap();
+        assert_eq!(client.transport.registration_count(), before.0 + 1);
+        drop(buf);
+        assert_eq!(
+            (
+                client.transport.registration_count(),
+                client.transport.registered_bytes()
+            ),
+            before
+        );
+    }
+
     #[test]
     fn test_client_creation() {
         let config = ClientConfig {
@@ -0,0 +1,434 @@
+//! Loading, building and validating `ServerConfig` / `ClientConfig`
+//!
+//! Every config struct uses `#[serde(default, deny_unknown_fields)]`, so a file
+//! only needs the settings it changes and a misspelled key is an error rather
+//! than a silently ignored setting. Durations are written as human-readable
+//! strings such as `"5s"` or `"10us"`.
+//!
+//! `ServerConfig::builder()` and `ClientConfig::builder()` start from the
+//! defaults like a struct literal with `..Default::default()` would, but
+//! `build()` also checks settings that only make sense together.
+
+use crate::bloom::BloomFilterConfig;
+use crate::client::{AdaptiveBufferConfig, ClientConfig, GET_BUFFER_SIZE};
+use crate::keys::{KeyHasherConfig, KeyLogPolicy};
+use crate::memory::{SizeClass, ValueDevice, Watermarks};
+use crate::server::{AdaptiveTtlConfig, EvictionPolicy, ServerConfig};
+use crate::transport::TransportConfig;
+use anyhow::{anyhow, bail, Result};
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Read and deserialize a TOML config file
+pub fn load_toml<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read config {}: {}", path.display(), e))?;
+    toml::from_str(&text).map_err(|e| anyhow!("Invalid config {}: {}", path.display(), e))
+}
+
+impl ServerConfig {
+    /// Start building a config from the defaults
+    pub fn builder() -> ServerConfigBuilder {
+        ServerConfigBuilder::default()
+    }
+
+    /// Check invariants that span several settings
+    pub fn validate(&self) -> Result<()> {
+        if self.memory_pool_size == 0 {
+            bail!("Invalid server config: memory_pool_size must be greater than 0");
+        }
+        if self.max_value_size > self.memory_pool_size {
+            bail!(
+                "Invalid server config: max_value_size ({}) exceeds memory_pool_size ({})",
+                self.max_value_size,
+                self.memory_pool_size
+            );
+        }
+        let reserved: usize = self.size_classes.iter().map(|class| class.capacity).sum();
+        if reserved > self.memory_pool_size {
+            bail!(
+                "Invalid server config: size classes reserve {} bytes but memory_pool_size is {}",
+                reserved,
+                self.memory_pool_size
+            );
+        }
+        if let Some(watermarks) = &self.pool_watermarks {
+            if watermarks.low_bytes > watermarks.high_bytes
+                || watermarks.high_bytes > self.memory_pool_size
+            {
+                bail!(
+                    "Invalid server config: pool watermarks need low_bytes ({}) <= high_bytes ({}) <= memory_pool_size ({})",
+                    watermarks.low_bytes,
+                    watermarks.high_bytes,
+                    self.memory_pool_size
+                );
+            }
+        }
+        if let Some(bloom) = &self.bloom_filter {
+            if !(bloom.false_positive_rate > 0.0 && bloom.false_positive_rate < 1.0) {
+                bail!(
+                    "Invalid server config: bloom filter false_positive_rate ({}) must be between 0 and 1",
+                    bloom.false_positive_rate
+                );
+            }
+        }
+        if self.max_value_chunks == 0 {
+            bail!("Invalid server config: max_value_chunks must be at least 1");
+        }
+        if self.event_channel_capacity == 0 {
+            bail!("Invalid server config: event_channel_capacity must be at least 1");
+        }
+        Ok(())
+    }
+}
+
+/// Chainable setters for a `ServerConfig`, validated by `build`
+#[derive(Clone, Debug, Default)]
+pub struct ServerConfigBuilder {
+    config: ServerConfig,
+}
+
+impl ServerConfigBuilder {
+    pub fn node_id(mut self, node_id: u32) -> Self {
+        self.config.node_id = node_id;
+        self
+    }
+
+    pub fn listen_addr(mut self, listen_addr: impl Into<String>) -> Self {
+        self.config.listen_addr = listen_addr.into();
+        self
+    }
+
+    pub fn memory_pool_size(mut self, bytes: usize) -> Self {
+        self.config.memory_pool_size = bytes;
+        self
+    }
+
+    pub fn value_device(mut self, device: ValueDevice) -> Self {
+        self.config.value_device = device;
+        self
+    }
+
+    pub fn max_value_size(mut self, bytes: usize) -> Self {
+        self.config.max_value_size = bytes;
+        self
+    }
+
+    pub fn max_key_size(mut self, bytes: usize) -> Self {
+        self.config.max_key_size = bytes;
+        self
+    }
+
+    pub fn size_classes(mut self, size_classes: Vec<SizeClass>) -> Self {
+        self.config.size_classes = size_classes;
+        self
+    }
+
+    pub fn pool_watermarks(mut self, watermarks: Watermarks) -> Self {
+        self.config.pool_watermarks = Some(watermarks);
+        self
+    }
+
+    pub fn transport(mut self, transport: TransportConfig) -> Self {
+        self.config.transport = transport;
+        self
+    }
+
+    pub fn bind_retry_timeout(mut self, timeout: Duration) -> Self {
+        self.config.bind_retry_timeout = timeout;
+        self
+    }
+
+    pub fn bloom_filter(mut self, bloom_filter: BloomFilterConfig) -> Self {
+        self.config.bloom_filter = Some(bloom_filter);
+        self
+    }
+
+    pub fn get_latency_budget(mut self, budget: Duration) -> Self {
+        self.config.get_latency_budget = Some(budget);
+        self
+    }
+
+    pub fn num_shards(mut self, num_shards: u8) -> Self {
+        self.config.num_shards = num_shards;
+        self
+    }
+
+    pub fn wal_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.wal_path = Some(path.into());
+        self
+    }
+
+    pub fn wal_compact_bytes(mut self, bytes: u64) -> Self {
+        self.config.wal_compact_bytes = bytes;
+        self
+    }
+
+    pub fn runtime_cpus(mut self, cpus: Vec<usize>) -> Self {
+        self.config.runtime_cpus = Some(cpus);
+        self
+    }
+
+    pub fn max_concurrent_gets(mut self, max: usize) -> Self {
+        self.config.max_concurrent_gets = max;
+        self
+    }
+
+    pub fn tombstone_ttl(mut self, ttl: Duration) -> Self {
+        self.config.tombstone_ttl = ttl;
+        self
+    }
+
+    pub fn ttl_sweep_interval(mut self, interval: Duration) -> Self {
+        self.config.ttl_sweep_interval = interval;
+        self
+    }
+
+    pub fn deferred_free(mut self, enabled: bool) -> Self {
+        self.config.deferred_free = enabled;
+        self
+    }
+
+    pub fn intern_keys(mut self, enabled: bool) -> Self {
+        self.config.intern_keys = enabled;
+        self
+    }
+
+    pub fn small_value_inline_threshold(mut self, bytes: usize) -> Self {
+        self.config.small_value_inline_threshold = bytes;
+        self
+    }
+
+    pub fn event_channel_capacity(mut self, capacity: usize) -> Self {
+        self.config.event_channel_capacity = capacity;
+        self
+    }
+
+    pub fn repair_on_expiry(mut self, enabled: bool) -> Self {
+        self.config.repair_on_expiry = enabled;
+        self
+    }
+
+    pub fn reuseport_shards(mut self, shards: usize) -> Self {
+        self.config.reuseport_shards = shards;
+        self
+    }
+
+    pub fn adaptive_ttl(mut self, adaptive_ttl: AdaptiveTtlConfig) -> Self {
+        self.config.adaptive_ttl = Some(adaptive_ttl);
+        self
+    }
+
+    pub fn key_hasher(mut self, key_hasher: KeyHasherConfig) -> Self {
+        self.config.key_hasher = key_hasher;
+        self
+    }
+
+    pub fn log_keys(mut self, policy: KeyLogPolicy) -> Self {
+        self.config.log_keys = policy;
+        self
+    }
+
+    pub fn max_value_chunks(mut self, max_chunks: usize) -> Self {
+        self.config.max_value_chunks = max_chunks;
+        self
+    }
+
+    pub fn eviction(mut self, policy: EvictionPolicy) -> Self {
+        self.config.eviction = policy;
+        self
+    }
+
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.config.max_entries = max_entries;
+        self
+    }
+
+    /// Validate and return the config
+    pub fn build(self) -> Result<ServerConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+impl ClientConfig {
+    /// Start building a config from the defaults
+    pub fn builder() -> ClientConfigBuilder {
+        ClientConfigBuilder::default()
+    }
+
+    /// Check invariants that span several settings
+    pub fn validate(&self) -> Result<()> {
+        if self.server_addr.is_empty() {
+            bail!("Invalid client config: server_addr is empty");
+        }
+        if self.receive_buffer_size == 0 {
+            bail!("Invalid client config: receive_buffer_size must be greater than 0");
+        }
+        if self.single_buffer_mode && self.receive_buffer_size < GET_BUFFER_SIZE {
+            bail!(
+                "Invalid client config: single_buffer_mode needs a receive_buffer_size of at least {} bytes, got {}",
+                GET_BUFFER_SIZE,
+                self.receive_buffer_size
+            );
+        }
+        if let Some(adaptive) = &self.adaptive_buffer {
+            if !(adaptive.alpha > 0.0 && adaptive.alpha <= 1.0) {
+                bail!(
+                    "Invalid client config: adaptive_buffer.alpha ({}) must be in (0, 1]",
+                    adaptive.alpha
+                );
+            }
+            if adaptive.headroom.is_nan() || adaptive.headroom < 0.0 {
+                bail!(
+                    "Invalid client config: adaptive_buffer.headroom ({}) must not be negative",
+                    adaptive.headroom
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Chainable setters for a `ClientConfig`, validated by `build`
+#[derive(Clone, Debug, Default)]
+pub struct ClientConfigBuilder {
+    config: ClientConfig,
+}
+
+impl ClientConfigBuilder {
+    pub fn client_id(mut self, client_id: u32) -> Self {
+        self.config.client_id = client_id;
+        self
+    }
+
+    pub fn server_addr(mut self, server_addr: impl Into<String>) -> Self {
+        self.config.server_addr = server_addr.into();
+        self
+    }
+
+    pub fn seed_addrs(mut self, seed_addrs: Vec<String>) -> Self {
+        self.config.seed_addrs = seed_addrs;
+        self
+    }
+
+    pub fn connect_retries(mut self, retries: u32) -> Self {
+        self.config.connect_retries = retries;
+        self
+    }
+
+    pub fn connect_backoff(mut self, backoff: Duration) -> Self {
+        self.config.connect_backoff = backoff;
+        self
+    }
+
+    pub fn receive_buffer_size(mut self, bytes: usize) -> Self {
+        self.config.receive_buffer_size = bytes;
+        self
+    }
+
+    pub fn transport(mut self, transport: TransportConfig) -> Self {
+        self.config.transport = transport;
+        self
+    }
+
+    pub fn max_pending(mut self, max_pending: usize) -> Self {
+        self.config.max_pending = max_pending;
+        self
+    }
+
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.config.priority = priority;
+        self
+    }
+
+    pub fn single_buffer_mode(mut self, enabled: bool) -> Self {
+        self.config.single_buffer_mode = enabled;
+        self
+    }
+
+    pub fn log_keys(mut self, policy: KeyLogPolicy) -> Self {
+        self.config.log_keys = policy;
+        self
+    }
+
+    pub fn adaptive_buffer(mut self, adaptive_buffer: AdaptiveBufferConfig) -> Self {
+        self.config.adaptive_buffer = Some(adaptive_buffer);
+        self
+    }
+
+    pub fn size_gets_first(mut self, enabled: bool) -> Self {
+        self.config.size_gets_first = enabled;
+        self
+    }
+
+    /// Validate and return the config
+    pub fn build(self) -> Result<ClientConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_key_is_reported() {
+        let path = std::env::temp_dir().join(format!("kv-config-{}.toml", std::process::id()));
+        std::fs::write(&path, "listen_addr = \"0.0.0.0:1\"\nmemory_pool_mb = 4\n").unwrap();
+        let err = load_toml::<ServerConfig>(&path).unwrap_err().to_string();
+        assert!(err.contains("memory_pool_mb"), "{}", err);
+
+        std::fs::write(
+            &path,
+            "bind_retry_timeout = \"2s\"\n[transport]\nnum_domains = 2\n",
+        )
+        .unwrap();
+        let config: ServerConfig = load_toml(&path).unwrap();
+        assert_eq!(config.bind_retry_timeout, Duration::from_secs(2));
+        assert_eq!(config.transport.num_domains, 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_builder_rejects_value_larger_than_pool() {
+        let err = ServerConfig::builder()
+            .memory_pool_size(16 * 1024 * 1024)
+            .max_value_size(32 * 1024 * 1024)
+            .build()
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("max_value_size (33554432) exceeds memory_pool_size (16777216)"),
+            "{}",
+            err
+        );
+
+        let config = ServerConfig::builder()
+            .listen_addr("127.0.0.1:0")
+            .memory_pool_size(16 * 1024 * 1024)
+            .max_value_size(1024 * 1024)
+            .build()
+            .unwrap();
+        assert_eq!(config.listen_addr, "127.0.0.1:0");
+        assert_eq!(
+            config.max_value_chunks,
+            ServerConfig::default().max_value_chunks
+        );
+
+        assert!(ClientConfig::builder()
+            .receive_buffer_size(0)
+            .build()
+            .is_err());
+        let config = ClientConfig::builder()
+            .server_addr("http://127.0.0.1:1")
+            .receive_buffer_size(4 * 1024 * 1024)
+            .single_buffer_mode(true)
+            .build()
+            .unwrap();
+        assert!(config.single_buffer_mode);
+    }
+}
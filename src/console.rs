@@ -0,0 +1,41 @@
+//! tokio-console integration (`console` feature)
+//!
+//! `layer` records the runtime's task and resource instrumentation and serves
+//! it to `tokio-console` on `TOKIO_CONSOLE_BIND` (default `127.0.0.1:6669`).
+//! Tokio only emits that instrumentation when built with
+//! `RUSTFLAGS="--cfg tokio_unstable"`; without it the console lists no tasks.
+
+use tracing::Subscriber;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// The console layer, configured from the `TOKIO_CONSOLE_*` environment
+///
+/// Its gRPC server runs on a background thread of its own, so this needs no
+/// Tokio runtime.
+pub fn layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    console_subscriber::ConsoleLayer::builder()
+        .with_default_env()
+        .spawn()
+}
+
+/// Install the global subscriber: the console layer plus fmt logging
+///
+/// `RUST_LOG` (or `default_filter`) filters the logging only; the console
+/// layer needs tokio's trace-level events.
+pub fn init_tracing(default_filter: &str) {
+    tracing_subscriber::registry()
+        .with(layer())
+        .with(tracing_subscriber::fmt::layer().with_filter(env_filter(default_filter)))
+        .init();
+}
+
+/// `RUST_LOG` if set, otherwise `default_filter`
+pub fn env_filter(default_filter: &str) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter))
+}
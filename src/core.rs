@@ -0,0 +1,289 @@
+//! In-memory cache core, usable without gRPC or a transport
+//!
+//! `KvCore` holds the key map, the value pool and the bookkeeping around them
+//! (TTLs, the Bloom filter, interned keys and the entry counters), all behind
+//! synchronous calls. `KvCacheServer` keeps its entries in one and layers
+//! replication, the WAL, events and RDMA transfers on top; embedding a
+//! `KvCore` directly gives a plain in-process cache.
+
+use crate::bloom::CountingBloomFilter;
+use crate::keys::{CacheKey, KeyArena, KeyHashBuilder};
+use crate::memory::{MemoryPool, MemoryPoolConfig, PoolAllocation};
+use crate::protocol::CacheEntry;
+use crate::server::ServerConfig;
+use anyhow::Result;
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Cache entries and the pool holding their values
+pub struct KvCore {
+    /// Cache entries: key -> CacheEntry
+    pub(crate) cache: DashMap<CacheKey, CacheEntry, KeyHashBuilder>,
+    /// Memory pool for storing cached values
+    pub(crate) memory_pool: Arc<RwLock<MemoryPool>>,
+    /// Key existence filter, kept in sync with `cache` on insert/remove
+    pub(crate) bloom: Option<CountingBloomFilter>,
+    /// Next version to assign to a written entry
+    pub(crate) next_version: AtomicU64,
+    /// Most pieces a value may be split into when the pool is fragmented
+    max_value_chunks: usize,
+    /// Entries in `cache`, maintained on every insert and removal
+    entry_count: AtomicU64,
+    /// Sum of the value lengths in `cache`
+    stored_bytes: AtomicU64,
+    /// Storage for interned `cache` keys; declared after `cache` so it is
+    /// dropped after the keys pointing into it
+    key_arena: Option<KeyArena>,
+}
+
+impl KvCore {
+    /// A core with its own unregistered pool, sized and keyed by `config`
+    ///
+    /// Only the storage settings are used: the pool size, size classes and
+    /// watermarks, `max_value_chunks`, the key hasher, key interning and the
+    /// Bloom filter.
+    pub fn new(config: &ServerConfig) -> Result<Self> {
+        let pool = MemoryPool::new(pool_config(config), config.node_id, None)?;
+        Ok(Self::with_pool(config, Arc::new(RwLock::new(pool))))
+    }
+
+    /// A core storing values in `memory_pool`, e.g. one registered for RDMA
+    pub(crate) fn with_pool(config: &ServerConfig, memory_pool: Arc<RwLock<MemoryPool>>) -> Self {
+        Self {
+            cache: DashMap::with_hasher(config.key_hasher.build()),
+            memory_pool,
+            bloom: config.bloom_filter.as_ref().map(CountingBloomFilter::new),
+            next_version: AtomicU64::new(1),
+            max_value_chunks: config.max_value_chunks,
+            entry_count: AtomicU64::new(0),
+            stored_bytes: AtomicU64::new(0),
+            key_arena: config.intern_keys.then(KeyArena::default),
+        }
+    }
+
+    /// Store a value, replacing any existing one (`ttl_millis` 0 = no expiration)
+    pub fn put(&self, key: &[u8], value: Vec<u8>, ttl_millis: u64) -> Result<()> {
+        let pool = self.memory_pool.read();
+        let allocations = pool.allocate_chunked(value.len(), self.max_value_chunks)?;
+        if let Err(e) = pool.write_chunks(&allocations, &value) {
+            free_all(&pool, &allocations);
+            return Err(e);
+        }
+
+        let version = self.next_version.fetch_add(1, Ordering::Relaxed);
+        let entry = CacheEntry::new(value, allocations, ttl_millis, version);
+        self.count_added(&entry);
+        let replaced = match self.cache.entry(self.cache_key(key.to_vec())) {
+            dashmap::Entry::Occupied(mut existing) => {
+                let old_entry = existing.insert(entry);
+                self.release_key(existing.into_key());
+                Some(old_entry)
+            }
+            dashmap::Entry::Vacant(vacant) => {
+                self.bloom_insert(vacant.key());
+                vacant.insert(entry);
+                None
+            }
+        };
+        if let Some(old_entry) = replaced {
+            self.count_removed(&old_entry);
+            free_all(&pool, &old_entry.into_allocations().collect::<Vec<_>>());
+        }
+        Ok(())
+    }
+
+    /// A copy of the live value for `key`; an expired entry is removed
+    pub fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        if !self.may_contain(key) {
+            return None;
+        }
+        let mut entry = self.cache.get_mut(key)?;
+        if entry.is_expired() {
+            drop(entry);
+            self.delete(key);
+            return None;
+        }
+        entry.last_accessed = std::time::Instant::now();
+        Some(entry.data.clone())
+    }
+
+    /// Remove a value and free its pool space, returning whether it existed
+    pub fn delete(&self, key: &[u8]) -> bool {
+        let Some(entry) = self.remove_entry(key) else {
+            return false;
+        };
+        free_all(
+            &self.memory_pool.read(),
+            &entry.into_allocations().collect::<Vec<_>>(),
+        );
+        true
+    }
+
+    /// Check whether a live (non-expired) entry exists for the key
+    pub fn contains(&self, key: &[u8]) -> bool {
+        if !self.may_contain(key) {
+            return false;
+        }
+        self.cache.get(key).is_some_and(|entry| !entry.is_expired())
+    }
+
+    /// Entries and stored value bytes, from the running counters
+    pub fn count(&self) -> (u64, u64) {
+        (
+            self.entry_count.load(Ordering::Relaxed),
+            self.stored_bytes.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Bloom filter pre-check; always true when no filter is configured
+    pub(crate) fn may_contain(&self, key: &[u8]) -> bool {
+        self.bloom
+            .as_ref()
+            .is_none_or(|bloom| bloom.may_contain(key))
+    }
+
+    /// Record a key about to go into the map in the Bloom filter
+    pub(crate) fn bloom_insert(&self, key: &[u8]) {
+        if let Some(bloom) = &self.bloom {
+            bloom.insert(key);
+        }
+    }
+
+    /// Map key for `key`, interned if interning is on and the key looks new
+    ///
+    /// If the key turns up meanwhile, its entry is overwritten and the spare
+    /// interned key released.
+    pub(crate) fn cache_key(&self, key: Vec<u8>) -> CacheKey {
+        match &self.key_arena {
+            Some(arena) if !self.cache.contains_key(key.as_slice()) => {
+                KeyArena::key(Some(arena), key)
+            }
+            _ => CacheKey::Owned(key),
+        }
+    }
+
+    /// Remove an entry from the map, keeping the Bloom filter in sync
+    ///
+    /// The caller is responsible for returning the entry's pool space.
+    pub(crate) fn remove_entry(&self, key: &[u8]) -> Option<CacheEntry> {
+        let (key, entry) = self.cache.remove(key)?;
+        self.forget_key(key);
+        self.count_removed(&entry);
+        Some(entry)
+    }
+
+    /// Remove `key`'s entry only if it has expired, otherwise like `remove_entry`
+    pub(crate) fn remove_if_expired(&self, key: &[u8]) -> Option<CacheEntry> {
+        let (key, entry) = self.cache.remove_if(key, |_, entry| entry.is_expired())?;
+        self.forget_key(key);
+        self.count_removed(&entry);
+        Some(entry)
+    }
+
+    /// Remove `key`'s entry only if it is still at `version`, otherwise like
+    /// `remove_entry`
+    pub(crate) fn remove_if_version(&self, key: &[u8], version: u64) -> Option<CacheEntry> {
+        let (key, entry) = self
+            .cache
+            .remove_if(key, |_, entry| entry.version == version)?;
+        self.forget_key(key);
+        self.count_removed(&entry);
+        Some(entry)
+    }
+
+    /// Count an entry about to go into the map
+    pub(crate) fn count_added(&self, entry: &CacheEntry) {
+        self.entry_count.fetch_add(1, Ordering::Relaxed);
+        self.stored_bytes
+            .fetch_add(entry.len() as u64, Ordering::Relaxed);
+    }
+
+    /// Uncount an entry taken out of the map
+    pub(crate) fn count_removed(&self, entry: &CacheEntry) {
+        self.entry_count.fetch_sub(1, Ordering::Relaxed);
+        self.stored_bytes
+            .fetch_sub(entry.len() as u64, Ordering::Relaxed);
+    }
+
+    /// Drop a key that was removed from the map from the Bloom filter and arena
+    pub(crate) fn forget_key(&self, key: CacheKey) {
+        if let Some(bloom) = &self.bloom {
+            bloom.remove(&key);
+        }
+        self.release_key(key);
+    }
+
+    /// Return an interned key's arena slot; owned keys are just dropped
+    pub(crate) fn release_key(&self, key: CacheKey) {
+        if let (Some(arena), CacheKey::Interned(key)) = (&self.key_arena, key) {
+            arena.release(key);
+        }
+    }
+}
+
+/// Pool settings taken from a server config
+pub(crate) fn pool_config(config: &ServerConfig) -> MemoryPoolConfig {
+    MemoryPoolConfig {
+        size: config.memory_pool_size,
+        alignment: 4096,
+        size_classes: config.size_classes.clone(),
+        watermarks: config.pool_watermarks.clone(),
+        device: config.value_device,
+        ..Default::default()
+    }
+}
+
+/// Free allocations that never made it into an entry
+pub(crate) fn free_all(pool: &MemoryPool, allocations: &[PoolAllocation]) {
+    for allocation in allocations {
+        pool.deallocate(allocation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn core() -> KvCore {
+        KvCore::new(&ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_put_get_delete() {
+        let core = core();
+        core.put(b"key1", b"value1".to_vec(), 0).unwrap();
+        assert!(core.contains(b"key1"));
+        assert_eq!(core.get(b"key1").as_deref(), Some(&b"value1"[..]));
+
+        core.put(b"key1", b"value2".to_vec(), 0).unwrap();
+        assert_eq!(core.get(b"key1").as_deref(), Some(&b"value2"[..]));
+        assert_eq!(core.count(), (1, 6));
+
+        assert!(core.delete(b"key1"));
+        assert!(!core.delete(b"key1"));
+        assert_eq!(core.get(b"key1"), None);
+        assert_eq!(core.count(), (0, 0));
+        assert_eq!(core.memory_pool.read().stats().used, 0);
+    }
+
+    #[test]
+    fn test_expired_value_is_gone_and_freed() {
+        let core = core();
+        core.put(b"short", b"lived".to_vec(), 50).unwrap();
+        core.put(b"forever", b"value".to_vec(), 0).unwrap();
+        assert_eq!(core.get(b"short").as_deref(), Some(&b"lived"[..]));
+
+        std::thread::sleep(Duration::from_millis(80));
+        assert!(!core.contains(b"short"));
+        assert_eq!(core.get(b"short"), None);
+        assert_eq!(core.get(b"forever").as_deref(), Some(&b"value"[..]));
+        assert_eq!(core.count(), (1, 5));
+    }
+}
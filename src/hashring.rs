@@ -0,0 +1,208 @@
+//! Consistent hash ring
+//!
+//! Each node is placed on a ring of `u64` points at `virtual_nodes` positions,
+//! hashed from its identity bytes, and a key belongs to the first node point at
+//! or after the key's hash (wrapping around). This gives:
+//!
+//! - Placement depends only on the node identities, the virtual-node count and
+//!   the hash function, not on the order nodes were added, so every process
+//!   configured alike maps a key to the same node.
+//! - Adding a node only moves keys onto the new node, about `1 / (n + 1)` of
+//!   them; no key moves between the existing nodes.
+//! - Removing a node only moves the keys it owned; every other key stays put.
+//!
+//! More virtual nodes even out the share each node gets at the cost of a
+//! larger ring; at 160 a node's share is typically within 10-15% of even.
+
+/// Virtual nodes per node used by `HashRing::default`
+pub const DEFAULT_VIRTUAL_NODES: usize = 160;
+
+/// FNV-1a with a splitmix64 finalizer
+///
+/// Stable across processes and Rust versions, unlike `DefaultHasher`. The
+/// finalizer spreads the near-identical keys typical of benchmarks over the ring.
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+/// Nodes identified by their bytes (e.g. server addresses), with keys routed
+/// by consistent hashing
+#[derive(Clone, Debug)]
+pub struct HashRing<T> {
+    nodes: Vec<T>,
+    /// Sorted (point, index into `nodes`) pairs
+    ring: Vec<(u64, usize)>,
+    virtual_nodes: usize,
+    hash: fn(&[u8]) -> u64,
+}
+
+impl<T: AsRef<[u8]>> Default for HashRing<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_VIRTUAL_NODES)
+    }
+}
+
+impl<T: AsRef<[u8]>> HashRing<T> {
+    /// An empty ring placing each node at `virtual_nodes` points, using `stable_hash`
+    pub fn new(virtual_nodes: usize) -> Self {
+        Self::with_hasher(virtual_nodes, stable_hash)
+    }
+
+    /// An empty ring using `hash` for both node points and keys
+    ///
+    /// Every process sharing a ring layout must use the same function.
+    pub fn with_hasher(virtual_nodes: usize, hash: fn(&[u8]) -> u64) -> Self {
+        Self {
+            nodes: Vec::new(),
+            ring: Vec::new(),
+            virtual_nodes: virtual_nodes.max(1),
+            hash,
+        }
+    }
+
+    /// Add a node, returning the one it replaced if one had the same identity
+    pub fn add_node(&mut self, node: T) -> Option<T> {
+        let replaced = self.remove_node(node.as_ref());
+        let idx = self.nodes.len();
+        for vnode in 0..self.virtual_nodes {
+            let mut label = node.as_ref().to_vec();
+            label.extend_from_slice(format!("#{}", vnode).as_bytes());
+            self.ring.push(((self.hash)(&label), idx));
+        }
+        self.nodes.push(node);
+        self.sort();
+        replaced
+    }
+
+    /// Remove the node with identity `node`, returning it
+    pub fn remove_node(&mut self, node: &[u8]) -> Option<T> {
+        let idx = self.nodes.iter().position(|n| n.as_ref() == node)?;
+        self.ring.retain(|&(_, i)| i != idx);
+        for (_, i) in &mut self.ring {
+            if *i > idx {
+                *i -= 1;
+            }
+        }
+        Some(self.nodes.remove(idx))
+    }
+
+    /// The node owning `key`, or `None` if the ring is empty
+    pub fn get_node(&self, key: &[u8]) -> Option<&T> {
+        self.node_index(key).map(|idx| &self.nodes[idx])
+    }
+
+    /// Position in `nodes()` of the node owning `key`
+    pub fn node_index(&self, key: &[u8]) -> Option<usize> {
+        if self.ring.is_empty() {
+            return None;
+        }
+        let hash = (self.hash)(key);
+        let pos = self.ring.partition_point(|&(point, _)| point < hash);
+        Some(self.ring[pos % self.ring.len()].1)
+    }
+
+    /// Nodes in the order they were added
+    pub fn nodes(&self) -> &[T] {
+        &self.nodes
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Sort points, breaking colliding ones by node identity so the layout
+    /// doesn't depend on insertion order
+    fn sort(&mut self) {
+        let nodes = &self.nodes;
+        self.ring.sort_unstable_by(|a, b| {
+            a.0.cmp(&b.0)
+                .then_with(|| nodes[a.1].as_ref().cmp(nodes[b.1].as_ref()))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring_of(nodes: &[&str]) -> HashRing<String> {
+        let mut ring = HashRing::default();
+        for node in nodes {
+            ring.add_node(node.to_string());
+        }
+        ring
+    }
+
+    fn owners(ring: &HashRing<String>, keys: usize) -> Vec<String> {
+        (0..keys)
+            .map(|i| {
+                ring.get_node(format!("key_{}", i).as_bytes())
+                    .unwrap()
+                    .clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_keys_spread_evenly() {
+        let ring = ring_of(&["a:1", "b:1", "c:1", "d:1", "e:1"]);
+        let owners = owners(&ring, 50_000);
+        for node in ring.nodes() {
+            let share = owners.iter().filter(|owner| *owner == node).count();
+            assert!((7_500..12_500).contains(&share), "{} owns {}", node, share);
+        }
+    }
+
+    #[test]
+    fn test_adding_a_node_only_moves_keys_to_it() {
+        let mut ring = ring_of(&["a:1", "b:1", "c:1", "d:1"]);
+        let before = owners(&ring, 20_000);
+        ring.add_node("e:1".to_string());
+        let after = owners(&ring, 20_000);
+
+        let moved: Vec<_> = before.iter().zip(&after).filter(|(b, a)| b != a).collect();
+        assert!(moved.iter().all(|(_, a)| *a == "e:1"));
+        assert!(
+            (3_000..5_000).contains(&moved.len()),
+            "{} keys moved",
+            moved.len()
+        );
+
+        // Same layout however the nodes arrived
+        assert_eq!(
+            after,
+            owners(&ring_of(&["e:1", "c:1", "a:1", "d:1", "b:1"]), 20_000)
+        );
+    }
+
+    #[test]
+    fn test_removing_a_node_only_remaps_its_keys() {
+        let mut ring = ring_of(&["a:1", "b:1", "c:1", "d:1"]);
+        let before = owners(&ring, 20_000);
+        assert_eq!(ring.remove_node(b"b:1"), Some("b:1".to_string()));
+        let after = owners(&ring, 20_000);
+
+        for (b, a) in before.iter().zip(&after) {
+            if b == "b:1" {
+                assert_ne!(a, "b:1");
+            } else {
+                assert_eq!(a, b);
+            }
+        }
+        assert!(ring.remove_node(b"b:1").is_none());
+        assert_eq!(ring.len(), 3);
+    }
+}
@@ -0,0 +1,22 @@
+//! Server-side value transformation
+//!
+//! A server built with `ValueInterceptor`s passes every value a client PUTs
+//! through each interceptor's `on_put` in the order they were added, and stores
+//! the result. A GET passes the stored bytes back through `on_get` in reverse
+//! order, so a chain of e.g. compression then encryption unwinds correctly.
+//!
+//! Transformed GETs can't be RDMA written from the pool, so they are always
+//! returned inline. GetMany, which can only RDMA write, is refused, and
+//! `KvCacheClient::mget` falls back to single GETs. Dump and copies between
+//! keys move the stored bytes as they are.
+
+use anyhow::Result;
+
+/// Hooks applied to values on their way into and out of the cache
+pub trait ValueInterceptor: Send + Sync {
+    /// Transform a value being stored under `key`; an error fails the PUT
+    fn on_put(&self, key: &[u8], value: &[u8]) -> Result<Vec<u8>>;
+
+    /// Transform a stored value being returned for `key`; an error fails the GET
+    fn on_get(&self, key: &[u8], value: &[u8]) -> Result<Vec<u8>>;
+}
@@ -0,0 +1,327 @@
+//! Compact storage for cache keys
+//!
+//! By default every key is its own `Vec<u8>`. With interning, key bytes are
+//! copied into large arena chunks and the map holds a pointer/length handle, so
+//! a new key costs no heap allocation of its own and keys sit together in
+//! memory. A released slot is reused by the next key of the same length, which
+//! suits fixed-format keys. Both forms hash and compare as their bytes, so maps
+//! keyed by `CacheKey` are still looked up with plain `&[u8]`.
+//!
+//! The map's hash function is configurable too: std's SipHash by default,
+//! aHash for trusted workloads that want faster lookups, or SipHash keyed with
+//! a server secret when clients are untrusted.
+//!
+//! `KeyLogPolicy` controls how keys appear in log lines, since keys can be
+//! sensitive in their own right.
+
+use crate::checksum::crc32c;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+/// Arena chunk size; longer keys get a chunk of their own
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A cache map key
+pub enum CacheKey {
+    Owned(Vec<u8>),
+    Interned(InternedKey),
+}
+
+impl Deref for CacheKey {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            CacheKey::Owned(key) => key,
+            CacheKey::Interned(key) => key.as_bytes(),
+        }
+    }
+}
+
+impl Borrow<[u8]> for CacheKey {
+    fn borrow(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Hash for CacheKey {
+    // Must match `[u8]`'s hash for `Borrow` lookups to work
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl PartialEq for CacheKey {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for CacheKey {}
+
+impl std::fmt::Debug for CacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+/// Handle to key bytes stored in a `KeyArena`
+///
+/// Only valid while the arena that produced it is alive.
+pub struct InternedKey {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// SAFETY: the bytes behind the handle are written once, before the handle is
+// handed out, and never mutated while it exists
+unsafe impl Send for InternedKey {}
+unsafe impl Sync for InternedKey {}
+
+impl InternedKey {
+    fn as_bytes(&self) -> &[u8] {
+        // SAFETY: the arena keeps the chunk alive and unmodified until `release`
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+struct ArenaState {
+    chunks: Vec<Box<[u8]>>,
+    /// Bytes handed out from the last regular-sized chunk
+    used: usize,
+    /// Released slots by key length
+    free: HashMap<usize, Vec<NonNull<u8>>>,
+}
+
+/// Append-only store for interned key bytes
+pub struct KeyArena {
+    state: Mutex<ArenaState>,
+}
+
+// SAFETY: the raw pointers in the state only point into chunks the arena owns,
+// and all access to them goes through the mutex
+unsafe impl Send for KeyArena {}
+unsafe impl Sync for KeyArena {}
+
+impl Default for KeyArena {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(ArenaState {
+                chunks: Vec::new(),
+                used: CHUNK_SIZE,
+                free: HashMap::new(),
+            }),
+        }
+    }
+}
+
+impl KeyArena {
+    /// Copy `key` into the arena
+    pub fn intern(&self, key: &[u8]) -> InternedKey {
+        let mut state = self.state.lock();
+
+        let ptr = match state.free.get_mut(&key.len()).and_then(Vec::pop) {
+            Some(ptr) => ptr,
+            None if key.len() > CHUNK_SIZE / 4 => {
+                // Keep the current chunk for short keys; the dedicated chunk goes
+                // before it so `used` still refers to the last chunk
+                let mut chunk = vec![0u8; key.len().max(1)].into_boxed_slice();
+                let ptr = NonNull::new(chunk.as_mut_ptr()).unwrap();
+                let last = state.chunks.len().saturating_sub(1);
+                state.chunks.insert(last, chunk);
+                ptr
+            }
+            None => {
+                if state.used + key.len() > CHUNK_SIZE {
+                    state.chunks.push(vec![0u8; CHUNK_SIZE].into_boxed_slice());
+                    state.used = 0;
+                }
+                let used = state.used;
+                state.used += key.len();
+                let chunk = state.chunks.last_mut().unwrap();
+                NonNull::new(chunk[used..].as_mut_ptr()).unwrap()
+            }
+        };
+
+        // SAFETY: `ptr` has room for `key.len()` bytes that no live handle uses
+        unsafe { std::ptr::copy_nonoverlapping(key.as_ptr(), ptr.as_ptr(), key.len()) };
+        InternedKey {
+            ptr,
+            len: key.len(),
+        }
+    }
+
+    /// Return a key's slot for reuse; the handle is consumed
+    pub fn release(&self, key: InternedKey) {
+        self.state
+            .lock()
+            .free
+            .entry(key.len)
+            .or_default()
+            .push(key.ptr);
+    }
+
+    /// Build a map key, interning it if an arena is given
+    pub fn key(arena: Option<&KeyArena>, key: Vec<u8>) -> CacheKey {
+        match arena {
+            Some(arena) => CacheKey::Interned(arena.intern(&key)),
+            None => CacheKey::Owned(key),
+        }
+    }
+}
+
+/// How keys are written in log messages
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyLogPolicy {
+    /// The key bytes, with non-printable ones escaped
+    Full,
+    /// A CRC32C of the key, stable across runs and processes
+    Hashed,
+    /// Only the key's length
+    LengthOnly,
+}
+
+impl Default for KeyLogPolicy {
+    /// Full keys in debug builds, hashed ones in release builds
+    fn default() -> Self {
+        if cfg!(debug_assertions) {
+            KeyLogPolicy::Full
+        } else {
+            KeyLogPolicy::Hashed
+        }
+    }
+}
+
+impl KeyLogPolicy {
+    /// `key` as it should appear in a log message
+    pub fn display(self, key: &[u8]) -> LoggedKey<'_> {
+        LoggedKey { policy: self, key }
+    }
+}
+
+/// A key formatted according to a `KeyLogPolicy`
+pub struct LoggedKey<'a> {
+    policy: KeyLogPolicy,
+    key: &'a [u8],
+}
+
+impl fmt::Display for LoggedKey<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.policy {
+            KeyLogPolicy::Full => write!(f, "\"{}\"", self.key.escape_ascii()),
+            KeyLogPolicy::Hashed => write!(f, "#{:08x}", crc32c(self.key)),
+            KeyLogPolicy::LengthOnly => write!(f, "<{} bytes>", self.key.len()),
+        }
+    }
+}
+
+/// Hash function for cache map keys
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum KeyHasherConfig {
+    /// std's SipHash-1-3 with random keys
+    #[default]
+    Std,
+    /// aHash with random keys; much faster on short keys, but not a
+    /// cryptographic PRF, so best kept to trusted clients
+    Ahash,
+    /// SipHash-1-3 keyed with a server secret, so clients can't precompute
+    /// colliding keys
+    KeyedSip { k0: u64, k1: u64 },
+}
+
+impl KeyHasherConfig {
+    pub fn build(&self) -> KeyHashBuilder {
+        match *self {
+            KeyHasherConfig::Std => KeyHashBuilder::Std(RandomState::new()),
+            KeyHasherConfig::Ahash => KeyHashBuilder::Ahash(ahash::RandomState::new()),
+            KeyHasherConfig::KeyedSip { k0, k1 } => KeyHashBuilder::KeyedSip { k0, k1 },
+        }
+    }
+}
+
+/// `BuildHasher` for the hash function picked by a `KeyHasherConfig`
+#[derive(Clone)]
+pub enum KeyHashBuilder {
+    Std(RandomState),
+    Ahash(ahash::RandomState),
+    KeyedSip { k0: u64, k1: u64 },
+}
+
+impl Default for KeyHashBuilder {
+    fn default() -> Self {
+        KeyHasherConfig::default().build()
+    }
+}
+
+impl BuildHasher for KeyHashBuilder {
+    type Hasher = KeyHasher;
+
+    fn build_hasher(&self) -> KeyHasher {
+        match self {
+            KeyHashBuilder::Std(state) => KeyHasher::Std(state.build_hasher()),
+            KeyHashBuilder::Ahash(state) => KeyHasher::Ahash(state.build_hasher()),
+            KeyHashBuilder::KeyedSip { k0, k1 } => {
+                KeyHasher::KeyedSip(siphasher::sip::SipHasher13::new_with_keys(*k0, *k1))
+            }
+        }
+    }
+}
+
+/// Hasher built by `KeyHashBuilder`
+pub enum KeyHasher {
+    Std(DefaultHasher),
+    Ahash(ahash::AHasher),
+    KeyedSip(siphasher::sip::SipHasher13),
+}
+
+impl Hasher for KeyHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        match self {
+            KeyHasher::Std(hasher) => hasher.write(bytes),
+            KeyHasher::Ahash(hasher) => hasher.write(bytes),
+            KeyHasher::KeyedSip(hasher) => hasher.write(bytes),
+        }
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        match self {
+            KeyHasher::Std(hasher) => hasher.write_usize(n),
+            KeyHasher::Ahash(hasher) => hasher.write_usize(n),
+            KeyHasher::KeyedSip(hasher) => hasher.write_usize(n),
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        match self {
+            KeyHasher::Std(hasher) => hasher.finish(),
+            KeyHasher::Ahash(hasher) => hasher.finish(),
+            KeyHasher::KeyedSip(hasher) => hasher.finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_released_slots_are_reused() {
+        let arena = KeyArena::default();
+        let first = arena.intern(b"key-0001");
+        let ptr = first.ptr;
+        arena.release(first);
+
+        let second = arena.intern(b"key-0002");
+        assert_eq!(second.ptr, ptr);
+        assert_eq!(second.as_bytes(), b"key-0002");
+    }
+}
@@ -1,8 +1,27 @@
+pub mod admission;
+pub mod affinity;
+pub mod bloom;
+pub mod checksum;
 pub mod client;
+pub mod config;
+#[cfg(feature = "console")]
+pub mod console;
+pub mod core;
+pub mod hashring;
+pub mod interceptor;
+pub mod keys;
+pub mod loader;
 pub mod memory;
+pub mod metrics;
+pub mod priority;
 pub mod protocol;
 pub mod server;
+pub mod sharded;
+#[cfg(feature = "otel")]
+pub mod telemetry;
 pub mod transport;
+pub mod util;
+pub mod wal;
 
 // Re-export generated protobuf types
 pub mod pb {
@@ -12,4 +31,5 @@ pub mod pb {
 pub use client::KvCacheClient;
 pub use protocol::{MemoryRegionDescriptor, ValueLocation};
 pub use server::KvCacheServer;
+pub use sharded::ShardedClient;
 pub use transport::{RdmaTransport, TransportConfig};
@@ -0,0 +1,18 @@
+//! Read-through loading for cache misses
+//!
+//! A server configured with a `ValueLoader` fills misses from a backing store
+//! instead of returning NOT_FOUND. Loaded values are stored without a TTL,
+//! except when `repair_on_expiry` reloads an expired entry, which keeps its TTL.
+
+use anyhow::Result;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Future returned by `ValueLoader::load`
+pub type LoadFuture<'a> = Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>>> + Send + 'a>>;
+
+/// Source of values for keys that are not resident in the cache
+pub trait ValueLoader: Send + Sync {
+    /// Load the value for a key; `Ok(None)` if the backing store doesn't have it
+    fn load<'a>(&'a self, key: &'a [u8]) -> LoadFuture<'a>;
+}
@@ -4,9 +4,16 @@
 //! for zero-copy data transfers.
 
 use crate::protocol::{MemoryRegionDescriptor, MemoryRegionHandle};
+use crate::transport::RdmaTransport;
 use anyhow::{anyhow, Result};
-use parking_lot::Mutex;
-use std::collections::BTreeMap;
+use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
 
 /// Configuration for the memory pool
 #[derive(Clone, Debug)]
@@ -15,6 +22,17 @@ pub struct MemoryPoolConfig {
     pub size: usize,
     /// Alignment for allocations (default: 4096 for page alignment)
     pub alignment: usize,
+    /// Dedicated regions for small allocations, in ascending `max_size` order;
+    /// whatever capacity they leave forms a final class for everything larger
+    pub size_classes: Vec<SizeClass>,
+    /// Usage thresholds reported through `subscribe_watermarks`
+    pub watermarks: Option<Watermarks>,
+    /// Times to try registering the pool with the transport before giving up
+    pub registration_attempts: u32,
+    /// Wait before the first registration retry, doubling after each one
+    pub registration_backoff: Duration,
+    /// Where the buffer is allocated and what it is registered as
+    pub device: ValueDevice,
 }
 
 impl Default for MemoryPoolConfig {
@@ -22,10 +40,63 @@ impl Default for MemoryPoolConfig {
         Self {
             size: 1024 * 1024 * 1024, // 1GB default
             alignment: 4096,
+            size_classes: Vec::new(),
+            watermarks: None,
+            registration_attempts: 3,
+            registration_backoff: Duration::from_millis(100),
+            device: ValueDevice::Host,
         }
     }
 }
 
+/// Memory the server's value pool lives in
+///
+/// Serialized with a `kind` tag, e.g. `{ kind = "cuda", device_id = 0 }`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ValueDevice {
+    #[default]
+    Host,
+    /// GPU memory on this CUDA device, registered for GPU-direct RDMA
+    ///
+    /// Needs the `rdma` feature (cuda-lib) and the fabric transport: values are
+    /// copied in and out with `cudaMemcpy`, and the pool can't be borrowed as
+    /// host bytes (`read`, `read_guard`, `buffer`).
+    Cuda { device_id: u32 },
+}
+
+/// Pool usage thresholds, in bytes used
+///
+/// Usage reaching `high_bytes` raises `WatermarkEvent::High`; after that,
+/// falling to `low_bytes` raises `WatermarkEvent::Low`. The gap keeps usage
+/// hovering around one threshold from raising a stream of events.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Watermarks {
+    pub high_bytes: usize,
+    pub low_bytes: usize,
+}
+
+/// A watermark crossing, with the pool usage that triggered it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatermarkEvent {
+    High { used: usize },
+    Low { used: usize },
+}
+
+/// Crossings not yet received before a slow subscriber starts missing them
+const WATERMARK_CHANNEL_CAPACITY: usize = 16;
+
+/// A region of the pool reserved for allocations up to `max_size` bytes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SizeClass {
+    /// Largest allocation routed to this class
+    pub max_size: usize,
+    /// Bytes of the pool reserved for the class
+    pub capacity: usize,
+}
+
 /// A simple bump allocator for the memory pool
 struct BumpAllocator {
     /// Current allocation offset
@@ -36,6 +107,10 @@ struct BumpAllocator {
     alignment: usize,
     /// Free list: offset -> size (for simple deallocation)
     free_list: BTreeMap<usize, usize>,
+    /// Bytes currently handed out, whether bumped or reused from the free list
+    live_bytes: usize,
+    /// Generation of each live allocation, by offset
+    generations: HashMap<usize, u64>,
 }
 
 impl BumpAllocator {
@@ -45,10 +120,18 @@ impl BumpAllocator {
             capacity,
             alignment,
             free_list: BTreeMap::new(),
+            live_bytes: 0,
+            generations: HashMap::new(),
         }
     }
 
-    fn allocate(&mut self, size: usize) -> Option<usize> {
+    fn allocate(&mut self, size: usize, generation: u64) -> Option<usize> {
+        let offset = self.allocate_offset(size)?;
+        self.generations.insert(offset, generation);
+        Some(offset)
+    }
+
+    fn allocate_offset(&mut self, size: usize) -> Option<usize> {
         // Try to find a suitable free block first
         let mut found_offset = None;
         for (&offset, &block_size) in &self.free_list {
@@ -63,12 +146,16 @@ impl BumpAllocator {
             // If block is larger than needed, put remainder back
             if block_size > size {
                 let remainder_offset = offset + size;
-                let aligned_remainder = (remainder_offset + self.alignment - 1) & !(self.alignment - 1);
-                let remainder_size = block_size - (aligned_remainder - offset);
+                let aligned_remainder =
+                    (remainder_offset + self.alignment - 1) & !(self.alignment - 1);
+                // Aligning may step past the end of a block that was freed
+                // at an unaligned size
+                let remainder_size = block_size.saturating_sub(aligned_remainder - offset);
                 if remainder_size >= self.alignment {
                     self.free_list.insert(aligned_remainder, remainder_size);
                 }
             }
+            self.live_bytes += size;
             return Some(offset);
         }
 
@@ -79,17 +166,73 @@ impl BumpAllocator {
         }
 
         self.offset = aligned_offset + size;
+        self.live_bytes += size;
         Some(aligned_offset)
     }
 
-    fn deallocate(&mut self, offset: usize, size: usize) {
+    /// Free a block if `generation` is the one it was handed out with
+    fn deallocate(&mut self, offset: usize, size: usize, generation: u64) -> bool {
+        if self.generations.get(&offset) != Some(&generation) {
+            return false;
+        }
+        self.generations.remove(&offset);
+
         // Simple strategy: just add to free list
         // A more sophisticated implementation would coalesce adjacent blocks
         self.free_list.insert(offset, size);
+        self.live_bytes = self.live_bytes.saturating_sub(size);
+        true
+    }
+
+    /// Merge free blocks separated only by alignment padding, fold a block
+    /// ending at the bump pointer back into the bump region, and return every
+    /// free range as `(offset, len)`, the bump region last
+    fn trim(&mut self) -> Vec<(usize, usize)> {
+        let alignment = self.alignment;
+        let align = |n: usize| (n + alignment - 1) & !(alignment - 1);
+
+        let mut merged = BTreeMap::new();
+        let mut current: Option<(usize, usize)> = None;
+        for (offset, size) in std::mem::take(&mut self.free_list) {
+            match &mut current {
+                Some((start, len)) if offset <= align(*start + *len) => {
+                    *len = (*len).max(offset + size - *start);
+                }
+                _ => {
+                    if let Some((start, len)) = current.replace((offset, size)) {
+                        merged.insert(start, len);
+                    }
+                }
+            }
+        }
+        if let Some((start, len)) = current {
+            if align(start + len) >= self.offset {
+                self.offset = start;
+            } else {
+                merged.insert(start, len);
+            }
+        }
+        self.free_list = merged;
+
+        let mut ranges: Vec<_> = self
+            .free_list
+            .iter()
+            .map(|(&off, &len)| (off, len))
+            .collect();
+        ranges.push((self.offset, self.capacity - self.offset));
+        ranges
     }
 
+    /// Largest single allocation that would succeed right now
+    fn largest_free(&self) -> usize {
+        let aligned_offset = (self.offset + self.alignment - 1) & !(self.alignment - 1);
+        let bump = self.capacity.saturating_sub(aligned_offset);
+        self.free_list.values().copied().fold(bump, usize::max)
+    }
+
+    /// Live bytes, not the bump high-water mark
     fn used(&self) -> usize {
-        self.offset
+        self.live_bytes
     }
 
     fn available(&self) -> usize {
@@ -97,16 +240,301 @@ impl BumpAllocator {
     }
 }
 
+/// One size class: a bump allocator over `[base, base + capacity)` of the pool
+struct ClassAllocator {
+    max_size: usize,
+    base: usize,
+    allocator: BumpAllocator,
+}
+
+/// Occupancy of one pool shard (size class)
+#[derive(Clone, Debug)]
+pub struct ShardStats {
+    /// Largest allocation routed to the class (`usize::MAX` for the final class)
+    pub max_size: usize,
+    pub total: usize,
+    pub used: usize,
+    pub available: usize,
+}
+
+/// Split the pool into class regions, the last one taking the remaining capacity
+fn build_classes(config: &MemoryPoolConfig) -> Result<Vec<ClassAllocator>> {
+    let align = |n: usize| (n + config.alignment - 1) & !(config.alignment - 1);
+
+    let mut classes = Vec::with_capacity(config.size_classes.len() + 1);
+    let mut base = 0;
+    let mut prev_max = 0;
+    for class in &config.size_classes {
+        if class.max_size <= prev_max {
+            return Err(anyhow!(
+                "Size classes must have ascending, non-zero max_size"
+            ));
+        }
+        if base + class.capacity > config.size {
+            return Err(anyhow!(
+                "Size classes reserve more than the pool size ({} bytes)",
+                config.size
+            ));
+        }
+        classes.push(ClassAllocator {
+            max_size: class.max_size,
+            base,
+            allocator: BumpAllocator::new(class.capacity, config.alignment),
+        });
+        prev_max = class.max_size;
+        base = align(base + class.capacity).min(config.size);
+    }
+    classes.push(ClassAllocator {
+        max_size: usize::MAX,
+        base,
+        allocator: BumpAllocator::new(config.size - base, config.alignment),
+    });
+
+    Ok(classes)
+}
+
+/// The pool's memory, owned through a raw pointer
+///
+/// Allocations are written through `&self` while other regions are being
+/// read, so every access derives from this pointer rather than from a
+/// `Vec` or slice the writes would alias.
+///
+/// On a CUDA device the pointer is device memory, which the host only
+/// reaches through `copy_in` and `copy_out`.
+struct PoolBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+    device: ValueDevice,
+}
+
+impl PoolBuffer {
+    /// Allocate `len` zeroed bytes on `device`
+    fn zeroed(len: usize, device: ValueDevice) -> Result<Self> {
+        let ptr = match device {
+            ValueDevice::Host => {
+                let buffer: Box<[u8]> = vec![0u8; len].into_boxed_slice();
+                // SAFETY: `Box::into_raw` never returns null
+                unsafe { NonNull::new_unchecked(Box::into_raw(buffer) as *mut u8) }
+            }
+            #[cfg(feature = "rdma")]
+            ValueDevice::Cuda { device_id } => cuda::alloc_zeroed(device_id, len)?,
+            #[cfg(not(feature = "rdma"))]
+            ValueDevice::Cuda { device_id } => {
+                return Err(anyhow!(
+                    "Cannot allocate the memory pool on CUDA device {}: built without the \
+                     'rdma' feature (cuda-lib)",
+                    device_id
+                ))
+            }
+        };
+        Ok(Self { ptr, len, device })
+    }
+
+    fn as_ptr(&self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    fn is_host(&self) -> bool {
+        self.device == ValueDevice::Host
+    }
+
+    /// Copy `data` to `offset`
+    ///
+    /// # Safety
+    ///
+    /// The range must be in bounds and not accessed by anyone else meanwhile.
+    unsafe fn copy_in(&self, offset: usize, data: &[u8]) -> Result<()> {
+        let dst = self.as_ptr().add(offset);
+        match self.device {
+            ValueDevice::Host => {
+                std::ptr::copy_nonoverlapping(data.as_ptr(), dst, data.len());
+                Ok(())
+            }
+            #[cfg(feature = "rdma")]
+            ValueDevice::Cuda { .. } => cuda::copy_to_device(dst, data),
+            #[cfg(not(feature = "rdma"))]
+            ValueDevice::Cuda { .. } => unreachable!("device buffers need the rdma feature"),
+        }
+    }
+
+    /// Copy the bytes at `offset` into `out`
+    ///
+    /// # Safety
+    ///
+    /// The range must be in bounds and not written meanwhile.
+    unsafe fn copy_out(&self, offset: usize, out: &mut [u8]) -> Result<()> {
+        let src = self.as_ptr().add(offset);
+        match self.device {
+            ValueDevice::Host => {
+                std::ptr::copy_nonoverlapping(src, out.as_mut_ptr(), out.len());
+                Ok(())
+            }
+            #[cfg(feature = "rdma")]
+            ValueDevice::Cuda { .. } => cuda::copy_from_device(out, src),
+            #[cfg(not(feature = "rdma"))]
+            ValueDevice::Cuda { .. } => unreachable!("device buffers need the rdma feature"),
+        }
+    }
+
+    /// Fail unless the buffer is host memory that can be borrowed directly
+    fn check_host(&self) -> Result<()> {
+        if self.is_host() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Memory pool is on {:?}; its bytes can only be copied out (read_chunks)",
+                self.device
+            ))
+        }
+    }
+
+    /// Whether `[offset, offset + len)` lies within the buffer
+    fn contains(&self, offset: usize, len: usize) -> bool {
+        offset.checked_add(len).is_some_and(|end| end <= self.len)
+    }
+
+    /// # Safety
+    ///
+    /// The range must be in bounds and not written for the lifetime of the slice.
+    unsafe fn slice(&self, offset: usize, len: usize) -> &[u8] {
+        std::slice::from_raw_parts(self.as_ptr().add(offset), len)
+    }
+}
+
+impl Drop for PoolBuffer {
+    fn drop(&mut self) {
+        match self.device {
+            // SAFETY: `ptr` and `len` came from the boxed slice in `zeroed`
+            ValueDevice::Host => drop(unsafe {
+                Box::from_raw(std::ptr::slice_from_raw_parts_mut(self.as_ptr(), self.len))
+            }),
+            #[cfg(feature = "rdma")]
+            ValueDevice::Cuda { device_id } => cuda::free(device_id, self.ptr),
+            #[cfg(not(feature = "rdma"))]
+            ValueDevice::Cuda { .. } => unreachable!("device buffers need the rdma feature"),
+        }
+    }
+}
+
+/// Device memory for `ValueDevice::Cuda` pools, through the CUDA runtime
+#[cfg(feature = "rdma")]
+mod cuda {
+    use anyhow::{anyhow, Result};
+    use cuda_lib::rt::{
+        cudaError, cudaFree, cudaMalloc, cudaMemcpy, cudaMemcpyKind, cudaMemset, cudaSetDevice,
+    };
+    use std::ffi::c_void;
+    use std::ptr::NonNull;
+
+    fn check(call: &str, status: cudaError) -> Result<()> {
+        if status == cudaError::cudaSuccess {
+            Ok(())
+        } else {
+            Err(anyhow!("{} failed: {:?}", call, status))
+        }
+    }
+
+    pub(super) fn alloc_zeroed(device_id: u32, len: usize) -> Result<NonNull<u8>> {
+        let device = i32::try_from(device_id)
+            .map_err(|_| anyhow!("Invalid CUDA device id {}", device_id))?;
+        let mut ptr: *mut c_void = std::ptr::null_mut();
+        // SAFETY: plain CUDA runtime calls; `ptr` is only used once cudaMalloc succeeded
+        unsafe {
+            check("cudaSetDevice", cudaSetDevice(device))?;
+            check("cudaMalloc", cudaMalloc(&mut ptr, len))?;
+            if let Err(e) = check("cudaMemset", cudaMemset(ptr, 0, len)) {
+                cudaFree(ptr);
+                return Err(e);
+            }
+        }
+        let ptr =
+            NonNull::new(ptr as *mut u8).ok_or_else(|| anyhow!("cudaMalloc returned null"))?;
+        tracing::info!("Allocated {} bytes on CUDA device {}", len, device_id);
+        Ok(ptr)
+    }
+
+    pub(super) fn free(device_id: u32, ptr: NonNull<u8>) {
+        // SAFETY: `ptr` came from cudaMalloc in `alloc_zeroed` and is freed once
+        let status = unsafe { cudaFree(ptr.as_ptr() as *mut c_void) };
+        if let Err(e) = check("cudaFree", status) {
+            tracing::warn!("Failed to free pool on CUDA device {}: {}", device_id, e);
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `dst` must be device memory with room for `src`.
+    pub(super) unsafe fn copy_to_device(dst: *mut u8, src: &[u8]) -> Result<()> {
+        check(
+            "cudaMemcpy",
+            cudaMemcpy(
+                dst as *mut c_void,
+                src.as_ptr() as *const c_void,
+                src.len(),
+                cudaMemcpyKind::cudaMemcpyHostToDevice,
+            ),
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `src` must be device memory holding at least `dst.len()` bytes.
+    pub(super) unsafe fn copy_from_device(dst: &mut [u8], src: *const u8) -> Result<()> {
+        check(
+            "cudaMemcpy",
+            cudaMemcpy(
+                dst.as_mut_ptr() as *mut c_void,
+                src as *const c_void,
+                dst.len(),
+                cudaMemcpyKind::cudaMemcpyDeviceToHost,
+            ),
+        )
+    }
+}
+
+// SAFETY: the buffer is plain memory owned by the pool; which regions may be
+// touched from which thread is governed by the allocator
+unsafe impl Send for PoolBuffer {}
+unsafe impl Sync for PoolBuffer {}
+
 /// Memory pool for RDMA-registered buffers
 pub struct MemoryPool {
     /// The actual memory buffer
-    buffer: Vec<u8>,
+    buffer: PoolBuffer,
     /// Memory region handle for local access
     handle: MemoryRegionHandle,
     /// Memory region descriptor for remote access
     descriptor: MemoryRegionDescriptor,
-    /// Allocator state
-    allocator: Mutex<BumpAllocator>,
+    /// Allocator state, one per size class, ordered by `max_size`
+    classes: Mutex<Vec<ClassAllocator>>,
+    /// Successful `allocate` calls so far
+    allocations: AtomicU64,
+    /// Source of `PoolAllocation::generation`, unique per allocation
+    next_generation: AtomicU64,
+    watermarks: Option<Watermarks>,
+    /// Whether usage has reached the high watermark and not yet fallen back
+    /// to the low one
+    above_high: AtomicBool,
+    watermark_events: broadcast::Sender<WatermarkEvent>,
+    /// Registered with RDMA hardware, which holds on to the pages it pinned
+    pinned: bool,
+    /// Transport the buffer is registered with, deregistered on drop
+    transport: Option<Arc<RdmaTransport>>,
+}
+
+impl Drop for MemoryPool {
+    fn drop(&mut self) {
+        // Before `buffer` is freed, so no transfer is validated against it after
+        if let Some(transport) = &self.transport {
+            if let Err(e) = transport.deregister_memory(&self.handle) {
+                tracing::warn!(
+                    "Failed to deregister {}-byte memory pool: {}",
+                    self.buffer.len,
+                    e
+                );
+            }
+        }
+    }
 }
 
 impl MemoryPool {
@@ -114,20 +542,38 @@ impl MemoryPool {
     ///
     /// This will:
     /// 1. Allocate page-aligned memory
-    /// 2. Register it with the RDMA transport (if provided)
+    /// 2. Register it with the RDMA transport (if provided), retrying with
+    ///    backoff up to `registration_attempts` times
     /// 3. Get the memory region descriptor for remote access
+    ///
+    /// If registration keeps failing the buffer is freed before returning.
+    /// Dropping the pool deregisters it.
     pub fn new(
         config: MemoryPoolConfig,
         _node_id: u32,
-        transport: Option<&crate::transport::RdmaTransport>,
+        transport: Option<&Arc<RdmaTransport>>,
     ) -> Result<Self> {
+        if let Some(watermarks) = &config.watermarks {
+            if watermarks.low_bytes > watermarks.high_bytes {
+                return Err(anyhow!(
+                    "Low watermark {} is above the high watermark {}",
+                    watermarks.low_bytes,
+                    watermarks.high_bytes
+                ));
+            }
+        }
+
+        // Before allocating, so a bad layout can't strand a registered buffer
+        let classes = Mutex::new(build_classes(&config)?);
+
         // Allocate aligned buffer
-        let mut buffer = vec![0u8; config.size];
-        let ptr = buffer.as_mut_ptr();
+        let buffer = PoolBuffer::zeroed(config.size, config.device)?;
+        let ptr = buffer.as_ptr();
 
         // Register memory with RDMA transport if provided
         let (handle, descriptor) = if let Some(transport) = transport {
-            transport.register_memory(ptr, config.size)?
+            // On failure `buffer` is dropped here; a failed registration holds nothing
+            register_with_retry(transport, ptr, &config)?
         } else {
             // Fallback: create fake registration for testing
             let handle = MemoryRegionHandle::new(ptr as u64, config.size);
@@ -135,51 +581,330 @@ impl MemoryPool {
             (handle, descriptor)
         };
 
-        let allocator = Mutex::new(BumpAllocator::new(config.size, config.alignment));
-
         Ok(Self {
             buffer,
             handle,
             descriptor,
-            allocator,
+            classes,
+            allocations: AtomicU64::new(0),
+            next_generation: AtomicU64::new(1),
+            watermarks: config.watermarks,
+            above_high: AtomicBool::new(false),
+            watermark_events: broadcast::channel(WATERMARK_CHANNEL_CAPACITY).0,
+            pinned: transport.is_some_and(|transport| !transport.is_mock()),
+            transport: transport.cloned(),
         })
     }
 
+    /// Receive an event each time usage crosses a configured watermark
+    pub fn subscribe_watermarks(&self) -> broadcast::Receiver<WatermarkEvent> {
+        self.watermark_events.subscribe()
+    }
+
+    /// Raise a watermark event if usage just crossed one; called with the
+    /// allocator lock held so crossings are seen in order
+    fn check_watermarks(&self, classes: &[ClassAllocator]) {
+        let Some(watermarks) = &self.watermarks else {
+            return;
+        };
+        let used = classes.iter().map(|class| class.allocator.used()).sum();
+        let above = self.above_high.load(Ordering::Relaxed);
+        let event = if !above && used >= watermarks.high_bytes {
+            tracing::warn!(
+                "Memory pool reached its high watermark: {} bytes used",
+                used
+            );
+            WatermarkEvent::High { used }
+        } else if above && used <= watermarks.low_bytes {
+            tracing::info!(
+                "Memory pool back under its low watermark: {} bytes used",
+                used
+            );
+            WatermarkEvent::Low { used }
+        } else {
+            return;
+        };
+        self.above_high.store(!above, Ordering::Relaxed);
+        // Nobody listening is fine
+        let _ = self.watermark_events.send(event);
+    }
+
     /// Allocate a region within the pool
+    ///
+    /// Uses the smallest size class that fits, spilling into larger classes
+    /// when it is full; large allocations never land in small classes.
     pub fn allocate(&self, size: usize) -> Result<PoolAllocation> {
-        let offset = self
-            .allocator
-            .lock()
-            .allocate(size)
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        let mut classes = self.classes.lock();
+        let offset = classes
+            .iter_mut()
+            .filter(|class| size <= class.max_size)
+            .find_map(|class| {
+                class
+                    .allocator
+                    .allocate(size, generation)
+                    .map(|off| class.base + off)
+            })
             .ok_or_else(|| anyhow!("Memory pool exhausted"))?;
+        self.check_watermarks(&classes);
+        drop(classes);
+        self.allocations.fetch_add(1, Ordering::Relaxed);
 
         Ok(PoolAllocation {
             offset,
             size,
-            ptr: unsafe { self.buffer.as_ptr().add(offset) as *mut u8 },
+            ptr: unsafe { self.buffer.as_ptr().add(offset) },
+            generation,
         })
     }
 
+    /// Allocate `size` bytes, split across up to `max_chunks` regions if no
+    /// single free block is large enough
+    ///
+    /// Returns one allocation whenever a contiguous one fits. Otherwise each
+    /// chunk takes the largest free block left, in the order the value's bytes
+    /// are laid out; if that takes more than `max_chunks` regions nothing is
+    /// allocated. Chunks come from the classes `size` itself is routed to.
+    pub fn allocate_chunked(&self, size: usize, max_chunks: usize) -> Result<Vec<PoolAllocation>> {
+        match self.allocate(size) {
+            Ok(allocation) => return Ok(vec![allocation]),
+            Err(e) if max_chunks <= 1 => return Err(e),
+            Err(_) => {}
+        }
+
+        let mut classes = self.classes.lock();
+        let mut chunks = Vec::new();
+        let mut remaining = size;
+        while remaining > 0 && chunks.len() < max_chunks {
+            let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+            let chunk = classes
+                .iter_mut()
+                .filter(|class| size <= class.max_size)
+                .filter_map(|class| {
+                    let len = class.allocator.largest_free().min(remaining);
+                    (len > 0).then_some((class, len))
+                })
+                .max_by_key(|(_, len)| *len)
+                .and_then(|(class, len)| {
+                    let offset = class.allocator.allocate(len, generation)?;
+                    Some((class.base + offset, len))
+                });
+            let Some((offset, len)) = chunk else { break };
+            chunks.push(PoolAllocation {
+                offset,
+                size: len,
+                ptr: unsafe { self.buffer.as_ptr().add(offset) },
+                generation,
+            });
+            remaining -= len;
+        }
+
+        if remaining > 0 {
+            for chunk in &chunks {
+                let idx = classes.partition_point(|class| class.base <= chunk.offset) - 1;
+                let class = &mut classes[idx];
+                class
+                    .allocator
+                    .deallocate(chunk.offset - class.base, chunk.size, chunk.generation);
+            }
+            return Err(anyhow!(
+                "Memory pool exhausted: {} bytes don't fit in {} free regions",
+                size,
+                max_chunks
+            ));
+        }
+        self.check_watermarks(&classes);
+        drop(classes);
+        self.allocations
+            .fetch_add(chunks.len() as u64, Ordering::Relaxed);
+        tracing::debug!(
+            "Split a {}-byte allocation into {} chunks",
+            size,
+            chunks.len()
+        );
+        Ok(chunks)
+    }
+
     /// Deallocate a region
-    pub fn deallocate(&self, allocation: &PoolAllocation) {
-        self.allocator.lock().deallocate(allocation.offset, allocation.size);
+    ///
+    /// An allocation that was already freed (even if its region has since been
+    /// handed out again) is ignored, returning false.
+    pub fn deallocate(&self, allocation: &PoolAllocation) -> bool {
+        let mut classes = self.classes.lock();
+        // The owning class is the last one starting at or before the offset
+        let idx = classes.partition_point(|class| class.base <= allocation.offset) - 1;
+        let class = &mut classes[idx];
+        let freed = class.allocator.deallocate(
+            allocation.offset - class.base,
+            allocation.size,
+            allocation.generation,
+        );
+        if freed {
+            self.check_watermarks(&classes);
+        } else {
+            tracing::warn!(
+                "Ignoring stale deallocation of offset {} (generation {})",
+                allocation.offset,
+                allocation.generation
+            );
+        }
+        freed
+    }
+
+    /// Return the pages of free regions to the OS, keeping the mapping
+    ///
+    /// Covers the space above each class's bump pointer, after folding free
+    /// blocks at the top back under it, and the whole pages inside the other
+    /// free blocks. Released pages fault back in zeroed when next allocated.
+    /// Returns the bytes released; always 0 off Linux, for device pools, and
+    /// for pools registered with RDMA hardware, where the NIC would keep using
+    /// the old pinned pages.
+    pub fn trim(&self) -> usize {
+        if self.pinned {
+            tracing::debug!("Not trimming a pool pinned by RDMA registration");
+            return 0;
+        }
+        if !self.buffer.is_host() {
+            tracing::debug!("Not trimming a pool in device memory");
+            return 0;
+        }
+        let mut classes = self.classes.lock();
+        let mut released = 0;
+        for class in classes.iter_mut() {
+            for (offset, len) in class.allocator.trim() {
+                released += self.release_pages(class.base + offset, len);
+            }
+        }
+        drop(classes);
+        tracing::info!("Trimmed memory pool: released {} bytes", released);
+        released
+    }
+
+    /// `madvise(MADV_DONTNEED)` the whole pages within `[offset, offset + len)`
+    ///
+    /// Called with the allocator lock held, on free space only.
+    #[cfg(target_os = "linux")]
+    fn release_pages(&self, offset: usize, len: usize) -> usize {
+        // SAFETY: sysconf has no preconditions
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let start = self.buffer.as_ptr() as usize + offset;
+        let first = (start + page - 1) & !(page - 1);
+        let last = (start + len) & !(page - 1);
+        if last <= first {
+            return 0;
+        }
+        // SAFETY: the pages are free pool memory, so nothing reads or writes
+        // them until they are allocated again, which the held lock prevents
+        let rc = unsafe {
+            libc::madvise(
+                first as *mut libc::c_void,
+                last - first,
+                libc::MADV_DONTNEED,
+            )
+        };
+        if rc != 0 {
+            tracing::warn!("madvise failed: {}", std::io::Error::last_os_error());
+            return 0;
+        }
+        last - first
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn release_pages(&self, _offset: usize, _len: usize) -> usize {
+        0
     }
 
     /// Write data to a specific offset in the pool
     pub fn write(&mut self, offset: usize, data: &[u8]) -> Result<()> {
-        if offset + data.len() > self.buffer.len() {
+        if !self.buffer.contains(offset, data.len()) {
             return Err(anyhow!("Write exceeds pool bounds"));
         }
-        self.buffer[offset..offset + data.len()].copy_from_slice(data);
+        // SAFETY: in bounds, and `&mut self` excludes every other access
+        unsafe { self.buffer.copy_in(offset, data) }
+    }
+
+    /// Copy `data` into an allocation the caller owns
+    ///
+    /// Takes `&self` because nobody else touches a region until its owner
+    /// publishes it, so writers can fill their own regions concurrently.
+    pub fn write_allocation(&self, allocation: &PoolAllocation, data: &[u8]) -> Result<()> {
+        if data.len() > allocation.size {
+            return Err(anyhow!(
+                "Write of {} bytes exceeds the {}-byte allocation",
+                data.len(),
+                allocation.size
+            ));
+        }
+        // SAFETY: the allocation lies within the buffer and is exclusively the caller's
+        unsafe { self.buffer.copy_in(allocation.offset, data) }
+    }
+
+    /// Copy `data` across allocations the caller owns, filling each in turn
+    pub fn write_chunks(&self, chunks: &[PoolAllocation], data: &[u8]) -> Result<()> {
+        let capacity: usize = chunks.iter().map(|chunk| chunk.size).sum();
+        if data.len() > capacity {
+            return Err(anyhow!(
+                "Write of {} bytes exceeds the {} bytes allocated",
+                data.len(),
+                capacity
+            ));
+        }
+        let mut rest = data;
+        for chunk in chunks {
+            let (part, tail) = rest.split_at(chunk.size.min(rest.len()));
+            self.write_allocation(chunk, part)?;
+            rest = tail;
+        }
         Ok(())
     }
 
+    /// Concatenate the contents of allocations, in order
+    ///
+    /// Copies out of device memory too, unlike `read`.
+    pub fn read_chunks(&self, chunks: &[PoolAllocation]) -> Result<Vec<u8>> {
+        let mut data = vec![0u8; chunks.iter().map(|chunk| chunk.size).sum()];
+        let mut rest = data.as_mut_slice();
+        for chunk in chunks {
+            if !self.buffer.contains(chunk.offset, chunk.size) {
+                return Err(anyhow!("Read exceeds pool bounds"));
+            }
+            let (part, tail) = rest.split_at_mut(chunk.size);
+            // SAFETY: in bounds; the caller owns the allocations, so nobody writes them
+            unsafe { self.buffer.copy_out(chunk.offset, part)? };
+            rest = tail;
+        }
+        Ok(data)
+    }
+
     /// Read data from a specific offset in the pool
+    ///
+    /// Fails for device pools, whose bytes can't be borrowed from the host.
     pub fn read(&self, offset: usize, len: usize) -> Result<&[u8]> {
-        if offset + len > self.buffer.len() {
+        self.buffer.check_host()?;
+        if !self.buffer.contains(offset, len) {
             return Err(anyhow!("Read exceeds pool bounds"));
         }
-        Ok(&self.buffer[offset..offset + len])
+        // SAFETY: in bounds; a region being read is published, so its owner
+        // no longer writes it
+        Ok(unsafe { self.buffer.slice(offset, len) })
+    }
+
+    /// Borrow pool bytes in place, holding the pool's read lock
+    ///
+    /// Unlike `read` on a short-lived lock guard, the returned guard keeps the
+    /// lock, so the bytes stay valid (no reset or grow can take the write lock)
+    /// for as long as the guard lives, and nothing is copied.
+    pub fn read_guard(
+        pool: &RwLock<MemoryPool>,
+        offset: usize,
+        len: usize,
+    ) -> Result<PoolReadGuard<'_>> {
+        let guard = pool.read();
+        guard.buffer.check_host()?;
+        if !guard.buffer.contains(offset, len) {
+            return Err(anyhow!("Read exceeds pool bounds"));
+        }
+        Ok(PoolReadGuard { guard, offset, len })
     }
 
     /// Get the local memory region handle
@@ -199,27 +924,74 @@ impl MemoryPool {
 
     /// Get a mutable pointer to the buffer at a specific offset
     pub fn ptr_at_mut(&mut self, offset: usize) -> *mut u8 {
-        unsafe { self.buffer.as_mut_ptr().add(offset) }
+        unsafe { self.buffer.as_ptr().add(offset) }
     }
 
     /// Get pool statistics
     pub fn stats(&self) -> PoolStats {
-        let alloc = self.allocator.lock();
+        let shards = self.class_stats();
         PoolStats {
-            total: self.buffer.len(),
-            used: alloc.used(),
-            available: alloc.available(),
+            total: self.buffer.len,
+            used: shards.iter().map(|s| s.used).sum(),
+            available: shards.iter().map(|s| s.available).sum(),
+            allocations: self.allocations.load(Ordering::Relaxed),
+            shards: (shards.len() > 1).then_some(shards),
         }
     }
 
+    /// Occupancy of each size class, smallest first
+    pub fn class_stats(&self) -> Vec<ShardStats> {
+        self.classes
+            .lock()
+            .iter()
+            .map(|class| ShardStats {
+                max_size: class.max_size,
+                total: class.allocator.capacity,
+                used: class.allocator.used(),
+                available: class.allocator.available(),
+            })
+            .collect()
+    }
+
     /// Get a reference to the underlying buffer
+    ///
+    /// Panics for device pools.
     pub fn buffer(&self) -> &[u8] {
-        &self.buffer
+        assert!(
+            self.buffer.is_host(),
+            "device pool memory can't be borrowed"
+        );
+        // SAFETY: the whole buffer is in bounds
+        unsafe { self.buffer.slice(0, self.buffer.len) }
     }
 
     /// Get a mutable reference to the underlying buffer
+    ///
+    /// Panics for device pools.
     pub fn buffer_mut(&mut self) -> &mut [u8] {
-        &mut self.buffer
+        assert!(
+            self.buffer.is_host(),
+            "device pool memory can't be borrowed"
+        );
+        // SAFETY: the whole buffer is in bounds, and `&mut self` excludes
+        // every other access
+        unsafe { std::slice::from_raw_parts_mut(self.buffer.as_ptr(), self.buffer.len) }
+    }
+}
+
+/// Read-locked view of a range of pool memory; derefs to `[u8]`
+pub struct PoolReadGuard<'a> {
+    guard: RwLockReadGuard<'a, MemoryPool>,
+    offset: usize,
+    len: usize,
+}
+
+impl std::ops::Deref for PoolReadGuard<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: bounds were checked in `read_guard`
+        unsafe { self.guard.buffer.slice(self.offset, self.len) }
     }
 }
 
@@ -229,6 +1001,8 @@ pub struct PoolAllocation {
     pub offset: usize,
     pub size: usize,
     pub ptr: *mut u8,
+    /// Distinguishes this allocation from later ones reusing the same offset
+    pub generation: u64,
 }
 
 // PoolAllocation needs to be Send for async usage
@@ -241,6 +1015,77 @@ pub struct PoolStats {
     pub total: usize,
     pub used: usize,
     pub available: usize,
+    /// Cumulative successful allocations
+    pub allocations: u64,
+    /// Per-shard breakdown; `None` when the pool isn't sharded
+    pub shards: Option<Vec<ShardStats>>,
+}
+
+impl std::fmt::Display for PoolStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const MB: f64 = 1024.0 * 1024.0;
+        write!(
+            f,
+            "total={:.1} MB, used={:.1} MB, available={:.1} MB",
+            self.total as f64 / MB,
+            self.used as f64 / MB,
+            self.available as f64 / MB
+        )?;
+        for (i, shard) in self.shards.iter().flatten().enumerate() {
+            let limit = match shard.max_size {
+                usize::MAX => "rest".to_string(),
+                max_size => format!("<= {} B", max_size),
+            };
+            write!(
+                f,
+                "\n  shard {} ({}): used={:.1} MB, available={:.1} MB",
+                i,
+                limit,
+                shard.used as f64 / MB,
+                shard.available as f64 / MB
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Register the pool's buffer, retrying transient failures with backoff
+fn register_with_retry(
+    transport: &RdmaTransport,
+    ptr: *mut u8,
+    config: &MemoryPoolConfig,
+) -> Result<(MemoryRegionHandle, MemoryRegionDescriptor)> {
+    let attempts = config.registration_attempts.max(1);
+    let mut backoff = config.registration_backoff;
+    let mut attempt = 1;
+    loop {
+        match transport.register_memory_on(ptr, config.size, config.device) {
+            Ok(registered) => return Ok(registered),
+            Err(e) if attempt < attempts => {
+                tracing::warn!(
+                    "Registering {} byte memory pool failed (attempt {}/{}), retrying in {:?}: {}",
+                    config.size,
+                    attempt,
+                    attempts,
+                    backoff,
+                    e
+                );
+                std::thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to register {} byte memory pool with the RDMA transport after {} attempts: {}. \
+                     The whole pool is pinned at once; try a smaller pool (memory_pool_size on the \
+                     server, receive_buffer_size on clients)",
+                    config.size,
+                    attempts,
+                    e
+                ))
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -252,6 +1097,7 @@ mod tests {
         let config = MemoryPoolConfig {
             size: 4096,
             alignment: 64,
+            ..Default::default()
         };
         let pool = MemoryPool::new(config, 1, None).unwrap();
 
@@ -263,11 +1109,111 @@ mod tests {
         assert!(alloc2.offset >= 100); // Should be after first allocation
     }
 
+    #[test]
+    fn test_registration_is_retried_then_fails_cleanly() {
+        use crate::transport::TransportConfig;
+
+        let config = MemoryPoolConfig {
+            size: 64 * 1024,
+            registration_attempts: 3,
+            registration_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+
+        // Two failures are absorbed by the third attempt
+        let transport = Arc::new(
+            RdmaTransport::new(TransportConfig {
+                mock_registration_failures: 2,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let pool = MemoryPool::new(config.clone(), 1, Some(&transport)).unwrap();
+        assert_eq!(transport.registration_count(), 1);
+        drop(pool);
+
+        // Four outlast three attempts
+        let transport = Arc::new(
+            RdmaTransport::new(TransportConfig {
+                mock_registration_failures: 4,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let err = MemoryPool::new(config.clone(), 1, Some(&transport))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("register 65536 byte memory pool"), "{}", err);
+        assert!(err.contains("after 3 attempts"), "{}", err);
+        assert!(err.contains("smaller pool"), "{}", err);
+        assert_eq!(transport.registration_count(), 0);
+
+        // Exactly three were made, leaving one injected failure
+        let single = MemoryPoolConfig {
+            registration_attempts: 1,
+            ..config
+        };
+        assert!(MemoryPool::new(single.clone(), 1, Some(&transport)).is_err());
+        assert!(MemoryPool::new(single, 1, Some(&transport)).is_ok());
+    }
+
+    #[cfg(not(feature = "rdma"))]
+    #[test]
+    fn test_device_pool_needs_the_rdma_feature() {
+        let config = MemoryPoolConfig {
+            size: 64 * 1024,
+            device: ValueDevice::Cuda { device_id: 0 },
+            ..Default::default()
+        };
+        let err = MemoryPool::new(config, 1, None).err().unwrap().to_string();
+        assert!(err.contains("built without the 'rdma' feature"), "{}", err);
+    }
+
+    #[test]
+    fn test_dropping_pool_deregisters_it() {
+        use crate::transport::{DomainRouting, TransferRequest, TransportConfig};
+
+        let transport = Arc::new(RdmaTransport::new(TransportConfig::default()).unwrap());
+        let config = MemoryPoolConfig {
+            size: 64 * 1024,
+            ..Default::default()
+        };
+        let src = MemoryPool::new(config.clone(), 1, Some(&transport)).unwrap();
+        let dst = MemoryPool::new(config, 1, Some(&transport)).unwrap();
+        let dst_descriptor = dst.descriptor().clone();
+        assert_eq!(transport.registration_count(), 2);
+
+        drop(dst);
+        assert_eq!(transport.registration_count(), 1);
+        assert_eq!(transport.registered_bytes(), 64 * 1024);
+
+        // The freed pool's range is no longer a valid destination
+        let err = transport
+            .submit_transfer(TransferRequest {
+                src_handle: src.handle(),
+                src_offset: 0,
+                length: 16,
+                imm_data: None,
+                dst_descriptor,
+                dst_offset: 0,
+                routing: DomainRouting::default(),
+            })
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("was not registered in this process"),
+            "{}",
+            err
+        );
+    }
+
     #[test]
     fn test_memory_pool_write_read() {
         let config = MemoryPoolConfig {
             size: 4096,
             alignment: 64,
+            ..Default::default()
         };
         let mut pool = MemoryPool::new(config, 1, None).unwrap();
 
@@ -277,4 +1223,285 @@ mod tests {
         let read_data = pool.read(0, data.len()).unwrap();
         assert_eq!(read_data, data);
     }
+
+    #[test]
+    fn test_reusing_unaligned_free_block_keeps_no_remainder() {
+        let mut allocator = BumpAllocator::new(4096, 64);
+        let offset = allocator.allocate(10, 1).unwrap();
+        assert!(allocator.deallocate(offset, 10, 1));
+
+        // The remainder starts at the next 64-byte boundary, past the block's end
+        assert_eq!(allocator.allocate(5, 2), Some(offset));
+        assert!(allocator.free_list.is_empty());
+    }
+
+    #[test]
+    fn test_used_tracks_live_bytes_across_free_list_reuse() {
+        let config = MemoryPoolConfig {
+            size: 4096,
+            alignment: 64,
+            ..Default::default()
+        };
+        let pool = MemoryPool::new(config, 1, None).unwrap();
+
+        let a = pool.allocate(256).unwrap();
+        let b = pool.allocate(128).unwrap();
+        assert_eq!(pool.stats().used, 384);
+
+        pool.deallocate(&a);
+        assert_eq!(pool.stats().used, 128);
+
+        // Served from the free list: the bump offset doesn't move, but used does
+        let c = pool.allocate(200).unwrap();
+        assert_eq!(c.offset, a.offset);
+        assert_eq!(pool.stats().used, 328);
+
+        pool.deallocate(&b);
+        pool.deallocate(&c);
+        assert_eq!(pool.stats().used, 0);
+    }
+
+    #[test]
+    fn test_stale_allocation_cannot_free_reallocated_region() {
+        let config = MemoryPoolConfig {
+            size: 4096,
+            alignment: 64,
+            ..Default::default()
+        };
+        let pool = MemoryPool::new(config, 1, None).unwrap();
+
+        let stale = pool.allocate(256).unwrap();
+        assert!(pool.deallocate(&stale));
+        let current = pool.allocate(256).unwrap();
+        assert_eq!(current.offset, stale.offset);
+
+        // The old token no longer owns the region
+        assert!(!pool.deallocate(&stale));
+        assert_eq!(pool.stats().used, 256);
+        let other = pool.allocate(256).unwrap();
+        assert_ne!(other.offset, current.offset);
+
+        assert!(pool.deallocate(&current));
+        assert!(!pool.deallocate(&current));
+    }
+
+    #[test]
+    fn test_size_classes_isolate_small_allocations() {
+        let config = MemoryPoolConfig {
+            size: 4 * 1024 * 1024,
+            alignment: 64,
+            size_classes: vec![
+                SizeClass {
+                    max_size: 1024,
+                    capacity: 64 * 1024,
+                },
+                SizeClass {
+                    max_size: 16 * 1024,
+                    capacity: 512 * 1024,
+                },
+            ],
+            ..Default::default()
+        };
+        let pool = MemoryPool::new(config, 1, None).unwrap();
+
+        let small: Vec<_> = (0..100).map(|_| pool.allocate(100).unwrap()).collect();
+        let medium: Vec<_> = (0..10).map(|_| pool.allocate(10 * 1024).unwrap()).collect();
+        let small_available = pool.class_stats()[0].available;
+
+        // Churn large values of varying (shrinking, so the free list can serve them) sizes
+        for round in 0..50 {
+            let large = pool.allocate(100 * 1024 + (50 - round) * 4096).unwrap();
+            assert!(
+                large.offset >= 64 * 1024 + 512 * 1024,
+                "large allocation in a small class"
+            );
+            pool.deallocate(&large);
+        }
+
+        let stats = pool.class_stats();
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats[0].used, 100 * 100);
+        assert_eq!(stats[1].used, 10 * 10 * 1024);
+        assert_eq!(stats[2].used, 0);
+        // The small class is untouched by the churn
+        assert_eq!(stats[0].available, small_available);
+        assert!(small.iter().all(|a| a.offset < 64 * 1024));
+        assert!(medium
+            .iter()
+            .all(|a| (64 * 1024..576 * 1024).contains(&a.offset)));
+
+        for a in small.iter().chain(&medium) {
+            pool.deallocate(a);
+        }
+        assert_eq!(pool.stats().used, 0);
+    }
+
+    #[test]
+    fn test_read_guard_borrows_pool_bytes() {
+        let config = MemoryPoolConfig {
+            size: 4096,
+            alignment: 64,
+            ..Default::default()
+        };
+        let pool = RwLock::new(MemoryPool::new(config, 1, None).unwrap());
+        pool.write().write(128, b"zero-copy").unwrap();
+
+        let guard = MemoryPool::read_guard(&pool, 128, 9).unwrap();
+        assert_eq!(&*guard, b"zero-copy");
+        // Same memory as the pool, not a copy
+        assert_eq!(guard.as_ptr(), pool.read().ptr_at(128));
+        // Writers are held off while the guard lives
+        assert!(pool.try_write().is_none());
+        drop(guard);
+        assert!(pool.try_write().is_some());
+
+        assert!(MemoryPool::read_guard(&pool, 4090, 9).is_err());
+    }
+
+    #[test]
+    fn test_pool_stats_break_down_skewed_shards() {
+        let unsharded_config = MemoryPoolConfig {
+            size: 1024 * 1024,
+            ..Default::default()
+        };
+        let unsharded = MemoryPool::new(unsharded_config, 1, None).unwrap();
+        assert!(unsharded.stats().shards.is_none());
+
+        let config = MemoryPoolConfig {
+            size: 1024 * 1024,
+            alignment: 64,
+            size_classes: vec![SizeClass {
+                max_size: 1024,
+                capacity: 256 * 1024,
+            }],
+            ..Default::default()
+        };
+        let pool = MemoryPool::new(config, 1, None).unwrap();
+        for _ in 0..200 {
+            pool.allocate(512).unwrap();
+        }
+        pool.allocate(4096).unwrap();
+
+        let stats = pool.stats();
+        let shards = stats.shards.as_ref().unwrap();
+        assert_eq!(shards.len(), 2);
+        assert_eq!(shards[0].used, 200 * 512);
+        assert_eq!(shards[1].used, 4096);
+        assert_eq!(shards[0].available, 256 * 1024 - 200 * 512);
+        assert_eq!(stats.used, shards[0].used + shards[1].used);
+
+        let printed = stats.to_string();
+        assert!(printed.contains("shard 0 (<= 1024 B)"), "{}", printed);
+        assert!(printed.contains("shard 1 (rest)"), "{}", printed);
+    }
+
+    #[test]
+    fn test_watermark_events_fire_on_crossings() {
+        let config = MemoryPoolConfig {
+            size: 64 * 1024,
+            alignment: 64,
+            watermarks: Some(Watermarks {
+                high_bytes: 1000,
+                low_bytes: 500,
+            }),
+            ..Default::default()
+        };
+        let pool = MemoryPool::new(config, 1, None).unwrap();
+        let mut events = pool.subscribe_watermarks();
+
+        let a = pool.allocate(400).unwrap();
+        let b = pool.allocate(400).unwrap();
+        assert!(events.try_recv().is_err());
+
+        // 1200 bytes: over the high watermark, reported once
+        let c = pool.allocate(400).unwrap();
+        assert_eq!(
+            events.try_recv().unwrap(),
+            WatermarkEvent::High { used: 1200 }
+        );
+        let d = pool.allocate(100).unwrap();
+        assert!(events.try_recv().is_err());
+
+        // Dropping below high isn't enough; reaching low is
+        pool.deallocate(&d);
+        pool.deallocate(&c);
+        assert!(events.try_recv().is_err());
+        pool.deallocate(&b);
+        assert_eq!(
+            events.try_recv().unwrap(),
+            WatermarkEvent::Low { used: 400 }
+        );
+
+        let _e = pool.allocate(600).unwrap();
+        assert_eq!(
+            events.try_recv().unwrap(),
+            WatermarkEvent::High { used: 1000 }
+        );
+        pool.deallocate(&a);
+        assert!(events.try_recv().is_err());
+
+        let inverted = MemoryPoolConfig {
+            watermarks: Some(Watermarks {
+                high_bytes: 100,
+                low_bytes: 200,
+            }),
+            ..Default::default()
+        };
+        assert!(MemoryPool::new(inverted, 1, None).is_err());
+    }
+
+    /// Bytes of `buf` backed by resident pages
+    #[cfg(target_os = "linux")]
+    fn resident_bytes(buf: &[u8]) -> usize {
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let start = buf.as_ptr() as usize & !(page - 1);
+        let len = buf.as_ptr() as usize + buf.len() - start;
+        let mut pages = vec![0u8; len.div_ceil(page)];
+        let rc = unsafe { libc::mincore(start as *mut libc::c_void, len, pages.as_mut_ptr()) };
+        assert_eq!(rc, 0, "{}", std::io::Error::last_os_error());
+        pages.iter().filter(|&&p| p & 1 != 0).count() * page
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_trim_returns_free_pages_to_os() {
+        const MB: usize = 1024 * 1024;
+        let config = MemoryPoolConfig {
+            size: 32 * MB,
+            alignment: 4096,
+            ..Default::default()
+        };
+        let pool = MemoryPool::new(config, 1, None).unwrap();
+
+        let allocations: Vec<_> = (0..8).map(|_| pool.allocate(2 * MB).unwrap()).collect();
+        for allocation in &allocations {
+            pool.write_allocation(allocation, &vec![0xAB; 2 * MB])
+                .unwrap();
+        }
+        assert!(resident_bytes(pool.buffer()) >= 16 * MB);
+
+        // Free all but the first, leaving a free block between live ones too
+        let kept = &allocations[0];
+        let middle = pool.allocate(MB).unwrap();
+        let top = pool.allocate(MB).unwrap();
+        pool.write_allocation(&top, &vec![0xCD; MB]).unwrap();
+        for allocation in &allocations[1..] {
+            pool.deallocate(allocation);
+        }
+        pool.deallocate(&top);
+        assert!(resident_bytes(pool.buffer()) >= 16 * MB);
+
+        let released = pool.trim();
+        assert!(released >= 14 * MB, "released {} bytes", released);
+        let resident = resident_bytes(pool.buffer());
+        assert!(resident <= 4 * MB, "{} bytes still resident", resident);
+
+        // Live data survives, and the pool keeps working
+        let data = pool.read(kept.offset, 2 * MB).unwrap();
+        assert!(data.iter().all(|&b| b == 0xAB));
+        pool.deallocate(&middle);
+        assert_eq!(pool.stats().used, 2 * MB);
+        let again = pool.allocate(12 * MB).unwrap();
+        pool.write_allocation(&again, &vec![0xEF; 12 * MB]).unwrap();
+    }
 }
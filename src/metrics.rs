@@ -0,0 +1,165 @@
+//! Lock-free server and client metrics
+//!
+//! Latency histogram buckets are powers of two in microseconds, so recording is
+//! a couple of atomic adds and quantiles are accurate to within a factor of two.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Bucket `i` holds samples in `[2^i, 2^(i+1))` µs; the last also takes everything above
+const NUM_BUCKETS: usize = 40;
+
+/// Histogram of request latencies
+pub struct LatencyHistogram {
+    buckets: [AtomicU64; NUM_BUCKETS],
+    count: AtomicU64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            count: AtomicU64::new(0),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// Record one sample
+    pub fn record(&self, latency: Duration) {
+        let micros = latency.as_micros().max(1).min(u64::MAX as u128) as u64;
+        let bucket = (63 - micros.leading_zeros() as usize).min(NUM_BUCKETS - 1);
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of samples recorded
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Upper bound of the bucket containing quantile `q` (0.0..=1.0); `None` if empty
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            seen += bucket.load(Ordering::Relaxed);
+            if seen >= rank {
+                return Some(Duration::from_micros(1u64 << (i + 1)));
+            }
+        }
+        Some(Duration::from_micros(1u64 << NUM_BUCKETS))
+    }
+}
+
+/// Cumulative data-plane and control-plane traffic
+#[derive(Default)]
+pub struct TrafficCounters {
+    gets: AtomicU64,
+    rdma_bytes: AtomicU64,
+    control_bytes: AtomicU64,
+}
+
+impl TrafficCounters {
+    /// Record a served GET and the bytes it RDMA wrote (0 if not modified)
+    pub fn record_get(&self, rdma_bytes: u64) {
+        self.gets.fetch_add(1, Ordering::Relaxed);
+        self.rdma_bytes.fetch_add(rdma_bytes, Ordering::Relaxed);
+    }
+
+    /// Record an RPC's encoded request plus response size
+    pub fn record_control(&self, bytes: usize) {
+        self.control_bytes
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn gets(&self) -> u64 {
+        self.gets.load(Ordering::Relaxed)
+    }
+
+    pub fn rdma_bytes(&self) -> u64 {
+        self.rdma_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Protobuf message bytes; excludes HTTP/2 and gRPC framing
+    pub fn control_bytes(&self) -> u64 {
+        self.control_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Average RDMA bytes per served GET (0 before any)
+    pub fn bytes_per_get(&self) -> f64 {
+        match self.gets() {
+            0 => 0.0,
+            gets => self.rdma_bytes() as f64 / gets as f64,
+        }
+    }
+
+    /// Control-plane bytes per RDMA byte (0 before any RDMA traffic)
+    pub fn control_overhead_ratio(&self) -> f64 {
+        match self.rdma_bytes() {
+            0 => 0.0,
+            rdma => self.control_bytes() as f64 / rdma as f64,
+        }
+    }
+}
+
+/// Calls, failures and latency of one kind of client operation
+#[derive(Default)]
+pub struct OpRecorder {
+    latency: LatencyHistogram,
+    errors: AtomicU64,
+    total_micros: AtomicU64,
+}
+
+impl OpRecorder {
+    /// Record one call and whether it succeeded
+    pub fn record(&self, latency: Duration, ok: bool) {
+        self.latency.record(latency);
+        self.total_micros.fetch_add(
+            latency.as_micros().min(u64::MAX as u128) as u64,
+            Ordering::Relaxed,
+        );
+        if !ok {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> OpMetrics {
+        let count = self.latency.count();
+        OpMetrics {
+            count,
+            errors: self.errors.load(Ordering::Relaxed),
+            mean: Duration::from_micros(self.total_micros.load(Ordering::Relaxed) / count.max(1)),
+            p50: self.latency.quantile(0.5),
+            p99: self.latency.quantile(0.99),
+        }
+    }
+}
+
+/// Snapshot of an `OpRecorder`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OpMetrics {
+    /// Calls made, successful or not
+    pub count: u64,
+    /// Calls that returned an error, including GET misses
+    pub errors: u64,
+    /// Mean latency (zero before any call)
+    pub mean: Duration,
+    /// Median and 99th percentile latency, to within a factor of two
+    pub p50: Option<Duration>,
+    pub p99: Option<Duration>,
+}
+
+/// Operation metrics of a `KvCacheClient`, from `KvCacheClient::metrics`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClientMetrics {
+    /// Every GET variant; one `get_with_retry` counts each attempt
+    pub get: OpMetrics,
+    /// Inline and buffer PUTs, including put-if-absent
+    pub put: OpMetrics,
+    pub delete: OpMetrics,
+}
@@ -0,0 +1,125 @@
+//! Priority-ordered concurrency limit for GET transfers
+//!
+//! When every slot is busy, waiters queue by priority (higher first, FIFO within
+//! a priority) instead of strictly by arrival, so a flood of bulk reads can't
+//! starve latency-critical ones.
+
+use parking_lot::Mutex;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use tokio::sync::oneshot;
+
+struct Waiter {
+    priority: u8,
+    seq: Reverse<u64>,
+    wake: oneshot::Sender<()>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        (self.priority, self.seq) == (other.priority, other.seq)
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.priority, self.seq).cmp(&(other.priority, other.seq))
+    }
+}
+
+struct GateState {
+    available: usize,
+    next_seq: u64,
+    waiters: BinaryHeap<Waiter>,
+}
+
+/// Semaphore whose waiters are served in priority order
+pub struct PriorityGate {
+    state: Mutex<GateState>,
+}
+
+impl PriorityGate {
+    /// Gate allowing `slots` concurrent holders
+    pub fn new(slots: usize) -> Self {
+        Self {
+            state: Mutex::new(GateState {
+                available: slots,
+                next_seq: 0,
+                waiters: BinaryHeap::new(),
+            }),
+        }
+    }
+
+    /// Wait for a slot; higher `priority` is served first
+    pub async fn acquire(&self, priority: u8) -> PriorityPermit<'_> {
+        let mut pending = {
+            let mut state = self.state.lock();
+            if state.available > 0 {
+                state.available -= 1;
+                return PriorityPermit { gate: self };
+            }
+            let (tx, rx) = oneshot::channel();
+            let seq = Reverse(state.next_seq);
+            state.next_seq += 1;
+            state.waiters.push(Waiter {
+                priority,
+                seq,
+                wake: tx,
+            });
+            PendingSlot { gate: self, rx }
+        };
+
+        // The releasing permit hands its slot straight to us
+        let _ = (&mut pending.rx).await;
+        PriorityPermit { gate: self }
+    }
+
+    /// Number of callers waiting for a slot
+    pub fn waiting(&self) -> usize {
+        self.state.lock().waiters.len()
+    }
+
+    fn release(&self) {
+        let mut state = self.state.lock();
+        while let Some(waiter) = state.waiters.pop() {
+            if waiter.wake.send(()).is_ok() {
+                return;
+            }
+        }
+        state.available += 1;
+    }
+}
+
+/// A queued `acquire`; passes on a slot it was handed but never claimed
+struct PendingSlot<'a> {
+    gate: &'a PriorityGate,
+    rx: oneshot::Receiver<()>,
+}
+
+impl Drop for PendingSlot<'_> {
+    fn drop(&mut self) {
+        // Only succeeds if the acquire was cancelled after being woken
+        if self.rx.try_recv().is_ok() {
+            self.gate.release();
+        }
+    }
+}
+
+/// A held slot; released to the highest-priority waiter on drop
+pub struct PriorityPermit<'a> {
+    gate: &'a PriorityGate,
+}
+
+impl Drop for PriorityPermit<'_> {
+    fn drop(&mut self) {
+        self.gate.release();
+    }
+}
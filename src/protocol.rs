@@ -3,9 +3,20 @@
 //! These types are designed to be compatible with the fabric-lib RDMA library
 //! and can be serialized for network transmission.
 
+use crate::memory::PoolAllocation;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
+/// Layout version of `MemoryRegionDescriptor` produced by this build
+///
+/// Peers that predate versioning don't send the field, which decodes as 0;
+/// their layout is identical to version 1.
+pub const DESCRIPTOR_FORMAT_VERSION: u32 = 1;
+
+/// Control-plane protocol revision, reported to clients at registration
+pub const PROTOCOL_VERSION: u32 = 1;
+
 /// Network address of an RDMA domain (NIC)
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct DomainAddress(pub Vec<u8>);
@@ -20,6 +31,20 @@ impl DomainAddress {
     }
 }
 
+/// Printable ASCII addresses (like the mock's `mock://...`) print as text,
+/// anything else as hex; `Debug` keeps the raw bytes
+impl std::fmt::Display for DomainAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.0.is_empty() && self.0.iter().all(|b| b.is_ascii_graphic()) {
+            // All ASCII, so valid UTF-8
+            f.write_str(std::str::from_utf8(&self.0).unwrap())
+        } else {
+            f.write_str("0x")?;
+            self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+        }
+    }
+}
+
 /// Remote key for RDMA memory access
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct MemoryRegionRemoteKey(pub u64);
@@ -33,6 +58,8 @@ pub struct MemoryRegionDescriptor {
     pub ptr: u64,
     /// Per-domain address and remote key pairs
     pub addr_rkey_list: SmallVec<[(DomainAddress, MemoryRegionRemoteKey); 4]>,
+    /// Wire layout version (see `DESCRIPTOR_FORMAT_VERSION`)
+    pub format_version: u32,
 }
 
 impl MemoryRegionDescriptor {
@@ -40,6 +67,7 @@ impl MemoryRegionDescriptor {
         Self {
             ptr,
             addr_rkey_list: SmallVec::from_vec(addr_rkey_list),
+            format_version: DESCRIPTOR_FORMAT_VERSION,
         }
     }
 
@@ -47,6 +75,115 @@ impl MemoryRegionDescriptor {
     pub fn first_domain(&self) -> Option<&DomainAddress> {
         self.addr_rkey_list.first().map(|(addr, _)| addr)
     }
+
+    /// Encode in the fixed byte layout, for clients that build descriptors
+    /// without protobuf or serde
+    ///
+    /// All integers are little-endian:
+    ///
+    /// | Size | Field                                            |
+    /// |------|--------------------------------------------------|
+    /// | 4    | `u32` format version (`DESCRIPTOR_FORMAT_VERSION`) |
+    /// | 8    | `u64` region base pointer                        |
+    /// | 4    | `u32` number of domains, N                       |
+    ///
+    /// followed by N domains, each:
+    ///
+    /// | Size | Field                       |
+    /// |------|-----------------------------|
+    /// | 4    | `u32` address length, L     |
+    /// | L    | domain address bytes        |
+    /// | 8    | `u64` remote key            |
+    pub fn to_wire_bytes(&self) -> Vec<u8> {
+        let domains_len: usize = self
+            .addr_rkey_list
+            .iter()
+            .map(|(addr, _)| 12 + addr.0.len())
+            .sum();
+        let mut bytes = Vec::with_capacity(16 + domains_len);
+        bytes.extend_from_slice(&DESCRIPTOR_FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.ptr.to_le_bytes());
+        bytes.extend_from_slice(&(self.addr_rkey_list.len() as u32).to_le_bytes());
+        for (addr, rkey) in &self.addr_rkey_list {
+            bytes.extend_from_slice(&(addr.0.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&addr.0);
+            bytes.extend_from_slice(&rkey.0.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decode the layout written by `to_wire_bytes`
+    ///
+    /// Rejects truncated input, trailing bytes and format versions other than
+    /// 1 through `DESCRIPTOR_FORMAT_VERSION`.
+    pub fn from_wire_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = WireReader(bytes);
+        let format_version = reader.u32("format version")?;
+        if format_version == 0 || format_version > DESCRIPTOR_FORMAT_VERSION {
+            return Err(anyhow!(
+                "Unsupported memory region descriptor format version {} (this build supports 1 to {})",
+                format_version,
+                DESCRIPTOR_FORMAT_VERSION
+            ));
+        }
+        let ptr = reader.u64("pointer")?;
+        let count = reader.u32("domain count")? as usize;
+        // Each domain takes at least 12 bytes, so a huge count can't allocate much
+        if count > reader.0.len() / 12 {
+            return Err(anyhow!(
+                "Descriptor truncated: {} domains claimed but only {} bytes follow",
+                count,
+                reader.0.len()
+            ));
+        }
+
+        let mut addr_rkey_list = SmallVec::with_capacity(count);
+        for _ in 0..count {
+            let len = reader.u32("address length")? as usize;
+            let addr = reader.take(len, "domain address")?.to_vec();
+            let rkey = reader.u64("remote key")?;
+            addr_rkey_list.push((DomainAddress(addr), MemoryRegionRemoteKey(rkey)));
+        }
+        if !reader.0.is_empty() {
+            return Err(anyhow!(
+                "{} trailing bytes after descriptor",
+                reader.0.len()
+            ));
+        }
+
+        Ok(Self {
+            ptr,
+            addr_rkey_list,
+            format_version,
+        })
+    }
+}
+
+/// Cursor over `from_wire_bytes` input
+struct WireReader<'a>(&'a [u8]);
+
+impl<'a> WireReader<'a> {
+    fn take(&mut self, len: usize, field: &str) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(anyhow!(
+                "Descriptor truncated reading {}: needs {} bytes, {} left",
+                field,
+                len,
+                self.0.len()
+            ));
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self, field: &str) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4, field)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self, field: &str) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8, field)?.try_into().unwrap()))
+    }
 }
 
 /// Location where a value is stored or where to write a response
@@ -92,33 +229,106 @@ impl MemoryRegionHandle {
 }
 
 /// Internal cache entry storing value and its location
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct CacheEntry {
     /// The actual value data
     pub data: Vec<u8>,
-    /// Offset within the server's memory pool where this is stored
-    pub offset: u64,
-    /// TTL in seconds (0 = no expiration)
-    pub ttl_seconds: u64,
+    /// Region of the server's memory pool where this is stored; for a value
+    /// split across regions, the one holding its first bytes
+    pub allocation: PoolAllocation,
+    /// The regions holding the rest of a split value, in order (empty when
+    /// the value is contiguous)
+    pub chunks: Vec<PoolAllocation>,
+    /// TTL in milliseconds (0 = no expiration)
+    pub ttl_millis: u64,
     /// Timestamp when entry was created
     pub created_at: std::time::Instant,
+    /// Server-assigned version, increasing with every write
+    pub version: u64,
+    /// Last time the entry was read or warmed
+    pub last_accessed: std::time::Instant,
+    /// CRC-32C the value was verified against when stored, if the writer sent one
+    pub checksum: Option<u32>,
 }
 
 impl CacheEntry {
-    pub fn new(data: Vec<u8>, offset: u64, ttl_seconds: u64) -> Self {
+    /// Build an entry over `allocations`, the value's regions in order; there
+    /// must be at least one
+    pub fn new(
+        data: Vec<u8>,
+        mut allocations: Vec<PoolAllocation>,
+        ttl_millis: u64,
+        version: u64,
+    ) -> Self {
+        let now = std::time::Instant::now();
+        let allocation = allocations.remove(0);
         Self {
             data,
-            offset,
-            ttl_seconds,
-            created_at: std::time::Instant::now(),
+            allocation,
+            chunks: allocations,
+            ttl_millis,
+            created_at: now,
+            version,
+            last_accessed: now,
+            checksum: None,
         }
     }
 
+    /// Offset within the server's memory pool
+    pub fn offset(&self) -> u64 {
+        self.allocation.offset as u64
+    }
+
+    /// Every region holding the value, in order
+    pub fn allocations(&self) -> impl Iterator<Item = &PoolAllocation> {
+        std::iter::once(&self.allocation).chain(&self.chunks)
+    }
+
+    /// `(offset, len)` of each stored piece of the value, in order
+    pub fn segments(&self) -> Vec<(u64, u64)> {
+        self.allocations()
+            .map(|allocation| (allocation.offset as u64, allocation.size as u64))
+            .collect()
+    }
+
+    /// Give up the entry's regions, for freeing
+    pub fn into_allocations(self) -> impl Iterator<Item = PoolAllocation> {
+        std::iter::once(self.allocation).chain(self.chunks)
+    }
+
     pub fn is_expired(&self) -> bool {
-        if self.ttl_seconds == 0 {
+        if self.ttl_millis == 0 {
             return false;
         }
-        self.created_at.elapsed().as_secs() >= self.ttl_seconds
+        self.created_at.elapsed().as_millis() >= self.ttl_millis as u128
+    }
+
+    /// Add `increment` milliseconds to the TTL, up to `max` (never shortening
+    /// it); entries without a TTL are left alone
+    pub fn extend_ttl(&mut self, increment: u64, max: u64) {
+        if self.ttl_millis == 0 {
+            return;
+        }
+        self.ttl_millis = self
+            .ttl_millis
+            .saturating_add(increment)
+            .min(max)
+            .max(self.ttl_millis);
+    }
+
+    /// TTL left before expiry in milliseconds (0 = no expiration); never
+    /// rounds a live entry down to 0
+    pub fn remaining_ttl_millis(&self) -> u64 {
+        if self.ttl_millis == 0 {
+            return 0;
+        }
+        let elapsed = u64::try_from(self.created_at.elapsed().as_millis()).unwrap_or(u64::MAX);
+        self.ttl_millis.saturating_sub(elapsed).max(1)
+    }
+
+    /// `remaining_ttl_millis` rounded up to whole seconds
+    pub fn remaining_ttl_seconds(&self) -> u64 {
+        self.remaining_ttl_millis().div_ceil(1000)
     }
 
     pub fn len(&self) -> usize {
@@ -131,8 +341,18 @@ impl CacheEntry {
 }
 
 // Conversion helpers between our types and protobuf types
-impl From<&crate::pb::MemoryRegionDescriptor> for MemoryRegionDescriptor {
-    fn from(pb: &crate::pb::MemoryRegionDescriptor) -> Self {
+impl TryFrom<&crate::pb::MemoryRegionDescriptor> for MemoryRegionDescriptor {
+    type Error = anyhow::Error;
+
+    fn try_from(pb: &crate::pb::MemoryRegionDescriptor) -> Result<Self> {
+        if pb.format_version > DESCRIPTOR_FORMAT_VERSION {
+            return Err(anyhow!(
+                "Unsupported memory region descriptor format version {} (this build supports up to {})",
+                pb.format_version,
+                DESCRIPTOR_FORMAT_VERSION
+            ));
+        }
+
         let addr_rkey_list = pb
             .addr_rkey_list
             .iter()
@@ -143,10 +363,11 @@ impl From<&crate::pb::MemoryRegionDescriptor> for MemoryRegionDescriptor {
                 )
             })
             .collect();
-        Self {
+        Ok(Self {
             ptr: pb.ptr,
             addr_rkey_list,
-        }
+            format_version: DESCRIPTOR_FORMAT_VERSION,
+        })
     }
 }
 
@@ -162,22 +383,26 @@ impl From<&MemoryRegionDescriptor> for crate::pb::MemoryRegionDescriptor {
                     rkey: rkey.0,
                 })
                 .collect(),
+            format_version: desc.format_version,
         }
     }
 }
 
-impl From<&crate::pb::ValueLocation> for ValueLocation {
-    fn from(pb: &crate::pb::ValueLocation) -> Self {
-        Self {
+impl TryFrom<&crate::pb::ValueLocation> for ValueLocation {
+    type Error = anyhow::Error;
+
+    fn try_from(pb: &crate::pb::ValueLocation) -> Result<Self> {
+        Ok(Self {
             node_id: pb.node_id,
             mr_descriptor: pb
                 .mr_descriptor
                 .as_ref()
-                .map(|d| d.into())
+                .map(|d| d.try_into())
+                .transpose()?
                 .unwrap_or_else(|| MemoryRegionDescriptor::new(0, vec![])),
             offset: pb.offset,
             length: pb.length,
-        }
+        })
     }
 }
 
@@ -191,3 +416,118 @@ impl From<&ValueLocation> for crate::pb::ValueLocation {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descriptor_round_trip_and_future_version() {
+        let desc = MemoryRegionDescriptor::new(
+            0x1000,
+            vec![(
+                DomainAddress::new(b"addr0".to_vec()),
+                MemoryRegionRemoteKey(7),
+            )],
+        );
+        let mut pb: crate::pb::MemoryRegionDescriptor = (&desc).into();
+        assert_eq!(pb.format_version, DESCRIPTOR_FORMAT_VERSION);
+
+        let decoded = MemoryRegionDescriptor::try_from(&pb).unwrap();
+        assert_eq!(decoded.ptr, desc.ptr);
+        assert_eq!(decoded.addr_rkey_list, desc.addr_rkey_list);
+
+        // Legacy peers don't set the field at all
+        pb.format_version = 0;
+        assert!(MemoryRegionDescriptor::try_from(&pb).is_ok());
+
+        pb.format_version = DESCRIPTOR_FORMAT_VERSION + 1;
+        let err = MemoryRegionDescriptor::try_from(&pb).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unsupported memory region descriptor format version"));
+    }
+
+    #[test]
+    fn test_wire_bytes_round_trip_with_fixed_layout() {
+        let desc = MemoryRegionDescriptor::new(
+            0x1122_3344_5566_7788,
+            vec![
+                (
+                    DomainAddress::new(b"ab".to_vec()),
+                    MemoryRegionRemoteKey(0x0102),
+                ),
+                (
+                    DomainAddress::new(Vec::new()),
+                    MemoryRegionRemoteKey(u64::MAX),
+                ),
+            ],
+        );
+        let bytes = desc.to_wire_bytes();
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            1, 0, 0, 0,
+            0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11,
+            2, 0, 0, 0,
+            2, 0, 0, 0, b'a', b'b', 0x02, 0x01, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        ];
+        assert_eq!(bytes, expected);
+
+        let decoded = MemoryRegionDescriptor::from_wire_bytes(&bytes).unwrap();
+        assert_eq!(decoded.ptr, desc.ptr);
+        assert_eq!(decoded.addr_rkey_list, desc.addr_rkey_list);
+        assert_eq!(decoded.format_version, DESCRIPTOR_FORMAT_VERSION);
+    }
+
+    #[test]
+    fn test_malformed_wire_bytes_are_rejected() {
+        let bytes = MemoryRegionDescriptor::new(
+            0x1000,
+            vec![(
+                DomainAddress::new(b"addr0".to_vec()),
+                MemoryRegionRemoteKey(7),
+            )],
+        )
+        .to_wire_bytes();
+        let err = |bytes: &[u8]| {
+            MemoryRegionDescriptor::from_wire_bytes(bytes)
+                .unwrap_err()
+                .to_string()
+        };
+
+        for len in 0..bytes.len() {
+            assert!(
+                err(&bytes[..len]).contains("truncated"),
+                "prefix of {} bytes",
+                len
+            );
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(err(&trailing).contains("trailing"));
+
+        let mut future = bytes.clone();
+        future[..4].copy_from_slice(&(DESCRIPTOR_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(err(&future).contains("Unsupported"));
+
+        let mut huge = bytes.clone();
+        huge[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(err(&huge).contains("4294967295 domains claimed"));
+
+        let mut long_addr = bytes;
+        long_addr[16..20].copy_from_slice(&1000u32.to_le_bytes());
+        assert!(err(&long_addr).contains("truncated reading domain address"));
+    }
+    #[test]
+    fn test_domain_address_display() {
+        let config = crate::transport::TransportConfig::default();
+        let transport = crate::transport::RdmaTransport::new(config).unwrap();
+        let mock = &transport.domain_addresses()[0];
+        assert_eq!(mock.to_string(), "mock://node0/domain0");
+
+        let binary = DomainAddress::new(vec![0xfe, 0x80, 0x00, 0x01]);
+        assert_eq!(binary.to_string(), "0xfe800001");
+        assert!(format!("{:?}", binary).contains("[254, 128, 0, 1]"));
+    }
+}
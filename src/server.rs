@@ -3,23 +3,62 @@
 //! The server handles control plane RPC requests and performs RDMA writes
 //! to send data to clients.
 
-use crate::memory::{MemoryPool, MemoryPoolConfig};
+use crate::admission::AdmissionController;
+use crate::bloom::BloomFilterConfig;
+use crate::checksum::crc32c;
+use crate::core::{free_all, pool_config, KvCore};
+use crate::interceptor::ValueInterceptor;
+use crate::keys::{CacheKey, KeyHasherConfig, KeyLogPolicy, LoggedKey};
+use crate::loader::ValueLoader;
+use crate::memory::{
+    MemoryPool, PoolAllocation, SizeClass, ValueDevice, WatermarkEvent, Watermarks,
+};
+use crate::metrics::{LatencyHistogram, TrafficCounters};
 use crate::pb::kv_cache_service_server::{KvCacheService, KvCacheServiceServer};
 use crate::pb::{
-    DeleteRequest, DeleteResponse, GetRequest, GetResponse, HeartbeatRequest, HeartbeatResponse,
-    PutRequest, PutResponse, RegisterClientRequest, RegisterClientResponse,
+    BatchPutRequest, BatchPutResponse, BatchPutResult, CompareAndSwapRequest,
+    CompareAndSwapResponse, CopyRequest, CopyResponse, CountRequest, CountResponse,
+    DeletePrefixRequest, DeletePrefixResponse, DeleteRequest, DeleteResponse,
+    DeregisterClientRequest, DeregisterClientResponse, DomainStats, DumpEntry, DumpRequest,
+    ExistsRequest, ExistsResponse, FlushRequest, FlushResponse, GetManyItem, GetManyRequest,
+    GetManyResponse, GetManyResult, GetRequest, GetResponse, GetSizeRequest, GetSizeResponse,
+    GetSource, HeartbeatRequest, HeartbeatResponse, ImportRecord, ImportResponse, KeyspaceEvent,
+    KeyspaceEventKind, LatencySummary, PoolShardStats, PutRequest, PutResponse,
+    RegisterClientRequest, RegisterClientResponse, RenameRequest, RenameResponse, RuntimeStats,
+    StatsRequest, StatsResponse, TouchRequest, TouchResponse, WatchEventsRequest,
+};
+use crate::priority::PriorityGate;
+use crate::protocol::{
+    CacheEntry, DomainAddress, MemoryRegionDescriptor, MemoryRegionHandle, ValueLocation,
+    PROTOCOL_VERSION,
 };
-use crate::protocol::{CacheEntry, DomainAddress, ValueLocation};
-use crate::transport::{DomainRouting, RdmaTransport, TransferRequest, TransportConfig};
-use anyhow::Result;
+use crate::transport::{
+    DomainRouting, RdmaTransport, ReadRequest, TransferRequest, TransportConfig,
+};
+use crate::wal::{Wal, WalRecord};
+use anyhow::{anyhow, Result};
 use dashmap::DashMap;
-use parking_lot::RwLock;
-use std::collections::HashMap;
+use parking_lot::{Mutex, RwLock};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
 use std::sync::Arc;
-use tonic::{Request, Response, Status};
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Code, Request, Response, Status, Streaming};
+use tracing::Instrument;
 
 /// Server configuration
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ServerConfig {
     /// Server node ID
     pub node_id: u32,
@@ -27,8 +66,111 @@ pub struct ServerConfig {
     pub listen_addr: String,
     /// Memory pool size in bytes
     pub memory_pool_size: usize,
+    /// Where the memory pool is allocated; `cuda` needs the fabric transport
+    pub value_device: ValueDevice,
+    /// PUTs of longer values are rejected (0 = limited only by the pool)
+    pub max_value_size: usize,
+    /// GETs, PUTs and DELETEs of longer keys are rejected with
+    /// `INVALID_ARGUMENT` (0 = unlimited)
+    pub max_key_size: usize,
+    /// Pool regions reserved for small values (empty = one shared region)
+    pub size_classes: Vec<SizeClass>,
+    /// Pool usage thresholds; crossings are logged and reported to
+    /// `subscribe_pool_watermarks`
+    pub pool_watermarks: Option<Watermarks>,
     /// Transport configuration
     pub transport: TransportConfig,
+    /// How long to keep retrying the bind while the address is in use (zero = fail fast)
+    #[serde(with = "humantime_serde")]
+    pub bind_retry_timeout: Duration,
+    /// Optional Bloom filter consulted before the cache map to short-circuit misses
+    pub bloom_filter: Option<BloomFilterConfig>,
+    /// Shed GETs with `RESOURCE_EXHAUSTED` once their estimated queue delay exceeds this
+    #[serde(with = "humantime_serde")]
+    pub get_latency_budget: Option<Duration>,
+    /// Stripes per GET transfer across NICs (0 = one per `transport.num_domains`)
+    pub num_shards: u8,
+    /// Log PUTs/DELETEs here and replay them on startup (TTLs restart at replay)
+    pub wal_path: Option<PathBuf>,
+    /// Compact the WAL into a snapshot of the live entries once it grows past
+    /// this many bytes and twice its size after the last compaction (0 = never)
+    pub wal_compact_bytes: u64,
+    /// CPUs for the gRPC runtime threads (`kv-server` only); keep these apart from
+    /// the transport's worker CPUs and near the NIC-local NUMA node as needed
+    pub runtime_cpus: Option<Vec<usize>>,
+    /// GETs transferring at once; beyond this they queue by priority (0 = unlimited)
+    pub max_concurrent_gets: usize,
+    /// How long a DELETE's tombstone keeps rejecting older replicated writes
+    /// (zero = no tombstones); only needed when peers replicate writes here
+    #[serde(with = "humantime_serde")]
+    pub tombstone_ttl: Duration,
+    /// How often a background task frees expired entries nobody has read
+    /// since they expired (zero = only GETs remove them)
+    #[serde(with = "humantime_serde")]
+    pub ttl_sweep_interval: Duration,
+    /// Return deleted entries' pool space from a background thread instead of
+    /// inline, so DELETEs don't wait for the pool's write lock
+    pub deferred_free: bool,
+    /// Keep keys in a shared arena instead of one heap allocation per key
+    pub intern_keys: bool,
+    /// GETs of values shorter than this return the bytes in the response
+    /// instead of writing them over RDMA (0 = always RDMA)
+    pub small_value_inline_threshold: usize,
+    /// Keyspace events buffered per watcher; a watcher further behind loses the oldest
+    pub event_channel_capacity: usize,
+    /// On a GET of an expired key, reload it through the read-through loader
+    /// (keeping its TTL) instead of returning NOT_FOUND
+    pub repair_on_expiry: bool,
+    /// Linux only: accept on this many `SO_REUSEPORT` listeners, each served by
+    /// its own thread and single-threaded runtime over the shared state
+    /// (0 or 1 = one listener on the main runtime)
+    pub reuseport_shards: usize,
+    /// Extend the TTL of entries as they're read, so hot keys outlive their
+    /// base TTL without explicit touches
+    pub adaptive_ttl: Option<AdaptiveTtlConfig>,
+    /// Hash function for the cache map; `ahash` is faster, `keyed_sip` with a
+    /// secret resists crafted collisions from untrusted clients
+    pub key_hasher: KeyHasherConfig,
+    /// Pool regions a value may be split across when no free block fits it
+    /// whole (1 = values are always contiguous)
+    pub max_value_chunks: usize,
+    /// How a PUT makes room when the pool or `max_entries` is full
+    pub eviction: EvictionPolicy,
+    /// Entries kept at most; a PUT of a new key beyond this evicts (or with
+    /// no eviction, fails) like one that doesn't fit the pool (0 = unlimited)
+    pub max_entries: usize,
+    /// How keys are written in log messages
+    pub log_keys: KeyLogPolicy,
+}
+
+/// What a PUT does when the cache is full
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionPolicy {
+    /// Fail the PUT
+    None,
+    /// Evict the entries read or written longest ago until the value fits
+    #[default]
+    Lru,
+}
+
+/// How reads extend an entry's TTL
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdaptiveTtlConfig {
+    /// Seconds added to the entry's TTL on every hit
+    pub increment_seconds: u64,
+    /// Longest TTL, counted from the entry's write, that hits can extend it to
+    pub max_ttl_seconds: u64,
+}
+
+impl Default for AdaptiveTtlConfig {
+    fn default() -> Self {
+        Self {
+            increment_seconds: 60,
+            max_ttl_seconds: 3600,
+        }
+    }
 }
 
 impl Default for ServerConfig {
@@ -37,16 +179,211 @@ impl Default for ServerConfig {
             node_id: 0,
             listen_addr: "[::1]:50051".to_string(),
             memory_pool_size: 1024 * 1024 * 1024, // 1GB
+            value_device: ValueDevice::Host,
+            max_value_size: 0,
+            max_key_size: 64 * 1024,
+            size_classes: Vec::new(),
+            pool_watermarks: None,
             transport: TransportConfig::default(),
+            bind_retry_timeout: Duration::ZERO,
+            bloom_filter: None,
+            get_latency_budget: None,
+            num_shards: 0,
+            wal_path: None,
+            wal_compact_bytes: 1024 * 1024 * 1024,
+            runtime_cpus: None,
+            max_concurrent_gets: 0,
+            tombstone_ttl: Duration::ZERO,
+            ttl_sweep_interval: Duration::from_secs(1),
+            deferred_free: false,
+            intern_keys: false,
+            small_value_inline_threshold: 0,
+            event_channel_capacity: 1024,
+            repair_on_expiry: false,
+            reuseport_shards: 0,
+            adaptive_ttl: None,
+            key_hasher: KeyHasherConfig::Std,
+            max_value_chunks: 16,
+            eviction: EvictionPolicy::Lru,
+            max_entries: 0,
+            log_keys: KeyLogPolicy::default(),
+        }
+    }
+}
+
+/// Values up to this size are sent inline in a Dump unless the request says otherwise
+const DEFAULT_DUMP_INLINE_BYTES: u64 = 64 * 1024;
+
+/// Why a PUT from an RDMA location is refused by a transport that can't read
+const RDMA_READ_UNSUPPORTED: &str =
+    "PUT from an RDMA location needs RDMA read, which this server's transport \
+     doesn't support; send the value inline";
+
+/// How often the WAL's size is checked against `wal_compact_bytes`
+const WAL_COMPACT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Outcome of a successful GET lookup
+struct GetResult {
+    value_len: u64,
+    version: u64,
+    /// The caller's version is current, so nothing was transferred
+    not_modified: bool,
+    /// The value itself, when it was small enough to skip the transfer
+    inline_value: Option<Vec<u8>>,
+    /// The key wasn't resident and the loader supplied it
+    loaded: bool,
+    /// TTL left at lookup (0 = no expiration)
+    remaining_ttl_seconds: u64,
+    /// CRC-32C the value was verified against when stored
+    checksum: Option<u32>,
+}
+
+/// Location of a live entry in the pool
+struct ResidentEntry {
+    value_len: u64,
+    /// `(offset, len)` of each region holding the value, in order
+    segments: Vec<(u64, u64)>,
+    version: u64,
+    /// TTL left at lookup (0 = no expiration)
+    remaining_ttl_seconds: u64,
+    checksum: Option<u32>,
+    /// Keep the regions allocated until the caller is done transferring from them
+    leases: Vec<RegionLease>,
+    /// Filled through the loader rather than found resident
+    loaded: bool,
+}
+
+impl ResidentEntry {
+    /// One transfer per region, landing back to back from `dst_offset`
+    fn transfers(
+        &self,
+        src_handle: MemoryRegionHandle,
+        dst_descriptor: &MemoryRegionDescriptor,
+        dst_offset: u64,
+        routing: DomainRouting,
+    ) -> Vec<TransferRequest> {
+        let mut dst_offset = dst_offset;
+        self.segments
+            .iter()
+            .map(|&(src_offset, length)| {
+                let request = TransferRequest {
+                    src_handle,
+                    src_offset,
+                    length,
+                    imm_data: None,
+                    dst_descriptor: dst_descriptor.clone(),
+                    dst_offset,
+                    routing: routing.clone(),
+                };
+                dst_offset += length;
+                request
+            })
+            .collect()
+    }
+}
+
+/// Pool regions that GETs are still transferring from
+///
+/// If such a region were freed, the next PUT could overwrite it mid-transfer and
+/// the client would receive a mix of two values, so its free waits for the last
+/// reader's lease to drop. The counts live in a sharded map, so GETs of
+/// different regions rarely contend.
+struct RegionReaders {
+    pool: Arc<RwLock<MemoryPool>>,
+    /// Reader count per allocation generation, plus the allocation once freed
+    regions: DashMap<u64, (usize, Option<PoolAllocation>)>,
+}
+
+impl RegionReaders {
+    /// Register a reader; call while the entry owning `allocation` is still held
+    /// in the map, so it can't be freed in between
+    fn acquire(self: &Arc<Self>, allocation: &PoolAllocation) -> RegionLease {
+        self.regions
+            .entry(allocation.generation)
+            .or_insert((0, None))
+            .0 += 1;
+        RegionLease {
+            readers: self.clone(),
+            generation: allocation.generation,
+        }
+    }
+
+    /// Hold on to a freed allocation until its readers finish
+    ///
+    /// Returns it back if there are none, for the caller to deallocate.
+    fn defer_free(&self, allocation: PoolAllocation) -> Option<PoolAllocation> {
+        match self.regions.get_mut(&allocation.generation) {
+            Some(mut region) => {
+                region.1 = Some(allocation);
+                None
+            }
+            None => Some(allocation),
+        }
+    }
+}
+
+/// A GET's claim on a pool region; the last one dropped after the region was
+/// freed deallocates it, so it must not be dropped under the pool lock
+struct RegionLease {
+    readers: Arc<RegionReaders>,
+    generation: u64,
+}
+
+impl Drop for RegionLease {
+    fn drop(&mut self) {
+        let freed = {
+            let dashmap::Entry::Occupied(mut region) = self.readers.regions.entry(self.generation)
+            else {
+                return;
+            };
+            region.get_mut().0 -= 1;
+            if region.get().0 > 0 {
+                return;
+            }
+            region.remove().1
+        };
+        if let Some(allocation) = freed {
+            self.readers.pool.read().deallocate(&allocation);
         }
     }
 }
 
+/// An entry `evict_lru` may remove, as it was when the map was last scanned
+struct EvictionCandidate {
+    key: Vec<u8>,
+    version: u64,
+    queued_at: Instant,
+}
+
+/// Outcome of looking a key up in the map
+enum Lookup {
+    Live(ResidentEntry),
+    Missing,
+    /// The entry had expired and was removed
+    Expired {
+        ttl_millis: u64,
+    },
+}
+
+/// Left by a DELETE so a replicated write that raced with it can't resurrect the key
+struct Tombstone {
+    /// Version assigned to the delete; replicated writes at or below it are dropped
+    version: u64,
+    expires_at: Instant,
+}
+
+impl Tombstone {
+    fn is_expired(&self) -> bool {
+        Instant::now() >= self.expires_at
+    }
+}
+
 /// Registered client information
 struct RegisteredClient {
     client_id: u32,
     domain_addresses: Vec<DomainAddress>,
     receive_buffer_size: u64,
+    priority: u8,
 }
 
 /// KV Cache Server
@@ -54,12 +391,50 @@ pub struct KvCacheServer {
     config: ServerConfig,
     /// RDMA transport for data transfers
     transport: Arc<RdmaTransport>,
-    /// Memory pool for storing cached values
-    memory_pool: Arc<RwLock<MemoryPool>>,
-    /// Cache entries: key -> CacheEntry
-    cache: Arc<DashMap<Vec<u8>, CacheEntry>>,
+    /// Cache entries and the pool holding their values
+    core: KvCore,
     /// Registered clients
     clients: Arc<RwLock<HashMap<u32, RegisteredClient>>>,
+    /// GET load shedding, present when a latency budget is configured
+    admission: Option<AdmissionController>,
+    /// Read-through source for misses
+    loader: Option<Arc<dyn ValueLoader>>,
+    /// Applied to values PUT and returned by GET, in order
+    interceptors: Vec<Arc<dyn ValueInterceptor>>,
+    /// Write-ahead log; appended under the key's map shard lock so each key's
+    /// log order matches its apply order
+    wal: Option<Wal>,
+    /// Priority-ordered limit on concurrent GETs
+    get_gate: Option<PriorityGate>,
+    /// GET latency (including queueing) per priority
+    get_latency: DashMap<u8, LatencyHistogram>,
+    /// GET latency of keys that were resident
+    hit_latency: LatencyHistogram,
+    /// GET latency of keys that weren't, whether or not the loader found them
+    miss_latency: LatencyHistogram,
+    /// Recently deleted keys, which older replicated writes must not resurrect
+    tombstones: DashMap<Vec<u8>, Tombstone>,
+    /// RDMA vs control-plane bytes, for the Stats RPC
+    traffic: TrafficCounters,
+    /// Queue to the deferred-free thread, when `deferred_free` is set
+    deferred_frees: Option<mpsc::Sender<PoolAllocation>>,
+    /// The deferred-free thread, joined when the server is dropped
+    deferred_free_worker: Option<std::thread::JoinHandle<()>>,
+    /// Set by `stop` to end the background tasks
+    shutdown: watch::Sender<bool>,
+    /// Tasks started by `start_background_tasks`, awaited by `stop`
+    background_tasks: Mutex<Vec<JoinHandle<()>>>,
+    /// In-flight GETs per pool region; frees of regions being read wait for them
+    region_readers: Arc<RegionReaders>,
+    /// Entries to evict next, least recently used first, refilled by scanning
+    /// the map once it runs dry
+    eviction_queue: Mutex<VecDeque<EvictionCandidate>>,
+    /// Keyspace events for WatchEvents streams
+    events: broadcast::Sender<KeyspaceEvent>,
+    /// When the server was created, for reporting uptime
+    started_at: Instant,
+    /// Connections accepted by each listener `run_server` starts
+    accepted_connections: Vec<AtomicU64>,
 }
 
 impl KvCacheServer {
@@ -68,350 +443,4909 @@ impl KvCacheServer {
         let mut transport_config = config.transport.clone();
         transport_config.node_id = config.node_id;
         let transport = Arc::new(RdmaTransport::new(transport_config)?);
+        if let ValueDevice::Cuda { device_id } = config.value_device {
+            if transport.is_mock() {
+                return Err(anyhow!(
+                    "value_device = cuda (device {}) needs the fabric transport: the mock \
+                     copies with host memcpy, which can't reach device memory",
+                    device_id
+                ));
+            }
+        }
 
-        let pool_config = MemoryPoolConfig {
-            size: config.memory_pool_size,
-            alignment: 4096,
-        };
         let memory_pool = Arc::new(RwLock::new(MemoryPool::new(
-            pool_config,
+            pool_config(&config),
             config.node_id,
             Some(&transport),
         )?));
 
-        Ok(Self {
+        let admission = config.get_latency_budget.map(AdmissionController::new);
+        let get_gate =
+            (config.max_concurrent_gets > 0).then(|| PriorityGate::new(config.max_concurrent_gets));
+        let (deferred_frees, deferred_free_worker) = config
+            .deferred_free
+            .then(|| spawn_deferred_free(memory_pool.clone()))
+            .transpose()?
+            .unzip();
+        let region_readers = Arc::new(RegionReaders {
+            pool: memory_pool.clone(),
+            regions: DashMap::new(),
+        });
+        let core = KvCore::with_pool(&config, memory_pool);
+        let (events, _) = broadcast::channel(config.event_channel_capacity.max(1));
+        let accepted_connections = (0..config.reuseport_shards.max(1))
+            .map(|_| AtomicU64::new(0))
+            .collect();
+
+        let mut server = Self {
             config,
             transport,
-            memory_pool,
-            cache: Arc::new(DashMap::new()),
+            core,
             clients: Arc::new(RwLock::new(HashMap::new())),
+            admission,
+            loader: None,
+            interceptors: Vec::new(),
+            wal: None,
+            get_gate,
+            get_latency: DashMap::new(),
+            hit_latency: LatencyHistogram::default(),
+            miss_latency: LatencyHistogram::default(),
+            tombstones: DashMap::new(),
+            traffic: TrafficCounters::default(),
+            deferred_frees,
+            deferred_free_worker,
+            shutdown: watch::Sender::new(false),
+            background_tasks: Mutex::new(Vec::new()),
+            region_readers,
+            eviction_queue: Mutex::new(VecDeque::new()),
+            events,
+            started_at: Instant::now(),
+            accepted_connections,
+        };
+
+        if let Some(path) = server.config.wal_path.clone() {
+            // Replay before attaching the log so replayed writes aren't logged again
+            let records = Wal::replay(&path)?;
+            tracing::info!(
+                "Replaying {} WAL records from {}",
+                records.len(),
+                path.display()
+            );
+            for record in records {
+                let (key, value, ttl_millis) = match record {
+                    WalRecord::Put {
+                        key,
+                        value,
+                        ttl_seconds,
+                    } => (key, value, ttl_seconds.saturating_mul(1000)),
+                    WalRecord::PutMillis {
+                        key,
+                        value,
+                        ttl_millis,
+                    } => (key, value, ttl_millis),
+                    WalRecord::Delete { key } => {
+                        if let Err(e) = server.delete_value(&key) {
+                            tracing::warn!("Skipping WAL delete: {}", e);
+                        }
+                        continue;
+                    }
+                };
+                let logged_key = server.log_key(&key).to_string();
+                if let Err(e) = server.put_value(key, value, ttl_millis) {
+                    tracing::warn!("Skipping WAL record for key {}: {}", logged_key, e);
+                }
+            }
+            server.wal = Some(Wal::open(&path)?);
+        }
+
+        Ok(server)
+    }
+
+    /// Fill cache misses from `loader` instead of returning NOT_FOUND
+    pub fn with_loader(mut self, loader: Arc<dyn ValueLoader>) -> Self {
+        self.loader = Some(loader);
+        self
+    }
+
+    /// Append `interceptor` to the chain values pass through; see `interceptor`
+    ///
+    /// Values already stored, including those replayed from the WAL, are not
+    /// transformed.
+    pub fn with_interceptor(mut self, interceptor: Arc<dyn ValueInterceptor>) -> Self {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Start the periodic TTL sweeper, tombstone reaper, domain prober and WAL
+    /// compactor; they run until `stop`
+    pub fn start_background_tasks(self: &Arc<Self>) {
+        let mut tasks = self.background_tasks.lock();
+        if !self.config.ttl_sweep_interval.is_zero() {
+            tasks.push(
+                self.spawn_periodic(self.config.ttl_sweep_interval, |server| {
+                    let swept = server.sweep_expired();
+                    if swept > 0 {
+                        tracing::debug!("Swept {} expired entries", swept);
+                    }
+                }),
+            );
+        }
+        if !self.config.tombstone_ttl.is_zero() {
+            tasks.push(self.spawn_periodic(self.config.tombstone_ttl, |server| {
+                let reaped = server.reap_tombstones();
+                if reaped > 0 {
+                    tracing::debug!("Reaped {} expired tombstones", reaped);
+                }
+            }));
+        }
+        let probe_interval = self.config.transport.domain_probe_interval;
+        if !probe_interval.is_zero() {
+            tasks.push(self.spawn_periodic(probe_interval, |server| {
+                // A real probe blocks until its write completes or times out
+                let transport = server.transport.clone();
+                tokio::task::spawn_blocking(move || transport.probe_domains());
+            }));
+        }
+        if self.wal.is_some() && self.config.wal_compact_bytes > 0 {
+            tasks.push(self.spawn_wal_compactor());
+        }
+    }
+
+    /// Check the WAL's size every `WAL_COMPACT_CHECK_INTERVAL`, compacting it
+    /// on a blocking thread once it needs it
+    fn spawn_wal_compactor(self: &Arc<Self>) -> JoinHandle<()> {
+        let server = self.clone();
+        let mut shutdown = self.shutdown.subscribe();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(WAL_COMPACT_CHECK_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown.wait_for(|stopped| *stopped) => return,
+                }
+                if !server.wal_needs_compaction() {
+                    continue;
+                }
+                let compacting = server.clone();
+                match tokio::task::spawn_blocking(move || compacting.compact_wal()).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => tracing::error!("Failed to compact WAL: {}", e),
+                    Err(e) => tracing::error!("WAL compaction task failed: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Run `tick` every `period` until the server is stopped
+    fn spawn_periodic(
+        self: &Arc<Self>,
+        period: Duration,
+        tick: impl Fn(&KvCacheServer) + Send + 'static,
+    ) -> JoinHandle<()> {
+        let server = self.clone();
+        let mut shutdown = self.shutdown.subscribe();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => tick(&server),
+                    _ = shutdown.wait_for(|stopped| *stopped) => return,
+                }
+            }
         })
     }
 
+    /// End the background tasks and wait until they have all exited
+    pub async fn stop(&self) {
+        self.shutdown.send_replace(true);
+        let tasks = std::mem::take(&mut *self.background_tasks.lock());
+        for task in tasks {
+            let _ = task.await;
+        }
+    }
+
     /// Get the gRPC service for this server
     pub fn into_service(self) -> KvCacheServiceServer<KvCacheServiceImpl> {
+        Arc::new(self).shared_service()
+    }
+
+    /// Get a gRPC service backed by this shared server
+    fn shared_service(self: &Arc<Self>) -> KvCacheServiceServer<KvCacheServiceImpl> {
         // Configure service to accept large messages (up to 128MB)
         KvCacheServiceServer::new(KvCacheServiceImpl {
-            inner: Arc::new(self),
+            inner: self.clone(),
         })
         .max_decoding_message_size(128 * 1024 * 1024) // 128MB receive limit
         .max_encoding_message_size(128 * 1024 * 1024) // 128MB send limit
     }
 
+    /// Receive an event each time pool usage crosses `pool_watermarks`
+    pub fn subscribe_pool_watermarks(&self) -> broadcast::Receiver<WatermarkEvent> {
+        self.core.memory_pool.read().subscribe_watermarks()
+    }
+
+    /// Return the pool's free pages to the OS, returning the bytes released
+    ///
+    /// Worth calling after a burst of large values has been deleted; see
+    /// `MemoryPool::trim`.
+    pub fn trim_memory(&self) -> usize {
+        self.core.memory_pool.read().trim()
+    }
+
+    /// Pick the domain of every GET transfer with `router` (`None` restores
+    /// the configured routing)
+    pub fn set_domain_router(&self, router: Option<Arc<dyn crate::transport::DomainRouter>>) {
+        self.transport.set_router(router);
+    }
+
     /// Get the listen address
     pub fn listen_addr(&self) -> &str {
         &self.config.listen_addr
     }
 
     /// Store a value in the cache
-    fn put_value(&self, key: Vec<u8>, value: Vec<u8>, ttl_seconds: u64) -> Result<()> {
-        let mut pool = self.memory_pool.write();
-
-        // Allocate space in the memory pool
-        let allocation = pool.allocate(value.len())?;
-
-        // Write data to the pool
-        pool.write(allocation.offset, &value)?;
-
-        // Create cache entry
-        let entry = CacheEntry::new(value, allocation.offset as u64, ttl_seconds);
+    fn put_value(&self, key: Vec<u8>, value: Vec<u8>, ttl_millis: u64) -> Result<()> {
+        let (value, _) = self.intercept_put(&key, value, None)?;
+        self.put_versioned(key, value, ttl_millis, None, false, None)
+            .map(|_| ())
+    }
 
-        // Store in cache (this will replace any existing entry)
-        if let Some(old_entry) = self.cache.insert(key, entry) {
-            // Deallocate old entry's memory
-            pool.deallocate(&crate::memory::PoolAllocation {
-                offset: old_entry.offset as usize,
-                size: old_entry.len(),
-                ptr: std::ptr::null_mut(),
-            });
+    /// Run a value being stored through the interceptors
+    ///
+    /// The checksum covers the bytes the client sent, so it is verified here
+    /// and not kept once the value has been transformed.
+    fn intercept_put(
+        &self,
+        key: &[u8],
+        value: Vec<u8>,
+        checksum: Option<u32>,
+    ) -> Result<(Vec<u8>, Option<u32>)> {
+        if self.interceptors.is_empty() {
+            return Ok((value, checksum));
         }
+        verify_checksum(&value, checksum)?;
+        let value = self
+            .interceptors
+            .iter()
+            .try_fold(value, |value, interceptor| interceptor.on_put(key, &value))?;
+        Ok((value, None))
+    }
 
-        Ok(())
+    /// Run a stored value being returned through the interceptors, last first
+    fn intercept_get(&self, key: &[u8], value: Vec<u8>) -> Result<Vec<u8>> {
+        self.interceptors
+            .iter()
+            .rev()
+            .try_fold(value, |value, interceptor| interceptor.on_get(key, &value))
     }
 
-    /// Get a value and RDMA write it to the client's buffer
-    async fn get_and_transfer(
+    /// Store a value, keeping `origin_version` if it came from a replicating peer
+    ///
+    /// A replicated write no newer than the stored entry or a live tombstone is
+    /// dropped and `Ok(false)` returned, as is an `if_absent` write to a key
+    /// with a live value. Other local writes always apply.
+    ///
+    /// PUTs run concurrently: the value is copied into its own allocation under
+    /// a shared pool lock, and only the commit serializes, per key. A failure
+    /// anywhere after allocating frees the new allocations and leaves any
+    /// stored entry, and its regions, in place.
+    fn put_versioned(
         &self,
-        key: &[u8],
-        response_location: &ValueLocation,
-    ) -> Result<u64, Status> {
-        tracing::debug!("GET: Looking up key (len={})", key.len());
+        key: Vec<u8>,
+        value: Vec<u8>,
+        ttl_millis: u64,
+        origin_version: Option<u64>,
+        if_absent: bool,
+        checksum: Option<u32>,
+    ) -> Result<bool> {
+        verify_checksum(&value, checksum)?;
+        let pool = self.core.memory_pool.read();
 
-        // Look up the value
-        let entry = self
+        // Optimistic check so dropped writes skip the copy; `commit_put` checks
+        // again under the key's lock
+        let stored = self
+            .core
             .cache
-            .get(key)
-            .ok_or_else(|| Status::not_found("Key not found"))?;
+            .get(key.as_slice())
+            .map(|e| (e.version, !e.is_expired()));
+        let live = stored.is_some_and(|(_, live)| live);
+        if (if_absent && live)
+            || self.is_stale(&key, origin_version, stored.map(|(version, _)| version))
+        {
+            return Ok(false);
+        }
 
-        // Check if expired
-        if entry.is_expired() {
-            drop(entry);
-            self.cache.remove(key);
-            return Err(Status::not_found("Key expired"));
+        self.check_value_size(value.len())?;
+
+        // Allocate space in the memory pool, split up if it's too fragmented
+        let allocations = self.allocate_evicting(&pool, &key, value.len(), if_absent)?;
+
+        // Nobody else can see the allocations yet, so no exclusive lock is needed
+        if let Err(e) = pool.write_chunks(&allocations, &value) {
+            free_all(&pool, &allocations);
+            return Err(e);
         }
 
-        let value_len = entry.len() as u64;
-        let src_offset = entry.offset;
-        drop(entry); // Release DashMap ref before acquiring pool lock
+        self.commit_put(
+            &pool,
+            key,
+            value,
+            allocations,
+            ttl_millis,
+            origin_version,
+            if_absent,
+            checksum,
+        )
+    }
 
-        tracing::debug!("GET: Found value, length={}, preparing RDMA transfer", value_len);
+    /// `key` formatted for logging under the `log_keys` policy
+    fn log_key<'a>(&self, key: &'a [u8]) -> LoggedKey<'a> {
+        self.config.log_keys.display(key)
+    }
 
-        // Get the pool's memory handle (release lock before await)
-        let request = {
-            let pool = self.memory_pool.read();
-            let src_handle = pool.handle();
+    /// Reject a request key longer than `max_key_size`
+    #[allow(clippy::result_large_err)] // Status is what the handlers return anyway
+    fn check_key_size(&self, key: &[u8]) -> Result<(), Status> {
+        let max = self.config.max_key_size;
+        if max > 0 && key.len() > max {
+            return Err(Status::invalid_argument(format!(
+                "Key of {} bytes exceeds max_key_size ({})",
+                key.len(),
+                max
+            )));
+        }
+        Ok(())
+    }
 
-            tracing::debug!("GET: Creating transfer request - src_offset={}, dst_offset={}, length={}",
-                src_offset, response_location.offset, value_len);
+    fn check_value_size(&self, len: usize) -> Result<()> {
+        let max = self.config.max_value_size;
+        if max > 0 && len > max {
+            return Err(anyhow!(
+                "Value of {} bytes exceeds max_value_size ({})",
+                len,
+                max
+            ));
+        }
+        Ok(())
+    }
 
-            // Create transfer request
-            TransferRequest {
-                src_handle,
-                src_offset,
-                length: value_len,
-                imm_data: None,
-                dst_descriptor: response_location.mr_descriptor.clone(),
-                dst_offset: response_location.offset,
-                routing: DomainRouting::default(),
+    /// Store a PUT's value, reading it from the client when it names a buffer
+    async fn put_from_source(
+        &self,
+        key: Vec<u8>,
+        value_source: crate::pb::put_request::ValueSource,
+        ttl_millis: u64,
+        origin_version: Option<u64>,
+        if_absent: bool,
+        checksum: Option<u32>,
+    ) -> Result<bool> {
+        match value_source {
+            crate::pb::put_request::ValueSource::InlineValue(value) => {
+                let (value, checksum) = self.intercept_put(&key, value, checksum)?;
+                self.put_versioned(key, value, ttl_millis, origin_version, if_absent, checksum)
             }
-        };
+            crate::pb::put_request::ValueSource::RdmaLocation(location) => {
+                self.put_remote(
+                    key,
+                    &location,
+                    ttl_millis,
+                    origin_version,
+                    if_absent,
+                    checksum,
+                )
+                .await
+            }
+        }
+    }
 
-        tracing::debug!("GET: Submitting RDMA write to client");
+    /// Store a value RDMA-read from a client's registered buffer
+    ///
+    /// The read lands straight in the value's pool allocations, one read per
+    /// region; the entry's copy of the bytes is taken from there once they
+    /// complete, and are checked against `checksum` before being committed.
+    async fn put_remote(
+        &self,
+        key: Vec<u8>,
+        location: &crate::pb::ValueLocation,
+        ttl_millis: u64,
+        origin_version: Option<u64>,
+        if_absent: bool,
+        checksum: Option<u32>,
+    ) -> Result<bool> {
+        if !self.transport.supports_read() {
+            return Err(anyhow!(RDMA_READ_UNSUPPORTED));
+        }
+        let location = ValueLocation::try_from(location)?;
+        let len = location.length as usize;
+        self.check_value_size(len)?;
 
-        // Perform RDMA write to client's buffer
-        let result = self
-            .transport
-            .submit_transfer_async(request)
+        let (allocations, dst_handle) = {
+            let pool = self.core.memory_pool.read();
+            (
+                self.allocate_evicting(&pool, &key, len, if_absent)?,
+                pool.handle(),
+            )
+        };
+
+        let mut src_offset = location.offset;
+        let reads = allocations.iter().map(|allocation| {
+            let request = ReadRequest {
+                src_descriptor: location.mr_descriptor.clone(),
+                src_offset,
+                length: allocation.size as u64,
+                dst_handle,
+                dst_offset: allocation.offset as u64,
+            };
+            src_offset += allocation.size as u64;
+            self.transport.submit_read_async(request)
+        });
+        let read = match futures::future::try_join_all(reads)
+            .instrument(tracing::info_span!("rdma.read", length = location.length))
             .await
-            .map_err(|e| {
-                tracing::error!("GET: Transfer failed: {}", e);
-                Status::internal(format!("Transfer failed: {}", e))
-            })?;
+        {
+            Ok(results) => match results.into_iter().find(|result| !result.success) {
+                None => Ok(()),
+                Some(result) => Err(anyhow!(result
+                    .error
+                    .unwrap_or_else(|| "Unknown error".to_string()))),
+            },
+            Err(e) => Err(e),
+        };
+        if let Err(e) = read {
+            free_all(&self.core.memory_pool.read(), &allocations);
+            return Err(e.context("RDMA read of PUT value failed"));
+        }
 
-        tracing::debug!("GET: Transfer completed, success={}", result.success);
+        let pool = self.core.memory_pool.read();
+        let value = match pool.read_chunks(&allocations) {
+            Ok(value) => value,
+            Err(e) => {
+                free_all(&pool, &allocations);
+                return Err(e);
+            }
+        };
+        if !self.interceptors.is_empty() {
+            // The transformed value needs allocations of its own
+            free_all(&pool, &allocations);
+            drop(pool);
+            let (value, checksum) = self.intercept_put(&key, value, checksum)?;
+            return self.put_versioned(key, value, ttl_millis, origin_version, if_absent, checksum);
+        }
+        if let Err(e) = verify_checksum(&value, checksum) {
+            free_all(&pool, &allocations);
+            return Err(e);
+        }
+        self.commit_put(
+            &pool,
+            key,
+            value,
+            allocations,
+            ttl_millis,
+            origin_version,
+            if_absent,
+            checksum,
+        )
+    }
 
-        if !result.success {
-            return Err(Status::internal(
-                result.error.unwrap_or_else(|| "Unknown error".to_string()),
-            ));
+    /// Allocate room for a `len`-byte value of `key`, evicting entries under
+    /// `eviction` while the pool or `max_entries` is full
+    ///
+    /// Evictions aren't logged, so a WAL replay brings evicted keys back (and
+    /// evicts again if they don't fit). Evicting stops, returning the
+    /// allocation error, once a victim's space is still being read and so
+    /// frees nothing. With `keep_key`, for writes conditional on `key`'s
+    /// current value, its own entry is never the victim.
+    fn allocate_evicting(
+        &self,
+        pool: &MemoryPool,
+        key: &[u8],
+        len: usize,
+        keep_key: bool,
+    ) -> Result<Vec<PoolAllocation>> {
+        let keep = keep_key.then_some(key);
+        let evict = self.config.eviction == EvictionPolicy::Lru;
+        let max_entries = self.config.max_entries as u64;
+        if max_entries > 0 && !self.core.cache.contains_key(key) {
+            while self.core.count().0 >= max_entries {
+                if !evict {
+                    return Err(anyhow!(
+                        "Cache already holds max_entries ({}) entries",
+                        max_entries
+                    ));
+                }
+                if self.evict_lru(pool, keep).is_none() {
+                    break;
+                }
+            }
         }
 
-        tracing::info!("GET: Successfully transferred {} bytes via RDMA", value_len);
-        Ok(value_len)
+        loop {
+            match pool.allocate_chunked(len, self.config.max_value_chunks) {
+                Ok(allocations) => return Ok(allocations),
+                // Emptying the cache can't make room for a value larger than the pool
+                Err(e) if !evict || len > pool.stats().total => return Err(e),
+                Err(e) => match self.evict_lru(pool, keep) {
+                    Some(freed) if freed > 0 => {}
+                    _ => return Err(e),
+                },
+            }
+        }
     }
 
-    /// Delete a value from the cache
-    fn delete_value(&self, key: &[u8]) -> bool {
-        if let Some((_, entry)) = self.cache.remove(key) {
-            let pool = self.memory_pool.write();
-            pool.deallocate(&crate::memory::PoolAllocation {
-                offset: entry.offset as usize,
-                size: entry.len(),
-                ptr: std::ptr::null_mut(),
-            });
-            true
-        } else {
-            false
+    /// Remove the entry read or written longest ago, returning the bytes that
+    /// freed (0 if GETs still hold its space); `None` if the cache is empty
+    ///
+    /// Candidates come from `eviction_queue`, so the map is only scanned once
+    /// per queue's worth of evictions. One written since it was queued is
+    /// skipped (its new version is picked up by the next scan) and one read
+    /// since goes to the back for a second chance. `keep` is passed over and
+    /// stays at the front.
+    fn evict_lru(&self, pool: &MemoryPool, keep: Option<&[u8]>) -> Option<usize> {
+        let mut queue = self.eviction_queue.lock();
+        let mut kept = None;
+        let freed = self.evict_from(pool, &mut queue, keep, &mut kept);
+        if let Some(candidate) = kept {
+            queue.push_front(candidate);
         }
+        freed
     }
-}
 
-/// gRPC service implementation wrapper
-pub struct KvCacheServiceImpl {
-    inner: Arc<KvCacheServer>,
-}
-
-#[tonic::async_trait]
-impl KvCacheService for KvCacheServiceImpl {
-    async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
-        let req = request.into_inner();
-        let request_id = req.request_id;
-
-        tracing::debug!("GET request: key={:?}, request_id={}", req.key, request_id);
-
-        let response_location = req
-            .response_location
-            .as_ref()
-            .ok_or_else(|| Status::invalid_argument("Missing response_location"))?;
+    /// `evict_lru`'s scan of `queue`, parking `keep`'s candidate in `kept`
+    fn evict_from(
+        &self,
+        pool: &MemoryPool,
+        queue: &mut VecDeque<EvictionCandidate>,
+        keep: Option<&[u8]>,
+        kept: &mut Option<EvictionCandidate>,
+    ) -> Option<usize> {
+        let mut refilled = false;
+        loop {
+            let Some(candidate) = queue.pop_front() else {
+                if refilled {
+                    return None;
+                }
+                *queue = self.eviction_candidates();
+                *kept = None;
+                refilled = true;
+                continue;
+            };
+            if keep == Some(candidate.key.as_slice()) {
+                *kept = Some(candidate);
+                continue;
+            }
+            let current = self
+                .core
+                .cache
+                .get(candidate.key.as_slice())
+                .map(|entry| (entry.version, entry.last_accessed));
+            match current {
+                Some((version, _)) if version != candidate.version => continue,
+                Some((_, last_accessed)) if last_accessed > candidate.queued_at => {
+                    queue.push_back(EvictionCandidate {
+                        queued_at: Instant::now(),
+                        ..candidate
+                    });
+                    continue;
+                }
+                Some(_) => {}
+                None => continue,
+            }
+            // A concurrent write or delete may have got to it first
+            let Some(entry) = self
+                .core
+                .remove_if_version(&candidate.key, candidate.version)
+            else {
+                continue;
+            };
+            tracing::debug!(
+                "Evicting {} ({} bytes)",
+                self.log_key(&candidate.key),
+                entry.len()
+            );
+            let freed = self.free_entry(pool, entry);
+            self.notify(self.keyspace_event(KeyspaceEventKind::Evicted, &candidate.key));
+            return Some(freed);
+        }
+    }
 
-        let value_location: ValueLocation = response_location.into();
+    /// Every entry in the map, least recently used first
+    fn eviction_candidates(&self) -> VecDeque<EvictionCandidate> {
+        let queued_at = Instant::now();
+        let mut candidates: Vec<_> = self
+            .core
+            .cache
+            .iter()
+            .map(|entry| (entry.last_accessed, entry.key().to_vec(), entry.version))
+            .collect();
+        candidates.sort_unstable_by_key(|(last_accessed, _, _)| *last_accessed);
+        candidates
+            .into_iter()
+            .map(|(_, key, version)| EvictionCandidate {
+                key,
+                version,
+                queued_at,
+            })
+            .collect()
+    }
 
-        match self.inner.get_and_transfer(&req.key, &value_location).await {
-            Ok(value_length) => {
+    /// Whether a replicated write is no newer than the key's stored version
+    /// or a live tombstone
+    fn is_stale(&self, key: &[u8], origin_version: Option<u64>, stored: Option<u64>) -> bool {
+        let Some(version) = origin_version else {
+            return false;
+        };
+        let deleted = self
+            .tombstones
+            .get(key)
+            .filter(|tombstone| !tombstone.is_expired())
+            .map(|tombstone| tombstone.version);
+        match stored.max(deleted).filter(|&latest| version <= latest) {
+            Some(latest) => {
                 tracing::debug!(
-                    "GET success: key={:?}, length={}, request_id={}",
-                    req.key,
-                    value_length,
-                    request_id
-                );
-                Ok(Response::new(GetResponse {
-                    success: true,
-                    value_length,
-                    error_message: String::new(),
-                    request_id,
-                }))
-            }
-            Err(status) => {
-                tracing::warn!(
-                    "GET failed: key={:?}, error={}, request_id={}",
-                    req.key,
-                    status.message(),
-                    request_id
+                    "Dropping stale replicated PUT: version {} <= {}",
+                    version,
+                    latest
                 );
-                Ok(Response::new(GetResponse {
-                    success: false,
-                    value_length: 0,
-                    error_message: status.message().to_string(),
-                    request_id,
-                }))
+                true
             }
+            None => false,
         }
     }
 
-    async fn put(&self, request: Request<PutRequest>) -> Result<Response<PutResponse>, Status> {
-        let req = request.into_inner();
+    /// Log and publish a value already written to `allocations`
+    ///
+    /// The staleness check, WAL append, versioning and map update all happen
+    /// under the key's map shard lock, so writes to one key (and DELETEs, which
+    /// log under the same lock) apply and log in the same order, and versions
+    /// only grow, without a global lock. The `if_absent` check sits under the
+    /// same lock, so of several racing put-if-absent writes exactly one
+    /// stores. `pool` is the caller's shared guard.
+    #[allow(clippy::too_many_arguments)]
+    fn commit_put(
+        &self,
+        pool: &MemoryPool,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        allocations: Vec<PoolAllocation>,
+        ttl_millis: u64,
+        origin_version: Option<u64>,
+        if_absent: bool,
+        checksum: Option<u32>,
+    ) -> Result<bool> {
+        let event = self.keyspace_event(KeyspaceEventKind::Set, &key);
 
-        tracing::debug!("PUT request: key={:?}", req.key);
+        let cache_key = self.core.cache_key(key);
 
-        let value = match req.value_source {
-            Some(crate::pb::put_request::ValueSource::InlineValue(v)) => v,
-            Some(crate::pb::put_request::ValueSource::RdmaLocation(_loc)) => {
-                // TODO: Implement RDMA read from client for large values
-                return Err(Status::unimplemented("RDMA read for PUT not yet implemented"));
+        let replaced = match self.core.cache.entry(cache_key) {
+            dashmap::Entry::Occupied(existing) if if_absent && !existing.get().is_expired() => {
+                free_all(pool, &allocations);
+                self.core.release_key(existing.into_key());
+                return Ok(false);
             }
-            None => return Err(Status::invalid_argument("Missing value")),
-        };
-
-        match self.inner.put_value(req.key, value, req.ttl_seconds) {
-            Ok(()) => {
-                tracing::debug!("PUT success");
-                Ok(Response::new(PutResponse {
-                    success: true,
-                    error_message: String::new(),
-                }))
+            dashmap::Entry::Occupied(mut existing) => {
+                let stored = Some(existing.get().version);
+                let outcome = self
+                    .new_entry(
+                        pool,
+                        existing.key(),
+                        stored,
+                        value,
+                        allocations,
+                        ttl_millis,
+                        origin_version,
+                        checksum,
+                    )
+                    .map(|entry| {
+                        entry.map(|entry| {
+                            self.core.count_added(&entry);
+                            existing.insert(entry)
+                        })
+                    });
+                self.core.release_key(existing.into_key());
+                match outcome? {
+                    Some(old_entry) => Some(old_entry),
+                    None => return Ok(false),
+                }
             }
-            Err(e) => {
-                tracing::warn!("PUT failed: {}", e);
-                Ok(Response::new(PutResponse {
-                    success: false,
-                    error_message: e.to_string(),
-                }))
+            dashmap::Entry::Vacant(vacant) => {
+                match self.new_entry(
+                    pool,
+                    vacant.key(),
+                    None,
+                    value,
+                    allocations,
+                    ttl_millis,
+                    origin_version,
+                    checksum,
+                ) {
+                    Ok(Some(entry)) => {
+                        // Record new keys in the filter before they become visible in the map
+                        self.core.bloom_insert(vacant.key());
+                        self.core.count_added(&entry);
+                        vacant.insert(entry);
+                        None
+                    }
+                    Ok(None) => {
+                        self.core.release_key(vacant.into_key());
+                        return Ok(false);
+                    }
+                    Err(e) => {
+                        self.core.release_key(vacant.into_key());
+                        return Err(e);
+                    }
+                }
             }
+        };
+
+        if let Some(old_entry) = replaced {
+            self.core.count_removed(&old_entry);
+            self.free_entry(pool, old_entry);
         }
+        self.notify(event);
+
+        Ok(true)
     }
 
-    async fn delete(
+    /// Build the entry for a write to `key`, whose stored version is `stored`
+    ///
+    /// Returns `None` for a stale replicated write. Either way out of a `None`
+    /// or an error, the allocations are freed. Called under the key's shard lock.
+    #[allow(clippy::too_many_arguments)]
+    fn new_entry(
         &self,
-        request: Request<DeleteRequest>,
-    ) -> Result<Response<DeleteResponse>, Status> {
-        let req = request.into_inner();
-
-        tracing::debug!("DELETE request: key={:?}", req.key);
+        pool: &MemoryPool,
+        key: &[u8],
+        stored: Option<u64>,
+        value: Vec<u8>,
+        allocations: Vec<PoolAllocation>,
+        ttl_millis: u64,
+        origin_version: Option<u64>,
+        checksum: Option<u32>,
+    ) -> Result<Option<CacheEntry>> {
+        if self.is_stale(key, origin_version, stored) {
+            free_all(pool, &allocations);
+            return Ok(None);
+        }
 
-        let existed = self.inner.delete_value(&req.key);
+        if let Some(wal) = &self.wal {
+            if let Err(e) = wal.append_put(key, &value, ttl_millis) {
+                free_all(pool, &allocations);
+                return Err(e);
+            }
+        }
 
-        Ok(Response::new(DeleteResponse {
-            success: true,
-            key_existed: existed,
+        // Local versions keep counting above any replicated one
+        let version = match origin_version {
+            Some(version) => {
+                self.core
+                    .next_version
+                    .fetch_max(version + 1, Ordering::Relaxed);
+                version
+            }
+            None => self.core.next_version.fetch_add(1, Ordering::Relaxed),
+        };
+        self.tombstones.remove(key);
+        Ok(Some(CacheEntry {
+            checksum,
+            ..CacheEntry::new(value, allocations, ttl_millis, version)
         }))
     }
 
-    async fn register_client(
-        &self,
-        request: Request<RegisterClientRequest>,
-    ) -> Result<Response<RegisterClientResponse>, Status> {
-        let req = request.into_inner();
+    /// Free a replaced entry's regions once in-flight GETs are done with them,
+    /// returning the bytes freed right away
+    fn free_entry(&self, pool: &MemoryPool, entry: CacheEntry) -> usize {
+        let mut freed = 0;
+        for allocation in entry.into_allocations() {
+            if let Some(allocation) = self.region_readers.defer_free(allocation) {
+                pool.deallocate(&allocation);
+                freed += allocation.size;
+            }
+        }
+        freed
+    }
 
-        tracing::info!(
-            "Client registration: id={}, buffer_size={}",
-            req.client_id,
-            req.receive_buffer_size
-        );
+    /// Remove expired entries and free their pool space, returning how many
+    pub fn sweep_expired(&self) -> usize {
+        let expired: Vec<Vec<u8>> = self
+            .core
+            .cache
+            .iter()
+            .filter(|entry| entry.is_expired())
+            .map(|entry| entry.key().to_vec())
+            .collect();
+        let pool = self.core.memory_pool.read();
+        expired
+            .iter()
+            .filter(|key| self.reap_expired(&pool, key))
+            .count()
+    }
 
-        let client = RegisteredClient {
-            client_id: req.client_id,
-            domain_addresses: req.domain_addresses.into_iter().map(DomainAddress::new).collect(),
-            receive_buffer_size: req.receive_buffer_size,
+    /// Remove `key`'s entry if it has expired and free its pool space, so it
+    /// stops counting; false if it's gone or was rewritten meanwhile
+    fn reap_expired(&self, pool: &MemoryPool, key: &[u8]) -> bool {
+        let Some(entry) = self.core.remove_if_expired(key) else {
+            return false;
         };
+        self.free_entry(pool, entry);
+        self.notify(self.keyspace_event(KeyspaceEventKind::Expired, key));
+        true
+    }
 
-        self.inner.clients.write().insert(req.client_id, client);
+    /// Drop expired tombstones, returning how many were removed
+    pub fn reap_tombstones(&self) -> usize {
+        let before = self.tombstones.len();
+        self.tombstones
+            .retain(|_, tombstone| !tombstone.is_expired());
+        before - self.tombstones.len()
+    }
 
-        let server_addresses: Vec<Vec<u8>> = self
-            .inner
-            .transport
+    /// Get a value and RDMA write it to the client's buffer
+    ///
+    /// With `if_version_gt`, the transfer is skipped when the stored version is
+    /// not newer than the caller's copy. A value larger than the client's
+    /// buffer fails with `OUT_OF_RANGE`, its length in `value-length` metadata.
+    async fn get_and_transfer(
+        &self,
+        key: &[u8],
+        response_location: &ValueLocation,
+        if_version_gt: Option<u64>,
+    ) -> Result<GetResult, Status> {
+        tracing::debug!("GET: Looking up key (len={})", key.len());
+
+        let entry = self.resident_entry(key).await?;
+        let (value_len, version, loaded) = (entry.value_len, entry.version, entry.loaded);
+        let (remaining_ttl_seconds, checksum) = (entry.remaining_ttl_seconds, entry.checksum);
+
+        if if_version_gt.is_some_and(|known| version <= known) {
+            tracing::debug!(
+                "GET: Version {} not newer than client's, skipping transfer",
+                version
+            );
+            return Ok(GetResult {
+                value_len,
+                version,
+                not_modified: true,
+                inline_value: None,
+                loaded,
+                remaining_ttl_seconds,
+                checksum,
+            });
+        }
+
+        if !self.interceptors.is_empty() {
+            let data = self
+                .core
+                .cache
+                .get(key)
+                .filter(|entry| entry.version == version)
+                .map(|entry| entry.data.clone())
+                .ok_or_else(|| Status::aborted("Key was overwritten during GET"))?;
+            let value = self
+                .intercept_get(key, data)
+                .map_err(|e| Status::internal(format!("Interceptor failed: {}", e)))?;
+            tracing::debug!("GET: Returning {} intercepted bytes inline", value.len());
+            return Ok(GetResult {
+                value_len: value.len() as u64,
+                version,
+                not_modified: false,
+                inline_value: Some(value),
+                loaded,
+                remaining_ttl_seconds,
+                checksum,
+            });
+        }
+
+        if (value_len as usize) < self.config.small_value_inline_threshold {
+            // Skip the copy if the entry was overwritten since the lookup; the
+            // transfer below then sends whatever the pool holds, as before
+            let data = self
+                .core
+                .cache
+                .get(key)
+                .filter(|entry| entry.version == version)
+                .map(|entry| entry.data.clone());
+            if let Some(data) = data {
+                tracing::debug!("GET: Returning {} bytes inline", value_len);
+                return Ok(GetResult {
+                    value_len,
+                    version,
+                    not_modified: false,
+                    inline_value: Some(data),
+                    loaded,
+                    remaining_ttl_seconds,
+                    checksum,
+                });
+            }
+        }
+
+        if value_len > response_location.length {
+            let mut status = Status::out_of_range(format!(
+                "Value of {} bytes doesn't fit the {}-byte buffer",
+                value_len, response_location.length
+            ));
+            if let Ok(value) = value_len.to_string().parse() {
+                status.metadata_mut().insert("value-length", value);
+            }
+            return Err(status);
+        }
+
+        tracing::debug!(
+            "GET: Found value, length={}, preparing RDMA transfer",
+            value_len
+        );
+
+        // Get the pool's memory handle (release lock before await)
+        let src_handle = self.core.memory_pool.read().handle();
+
+        tracing::debug!(
+            "GET: Creating transfer request - segments={:?}, dst_offset={}, length={}",
+            entry.segments,
+            response_location.offset,
+            value_len
+        );
+
+        let mut requests = entry.transfers(
+            src_handle,
+            &response_location.mr_descriptor,
+            response_location.offset,
+            self.routing(),
+        );
+
+        tracing::debug!("GET: Submitting RDMA write to client");
+
+        // Perform RDMA write to client's buffer; a split value goes as one
+        // scatter-gather batch
+        let results = match requests.len() {
+            1 => self
+                .transport
+                .submit_transfer_async(requests.pop().unwrap())
+                .instrument(tracing::info_span!("rdma.transfer", length = value_len))
+                .await
+                .map(|result| vec![result]),
+            _ => {
+                self.transport
+                    .submit_batch_async(requests)
+                    .instrument(tracing::info_span!("rdma.transfer", length = value_len))
+                    .await
+            }
+        }
+        .map_err(|e| {
+            tracing::error!("GET: Transfer failed: {}", e);
+            Status::internal(format!("Transfer failed: {}", e))
+        })?;
+
+        tracing::debug!(
+            "GET: Transfer completed, success={}",
+            results.iter().all(|r| r.success)
+        );
+
+        if let Some(failed) = results.into_iter().find(|result| !result.success) {
+            return Err(Status::internal(
+                failed.error.unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+        drop(entry);
+
+        tracing::info!("GET: Successfully transferred {} bytes via RDMA", value_len);
+        Ok(GetResult {
+            value_len,
+            version,
+            not_modified: false,
+            inline_value: None,
+            loaded,
+            remaining_ttl_seconds,
+            checksum,
+        })
+    }
+
+    /// Write several values into one client buffer with a single batched submission
+    ///
+    /// Returns each item's value length, `None` for a miss. Nothing is
+    /// transferred if any value is larger than its slot, or at all with
+    /// interceptors, whose output can't be RDMA written from the pool.
+    async fn get_many_and_transfer(
+        &self,
+        items: &[GetManyItem],
+        buffer: &MemoryRegionDescriptor,
+    ) -> Result<Vec<Option<u64>>, Status> {
+        if !self.interceptors.is_empty() {
+            return Err(Status::failed_precondition(
+                "GetMany is not supported with value interceptors; use GET",
+            ));
+        }
+        let src_handle = self.core.memory_pool.read().handle();
+        let mut lengths = Vec::with_capacity(items.len());
+        let mut requests = Vec::with_capacity(items.len());
+        // Held until the transfers below finish
+        let mut leases = Vec::with_capacity(items.len());
+
+        for item in items {
+            let entry = match self.resident_entry(&item.key).await {
+                Ok(entry) => entry,
+                Err(status) if status.code() == Code::NotFound => {
+                    lengths.push(None);
+                    continue;
+                }
+                Err(status) => return Err(status),
+            };
+            if entry.value_len > item.capacity {
+                return Err(Status::out_of_range(format!(
+                    "Value of {} bytes doesn't fit its {}-byte slot at offset {}",
+                    entry.value_len, item.capacity, item.offset
+                )));
+            }
+            requests.extend(entry.transfers(src_handle, buffer, item.offset, self.routing()));
+            lengths.push(Some(entry.value_len));
+            leases.push(entry.leases);
+        }
+
+        tracing::debug!("GET_MANY: Submitting {} RDMA writes", requests.len());
+        let results = self
+            .transport
+            .submit_batch_async(requests)
+            .await
+            .map_err(|e| Status::internal(format!("Transfer failed: {}", e)))?;
+        if let Some(failed) = results.into_iter().find(|result| !result.success) {
+            return Err(Status::internal(
+                failed.error.unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        Ok(lengths)
+    }
+
+    /// Routing for GET transfers, striping across the configured domains
+    fn routing(&self) -> DomainRouting {
+        let num_shards = match self.config.num_shards {
+            0 => self.config.transport.num_domains.clamp(1, u8::MAX as usize) as u8,
+            n => n,
+        };
+        DomainRouting::RoundRobinSharded { num_shards }
+    }
+
+    /// Make a key resident (loading it on a miss) without transferring it
+    async fn warm(&self, key: &[u8]) -> Result<GetResult, Status> {
+        let entry = self.resident_entry(key).await?;
+        Ok(GetResult {
+            value_len: entry.value_len,
+            version: entry.version,
+            not_modified: false,
+            inline_value: None,
+            loaded: entry.loaded,
+            remaining_ttl_seconds: entry.remaining_ttl_seconds,
+            checksum: entry.checksum,
+        })
+    }
+
+    /// Find a live entry, going to the read-through loader on a miss
+    ///
+    /// Refreshes the entry's access time. An expired entry is a miss unless
+    /// `repair_on_expiry` is set, in which case it is reloaded with its old TTL.
+    /// A value written while the loader runs wins over the loaded one.
+    async fn resident_entry(&self, key: &[u8]) -> Result<ResidentEntry, Status> {
+        let ttl_millis = match self.touch_live(key) {
+            Lookup::Live(entry) => return Ok(entry),
+            Lookup::Missing => 0,
+            Lookup::Expired { ttl_millis } if self.config.repair_on_expiry => ttl_millis,
+            Lookup::Expired { .. } => return Err(Status::not_found("Key expired")),
+        };
+
+        let loader = self
+            .loader
+            .as_ref()
+            .ok_or_else(|| Status::not_found("Key not found"))?;
+        let value = loader
+            .load(key)
+            .await
+            .map_err(|e| Status::unavailable(format!("Loader failed: {}", e)))?
+            .ok_or_else(|| Status::not_found("Key not found"))?;
+
+        tracing::debug!(
+            "GET: Loaded {} bytes through read-through loader",
+            value.len()
+        );
+        let stored = self
+            .intercept_put(key, value, None)
+            .and_then(|(value, _)| {
+                self.put_versioned(key.to_vec(), value, ttl_millis, None, true, None)
+            })
+            .map_err(|e| {
+                Status::resource_exhausted(format!("Failed to store loaded value: {}", e))
+            })?;
+        if !stored {
+            tracing::debug!("GET: Key was written during the load, keeping that value");
+        }
+
+        match self.touch_live(key) {
+            Lookup::Live(entry) => Ok(ResidentEntry {
+                loaded: stored,
+                ..entry
+            }),
+            Lookup::Missing | Lookup::Expired { .. } => Err(Status::not_found("Key not found")),
+        }
+    }
+
+    /// Look up a live entry and mark it accessed; an expired entry is removed
+    ///
+    /// With `adaptive_ttl`, the hit also extends the entry's TTL.
+    fn touch_live(&self, key: &[u8]) -> Lookup {
+        if !self.core.may_contain(key) {
+            return Lookup::Missing;
+        }
+
+        let Some(mut entry) = self.core.cache.get_mut(key) else {
+            return Lookup::Missing;
+        };
+
+        // Check if expired
+        if entry.is_expired() {
+            let ttl_millis = entry.ttl_millis;
+            drop(entry);
+            self.reap_expired(&self.core.memory_pool.read(), key);
+            return Lookup::Expired { ttl_millis };
+        }
+
+        entry.last_accessed = std::time::Instant::now();
+        if let Some(adaptive) = &self.config.adaptive_ttl {
+            entry.extend_ttl(
+                adaptive.increment_seconds.saturating_mul(1000),
+                adaptive.max_ttl_seconds.saturating_mul(1000),
+            );
+        }
+        Lookup::Live(ResidentEntry {
+            value_len: entry.len() as u64,
+            segments: entry.segments(),
+            version: entry.version,
+            remaining_ttl_seconds: entry.remaining_ttl_seconds(),
+            checksum: entry.checksum,
+            leases: entry
+                .allocations()
+                .map(|allocation| self.region_readers.acquire(allocation))
+                .collect(),
+            loaded: false,
+        })
+    }
+
+    /// Check whether a live (non-expired) entry exists for the key; an
+    /// expired entry is removed
+    pub fn contains(&self, key: &[u8]) -> bool {
+        self.value_len(key).is_some()
+    }
+
+    /// Length of `key`'s live value, leaving a live entry and the loader
+    /// alone; an expired entry is removed
+    pub fn value_len(&self, key: &[u8]) -> Option<u64> {
+        if !self.core.may_contain(key) {
+            return None;
+        }
+        let (len, expired) = self
+            .core
+            .cache
+            .get(key)
+            .map(|entry| (entry.len() as u64, entry.is_expired()))?;
+        if expired {
+            self.reap_expired(&self.core.memory_pool.read(), key);
+            return None;
+        }
+        Some(len)
+    }
+
+    /// Build an event for `key`, or `None` if nobody is watching
+    fn keyspace_event(&self, kind: KeyspaceEventKind, key: &[u8]) -> Option<KeyspaceEvent> {
+        (self.events.receiver_count() > 0).then(|| KeyspaceEvent {
+            kind: kind as i32,
+            key: key.to_vec(),
+            missed: 0,
+        })
+    }
+
+    /// Publish an event built by `keyspace_event` to current watchers
+    fn notify(&self, event: Option<KeyspaceEvent>) {
+        if let Some(event) = event {
+            // Only fails if the last watcher has just gone away
+            let _ = self.events.send(event);
+        }
+    }
+
+    /// Entries and stored value bytes, from the running counters
+    pub fn count(&self) -> (u64, u64) {
+        self.core.count()
+    }
+
+    /// Snapshot one live entry for a Dump; `None` if it was removed or has
+    /// expired, in which case it is removed now
+    fn dump_entry(&self, key: &[u8], max_inline_bytes: u64) -> Option<DumpEntry> {
+        let entry = self.core.cache.get(key)?;
+        if entry.is_expired() {
+            drop(entry);
+            self.reap_expired(&self.core.memory_pool.read(), key);
+            return None;
+        }
+        let value_length = entry.len() as u64;
+        Some(DumpEntry {
+            key: key.to_vec(),
+            inline_value: (value_length <= max_inline_bytes).then(|| entry.data.clone()),
+            value_length,
+            ttl_seconds: entry.remaining_ttl_seconds(),
+        })
+    }
+
+    /// Effective priority of a GET: the request's override, else the client's registration
+    fn get_priority(&self, requested: Option<u32>, client_id: u32) -> u8 {
+        match requested {
+            Some(priority) => priority.min(u8::MAX as u32) as u8,
+            None => self
+                .clients
+                .read()
+                .get(&client_id)
+                .map_or(0, |client| client.priority),
+        }
+    }
+
+    /// Quantile `q` of GET latency at `priority`; `None` before any such GET
+    pub fn get_latency_quantile(&self, priority: u8, q: f64) -> Option<Duration> {
+        self.get_latency.get(&priority)?.quantile(q)
+    }
+
+    /// Make every write acknowledged so far durable
+    ///
+    /// Returns the durable WAL sequence; without a WAL there is nothing to wait
+    /// for and this returns 0.
+    fn flush(&self) -> Result<u64> {
+        match &self.wal {
+            Some(wal) => wal.flush(),
+            None => Ok(0),
+        }
+    }
+
+    /// Rewrite the WAL as a snapshot of the live entries, leaving it durable
+    ///
+    /// Writes carry on meanwhile; see `Wal::compact`. Does nothing without a WAL.
+    pub fn compact_wal(&self) -> Result<()> {
+        let Some(wal) = &self.wal else {
+            return Ok(());
+        };
+        wal.compact(|snapshot| {
+            for entry in self.core.cache.iter() {
+                if !entry.is_expired() {
+                    snapshot.put(entry.key(), &entry.data, entry.remaining_ttl_millis())?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Whether the WAL has grown past `wal_compact_bytes` and twice its
+    /// compacted size
+    fn wal_needs_compaction(&self) -> bool {
+        let Some(wal) = &self.wal else {
+            return false;
+        };
+        let threshold = self.config.wal_compact_bytes;
+        threshold > 0 && wal.len() >= threshold.max(wal.compacted_len().saturating_mul(2))
+    }
+
+    /// Delete a value from the cache
+    fn delete_value(&self, key: &[u8]) -> Result<bool> {
+        Ok(self.delete_versioned(key, None)? == Some(true))
+    }
+
+    /// Delete a value, versioned like a PUT when `origin_version` is set by a
+    /// replicating peer; returns whether the key existed, or `None` if a
+    /// replicated delete was no newer than the stored value and was dropped
+    ///
+    /// The key is tombstoned even if absent, since the write it races with may
+    /// not have arrived yet. If the WAL can't log the delete, nothing changes.
+    fn delete_versioned(&self, key: &[u8], origin_version: Option<u64>) -> Result<Option<bool>> {
+        // PUTs only take a shared lock, so this excludes them from the pool
+        // unless frees are deferred, which only need ordering against the allocator
+        let read_guard;
+        let write_guard;
+        let pool: &MemoryPool = if self.deferred_frees.is_some() {
+            read_guard = self.core.memory_pool.read();
+            &read_guard
+        } else {
+            write_guard = self.core.memory_pool.write();
+            &write_guard
+        };
+
+        // Logged under the key's shard lock, like PUTs, so the log orders a
+        // PUT and DELETE of the same key the way they applied
+        let removed = match self.core.cache.entry(CacheKey::Owned(key.to_vec())) {
+            dashmap::Entry::Occupied(existing)
+                if origin_version.is_some_and(|version| version <= existing.get().version) =>
+            {
+                tracing::debug!(
+                    "Dropping stale replicated DELETE: version {:?} <= {}",
+                    origin_version,
+                    existing.get().version
+                );
+                return Ok(None);
+            }
+            dashmap::Entry::Occupied(existing) => {
+                if let Some(wal) = &self.wal {
+                    wal.append_delete(key)?;
+                }
+                self.record_tombstone(key, origin_version);
+                existing.remove_entry()
+            }
+            dashmap::Entry::Vacant(_) => {
+                self.record_tombstone(key, origin_version);
+                return Ok(Some(false));
+            }
+        };
+        let (stored_key, entry) = removed;
+        self.core.forget_key(stored_key);
+        self.core.count_removed(&entry);
+        self.notify(self.keyspace_event(KeyspaceEventKind::Del, key));
+
+        for allocation in entry.into_allocations() {
+            let Some(allocation) = self.region_readers.defer_free(allocation) else {
+                continue;
+            };
+            match &self.deferred_frees {
+                Some(frees) => {
+                    // The thread only goes away with the server; free inline if it died
+                    if let Err(mpsc::SendError(allocation)) = frees.send(allocation) {
+                        pool.deallocate(&allocation);
+                    }
+                }
+                None => {
+                    pool.deallocate(&allocation);
+                }
+            }
+        }
+        Ok(Some(true))
+    }
+
+    /// Delete every key starting with `prefix`; returns how many were removed
+    ///
+    /// Matching keys are snapshotted first, so keys written during the scan may
+    /// survive it. Stops at the first delete the WAL can't log.
+    fn delete_prefix(&self, prefix: &[u8]) -> Result<usize> {
+        let keys: Vec<Vec<u8>> = self
+            .core
+            .cache
+            .iter()
+            .filter(|e| e.key().starts_with(prefix))
+            .map(|e| e.key().to_vec())
+            .collect();
+        let mut deleted = 0;
+        for key in &keys {
+            if self.delete_value(key)? {
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// Remember a deletion so older replicated writes of `key` are dropped
+    ///
+    /// A replicated delete carries the origin's version, so the tombstone
+    /// orders against the origin's writes rather than this node's counter.
+    fn record_tombstone(&self, key: &[u8], origin_version: Option<u64>) {
+        if self.config.tombstone_ttl.is_zero() {
+            return;
+        }
+        let version = match origin_version {
+            Some(version) => {
+                self.core
+                    .next_version
+                    .fetch_max(version + 1, Ordering::Relaxed);
+                version
+            }
+            None => self.core.next_version.fetch_add(1, Ordering::Relaxed),
+        };
+        let expires_at = Instant::now() + self.config.tombstone_ttl;
+        self.tombstones
+            .entry(key.to_vec())
+            .and_modify(|tombstone| {
+                tombstone.version = tombstone.version.max(version);
+                tombstone.expires_at = expires_at;
+            })
+            .or_insert(Tombstone {
+                version,
+                expires_at,
+            });
+    }
+
+    /// Replace `key`'s value with `value` only if it currently holds `expected`
+    ///
+    /// `None` expects no live value, like a put-if-absent, and stores a value
+    /// that doesn't expire; a swap keeps the replaced value's remaining TTL.
+    /// The value is compared before anything is allocated, so a mismatch
+    /// evicts nothing, and allocating never evicts `key` itself. The final
+    /// comparison and the replacement happen under the key's map shard lock,
+    /// so of several swaps racing from the same value exactly one succeeds.
+    /// Returns whether it was stored.
+    fn compare_and_swap(
+        &self,
+        key: Vec<u8>,
+        expected: Option<&[u8]>,
+        value: Vec<u8>,
+    ) -> Result<bool> {
+        let (value, checksum) = self.intercept_put(&key, value, None)?;
+        let Some(expected) = expected else {
+            return self.put_versioned(key, value, 0, None, true, checksum);
+        };
+        self.check_value_size(value.len())?;
+
+        // Clients compare against what a GET returns, not the stored bytes
+        let matches = |entry: &CacheEntry| {
+            if entry.is_expired() {
+                Ok(false)
+            } else if self.interceptors.is_empty() {
+                Ok(entry.data == expected)
+            } else {
+                self.intercept_get(&key, entry.data.clone())
+                    .map(|current| current == expected)
+            }
+        };
+        let compared = match self.core.cache.get(key.as_slice()) {
+            Some(existing) if matches(&existing)? => existing.version,
+            _ => return Ok(false),
+        };
+
+        let pool = self.core.memory_pool.read();
+        let allocations = self.allocate_evicting(&pool, &key, value.len(), true)?;
+        if let Err(e) = pool.write_chunks(&allocations, &value) {
+            free_all(&pool, &allocations);
+            return Err(e);
+        }
+
+        let Some(mut existing) = self.core.cache.get_mut(key.as_slice()) else {
+            free_all(&pool, &allocations);
+            return Ok(false);
+        };
+        // Only a write in between needs comparing again
+        if existing.version != compared {
+            match matches(&existing) {
+                Ok(true) => {}
+                other => {
+                    free_all(&pool, &allocations);
+                    return other;
+                }
+            }
+        }
+
+        let event = self.keyspace_event(KeyspaceEventKind::Set, &key);
+        let stored = Some(existing.version);
+        let ttl_millis = existing.remaining_ttl_millis();
+        let Some(entry) = self.new_entry(
+            &pool,
+            &key,
+            stored,
+            value,
+            allocations,
+            ttl_millis,
+            None,
+            checksum,
+        )?
+        else {
+            return Ok(false);
+        };
+        self.core.count_added(&entry);
+        let old_entry = std::mem::replace(&mut *existing, entry);
+        drop(existing);
+        self.core.count_removed(&old_entry);
+        self.free_entry(&pool, old_entry);
+        self.notify(event);
+        Ok(true)
+    }
+
+    /// Move `src`'s entry to `dst`, replacing any value there
+    ///
+    /// The pool bytes stay where they are; the entry keeps its TTL clock and
+    /// takes a new version. Returns false if `src` has no live value. Not
+    /// atomic: the entry leaves `src` before it appears under `dst`, so a
+    /// reader in between sees neither.
+    ///
+    /// Each half is logged before it is applied. If the WAL can't log the
+    /// source's removal nothing changes; if it can't log the destination, the
+    /// value is dropped, matching what the log will replay.
+    fn rename_value(&self, src: &[u8], dst: &[u8]) -> Result<bool> {
+        if src == dst {
+            return Ok(self.contains(src));
+        }
+
+        let pool = self.core.memory_pool.read();
+
+        // Each half logs under its key's shard lock, as DELETE and PUT do;
+        // taking one lock at a time keeps opposing renames from deadlocking
+        let (src_key, mut entry) = match self.core.cache.entry(CacheKey::Owned(src.to_vec())) {
+            dashmap::Entry::Occupied(existing) if !existing.get().is_expired() => {
+                if let Some(wal) = &self.wal {
+                    wal.append_delete(src)?;
+                }
+                existing.remove_entry()
+            }
+            _ => return Ok(false),
+        };
+        self.record_tombstone(src, None);
+        self.core.forget_key(src_key);
+        self.core.count_removed(&entry);
+        self.notify(self.keyspace_event(KeyspaceEventKind::Del, src));
+
+        let event = self.keyspace_event(KeyspaceEventKind::Set, dst);
+        let cache_key = self.core.cache_key(dst.to_vec());
+        let log_put = |entry: &CacheEntry| match &self.wal {
+            Some(wal) => wal
+                .append_put(dst, &entry.data, entry.remaining_ttl_millis())
+                .map(|_| ()),
+            None => Ok(()),
+        };
+        let logged = match self.core.cache.entry(cache_key) {
+            dashmap::Entry::Occupied(mut existing) => match log_put(&entry) {
+                Ok(()) => {
+                    entry.version = self.core.next_version.fetch_add(1, Ordering::Relaxed);
+                    self.tombstones.remove(dst);
+                    self.core.count_added(&entry);
+                    let old_entry = existing.insert(entry);
+                    self.core.release_key(existing.into_key());
+                    Ok(Some(old_entry))
+                }
+                Err(e) => Err((e, entry)),
+            },
+            dashmap::Entry::Vacant(vacant) => match log_put(&entry) {
+                Ok(()) => {
+                    entry.version = self.core.next_version.fetch_add(1, Ordering::Relaxed);
+                    self.tombstones.remove(dst);
+                    self.core.bloom_insert(vacant.key());
+                    self.core.count_added(&entry);
+                    vacant.insert(entry);
+                    Ok(None)
+                }
+                Err(e) => Err((e, entry)),
+            },
+        };
+        let replaced = match logged {
+            Ok(replaced) => replaced,
+            Err((e, entry)) => {
+                self.free_entry(&pool, entry);
+                return Err(e.context("RENAME removed the source but couldn't log the destination"));
+            }
+        };
+
+        if let Some(old_entry) = replaced {
+            self.core.count_removed(&old_entry);
+            self.free_entry(&pool, old_entry);
+        }
+        self.notify(event);
+        Ok(true)
+    }
+
+    /// Give a live entry `ttl_millis` more to live from now (0 = no expiration)
+    ///
+    /// Returns false if `key` has no live value; an expired entry is removed.
+    /// The new TTL is logged before it is applied, so a failed WAL append
+    /// leaves the entry's clock alone.
+    fn touch_value(&self, key: &[u8], ttl_millis: u64) -> Result<bool> {
+        let Some(mut entry) = self.core.cache.get_mut(key) else {
+            return Ok(false);
+        };
+        if entry.is_expired() {
+            drop(entry);
+            self.reap_expired(&self.core.memory_pool.read(), key);
+            return Ok(false);
+        }
+        if let Some(wal) = &self.wal {
+            wal.append_put(key, &entry.data, ttl_millis)?;
+        }
+        entry.ttl_millis = match ttl_millis {
+            0 => 0,
+            ttl_millis => {
+                let elapsed =
+                    u64::try_from(entry.created_at.elapsed().as_millis()).unwrap_or(u64::MAX);
+                elapsed.saturating_add(ttl_millis)
+            }
+        };
+        Ok(true)
+    }
+
+    /// Store a copy of `src`'s value under `dst` in a new pool region
+    ///
+    /// Without `ttl_seconds` the copy expires with the source. Returns false
+    /// if `src` has no live value; an expired one is removed.
+    fn copy_value(&self, src: &[u8], dst: &[u8], ttl_seconds: Option<u64>) -> Result<bool> {
+        let Some(entry) = self.core.cache.get(src) else {
+            return Ok(false);
+        };
+        if entry.is_expired() {
+            drop(entry);
+            self.reap_expired(&self.core.memory_pool.read(), src);
+            return Ok(false);
+        }
+        let (value, remaining_ttl, checksum) = (
+            entry.data.clone(),
+            entry.remaining_ttl_millis(),
+            entry.checksum,
+        );
+        drop(entry);
+        let ttl_millis = ttl_seconds.map_or(remaining_ttl, |ttl| ttl.saturating_mul(1000));
+        self.put_versioned(dst.to_vec(), value, ttl_millis, None, false, checksum)?;
+        Ok(true)
+    }
+}
+
+impl Drop for KvCacheServer {
+    fn drop(&mut self) {
+        // Closing the queue lets the deferred-free thread drain it and exit
+        self.deferred_frees = None;
+        if let Some(worker) = self.deferred_free_worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Start the thread that returns deferred frees to the pool
+///
+/// It batches whatever has queued up under one read lock, and exits once the
+/// server drops the sender.
+fn spawn_deferred_free(
+    pool: Arc<RwLock<MemoryPool>>,
+) -> Result<(mpsc::Sender<PoolAllocation>, std::thread::JoinHandle<()>)> {
+    let (tx, rx) = mpsc::channel::<PoolAllocation>();
+    let worker = std::thread::Builder::new()
+        .name("kv-deferred-free".to_string())
+        .spawn(move || {
+            while let Ok(first) = rx.recv() {
+                let pool = pool.read();
+                for allocation in std::iter::once(first).chain(rx.try_iter()) {
+                    pool.deallocate(&allocation);
+                }
+            }
+        })?;
+    Ok((tx, worker))
+}
+
+/// gRPC service implementation wrapper
+pub struct KvCacheServiceImpl {
+    inner: Arc<KvCacheServer>,
+}
+
+/// Metrics of the tokio runtime the calling task runs on
+fn runtime_stats() -> RuntimeStats {
+    let metrics = tokio::runtime::Handle::current().metrics();
+    RuntimeStats {
+        workers: metrics.num_workers() as u32,
+        alive_tasks: metrics.num_alive_tasks() as u64,
+        global_queue_depth: metrics.global_queue_depth() as u64,
+        #[cfg(tokio_unstable)]
+        blocking_threads: metrics.num_blocking_threads() as u32,
+        #[cfg(not(tokio_unstable))]
+        blocking_threads: 0,
+    }
+}
+
+/// Count and quantiles of a histogram, for the Stats RPC (zeros when empty)
+fn latency_summary(histogram: &LatencyHistogram) -> LatencySummary {
+    let micros = |q| {
+        histogram
+            .quantile(q)
+            .map_or(0, |d: Duration| d.as_micros() as u64)
+    };
+    LatencySummary {
+        count: histogram.count(),
+        p50_micros: micros(0.5),
+        p99_micros: micros(0.99),
+    }
+}
+
+/// Require a PUT to carry a value
+#[allow(clippy::result_large_err)] // Status is what the handlers return anyway
+fn put_value_source(
+    value_source: Option<crate::pb::put_request::ValueSource>,
+) -> Result<crate::pb::put_request::ValueSource, Status> {
+    value_source.ok_or_else(|| Status::invalid_argument("Missing value"))
+}
+
+/// A PUT's value doesn't match the checksum sent with it
+#[derive(Debug)]
+struct ChecksumMismatch {
+    expected: u32,
+    actual: u32,
+}
+
+impl std::fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Value checksum mismatch: expected CRC-32C {:#010x}, got {:#010x}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for ChecksumMismatch {}
+
+fn verify_checksum(value: &[u8], expected: Option<u32>) -> Result<()> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let actual = crc32c(value);
+    if actual != expected {
+        return Err(ChecksumMismatch { expected, actual }.into());
+    }
+    Ok(())
+}
+
+/// A PUT's TTL in milliseconds: `ttl_millis` if set, else `ttl_seconds`
+fn put_ttl_millis(req: &PutRequest) -> u64 {
+    match req.ttl_millis {
+        0 => req.ttl_seconds.saturating_mul(1000),
+        ttl_millis => ttl_millis,
+    }
+}
+
+impl KvCacheServiceImpl {
+    /// GET handler body; `get` wraps it to account control-plane bytes
+    async fn handle_get(&self, req: GetRequest) -> Result<GetResponse, Status> {
+        let request_id = req.request_id;
+        self.inner.check_key_size(&req.key)?;
+
+        tracing::debug!(
+            "GET request: key={}, request_id={}",
+            self.inner.log_key(&req.key),
+            request_id
+        );
+
+        if req.warm_only {
+            return Ok(match self.inner.warm(&req.key).await {
+                Ok(result) => GetResponse {
+                    success: true,
+                    value_length: result.value_len,
+                    request_id,
+                    version: result.version,
+                    ..Default::default()
+                },
+                Err(status) => GetResponse {
+                    success: false,
+                    error_message: status.message().to_string(),
+                    request_id,
+                    not_found: status.code() == Code::NotFound,
+                    ..Default::default()
+                },
+            });
+        }
+
+        let response_location = req
+            .response_location
+            .as_ref()
+            .ok_or_else(|| Status::invalid_argument("Missing response_location"))?;
+
+        let value_location = ValueLocation::try_from(response_location)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let _admitted = match &self.inner.admission {
+            Some(admission) => Some(admission.try_admit().map_err(|delay| {
+                tracing::debug!(
+                    "GET shed: estimated queue delay {:?}, request_id={}",
+                    delay,
+                    request_id
+                );
+                let mut status = Status::resource_exhausted(format!(
+                    "Server overloaded: estimated queue delay {}ms exceeds budget",
+                    delay.as_millis()
+                ));
+                if let Ok(value) = delay.as_millis().to_string().parse() {
+                    status.metadata_mut().insert("retry-after-ms", value);
+                }
+                status
+            })?),
+            None => None,
+        };
+
+        let priority = self.inner.get_priority(req.priority, req.client_id);
+        let started = std::time::Instant::now();
+        let _permit = match &self.inner.get_gate {
+            Some(gate) => Some(gate.acquire(priority).await),
+            None => None,
+        };
+
+        let result = self
+            .inner
+            .get_and_transfer(&req.key, &value_location, req.if_version_gt)
+            .await;
+        let elapsed = started.elapsed();
+        self.inner
+            .get_latency
+            .entry(priority)
+            .or_default()
+            .record(elapsed);
+        match &result {
+            Ok(result) if !result.loaded => self.inner.hit_latency.record(elapsed),
+            Ok(_) => self.inner.miss_latency.record(elapsed),
+            Err(status) if status.code() == Code::NotFound => {
+                self.inner.miss_latency.record(elapsed)
+            }
+            Err(_) => {}
+        }
+
+        match result {
+            Ok(result) => {
+                tracing::debug!(
+                    "GET success: key={}, length={}, request_id={}",
+                    self.inner.log_key(&req.key),
+                    result.value_len,
+                    request_id
+                );
+                let transferred = if result.not_modified || result.inline_value.is_some() {
+                    0
+                } else {
+                    result.value_len
+                };
+                self.inner.traffic.record_get(transferred);
+                Ok(GetResponse {
+                    success: true,
+                    value_length: result.value_len,
+                    error_message: String::new(),
+                    request_id,
+                    not_modified: result.not_modified,
+                    version: result.version,
+                    inline_value: result.inline_value,
+                    not_found: false,
+                    source: if result.loaded {
+                        GetSource::Loaded
+                    } else {
+                        GetSource::Hit
+                    }
+                    .into(),
+                    remaining_ttl_seconds: result.remaining_ttl_seconds,
+                    crc32c: result.checksum,
+                })
+            }
+            // The client retries with a buffer of the size it carries
+            Err(status) if status.code() == Code::OutOfRange => {
+                tracing::debug!("GET: {}, request_id={}", status.message(), request_id);
+                Err(status)
+            }
+            Err(status) => {
+                tracing::warn!(
+                    "GET failed: key={}, error={}, request_id={}",
+                    self.inner.log_key(&req.key),
+                    status.message(),
+                    request_id
+                );
+                Ok(GetResponse {
+                    success: false,
+                    value_length: 0,
+                    error_message: status.message().to_string(),
+                    request_id,
+                    not_found: status.code() == Code::NotFound,
+                    ..Default::default()
+                })
+            }
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl KvCacheService for KvCacheServiceImpl {
+    async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        let span = tracing::info_span!("kv.server.get");
+        #[cfg(feature = "otel")]
+        crate::telemetry::set_parent_from(&span, request.metadata());
+
+        let req = request.into_inner();
+        let request_len = req.encoded_len();
+        let response = self.handle_get(req).instrument(span).await?;
+        self.inner
+            .traffic
+            .record_control(request_len + response.encoded_len());
+        Ok(Response::new(response))
+    }
+
+    async fn put(&self, request: Request<PutRequest>) -> Result<Response<PutResponse>, Status> {
+        let req = request.into_inner();
+        let request_len = req.encoded_len();
+        self.inner.check_key_size(&req.key)?;
+
+        tracing::debug!("PUT request: key={}", self.inner.log_key(&req.key));
+
+        let ttl_millis = put_ttl_millis(&req);
+        let value_source = put_value_source(req.value_source)?;
+        if matches!(
+            value_source,
+            crate::pb::put_request::ValueSource::RdmaLocation(_)
+        ) && !self.inner.transport.supports_read()
+        {
+            return Err(Status::unimplemented(RDMA_READ_UNSUPPORTED));
+        }
+
+        let response = match self
+            .inner
+            .put_from_source(
+                req.key,
+                value_source,
+                ttl_millis,
+                req.version,
+                req.put_if_absent,
+                req.crc32c,
+            )
+            .await
+        {
+            Ok(applied) => {
+                tracing::debug!("PUT success, applied={}", applied);
+                PutResponse {
+                    success: true,
+                    error_message: String::new(),
+                    // A put-if-absent only drops for a live key; replicated
+                    // writes only for staleness
+                    stale: !applied && !req.put_if_absent,
+                    key_existed: !applied && req.put_if_absent,
+                }
+            }
+            Err(e) if e.is::<ChecksumMismatch>() => {
+                tracing::warn!("PUT rejected: {}", e);
+                return Err(Status::data_loss(e.to_string()));
+            }
+            Err(e) => {
+                tracing::warn!("PUT failed: {}", e);
+                PutResponse {
+                    success: false,
+                    error_message: e.to_string(),
+                    ..Default::default()
+                }
+            }
+        };
+        self.inner
+            .traffic
+            .record_control(request_len + response.encoded_len());
+        Ok(Response::new(response))
+    }
+
+    async fn delete(
+        &self,
+        request: Request<DeleteRequest>,
+    ) -> Result<Response<DeleteResponse>, Status> {
+        let req = request.into_inner();
+        self.inner.check_key_size(&req.key)?;
+
+        tracing::debug!("DELETE request: key={}", self.inner.log_key(&req.key));
+
+        let existed = self
+            .inner
+            .delete_versioned(&req.key, req.version)
+            .map_err(|e| Status::internal(format!("Failed to log DELETE: {}", e)))?;
+
+        let response = DeleteResponse {
+            success: true,
+            key_existed: existed == Some(true),
+            stale: existed.is_none(),
+        };
+        self.inner
+            .traffic
+            .record_control(req.encoded_len() + response.encoded_len());
+        Ok(Response::new(response))
+    }
+
+    async fn delete_prefix(
+        &self,
+        request: Request<DeletePrefixRequest>,
+    ) -> Result<Response<DeletePrefixResponse>, Status> {
+        let req = request.into_inner();
+        self.inner.check_key_size(&req.prefix)?;
+
+        let inner = self.inner.clone();
+        let deleted = tokio::task::spawn_blocking(move || inner.delete_prefix(&req.prefix))
+            .await
+            .map_err(|e| Status::internal(format!("Delete task failed: {}", e)))?
+            .map_err(|e| Status::internal(format!("Failed to log DELETE: {}", e)))?;
+        tracing::info!("DELETE_PREFIX: removed {} keys", deleted);
+
+        Ok(Response::new(DeletePrefixResponse {
+            success: true,
+            deleted: deleted as u64,
+        }))
+    }
+
+    async fn rename(
+        &self,
+        request: Request<RenameRequest>,
+    ) -> Result<Response<RenameResponse>, Status> {
+        let req = request.into_inner();
+        self.inner.check_key_size(&req.src_key)?;
+        self.inner.check_key_size(&req.dst_key)?;
+
+        tracing::debug!(
+            "RENAME request: {} -> {}",
+            self.inner.log_key(&req.src_key),
+            self.inner.log_key(&req.dst_key)
+        );
+
+        let existed = self
+            .inner
+            .rename_value(&req.src_key, &req.dst_key)
+            .map_err(|e| Status::internal(format!("Failed to log RENAME: {:#}", e)))?;
+
+        let response = RenameResponse {
+            success: true,
+            key_existed: existed,
+        };
+        self.inner
+            .traffic
+            .record_control(req.encoded_len() + response.encoded_len());
+        Ok(Response::new(response))
+    }
+
+    async fn copy(&self, request: Request<CopyRequest>) -> Result<Response<CopyResponse>, Status> {
+        let req = request.into_inner();
+        self.inner.check_key_size(&req.src_key)?;
+        self.inner.check_key_size(&req.dst_key)?;
+
+        tracing::debug!(
+            "COPY request: {} -> {}",
+            self.inner.log_key(&req.src_key),
+            self.inner.log_key(&req.dst_key)
+        );
+
+        let response = match self
+            .inner
+            .copy_value(&req.src_key, &req.dst_key, req.ttl_seconds)
+        {
+            Ok(existed) => CopyResponse {
+                success: true,
+                key_existed: existed,
+                ..Default::default()
+            },
+            Err(e) => {
+                tracing::warn!("COPY failed: {}", e);
+                CopyResponse {
+                    success: false,
+                    error_message: e.to_string(),
+                    ..Default::default()
+                }
+            }
+        };
+        self.inner
+            .traffic
+            .record_control(req.encoded_len() + response.encoded_len());
+        Ok(Response::new(response))
+    }
+
+    async fn compare_and_swap(
+        &self,
+        request: Request<CompareAndSwapRequest>,
+    ) -> Result<Response<CompareAndSwapResponse>, Status> {
+        let req = request.into_inner();
+        let request_len = req.encoded_len();
+        self.inner.check_key_size(&req.key)?;
+
+        tracing::debug!("CAS request: key={}", self.inner.log_key(&req.key));
+
+        let response =
+            match self
+                .inner
+                .compare_and_swap(req.key, req.expected.as_deref(), req.value)
+            {
+                Ok(swapped) => CompareAndSwapResponse {
+                    success: true,
+                    swapped,
+                    ..Default::default()
+                },
+                Err(e) => {
+                    tracing::warn!("CAS failed: {}", e);
+                    CompareAndSwapResponse {
+                        success: false,
+                        error_message: e.to_string(),
+                        ..Default::default()
+                    }
+                }
+            };
+        self.inner
+            .traffic
+            .record_control(request_len + response.encoded_len());
+        Ok(Response::new(response))
+    }
+
+    async fn register_client(
+        &self,
+        request: Request<RegisterClientRequest>,
+    ) -> Result<Response<RegisterClientResponse>, Status> {
+        let req = request.into_inner();
+
+        let client = RegisteredClient {
+            client_id: req.client_id,
+            domain_addresses: req
+                .domain_addresses
+                .into_iter()
+                .map(DomainAddress::new)
+                .collect(),
+            receive_buffer_size: req.receive_buffer_size,
+            priority: req.priority.min(u8::MAX as u32) as u8,
+        };
+
+        tracing::info!(
+            "Client registration: id={}, buffer_size={}, domains=[{}]",
+            client.client_id,
+            client.receive_buffer_size,
+            client
+                .domain_addresses
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        self.inner.clients.write().insert(req.client_id, client);
+
+        let server_addresses: Vec<Vec<u8>> = self
+            .inner
+            .transport
             .domain_addresses()
             .iter()
-            .map(|a| a.0.clone())
+            .map(|a| a.0.clone())
+            .collect();
+
+        Ok(Response::new(RegisterClientResponse {
+            success: true,
+            server_id: self.inner.config.node_id,
+            server_domain_addresses: server_addresses,
+            server_version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_seconds: self.inner.started_at.elapsed().as_secs(),
+            protocol_version: PROTOCOL_VERSION,
+        }))
+    }
+
+    async fn deregister_client(
+        &self,
+        request: Request<DeregisterClientRequest>,
+    ) -> Result<Response<DeregisterClientResponse>, Status> {
+        let client_id = request.into_inner().client_id;
+        let was_registered = self.inner.clients.write().remove(&client_id).is_some();
+        tracing::info!(
+            "Client deregistration: id={}, was_registered={}",
+            client_id,
+            was_registered
+        );
+        Ok(Response::new(DeregisterClientResponse { was_registered }))
+    }
+
+    async fn heartbeat(
+        &self,
+        request: Request<HeartbeatRequest>,
+    ) -> Result<Response<HeartbeatResponse>, Status> {
+        let req = request.into_inner();
+        tracing::trace!("Heartbeat from client {}", req.client_id);
+        Ok(Response::new(HeartbeatResponse { alive: true }))
+    }
+
+    async fn flush(
+        &self,
+        _request: Request<FlushRequest>,
+    ) -> Result<Response<FlushResponse>, Status> {
+        let inner = self.inner.clone();
+        // fsync can take a while; keep it off the async workers
+        let result = tokio::task::spawn_blocking(move || inner.flush())
+            .await
+            .map_err(|e| Status::internal(format!("Flush task failed: {}", e)))?;
+
+        Ok(Response::new(match result {
+            Ok(durable_sequence) => FlushResponse {
+                success: true,
+                error_message: String::new(),
+                durable_sequence,
+            },
+            Err(e) => {
+                tracing::error!("FLUSH failed: {}", e);
+                FlushResponse {
+                    success: false,
+                    error_message: e.to_string(),
+                    durable_sequence: 0,
+                }
+            }
+        }))
+    }
+
+    async fn batch_put(
+        &self,
+        request: Request<BatchPutRequest>,
+    ) -> Result<Response<BatchPutResponse>, Status> {
+        let req = request.into_inner();
+        let request_len = req.encoded_len();
+        tracing::debug!("BATCH_PUT request: {} entries", req.entries.len());
+
+        let mut response = BatchPutResponse {
+            success: true,
+            ..Default::default()
+        };
+        for entry in req.entries {
+            let ttl_millis = put_ttl_millis(&entry);
+            let put_if_absent = entry.put_if_absent;
+            let result = match self
+                .inner
+                .check_key_size(&entry.key)
+                .and_then(|()| put_value_source(entry.value_source))
+            {
+                Ok(value_source) => {
+                    self.inner
+                        .put_from_source(
+                            entry.key,
+                            value_source,
+                            ttl_millis,
+                            entry.version,
+                            entry.put_if_absent,
+                            entry.crc32c,
+                        )
+                        .await
+                }
+                Err(status) => Err(anyhow!("{}", status.message())),
+            };
+            if req.continue_on_error {
+                let dropped = matches!(result, Ok(false));
+                response.results.push(BatchPutResult {
+                    stored: matches!(result, Ok(true)),
+                    error_message: result
+                        .as_ref()
+                        .err()
+                        .map(ToString::to_string)
+                        .unwrap_or_default(),
+                    stale: dropped && !put_if_absent,
+                    key_existed: dropped && put_if_absent,
+                });
+            }
+            match result {
+                Ok(true) => response.stored += 1,
+                // Dropped as stale or because the key was live, like a PUT
+                Ok(false) => {}
+                Err(e) if req.continue_on_error => {
+                    tracing::debug!(
+                        "BATCH_PUT entry {} failed: {}",
+                        response.results.len() - 1,
+                        e
+                    );
+                    if response.success {
+                        response.success = false;
+                        response.error_message = e.to_string();
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("BATCH_PUT failed after {} entries: {}", response.stored, e);
+                    response.success = false;
+                    response.error_message = e.to_string();
+                    break;
+                }
+            }
+        }
+
+        self.inner
+            .traffic
+            .record_control(request_len + response.encoded_len());
+        Ok(Response::new(response))
+    }
+
+    async fn import(
+        &self,
+        request: Request<Streaming<ImportRecord>>,
+    ) -> Result<Response<ImportResponse>, Status> {
+        let mut records = request.into_inner();
+        let mut response = ImportResponse::default();
+        let mut request_len = 0;
+        while let Some(record) = records.message().await? {
+            request_len += record.encoded_len();
+            let ttl_millis = record.ttl_seconds.saturating_mul(1000);
+            let result = match self.inner.check_key_size(&record.key) {
+                Ok(()) => self
+                    .inner
+                    .put_value(record.key, record.value, ttl_millis)
+                    .map_err(|e| e.to_string()),
+                Err(status) => Err(status.message().to_string()),
+            };
+            match result {
+                Ok(()) => response.stored += 1,
+                Err(e) => {
+                    if response.failed == 0 {
+                        tracing::warn!("IMPORT: record {} failed: {}", response.stored, e);
+                        response.first_error = e;
+                    }
+                    response.failed += 1;
+                }
+            }
+        }
+
+        tracing::info!(
+            "IMPORT: stored {}, failed {}",
+            response.stored,
+            response.failed
+        );
+        self.inner
+            .traffic
+            .record_control(request_len + response.encoded_len());
+        Ok(Response::new(response))
+    }
+
+    async fn get_many(
+        &self,
+        request: Request<GetManyRequest>,
+    ) -> Result<Response<GetManyResponse>, Status> {
+        let req = request.into_inner();
+        let request_id = req.request_id;
+        let request_len = req.encoded_len();
+        tracing::debug!(
+            "GET_MANY request: {} keys, request_id={}",
+            req.items.len(),
+            request_id
+        );
+        for item in &req.items {
+            self.inner.check_key_size(&item.key)?;
+        }
+
+        let buffer = req
+            .buffer
+            .as_ref()
+            .ok_or_else(|| Status::invalid_argument("Missing buffer"))?;
+        let buffer = MemoryRegionDescriptor::try_from(buffer)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let priority = self.inner.get_priority(None, req.client_id);
+        let _permit = match &self.inner.get_gate {
+            Some(gate) => Some(gate.acquire(priority).await),
+            None => None,
+        };
+
+        let response = match self.inner.get_many_and_transfer(&req.items, &buffer).await {
+            Ok(lengths) => {
+                for length in lengths.iter().flatten() {
+                    self.inner.traffic.record_get(*length);
+                }
+                GetManyResponse {
+                    success: true,
+                    results: lengths
+                        .into_iter()
+                        .map(|length| GetManyResult {
+                            found: length.is_some(),
+                            value_length: length.unwrap_or(0),
+                        })
+                        .collect(),
+                    request_id,
+                    ..Default::default()
+                }
+            }
+            Err(status) => {
+                tracing::warn!(
+                    "GET_MANY failed: {}, request_id={}",
+                    status.message(),
+                    request_id
+                );
+                GetManyResponse {
+                    success: false,
+                    error_message: status.message().to_string(),
+                    request_id,
+                    ..Default::default()
+                }
+            }
+        };
+        self.inner
+            .traffic
+            .record_control(request_len + response.encoded_len());
+        Ok(Response::new(response))
+    }
+
+    async fn touch(
+        &self,
+        request: Request<TouchRequest>,
+    ) -> Result<Response<TouchResponse>, Status> {
+        let req = request.into_inner();
+        self.inner.check_key_size(&req.key)?;
+
+        tracing::debug!(
+            "TOUCH request: key={}, ttl={}s",
+            self.inner.log_key(&req.key),
+            req.ttl_seconds
+        );
+
+        let existed = self
+            .inner
+            .touch_value(&req.key, req.ttl_seconds.saturating_mul(1000))
+            .map_err(|e| Status::internal(format!("Failed to log TOUCH: {}", e)))?;
+
+        let response = TouchResponse {
+            success: true,
+            key_existed: existed,
+        };
+        self.inner
+            .traffic
+            .record_control(req.encoded_len() + response.encoded_len());
+        Ok(Response::new(response))
+    }
+
+    async fn get_size(
+        &self,
+        request: Request<GetSizeRequest>,
+    ) -> Result<Response<GetSizeResponse>, Status> {
+        let req = request.into_inner();
+        self.inner.check_key_size(&req.key)?;
+
+        let value_length = self.inner.value_len(&req.key);
+        let response = GetSizeResponse {
+            found: value_length.is_some(),
+            value_length: value_length.unwrap_or(0),
+        };
+        self.inner
+            .traffic
+            .record_control(req.encoded_len() + response.encoded_len());
+        Ok(Response::new(response))
+    }
+
+    async fn exists(
+        &self,
+        request: Request<ExistsRequest>,
+    ) -> Result<Response<ExistsResponse>, Status> {
+        let req = request.into_inner();
+        self.inner.check_key_size(&req.key)?;
+
+        let response = ExistsResponse {
+            exists: self.inner.contains(&req.key),
+        };
+        self.inner
+            .traffic
+            .record_control(req.encoded_len() + response.encoded_len());
+        Ok(Response::new(response))
+    }
+
+    async fn count(
+        &self,
+        _request: Request<CountRequest>,
+    ) -> Result<Response<CountResponse>, Status> {
+        let (entries, stored_bytes) = self.inner.count();
+        Ok(Response::new(CountResponse {
+            entries,
+            stored_bytes,
+        }))
+    }
+
+    async fn stats(
+        &self,
+        _request: Request<StatsRequest>,
+    ) -> Result<Response<StatsResponse>, Status> {
+        let pool = self.inner.core.memory_pool.read().stats();
+        let traffic = &self.inner.traffic;
+        Ok(Response::new(StatsResponse {
+            num_entries: self.inner.core.cache.len() as u64,
+            pool_used_bytes: pool.used as u64,
+            pool_available_bytes: pool.available as u64,
+            gets: traffic.gets(),
+            rdma_bytes: traffic.rdma_bytes(),
+            control_bytes: traffic.control_bytes(),
+            bytes_per_get: traffic.bytes_per_get(),
+            control_overhead_ratio: traffic.control_overhead_ratio(),
+            pool_total_bytes: pool.total as u64,
+            pool_shards: pool
+                .shards
+                .iter()
+                .flatten()
+                .map(|shard| PoolShardStats {
+                    max_size: shard.max_size as u64,
+                    total_bytes: shard.total as u64,
+                    used_bytes: shard.used as u64,
+                    available_bytes: shard.available as u64,
+                })
+                .collect(),
+            acceptor_connections: self
+                .inner
+                .accepted_connections
+                .iter()
+                .map(|count| count.load(Ordering::Relaxed))
+                .collect(),
+            hit_latency: Some(latency_summary(&self.inner.hit_latency)),
+            miss_latency: Some(latency_summary(&self.inner.miss_latency)),
+            registered_clients: self.inner.clients.read().len() as u64,
+            domains: self
+                .inner
+                .transport
+                .domain_stats()
+                .into_iter()
+                .map(|domain| DomainStats {
+                    domain_idx: domain.domain_idx as u32,
+                    healthy: domain.healthy,
+                    consecutive_failures: domain.consecutive_failures,
+                    failures: domain.failures,
+                    bytes_transferred: domain.bytes_transferred,
+                })
+                .collect(),
+            runtime: Some(runtime_stats()),
+        }))
+    }
+
+    type DumpStream = ReceiverStream<Result<DumpEntry, Status>>;
+
+    async fn dump(
+        &self,
+        request: Request<DumpRequest>,
+    ) -> Result<Response<Self::DumpStream>, Status> {
+        let max_inline_bytes = match request.into_inner().max_inline_bytes {
+            0 => DEFAULT_DUMP_INLINE_BYTES,
+            n => n,
+        };
+
+        // Snapshot the keys so no map guard is held while waiting on the stream;
+        // entries removed in the meantime are skipped
+        let keys: Vec<Vec<u8>> = self
+            .inner
+            .core
+            .cache
+            .iter()
+            .map(|e| e.key().to_vec())
+            .collect();
+        tracing::info!("DUMP: streaming up to {} entries", keys.len());
+
+        let inner = self.inner.clone();
+        let (tx, rx) = tokio::sync::mpsc::channel(64);
+        tokio::spawn(async move {
+            for key in keys {
+                let Some(entry) = inner.dump_entry(&key, max_inline_bytes) else {
+                    continue;
+                };
+                if tx.send(Ok(entry)).await.is_err() {
+                    tracing::debug!("DUMP: client went away");
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    type WatchEventsStream = ReceiverStream<Result<KeyspaceEvent, Status>>;
+
+    async fn watch_events(
+        &self,
+        _request: Request<WatchEventsRequest>,
+    ) -> Result<Response<Self::WatchEventsStream>, Status> {
+        // Subscribe before responding so the caller sees everything after the call
+        let mut events = self.inner.events.subscribe();
+        let (tx, rx) = tokio::sync::mpsc::channel(64);
+        tokio::spawn(async move {
+            let mut missed = 0;
+            loop {
+                let received = tokio::select! {
+                    received = events.recv() => received,
+                    _ = tx.closed() => break,
+                };
+                match received {
+                    Ok(mut event) => {
+                        event.missed = std::mem::take(&mut missed);
+                        if tx.send(Ok(event)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("WATCH: watcher fell behind, dropped {} events", skipped);
+                        missed += skipped;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            tracing::debug!("WATCH: watcher went away");
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+/// Bind the gRPC listener up front so bind failures get a descriptive error
+///
+/// While the address is in use, retries with exponential backoff until `retry_for`
+/// has elapsed. Other bind errors fail immediately.
+pub async fn bind_listener(addr: SocketAddr, retry_for: Duration) -> Result<TcpListener> {
+    let deadline = tokio::time::Instant::now() + retry_for;
+    let mut backoff = Duration::from_millis(50);
+
+    loop {
+        match TcpListener::bind(addr).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                let now = tokio::time::Instant::now();
+                if now >= deadline {
+                    return Err(anyhow!("address already in use: {}", addr));
+                }
+                tracing::warn!("Address {} in use, retrying bind in {:?}", addr, backoff);
+                tokio::time::sleep(backoff.min(deadline - now)).await;
+                backoff = (backoff * 2).min(Duration::from_secs(1));
+            }
+            Err(e) => return Err(anyhow!("failed to bind {}: {}", addr, e)),
+        }
+    }
+}
+
+/// Bind one of several `SO_REUSEPORT` listeners sharing `addr`
+#[cfg(target_os = "linux")]
+fn bind_reuseport(addr: SocketAddr) -> Result<TcpListener> {
+    let socket = if addr.is_ipv4() {
+        tokio::net::TcpSocket::new_v4()?
+    } else {
+        tokio::net::TcpSocket::new_v6()?
+    };
+    socket.set_reuseport(true)?;
+    socket
+        .bind(addr)
+        .map_err(|e| anyhow!("failed to bind {} with SO_REUSEPORT: {}", addr, e))?;
+    Ok(socket.listen(1024)?)
+}
+
+/// What `run_server` accepts connections on
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+    /// One `SO_REUSEPORT` listener per acceptor shard, bound by the shards
+    Reuseport(SocketAddr),
+}
+
+/// Bind a Unix domain socket, replacing a socket file left by an earlier run
+///
+/// A socket file some server still accepts connections on is left alone; only
+/// one that refuses connections is stale.
+#[cfg(unix)]
+fn bind_unix(path: &std::path::Path) -> Result<Listener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            match std::os::unix::net::UnixStream::connect(path) {
+                Ok(_) => return Err(anyhow!("address already in use: {}", path.display())),
+                Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                    std::fs::remove_file(path)?
+                }
+                Err(e) => {
+                    return Err(anyhow!(
+                        "failed to check existing socket {}: {}",
+                        path.display(),
+                        e
+                    ))
+                }
+            }
+        }
+        Ok(_) => return Err(anyhow!("{} exists and is not a socket", path.display())),
+        Err(_) => {}
+    }
+    tokio::net::UnixListener::bind(path)
+        .map(Listener::Unix)
+        .map_err(|e| anyhow!("failed to bind {}: {}", path.display(), e))
+}
+
+#[cfg(not(unix))]
+fn bind_unix(_path: &std::path::Path) -> Result<Listener> {
+    Err(anyhow!("unix: listen addresses require a Unix platform"))
+}
+
+/// Serve `listener`, counting its connections into `server.accepted_connections[shard]`
+async fn serve_listener(
+    server: Arc<KvCacheServer>,
+    listener: TcpListener,
+    shard: usize,
+    shutdown: impl std::future::Future<Output = ()>,
+) -> Result<()> {
+    let incoming = TcpIncoming::from_listener(listener, false, None).map_err(|e| anyhow!(e))?;
+    serve_incoming(server, incoming, shard, shutdown).await
+}
+
+/// Serve connections from `incoming`, counting them like `serve_listener`
+async fn serve_incoming<IO, IE>(
+    server: Arc<KvCacheServer>,
+    incoming: impl futures::Stream<Item = Result<IO, IE>> + Send + 'static,
+    shard: usize,
+    shutdown: impl std::future::Future<Output = ()>,
+) -> Result<()>
+where
+    IO: tonic::transport::server::Connected
+        + tokio::io::AsyncRead
+        + tokio::io::AsyncWrite
+        + Unpin
+        + Send
+        + 'static,
+    IO::ConnectInfo: Clone + Send + Sync + 'static,
+    IE: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let accepted = server.clone();
+    let incoming = futures::StreamExt::inspect(incoming, move |conn| {
+        if conn.is_ok() {
+            accepted.accepted_connections[shard].fetch_add(1, Ordering::Relaxed);
+        }
+    });
+
+    // Configure tonic server for high concurrency
+    tonic::transport::Server::builder()
+        .concurrency_limit_per_connection(256) // Allow up to 256 concurrent requests per connection
+        .add_service(server.shared_service())
+        .serve_with_incoming_shutdown(incoming, shutdown)
+        .await?;
+    Ok(())
+}
+
+/// Run one acceptor thread per reuseport shard until one of them fails or
+/// `shutdown` completes
+///
+/// The shards stop once the returned future is dropped.
+#[cfg(target_os = "linux")]
+async fn serve_reuseport(
+    server: Arc<KvCacheServer>,
+    addr: SocketAddr,
+    shards: usize,
+    shutdown: impl std::future::Future<Output = ()>,
+) -> Result<()> {
+    let first = bind_reuseport(addr)?;
+    // With port 0 every shard must join the port the first one got
+    let addr = first.local_addr()?;
+    let mut listeners = vec![first.into_std()?];
+    for _ in 1..shards {
+        listeners.push(bind_reuseport(addr)?.into_std()?);
+    }
+
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(());
+    let (done_tx, mut done_rx) = tokio::sync::mpsc::unbounded_channel();
+    for (shard, listener) in listeners.into_iter().enumerate() {
+        let server = server.clone();
+        let mut shutdown = shutdown_rx.clone();
+        let done = done_tx.clone();
+        std::thread::Builder::new()
+            .name(format!("kv-acceptor-{}", shard))
+            .spawn(move || {
+                let result = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|runtime| {
+                        runtime.block_on(async move {
+                            let listener = TcpListener::from_std(listener)?;
+                            let shutdown = async move {
+                                let _ = shutdown.changed().await;
+                            };
+                            serve_listener(server, listener, shard, shutdown).await
+                        })
+                    });
+                let _ = done.send((shard, result));
+            })?;
+    }
+    tracing::info!("Accepting on {} reuseport shards", shards);
+
+    let (shard, result) = tokio::select! {
+        done = done_rx.recv() => done.ok_or_else(|| anyhow!("Acceptor threads exited"))?,
+        _ = shutdown => return Ok(()),
+    };
+    drop(shutdown_tx);
+    result.map_err(|e| anyhow!("acceptor shard {} failed: {}", shard, e))
+}
+
+/// Run the server
+///
+/// `listen_addr` is a TCP socket address, or `unix:<path>` for a Unix domain
+/// socket (Unix only), which spares co-located clients the TCP stack.
+pub async fn run_server(config: ServerConfig) -> Result<()> {
+    serve_with_shutdown(config, std::future::pending()).await
+}
+
+/// Run the server like `run_server` until `signal` completes
+///
+/// Once the listeners have shut down, the background tasks are stopped and
+/// awaited before returning.
+pub async fn serve_with_shutdown(
+    config: ServerConfig,
+    signal: impl std::future::Future<Output = ()>,
+) -> Result<()> {
+    let shards = config.reuseport_shards;
+    let listener = match config.listen_addr.strip_prefix("unix:") {
+        Some(path) => {
+            if shards > 1 {
+                return Err(anyhow!("reuseport_shards requires a TCP listen address"));
+            }
+            bind_unix(path.as_ref())?
+        }
+        None => {
+            let addr: SocketAddr = config.listen_addr.parse()?;
+            if shards > 1 && cfg!(not(target_os = "linux")) {
+                return Err(anyhow!("reuseport_shards requires Linux"));
+            }
+            match shards {
+                0 | 1 => Listener::Tcp(bind_listener(addr, config.bind_retry_timeout).await?),
+                _ => Listener::Reuseport(addr),
+            }
+        }
+    };
+    let listen_addr = config.listen_addr.clone();
+    let server = Arc::new(KvCacheServer::new(config)?);
+
+    tracing::info!("Starting KV cache server on {}", listen_addr);
+
+    server.start_background_tasks();
+
+    let result = match listener {
+        Listener::Tcp(listener) => serve_listener(server.clone(), listener, 0, signal).await,
+        #[cfg(unix)]
+        Listener::Unix(listener) => {
+            let incoming = tokio_stream::wrappers::UnixListenerStream::new(listener);
+            serve_incoming(server.clone(), incoming, 0, signal).await
+        }
+        #[cfg(target_os = "linux")]
+        Listener::Reuseport(addr) => serve_reuseport(server.clone(), addr, shards, signal).await,
+        #[cfg(not(target_os = "linux"))]
+        Listener::Reuseport(_) => unreachable!("rejected above"),
+    };
+
+    server.stop().await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_creation() {
+        let config = ServerConfig {
+            node_id: 1,
+            memory_pool_size: 1024 * 1024, // 1MB for testing
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config).unwrap();
+        assert!(server.core.cache.is_empty());
+    }
+
+    #[test]
+    fn test_put_and_lookup() {
+        let config = ServerConfig {
+            node_id: 1,
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config).unwrap();
+
+        // Put a value
+        server
+            .put_value(b"key1".to_vec(), b"value1".to_vec(), 0)
+            .unwrap();
+
+        // Verify it's in the cache
+        assert!(server.core.cache.contains_key(b"key1".as_slice()));
+
+        // Check the value
+        let entry = server.core.cache.get(b"key1".as_slice()).unwrap();
+        assert_eq!(entry.data, b"value1");
+    }
+
+    #[test]
+    fn test_device_value_pool_needs_the_fabric_transport() {
+        let config: ServerConfig = toml::from_str(
+            "memory_pool_size = 1048576\nvalue_device = { kind = \"cuda\", device_id = 1 }\n",
+        )
+        .unwrap();
+        assert_eq!(config.value_device, ValueDevice::Cuda { device_id: 1 });
+        let err = KvCacheServer::new(config).err().unwrap().to_string();
+        assert!(
+            err.contains("value_device = cuda (device 1) needs the fabric transport"),
+            "{}",
+            err
+        );
+
+        let config: ServerConfig = toml::from_str("memory_pool_size = 1048576\n").unwrap();
+        assert_eq!(config.value_device, ValueDevice::Host);
+        assert!(KvCacheServer::new(config).is_ok());
+    }
+
+    #[test]
+    fn test_put_beyond_max_value_size_is_rejected() {
+        let config = ServerConfig::builder()
+            .memory_pool_size(1024 * 1024)
+            .max_value_size(1024)
+            .build()
+            .unwrap();
+        let server = KvCacheServer::new(config).unwrap();
+
+        server
+            .put_value(b"small".to_vec(), vec![1; 1024], 0)
+            .unwrap();
+        let err = server
+            .put_value(b"big".to_vec(), vec![1; 1025], 0)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("exceeds max_value_size (1024)"), "{}", err);
+        assert!(!server.core.cache.contains_key(b"big".as_slice()));
+    }
+
+    #[tokio::test]
+    async fn test_keys_beyond_max_key_size_are_rejected() {
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(
+                KvCacheServer::new(ServerConfig {
+                    memory_pool_size: 1024 * 1024,
+                    max_key_size: 16,
+                    ..Default::default()
+                })
+                .unwrap(),
+            ),
+        };
+        let put = |key: &[u8]| PutRequest {
+            key: key.to_vec(),
+            value_source: Some(crate::pb::put_request::ValueSource::InlineValue(
+                b"value".to_vec(),
+            )),
+            ..Default::default()
+        };
+        let long_key = [b'k'; 17];
+
+        let status = service.put(Request::new(put(&long_key))).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            "Key of 17 bytes exceeds max_key_size (16)"
+        );
+        assert_eq!(service.inner.core.memory_pool.read().stats().used, 0);
+        let get = GetRequest {
+            key: long_key.to_vec(),
+            ..Default::default()
+        };
+        let status = service.get(Request::new(get)).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        let delete = DeleteRequest {
+            key: long_key.to_vec(),
+            ..Default::default()
+        };
+        let status = service.delete(Request::new(delete)).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        // A write that names the key as its destination is held to the limit too
+        service
+            .inner
+            .put_value(b"src".to_vec(), b"value".to_vec(), 0)
+            .unwrap();
+        let rename = RenameRequest {
+            src_key: b"src".to_vec(),
+            dst_key: long_key.to_vec(),
+        };
+        let status = service.rename(Request::new(rename)).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert!(service.inner.contains(b"src"));
+        assert!(!service.inner.contains(&long_key));
+
+        let response = service.put(Request::new(put(&[b'k'; 16]))).await.unwrap();
+        assert!(response.into_inner().success);
+        assert!(service.inner.contains(&[b'k'; 16]));
+    }
+
+    #[tokio::test]
+    async fn test_batch_put_reports_rejected_and_dropped_entries() {
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(
+                KvCacheServer::new(ServerConfig {
+                    memory_pool_size: 1024 * 1024,
+                    max_key_size: 16,
+                    ..Default::default()
+                })
+                .unwrap(),
+            ),
+        };
+        service
+            .inner
+            .put_value(b"present".to_vec(), b"old".to_vec(), 0)
+            .unwrap();
+        let put = |key: &[u8], put_if_absent| PutRequest {
+            key: key.to_vec(),
+            value_source: Some(crate::pb::put_request::ValueSource::InlineValue(
+                b"new".to_vec(),
+            )),
+            put_if_absent,
+            ..Default::default()
+        };
+        let request = BatchPutRequest {
+            entries: vec![
+                put(b"first", false),
+                put(&[b'k'; 17], false),
+                put(b"present", true),
+                put(b"last", false),
+            ],
+            continue_on_error: true,
+        };
+
+        // The oversized key fails on its own and the live key is left alone
+        let response = service
+            .batch_put(Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!response.success);
+        assert_eq!(response.stored, 2);
+        assert_eq!(
+            response.error_message,
+            "Key of 17 bytes exceeds max_key_size (16)"
+        );
+        let stored: Vec<_> = response.results.iter().map(|r| r.stored).collect();
+        assert_eq!(stored, [true, false, false, true]);
+        assert!(response.results[2].key_existed);
+        assert!(response.results[2].error_message.is_empty());
+        assert_eq!(
+            service.inner.core.cache.get(&b"present"[..]).unwrap().data,
+            b"old"
+        );
+        assert!(service.inner.contains(b"last"));
+    }
+
+    #[tokio::test]
+    async fn test_full_pool_evicts_least_recently_used() {
+        // 16 pages of 4KB, one per value
+        let server = KvCacheServer::new(ServerConfig {
+            memory_pool_size: 64 * 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        for i in 0..16 {
+            server
+                .put_value(format!("key{}", i).into_bytes(), vec![i as u8; 4096], 0)
+                .unwrap();
+        }
+        assert_eq!(server.core.memory_pool.read().stats().available, 0);
+
+        // Reading key0 leaves key1 the least recently used
+        server.warm(b"key0").await.unwrap();
+        server
+            .put_value(b"new".to_vec(), vec![0xff; 4096], 0)
+            .unwrap();
+        assert!(!server.contains(b"key1"));
+        assert!(server.contains(b"key0"));
+        assert_eq!(
+            server.core.cache.get(b"new".as_slice()).unwrap().data,
+            vec![0xff; 4096]
+        );
+        assert_eq!(server.count(), (16, 16 * 4096));
+
+        // Without eviction a full pool fails the PUT instead
+        let server = KvCacheServer::new(ServerConfig {
+            memory_pool_size: 64 * 1024,
+            eviction: EvictionPolicy::None,
+            ..Default::default()
+        })
+        .unwrap();
+        for i in 0..16 {
+            server
+                .put_value(format!("key{}", i).into_bytes(), vec![0; 4096], 0)
+                .unwrap();
+        }
+        let err = server
+            .put_value(b"new".to_vec(), vec![0; 4096], 0)
+            .unwrap_err();
+        assert!(err.to_string().contains("Memory pool exhausted"), "{}", err);
+        assert!(server.contains(b"key0"));
+    }
+
+    #[test]
+    fn test_compare_and_swap_on_full_pool_keeps_its_key_and_ttl() {
+        // 16 pages of 4KB, one per value; key0 is the least recently used
+        let server = KvCacheServer::new(ServerConfig {
+            memory_pool_size: 64 * 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        server
+            .put_value(b"key0".to_vec(), vec![0; 4096], 60_000)
+            .unwrap();
+        for i in 1..16 {
+            server
+                .put_value(format!("key{}", i).into_bytes(), vec![i as u8; 4096], 0)
+                .unwrap();
+        }
+
+        // A mismatch is settled before allocating, so it evicts nothing
+        assert!(!server
+            .compare_and_swap(b"key2".to_vec(), Some(&[0; 4096]), vec![0xff; 4096])
+            .unwrap());
+        assert_eq!(server.count().0, 16);
+
+        assert!(server
+            .compare_and_swap(b"key0".to_vec(), Some(&[0; 4096]), vec![0xff; 4096])
+            .unwrap());
+        let entry = server.core.cache.get(b"key0".as_slice()).unwrap();
+        assert_eq!(entry.data, vec![0xff; 4096]);
+        assert!(
+            (1..=60_000).contains(&entry.ttl_millis),
+            "{}",
+            entry.ttl_millis
+        );
+        drop(entry);
+        assert!(!server.contains(b"key1"));
+        assert_eq!(server.count().0, 15);
+    }
+
+    #[test]
+    fn test_eviction_stops_once_it_frees_nothing() {
+        let server = KvCacheServer::new(ServerConfig {
+            memory_pool_size: 64 * 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        for i in 0..16 {
+            server
+                .put_value(format!("key{}", i).into_bytes(), vec![i as u8; 4096], 0)
+                .unwrap();
+        }
+
+        // An in-flight GET holds key0, the first to be evicted
+        let lease = {
+            let entry = server.core.cache.get(b"key0".as_slice()).unwrap();
+            server.region_readers.acquire(&entry.allocation)
+        };
+        let err = server
+            .put_value(b"new".to_vec(), vec![0xff; 4096], 0)
+            .unwrap_err();
+        assert!(err.to_string().contains("Memory pool exhausted"), "{}", err);
+        assert_eq!(server.count().0, 15);
+        assert!(server.contains(b"key1"));
+
+        // Once the GET is done, key0's space is back and goes to the next PUT
+        drop(lease);
+        server
+            .put_value(b"new".to_vec(), vec![0xff; 4096], 0)
+            .unwrap();
+        assert!(server.contains(b"key1"));
+    }
+
+    #[test]
+    fn test_max_entries_evicts_before_pool_fills() {
+        let server = KvCacheServer::new(ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            max_entries: 2,
+            ..Default::default()
+        })
+        .unwrap();
+        server.put_value(b"a".to_vec(), b"1".to_vec(), 0).unwrap();
+        server.put_value(b"b".to_vec(), b"2".to_vec(), 0).unwrap();
+        // Overwriting a stored key doesn't count against the limit
+        server.put_value(b"b".to_vec(), b"3".to_vec(), 0).unwrap();
+        assert_eq!(server.count().0, 2);
+
+        server.put_value(b"c".to_vec(), b"4".to_vec(), 0).unwrap();
+        assert!(!server.contains(b"a"));
+        assert!(server.contains(b"b") && server.contains(b"c"));
+        assert_eq!(server.count().0, 2);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_failed_put_frees_its_allocation_and_keeps_old_value() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let mut server = KvCacheServer::new(config).unwrap();
+        server
+            .put_value(b"key1".to_vec(), b"v1".to_vec(), 0)
+            .unwrap();
+        let used = server.core.memory_pool.read().stats().used;
+
+        // Every WAL write now fails, after the new value is already in the pool;
+        // a value over the log's buffer size hits the file straight away
+        server.wal = Some(Wal::open("/dev/full").unwrap());
+        let err = server
+            .put_value(b"key1".to_vec(), vec![2; 64 * 1024], 0)
+            .unwrap_err();
+        assert!(err.to_string().contains("No space left"), "{}", err);
+
+        assert_eq!(server.core.memory_pool.read().stats().used, used);
+        assert_eq!(
+            server.core.cache.get(b"key1".as_slice()).unwrap().data,
+            b"v1"
+        );
+        assert_eq!(server.count(), (1, 2));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_failed_wal_append_leaves_delete_rename_and_touch_unapplied() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let mut server = KvCacheServer::new(config).unwrap();
+        // Records over the log's buffer size hit the file straight away
+        let key = vec![b'k'; 16 * 1024];
+        server
+            .put_value(key.clone(), vec![2; 64 * 1024], 0)
+            .unwrap();
+        server.wal = Some(Wal::open("/dev/full").unwrap());
+
+        assert!(server.touch_value(&key, 60_000).is_err());
+        assert_eq!(server.core.cache.get(key.as_slice()).unwrap().ttl_millis, 0);
+        assert!(server.delete_value(&key).is_err());
+        assert!(server.rename_value(&key, b"dst").is_err());
+        assert!(server.contains(&key));
+        assert!(!server.contains(b"dst"));
+        assert_eq!(server.count().0, 1);
+    }
+
+    #[tokio::test]
+    async fn test_put_with_wrong_checksum_is_rejected() {
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(
+                KvCacheServer::new(ServerConfig {
+                    memory_pool_size: 1024 * 1024,
+                    ..Default::default()
+                })
+                .unwrap(),
+            ),
+        };
+        let put = |crc32c| PutRequest {
+            key: b"key".to_vec(),
+            value_source: Some(crate::pb::put_request::ValueSource::InlineValue(
+                b"value".to_vec(),
+            )),
+            crc32c: Some(crc32c),
+            ..Default::default()
+        };
+
+        let status = service
+            .put(Request::new(put(crc32c(b"valuE"))))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::DataLoss);
+        assert!(!service.inner.contains(b"key"));
+        assert_eq!(service.inner.core.memory_pool.read().stats().used, 0);
+
+        let response = service
+            .put(Request::new(put(crc32c(b"value"))))
+            .await
+            .unwrap();
+        assert!(response.into_inner().success);
+        let result = service.inner.warm(b"key").await.unwrap();
+        assert_eq!(result.checksum, Some(crc32c(b"value")));
+    }
+
+    /// Log output collected by a test's tracing subscriber
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hashed_log_keys_hide_key_bytes() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(
+                KvCacheServer::new(ServerConfig {
+                    memory_pool_size: 1024 * 1024,
+                    log_keys: KeyLogPolicy::Hashed,
+                    ..Default::default()
+                })
+                .unwrap(),
+            ),
+        };
+        let key = b"secret-key".to_vec();
+        let put = PutRequest {
+            key: key.clone(),
+            value_source: Some(crate::pb::put_request::ValueSource::InlineValue(
+                b"value".to_vec(),
+            )),
+            ..Default::default()
+        };
+        service.put(Request::new(put)).await.unwrap();
+        let get = GetRequest {
+            key: key.clone(),
+            warm_only: true,
+            ..Default::default()
+        };
+        service.get(Request::new(get)).await.unwrap();
+        service
+            .delete(Request::new(DeleteRequest {
+                key: key.clone(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let logs = String::from_utf8(logs.0.lock().clone()).unwrap();
+        assert!(!logs.contains("secret-key"), "{}", logs);
+        assert!(!logs.contains(&format!("{:?}", key)), "{}", logs);
+        let hash = "#f14a9cea";
+        assert_eq!(format!("#{:08x}", crc32c(&key)), hash);
+        for line in [
+            "PUT request: key=",
+            "GET request: key=",
+            "DELETE request: key=",
+        ] {
+            assert!(logs.contains(&format!("{}{}", line, hash)), "{}", logs);
+        }
+    }
+
+    #[test]
+    fn test_interned_keys_survive_overwrite_and_delete() {
+        let config = ServerConfig {
+            node_id: 1,
+            memory_pool_size: 1024 * 1024,
+            intern_keys: true,
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config).unwrap();
+
+        server
+            .put_value(b"key1".to_vec(), b"v1".to_vec(), 0)
+            .unwrap();
+        server
+            .put_value(b"key1".to_vec(), b"v2".to_vec(), 0)
+            .unwrap();
+        assert_eq!(server.core.cache.len(), 1);
+        assert_eq!(
+            server.core.cache.get(b"key1".as_slice()).unwrap().data,
+            b"v2"
+        );
+
+        // The freed slot is reused by the next key of the same length
+        assert!(server.delete_value(b"key1").unwrap());
+        server
+            .put_value(b"key2".to_vec(), b"v3".to_vec(), 0)
+            .unwrap();
+        assert!(!server.core.cache.contains_key(b"key1".as_slice()));
+        assert_eq!(
+            server.core.cache.get(b"key2".as_slice()).unwrap().data,
+            b"v3"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket_in_use_is_not_taken_over() {
+        let path = std::env::temp_dir().join(format!("kv-unix-in-use-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let running = std::os::unix::net::UnixListener::bind(&path).unwrap();
+
+        let err = bind_unix(&path).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!("address already in use: {}", path.display())
+        );
+
+        // Once nothing listens, the socket file is stale and gets replaced
+        drop(running);
+        assert!(bind_unix(&path).is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_second_server_reports_address_in_use() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = ServerConfig {
+            listen_addr: format!("127.0.0.1:{}", port),
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        };
+
+        let first = tokio::spawn(run_server(config.clone()));
+        while tokio::net::TcpStream::connect(&config.listen_addr)
+            .await
+            .is_err()
+        {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let err = run_server(ServerConfig {
+            bind_retry_timeout: Duration::from_millis(200),
+            ..config
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("address already in use: 127.0.0.1:{}", port)
+        );
+
+        first.abort();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_reuseport_shards_both_serve_the_port() {
+        use crate::pb::kv_cache_service_client::KvCacheServiceClient;
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let addr = format!("http://127.0.0.1:{}", port);
+        let server = tokio::spawn(run_server(ServerConfig {
+            listen_addr: format!("127.0.0.1:{}", port),
+            memory_pool_size: 1024 * 1024,
+            reuseport_shards: 2,
+            ..Default::default()
+        }));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // Each connection comes from a fresh source port, which the kernel
+        // hashes to one of the listeners
+        for _ in 0..32 {
+            let mut client = KvCacheServiceClient::connect(addr.clone()).await.unwrap();
+            let response = client
+                .heartbeat(HeartbeatRequest { client_id: 1 })
+                .await
+                .unwrap();
+            assert!(response.into_inner().alive);
+        }
+
+        let stats = KvCacheServiceClient::connect(addr)
+            .await
+            .unwrap()
+            .stats(StatsRequest {})
+            .await
+            .unwrap()
+            .into_inner();
+        let accepted = stats.acceptor_connections;
+        assert_eq!(accepted.len(), 2);
+        assert!(
+            accepted.iter().all(|&n| n > 0),
+            "connections per shard: {:?}",
+            accepted
+        );
+        assert!(accepted.iter().sum::<u64>() >= 33);
+
+        server.abort();
+    }
+
+    #[test]
+    fn test_bloom_filter_tracks_put_and_delete() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            bloom_filter: Some(BloomFilterConfig {
+                expected_items: 1000,
+                false_positive_rate: 0.01,
+            }),
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config).unwrap();
+
+        server
+            .put_value(b"key1".to_vec(), b"v1".to_vec(), 0)
+            .unwrap();
+        server
+            .put_value(b"key1".to_vec(), b"v2".to_vec(), 0)
+            .unwrap();
+        assert!(server.contains(b"key1"));
+
+        // A single delete must clear the key even though it was written twice
+        assert!(server.delete_value(b"key1").unwrap());
+        assert!(!server.contains(b"key1"));
+        assert!(!server.core.bloom.as_ref().unwrap().may_contain(b"key1"));
+    }
+
+    #[tokio::test]
+    async fn test_conditional_get_skips_transfer_when_current() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config).unwrap();
+        server
+            .put_value(b"key1".to_vec(), b"value1".to_vec(), 0)
+            .unwrap();
+        let version = server.core.cache.get(b"key1".as_slice()).unwrap().version;
+
+        let mut dst = vec![0u8; 64];
+        let (_, descriptor) = server
+            .transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+        let location = ValueLocation::new(1, descriptor, 0, dst.len() as u64);
+
+        let result = server
+            .get_and_transfer(b"key1", &location, Some(version))
+            .await
+            .unwrap();
+        assert!(result.not_modified);
+        assert_eq!(result.version, version);
+        assert_eq!(dst, vec![0u8; 64]);
+
+        let result = server
+            .get_and_transfer(b"key1", &location, Some(version - 1))
+            .await
+            .unwrap();
+        assert!(!result.not_modified);
+        assert_eq!(&dst[..result.value_len as usize], b"value1");
+    }
+
+    #[tokio::test]
+    async fn test_get_into_short_buffer_reports_size_without_writing() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config).unwrap();
+        server
+            .put_value(b"key1".to_vec(), vec![7u8; 200], 0)
+            .unwrap();
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(server),
+        };
+
+        // The region is registered whole, as a client's receive pool is, but
+        // the GET only owns its first 64 bytes
+        let mut dst = vec![0u8; 256];
+        let (_, descriptor) = service
+            .inner
+            .transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+        let location = ValueLocation::new(1, descriptor, 0, 64);
+        let status = service
+            .get(Request::new(GetRequest {
+                key: b"key1".to_vec(),
+                response_location: Some((&location).into()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), Code::OutOfRange);
+        assert_eq!(status.metadata().get("value-length").unwrap(), "200");
+        assert!(status
+            .message()
+            .contains("200 bytes doesn't fit the 64-byte buffer"));
+        assert_eq!(dst, vec![0u8; 256]);
+    }
+
+    #[tokio::test]
+    async fn test_overload_sheds_gets_instead_of_queueing() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            transport: TransportConfig {
+                mock_transfer_delay: Duration::from_millis(50),
+                ..Default::default()
+            },
+            get_latency_budget: Some(Duration::from_millis(120)),
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config).unwrap();
+        server
+            .put_value(b"key1".to_vec(), b"value1".to_vec(), 0)
+            .unwrap();
+        let service = Arc::new(KvCacheServiceImpl {
+            inner: Arc::new(server),
+        });
+
+        let mut dst = vec![0u8; 64 * 11];
+        let (_, descriptor) = service
+            .inner
+            .transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+        let request = move |slot: u64| {
+            let location = ValueLocation::new(1, descriptor.clone(), slot * 64, 64);
+            Request::new(GetRequest {
+                key: b"key1".to_vec(),
+                response_location: Some((&location).into()),
+                request_id: slot,
+                ..Default::default()
+            })
+        };
+
+        // Warm up the service time estimate
+        service.get(request(0)).await.unwrap();
+
+        let handles: Vec<_> = (1..=10)
+            .map(|slot| {
+                let service = service.clone();
+                let request = request(slot);
+                tokio::spawn(async move { service.get(request).await })
+            })
+            .collect();
+
+        let (mut admitted, mut shed) = (0, 0);
+        for handle in handles {
+            match handle.await.unwrap() {
+                Ok(response) => {
+                    assert!(response.into_inner().success);
+                    admitted += 1;
+                }
+                Err(status) => {
+                    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+                    assert!(status.metadata().get("retry-after-ms").is_some());
+                    shed += 1;
+                }
+            }
+        }
+
+        // Each admitted GET adds at least 50ms of estimated delay against a 120ms budget
+        assert!((1..=3).contains(&admitted), "admitted {}", admitted);
+        assert_eq!(shed, 10 - admitted);
+        assert_eq!(service.inner.admission.as_ref().unwrap().in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_large_get_is_striped_across_domains() {
+        let config = ServerConfig {
+            memory_pool_size: 4 * 1024 * 1024,
+            transport: TransportConfig {
+                num_domains: 4,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config).unwrap();
+        let value: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+        server.put_value(b"big".to_vec(), value.clone(), 0).unwrap();
+
+        let mut dst = vec![0u8; value.len()];
+        let (_, descriptor) = server
+            .transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+        let location = ValueLocation::new(1, descriptor, 0, dst.len() as u64);
+        server
+            .get_and_transfer(b"big", &location, None)
+            .await
+            .unwrap();
+
+        assert_eq!(dst, value);
+        let per_domain = server.transport.domain_bytes_transferred();
+        assert_eq!(per_domain, vec![value.len() as u64 / 4; 4]);
+    }
+
+    #[tokio::test]
+    async fn test_flushed_put_survives_crash_and_replay() {
+        let wal_path = std::env::temp_dir().join(format!("kv-wal-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&wal_path);
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            wal_path: Some(wal_path.clone()),
+            ..Default::default()
+        };
+
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(KvCacheServer::new(config.clone()).unwrap()),
+        };
+        for (key, value) in [(b"key1", b"value1"), (b"key2", b"value2")] {
+            let response = service
+                .put(Request::new(PutRequest {
+                    key: key.to_vec(),
+                    value_source: Some(crate::pb::put_request::ValueSource::InlineValue(
+                        value.to_vec(),
+                    )),
+                    ttl_seconds: 0,
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+            assert!(response.into_inner().success);
+        }
+        service
+            .delete(Request::new(DeleteRequest {
+                key: b"key2".to_vec(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+        let flushed = service
+            .flush(Request::new(FlushRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(flushed.success);
+        assert_eq!(flushed.durable_sequence, 3);
+
+        // Crash: skip destructors, so nothing beyond the flush reaches the file
+        std::mem::forget(service);
+
+        let restarted = KvCacheServer::new(config).unwrap();
+        std::fs::remove_file(&wal_path).unwrap();
+        assert_eq!(
+            restarted.core.cache.get(b"key1".as_slice()).unwrap().data,
+            b"value1"
+        );
+        assert!(!restarted.contains(b"key2"));
+    }
+
+    #[test]
+    fn test_wal_torn_tail_is_cut_before_new_appends() {
+        let wal_path = std::env::temp_dir().join(format!("kv-wal-torn-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&wal_path);
+        let wal = Wal::open(&wal_path).unwrap();
+        wal.append_put(b"key1", b"value1", 0).unwrap();
+        wal.flush().unwrap();
+        drop(wal);
+        // Crash mid-append: a length prefix promising more than was written
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&wal_path)
+            .unwrap();
+        std::io::Write::write_all(&mut file, &[64, 0, 0, 0, 1, 2, 3]).unwrap();
+        drop(file);
+
+        // The restarted server replays key1, then logs a write of its own
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            wal_path: Some(wal_path.clone()),
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config.clone()).unwrap();
+        server
+            .put_value(b"key2".to_vec(), b"value2".to_vec(), 0)
+            .unwrap();
+        server.wal.as_ref().unwrap().flush().unwrap();
+        drop(server);
+
+        assert_eq!(Wal::replay(&wal_path).unwrap().len(), 2);
+        let restarted = KvCacheServer::new(config).unwrap();
+        std::fs::remove_file(&wal_path).unwrap();
+        assert_eq!(
+            restarted.core.cache.get(b"key1".as_slice()).unwrap().data,
+            b"value1"
+        );
+        assert_eq!(
+            restarted.core.cache.get(b"key2".as_slice()).unwrap().data,
+            b"value2"
+        );
+    }
+
+    #[test]
+    fn test_wal_compaction_keeps_only_live_entries() {
+        let wal_path =
+            std::env::temp_dir().join(format!("kv-wal-compact-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&wal_path);
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            wal_path: Some(wal_path.clone()),
+            wal_compact_bytes: 4096,
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config.clone()).unwrap();
+        for i in 0..100u32 {
+            server
+                .put_value(b"hot".to_vec(), i.to_le_bytes().repeat(16), 0)
+                .unwrap();
+        }
+        server
+            .put_value(b"gone".to_vec(), b"value".to_vec(), 0)
+            .unwrap();
+        assert!(server.delete_value(b"gone").unwrap());
+        assert!(server.wal_needs_compaction());
+
+        server.compact_wal().unwrap();
+        assert!(!server.wal_needs_compaction());
+        let wal = server.wal.as_ref().unwrap();
+        assert_eq!(Wal::replay(&wal_path).unwrap().len(), 1);
+        assert_eq!(wal.len(), wal.compacted_len());
+
+        // Writes after the compaction append to the new log
+        server
+            .put_value(b"after".to_vec(), b"value".to_vec(), 0)
+            .unwrap();
+        wal.flush().unwrap();
+        drop(server);
+
+        let restarted = KvCacheServer::new(config).unwrap();
+        std::fs::remove_file(&wal_path).unwrap();
+        assert_eq!(
+            restarted.core.cache.get(b"hot".as_slice()).unwrap().data,
+            99u32.to_le_bytes().repeat(16)
+        );
+        assert!(restarted.contains(b"after"));
+        assert!(!restarted.contains(b"gone"));
+    }
+
+    #[test]
+    fn test_wal_appends_during_compaction_follow_the_snapshot() {
+        let wal_path =
+            std::env::temp_dir().join(format!("kv-wal-concurrent-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&wal_path);
+        let wal = Wal::open(&wal_path).unwrap();
+        wal.append_put(b"key1", b"old", 0).unwrap();
+
+        wal.compact(|snapshot| {
+            // A write landing while the snapshot is taken
+            wal.append_put(b"key1", b"new", 0)?;
+            wal.append_delete(b"key2")?;
+            snapshot.put(b"key1", b"old", 0)
+        })
+        .unwrap();
+        drop(wal);
+
+        let records = Wal::replay(&wal_path).unwrap();
+        std::fs::remove_file(&wal_path).unwrap();
+        let put = |value: &[u8]| WalRecord::PutMillis {
+            key: b"key1".to_vec(),
+            value: value.to_vec(),
+            ttl_millis: 0,
+        };
+        assert_eq!(
+            records,
+            vec![
+                put(b"old"),
+                put(b"new"),
+                WalRecord::Delete {
+                    key: b"key2".to_vec()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wal_replay_keeps_millisecond_ttls() {
+        let wal_path = std::env::temp_dir().join(format!("kv-wal-ttl-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&wal_path);
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            wal_path: Some(wal_path.clone()),
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config.clone()).unwrap();
+        server
+            .put_value(b"short".to_vec(), b"value".to_vec(), 200)
+            .unwrap();
+        server.wal.as_ref().unwrap().flush().unwrap();
+        drop(server);
+
+        let restarted = KvCacheServer::new(config).unwrap();
+        std::fs::remove_file(&wal_path).unwrap();
+        assert_eq!(
+            restarted
+                .core
+                .cache
+                .get(b"short".as_slice())
+                .unwrap()
+                .ttl_millis,
+            200
+        );
+    }
+
+    #[test]
+    fn test_wal_records_that_no_longer_fit_are_skipped_on_replay() {
+        let wal_path = std::env::temp_dir().join(format!("kv-wal-full-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&wal_path);
+        let wal = Wal::open(&wal_path).unwrap();
+        wal.append_put(b"big", &vec![1; 256 * 1024], 0).unwrap();
+        wal.append_put(b"small", b"value", 0).unwrap();
+        wal.flush().unwrap();
+        drop(wal);
+
+        // Restart with a pool too small for the first record
+        let server = KvCacheServer::new(ServerConfig {
+            memory_pool_size: 64 * 1024,
+            wal_path: Some(wal_path.clone()),
+            ..Default::default()
+        })
+        .unwrap();
+        std::fs::remove_file(&wal_path).unwrap();
+        assert!(!server.contains(b"big"));
+        assert!(server.contains(b"small"));
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_gets_are_not_starved_by_bulk_flood() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            transport: TransportConfig {
+                mock_transfer_delay: Duration::from_millis(10),
+                ..Default::default()
+            },
+            max_concurrent_gets: 1,
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config).unwrap();
+        server
+            .put_value(b"key1".to_vec(), b"value1".to_vec(), 0)
+            .unwrap();
+        let service = Arc::new(KvCacheServiceImpl {
+            inner: Arc::new(server),
+        });
+        service
+            .register_client(Request::new(RegisterClientRequest {
+                client_id: 2,
+                priority: 10,
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let mut dst = vec![0u8; 64 * 64];
+        let (_, descriptor) = service
+            .inner
+            .transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+        let request = move |slot: u64, client_id: u32| {
+            let location = ValueLocation::new(1, descriptor.clone(), slot * 64, 64);
+            Request::new(GetRequest {
+                key: b"key1".to_vec(),
+                response_location: Some((&location).into()),
+                request_id: slot,
+                client_id,
+                ..Default::default()
+            })
+        };
+
+        // 40 bulk GETs take ~400ms to drain through the single slot
+        let flood: Vec<_> = (0..40)
+            .map(|slot| {
+                let service = service.clone();
+                let request = request(slot, 1);
+                tokio::spawn(async move { service.get(request).await.unwrap() })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        for slot in 40..45 {
+            let started = std::time::Instant::now();
+            service.get(request(slot, 2)).await.unwrap();
+            assert!(
+                started.elapsed() < Duration::from_millis(100),
+                "{:?}",
+                started.elapsed()
+            );
+        }
+        assert!(
+            service.inner.get_gate.as_ref().unwrap().waiting() > 0,
+            "flood already drained"
+        );
+
+        let p99 = service.inner.get_latency_quantile(10, 0.99).unwrap();
+        assert!(
+            p99 <= Duration::from_millis(128),
+            "high-priority p99 {:?}",
+            p99
+        );
+
+        for handle in flood {
+            handle.await.unwrap();
+        }
+        assert_eq!(service.inner.get_latency.get(&0).unwrap().count(), 40);
+    }
+
+    #[tokio::test]
+    async fn test_sweeper_frees_expired_entries_without_gets() {
+        let server = Arc::new(
+            KvCacheServer::new(ServerConfig {
+                memory_pool_size: 1024 * 1024,
+                ttl_sweep_interval: Duration::from_millis(50),
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        server.start_background_tasks();
+        server
+            .put_value(b"short".to_vec(), vec![1; 4096], 1000)
+            .unwrap();
+        server
+            .put_value(b"forever".to_vec(), vec![2; 4096], 0)
+            .unwrap();
+        let used = server.core.memory_pool.read().stats().used;
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while server.core.memory_pool.read().stats().used == used {
+            assert!(Instant::now() < deadline, "expired entry was never swept");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(server.core.memory_pool.read().stats().used, used - 4096);
+        assert!(!server.core.cache.contains_key(b"short".as_slice()));
+        assert_eq!(server.count(), (1, 4096));
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_put_during_slow_load_is_not_overwritten() {
+        /// Loader that answers only once the test lets it
+        struct GatedLoader(tokio::sync::Notify);
+
+        impl ValueLoader for GatedLoader {
+            fn load<'a>(&'a self, _key: &'a [u8]) -> crate::loader::LoadFuture<'a> {
+                Box::pin(async move {
+                    self.0.notified().await;
+                    Ok(Some(b"loaded".to_vec()))
+                })
+            }
+        }
+
+        let loader = Arc::new(GatedLoader(tokio::sync::Notify::new()));
+        let server = Arc::new(
+            KvCacheServer::new(ServerConfig {
+                memory_pool_size: 1024 * 1024,
+                ..Default::default()
+            })
+            .unwrap()
+            .with_loader(loader.clone()),
+        );
+
+        let loading = tokio::spawn({
+            let server = server.clone();
+            async move {
+                server
+                    .resident_entry(b"key")
+                    .await
+                    .map(|entry| entry.loaded)
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        server
+            .put_value(b"key".to_vec(), b"written".to_vec(), 0)
+            .unwrap();
+        loader.0.notify_one();
+
+        assert!(matches!(loading.await.unwrap(), Ok(false)));
+        assert_eq!(
+            server.core.cache.get(b"key".as_slice()).unwrap().data,
+            b"written"
+        );
+        // The loaded value's allocation was never made, or was freed
+        assert_eq!(
+            server.core.memory_pool.read().stats().used,
+            b"written".len()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_of_expired_key_frees_its_pool_space() {
+        let server = KvCacheServer::new(ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ttl_sweep_interval: Duration::ZERO,
+            ..Default::default()
+        })
+        .unwrap();
+        server
+            .put_value(b"short".to_vec(), vec![1; 4096], 50)
+            .unwrap();
+        assert_eq!(server.core.memory_pool.read().stats().used, 4096);
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        let result = server.resident_entry(b"short").await;
+        assert!(result.is_err_and(|status| status.code() == Code::NotFound));
+        assert_eq!(server.core.memory_pool.read().stats().used, 0);
+        assert_eq!(server.count(), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_stop_ends_background_tasks() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            tombstone_ttl: Duration::from_millis(20),
+            ..Default::default()
+        };
+        let server = Arc::new(KvCacheServer::new(config).unwrap());
+        server.start_background_tasks();
+        let tasks: Vec<_> = server
+            .background_tasks
+            .lock()
+            .iter()
+            .map(|task| task.abort_handle())
             .collect();
+        assert_eq!(tasks.len(), 3);
 
-        Ok(Response::new(RegisterClientResponse {
-            success: true,
-            server_id: self.inner.config.node_id,
-            server_domain_addresses: server_addresses,
-        }))
-    }
+        // The reaper is running: it drops the tombstone once it expires
+        server
+            .put_value(b"key".to_vec(), b"value".to_vec(), 0)
+            .unwrap();
+        assert!(server.delete_value(b"key").unwrap());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !server.tombstones.is_empty() {
+            assert!(Instant::now() < deadline, "tombstone was never reaped");
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
 
-    async fn heartbeat(
-        &self,
-        request: Request<HeartbeatRequest>,
-    ) -> Result<Response<HeartbeatResponse>, Status> {
-        let req = request.into_inner();
-        tracing::trace!("Heartbeat from client {}", req.client_id);
-        Ok(Response::new(HeartbeatResponse { alive: true }))
+        server.stop().await;
+        assert!(tasks.iter().all(|task| task.is_finished()));
+        assert!(server.background_tasks.lock().is_empty());
+        // Nothing left running holds on to the server
+        assert_eq!(Arc::strong_count(&server), 1);
     }
-}
 
-/// Run the server
-pub async fn run_server(config: ServerConfig) -> Result<()> {
-    let addr = config.listen_addr.parse()?;
-    let server = KvCacheServer::new(config)?;
+    #[tokio::test]
+    async fn test_delayed_replicated_put_does_not_resurrect_deleted_key() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            tombstone_ttl: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(KvCacheServer::new(config).unwrap()),
+        };
+        let replicated_put = |value: &[u8], version: u64| {
+            Request::new(PutRequest {
+                key: b"key1".to_vec(),
+                value_source: Some(crate::pb::put_request::ValueSource::InlineValue(
+                    value.to_vec(),
+                )),
+                version: Some(version),
+                ..Default::default()
+            })
+        };
 
-    tracing::info!("Starting KV cache server on {}", addr);
+        // The origin wrote v5, v6 and v7, then deleted at v8; v5 reaches this
+        // replica, v6 and v7 are still in flight
+        let response = service
+            .put(replicated_put(b"v5", 5))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.success && !response.stale);
 
-    // Configure tonic server for high concurrency
-    tonic::transport::Server::builder()
-        .concurrency_limit_per_connection(256) // Allow up to 256 concurrent requests per connection
-        .add_service(server.into_service())
-        .serve(addr)
-        .await?;
+        let response = service
+            .delete(Request::new(DeleteRequest {
+                key: b"key1".to_vec(),
+                version: Some(8),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.key_existed && !response.stale);
 
-    Ok(())
-}
+        // The tombstone carries the origin's version, not this node's counter
+        for (value, version) in [(b"v6", 6), (b"v7", 7)] {
+            let response = service
+                .put(replicated_put(value, version))
+                .await
+                .unwrap()
+                .into_inner();
+            assert!(response.success && response.stale);
+        }
+        assert!(!service.inner.contains(b"key1"));
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        // Once reaped, the tombstone no longer blocks anything
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(service.inner.reap_tombstones(), 1);
+        let response = service
+            .put(replicated_put(b"v6", 6))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!response.stale);
+        assert!(service.inner.contains(b"key1"));
+
+        // A replicated delete older than the stored value is dropped
+        let response = service
+            .delete(Request::new(DeleteRequest {
+                key: b"key1".to_vec(),
+                version: Some(4),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.stale && !response.key_existed);
+        assert!(service.inner.contains(b"key1"));
+    }
 
     #[test]
-    fn test_server_creation() {
+    fn test_tombstones_are_off_by_default() {
         let config = ServerConfig {
-            node_id: 1,
-            memory_pool_size: 1024 * 1024, // 1MB for testing
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config).unwrap();
+
+        server
+            .put_value(b"key1".to_vec(), b"value".to_vec(), 0)
+            .unwrap();
+        assert!(server.delete_value(b"key1").unwrap());
+        assert!(!server.delete_value(b"missing").unwrap());
+        assert!(server.tombstones.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_stats_report_runtime_metrics() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(KvCacheServer::new(config).unwrap()),
+        };
+        let (_release, released) = tokio::sync::oneshot::channel::<()>();
+        let parked = tokio::spawn(released);
+
+        let stats = service
+            .stats(Request::new(StatsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        let runtime = stats.runtime.unwrap();
+        assert_eq!(runtime.workers, 2);
+        assert!(runtime.alive_tasks >= 1, "{:?}", runtime);
+        parked.abort();
+    }
+
+    #[tokio::test]
+    async fn test_stats_report_bytes_per_get() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config).unwrap();
+        server
+            .put_value(b"key1".to_vec(), vec![7u8; 1000], 0)
+            .unwrap();
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(server),
+        };
+
+        let mut dst = vec![0u8; 4096];
+        let (_, descriptor) = service
+            .inner
+            .transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+        let location = ValueLocation::new(1, descriptor, 0, 4096);
+        for request_id in 0..10 {
+            let response = service
+                .get(Request::new(GetRequest {
+                    key: b"key1".to_vec(),
+                    response_location: Some((&location).into()),
+                    request_id,
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+            assert!(response.into_inner().success);
+        }
+
+        let stats = service
+            .stats(Request::new(StatsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(stats.gets, 10);
+        assert_eq!(stats.rdma_bytes, 10_000);
+        assert!(
+            (stats.bytes_per_get - 1000.0).abs() < 0.5,
+            "{}",
+            stats.bytes_per_get
+        );
+        // Requests carry a descriptor, so the ratio is small but nonzero
+        assert!(stats.control_bytes > 0);
+        assert!(stats.control_overhead_ratio > 0.0 && stats.control_overhead_ratio < 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_delete_prefix_removes_only_matching_keys() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config).unwrap();
+        for i in 1..=10 {
+            server
+                .put_value(format!("app:{}", i).into_bytes(), vec![0u8; 100], 0)
+                .unwrap();
+        }
+        for key in ["ap", "app", "other:1", "xapp:1"] {
+            server
+                .put_value(key.as_bytes().to_vec(), vec![0u8; 100], 0)
+                .unwrap();
+        }
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(server),
+        };
+
+        let response = service
+            .delete_prefix(Request::new(DeletePrefixRequest {
+                prefix: b"app:".to_vec(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.success);
+        assert_eq!(response.deleted, 10);
+
+        let mut remaining: Vec<Vec<u8>> = service
+            .inner
+            .core
+            .cache
+            .iter()
+            .map(|e| e.key().to_vec())
+            .collect();
+        remaining.sort();
+        assert_eq!(remaining, [&b"ap"[..], b"app", b"other:1", b"xapp:1"]);
+        assert_eq!(service.inner.core.memory_pool.read().stats().used, 4 * 100);
+    }
+
+    #[tokio::test]
+    async fn test_interceptor_transforms_put_and_get() {
+        struct Uppercase;
+
+        impl ValueInterceptor for Uppercase {
+            fn on_put(&self, _key: &[u8], value: &[u8]) -> Result<Vec<u8>> {
+                Ok(value.to_ascii_uppercase())
+            }
+
+            fn on_get(&self, _key: &[u8], value: &[u8]) -> Result<Vec<u8>> {
+                Ok(value.to_vec())
+            }
+        }
+
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config)
+            .unwrap()
+            .with_interceptor(Arc::new(Uppercase));
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(server),
+        };
+        let large = b"abc".repeat(1000);
+        for (key, value) in [(&b"small"[..], &b"hello"[..]), (b"large", &large)] {
+            let response = service
+                .put(Request::new(PutRequest {
+                    key: key.to_vec(),
+                    value_source: Some(crate::pb::put_request::ValueSource::InlineValue(
+                        value.to_vec(),
+                    )),
+                    crc32c: Some(crate::checksum::crc32c(value)),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert!(response.success, "{}", response.error_message);
+        }
+        assert_eq!(
+            service.inner.core.cache.get(&b"small"[..]).unwrap().data,
+            b"HELLO"
+        );
+
+        let mut dst = vec![0u8; 4096];
+        let (_, descriptor) = service
+            .inner
+            .transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+        let location = ValueLocation::new(1, descriptor, 0, 4096);
+        for (key, expected) in [
+            (&b"small"[..], b"HELLO".to_vec()),
+            (b"large", b"ABC".repeat(1000)),
+        ] {
+            let response = service
+                .get(Request::new(GetRequest {
+                    key: key.to_vec(),
+                    response_location: Some((&location).into()),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert!(response.success);
+            // Even the large value comes back inline rather than from the pool
+            assert_eq!(response.inline_value, Some(expected));
+            assert_eq!(response.crc32c, None);
+        }
+
+        // GetMany could only write the stored bytes, so it is refused
+        let response = service
+            .get_many(Request::new(GetManyRequest {
+                items: vec![GetManyItem {
+                    key: b"small".to_vec(),
+                    offset: 0,
+                    capacity: 4096,
+                }],
+                buffer: Some((&location.mr_descriptor).into()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!response.success);
+        assert!(response
+            .error_message
+            .contains("not supported with value interceptors"));
+        assert_eq!(service.inner.traffic.rdma_bytes(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_small_values_are_returned_inline() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            small_value_inline_threshold: 64,
             ..Default::default()
         };
         let server = KvCacheServer::new(config).unwrap();
-        assert!(server.cache.is_empty());
+        server
+            .put_value(b"small".to_vec(), vec![1u8; 32], 0)
+            .unwrap();
+        server
+            .put_value(b"large".to_vec(), vec![2u8; 1000], 0)
+            .unwrap();
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(server),
+        };
+
+        let mut dst = vec![0u8; 4096];
+        let (_, descriptor) = service
+            .inner
+            .transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+        let location = ValueLocation::new(1, descriptor, 0, 4096);
+        let get = |key: &[u8]| {
+            service.get(Request::new(GetRequest {
+                key: key.to_vec(),
+                response_location: Some((&location).into()),
+                ..Default::default()
+            }))
+        };
+
+        let small = get(b"small").await.unwrap().into_inner();
+        assert!(small.success);
+        assert_eq!(small.inline_value.as_deref(), Some(&[1u8; 32][..]));
+        assert_eq!(service.inner.traffic.rdma_bytes(), 0);
+        assert!(
+            dst.iter().all(|&b| b == 0),
+            "inline GET wrote the client buffer"
+        );
+
+        let large = get(b"large").await.unwrap().into_inner();
+        assert!(large.success);
+        assert_eq!(large.inline_value, None);
+        assert_eq!(service.inner.traffic.rdma_bytes(), 1000);
+        assert_eq!(&dst[..1000], &[2u8; 1000][..]);
     }
 
     #[test]
-    fn test_put_and_lookup() {
+    fn test_alternative_key_hashers_behave_like_the_default() {
+        for key_hasher in [
+            KeyHasherConfig::Ahash,
+            KeyHasherConfig::KeyedSip { k0: 7, k1: 42 },
+        ] {
+            let config = ServerConfig {
+                memory_pool_size: 1024 * 1024,
+                key_hasher,
+                ..Default::default()
+            };
+            let server = KvCacheServer::new(config).unwrap();
+            for i in 0..200 {
+                server
+                    .put_value(format!("key{}", i).into_bytes(), vec![i as u8; 16], 0)
+                    .unwrap();
+            }
+            server
+                .put_value(b"key7".to_vec(), vec![0xff; 16], 0)
+                .unwrap();
+            assert!(server.delete_value(b"key8").unwrap());
+
+            assert_eq!(server.core.cache.len(), 199);
+            assert_eq!(
+                server.core.cache.get(&b"key7"[..]).unwrap().data,
+                vec![0xff; 16]
+            );
+            assert_eq!(
+                server.core.cache.get(&b"key9"[..]).unwrap().data,
+                vec![9u8; 16]
+            );
+            assert!(!server.contains(b"key8"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_ttl_keeps_read_keys_alive() {
         let config = ServerConfig {
-            node_id: 1,
             memory_pool_size: 1024 * 1024,
+            adaptive_ttl: Some(AdaptiveTtlConfig {
+                increment_seconds: 1,
+                max_ttl_seconds: 10,
+            }),
             ..Default::default()
         };
         let server = KvCacheServer::new(config).unwrap();
+        server
+            .put_value(b"hot".to_vec(), vec![1u8; 100], 1000)
+            .unwrap();
+        server
+            .put_value(b"cold".to_vec(), vec![2u8; 100], 1000)
+            .unwrap();
 
-        // Put a value
+        let mut dst = vec![0u8; 4096];
+        let (_, descriptor) = server
+            .transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+        let location = ValueLocation::new(1, descriptor, 0, dst.len() as u64);
+
+        // Each read lands before the TTL extended by the previous one runs out
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(700)).await;
+            let result = server
+                .get_and_transfer(b"hot", &location, None)
+                .await
+                .unwrap();
+            assert_eq!(result.value_len, 100);
+        }
+
+        assert!(server.contains(b"hot"), "read key expired despite hits");
+        assert!(
+            !server.contains(b"cold"),
+            "unread key outlived its base TTL"
+        );
+        assert_eq!(server.core.cache.get(&b"hot"[..]).unwrap().ttl_millis, 4000);
+    }
+
+    #[tokio::test]
+    async fn test_stats_separate_hit_and_miss_latency() {
+        /// Loader that is slow enough to stand out in the miss histogram
+        struct SlowLoader;
+
+        impl ValueLoader for SlowLoader {
+            fn load<'a>(&'a self, key: &'a [u8]) -> crate::loader::LoadFuture<'a> {
+                Box::pin(async move {
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    Ok((key == b"backed").then(|| vec![3u8; 100]))
+                })
+            }
+        }
+
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let server = KvCacheServer::new(config)
+            .unwrap()
+            .with_loader(Arc::new(SlowLoader));
         server
-            .put_value(b"key1".to_vec(), b"value1".to_vec(), 0)
+            .put_value(b"resident".to_vec(), vec![1u8; 100], 0)
             .unwrap();
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(server),
+        };
 
-        // Verify it's in the cache
-        assert!(server.cache.contains_key(&b"key1".to_vec()));
+        let mut dst = vec![0u8; 4096];
+        let (_, descriptor) = service
+            .inner
+            .transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+        let location = ValueLocation::new(1, descriptor, 0, 4096);
+        let get = |key: &[u8]| {
+            service.get(Request::new(GetRequest {
+                key: key.to_vec(),
+                response_location: Some((&location).into()),
+                ..Default::default()
+            }))
+        };
 
-        // Check the value
-        let entry = server.cache.get(&b"key1".to_vec()).unwrap();
-        assert_eq!(entry.data, b"value1");
+        for _ in 0..10 {
+            assert!(get(b"resident").await.unwrap().into_inner().success);
+        }
+        for _ in 0..3 {
+            assert!(!get(b"absent").await.unwrap().into_inner().success);
+        }
+        // Filled by the loader: a miss, even though the GET succeeds
+        assert!(get(b"backed").await.unwrap().into_inner().success);
+        assert!(get(b"backed").await.unwrap().into_inner().success);
+
+        let stats = service
+            .stats(Request::new(StatsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        let hits = stats.hit_latency.unwrap();
+        let misses = stats.miss_latency.unwrap();
+        assert_eq!(
+            hits.count, 11,
+            "the loaded key is resident on its second read"
+        );
+        assert_eq!(misses.count, 4);
+        assert!(hits.p50_micros > 0 && hits.p50_micros <= hits.p99_micros);
+        assert!(misses.p50_micros > 0 && misses.p50_micros <= misses.p99_micros);
+        // The slow load lands in the miss tail, not among the hits
+        assert!(
+            misses.p99_micros >= 5000,
+            "miss p99 {}us",
+            misses.p99_micros
+        );
+        assert!(hits.p50_micros < misses.p99_micros);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_get_never_sees_torn_value_during_overwrites() {
+        const SIZE: usize = 256 * 1024;
+        let config = ServerConfig {
+            memory_pool_size: 8 * 1024 * 1024,
+            transport: TransportConfig {
+                mock_transfer_delay: Duration::from_micros(200),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let server = Arc::new(KvCacheServer::new(config).unwrap());
+        server
+            .put_value(b"key".to_vec(), vec![0u8; SIZE], 0)
+            .unwrap();
+
+        let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let writer = {
+            let server = server.clone();
+            let done = done.clone();
+            std::thread::spawn(move || {
+                let mut fill = 0u8;
+                while !done.load(Ordering::Relaxed) {
+                    fill = fill.wrapping_add(1);
+                    server
+                        .put_value(b"key".to_vec(), vec![fill; SIZE], 0)
+                        .unwrap();
+                }
+            })
+        };
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let server = server.clone();
+                tokio::spawn(async move {
+                    let mut dst = vec![0u8; SIZE];
+                    let (_, descriptor) = server
+                        .transport
+                        .register_memory(dst.as_mut_ptr(), dst.len())
+                        .unwrap();
+                    let location = ValueLocation::new(1, descriptor, 0, SIZE as u64);
+                    for _ in 0..200 {
+                        server
+                            .get_and_transfer(b"key", &location, None)
+                            .await
+                            .unwrap();
+                        let first = dst[0];
+                        assert!(
+                            dst.iter().all(|&b| b == first),
+                            "GET returned a mix of two writes"
+                        );
+                    }
+                })
+            })
+            .collect();
+        for reader in readers {
+            reader.await.unwrap();
+        }
+        done.store(true, Ordering::Relaxed);
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn test_parallel_puts_to_distinct_keys_land_intact() {
+        const SIZE: usize = 256 * 1024;
+        const KEYS_PER_THREAD: usize = 8;
+        const PUTS: usize = 400;
+
+        let server = Arc::new(
+            KvCacheServer::new(ServerConfig {
+                memory_pool_size: 64 * 1024 * 1024,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+
+        // The same number of PUTs split across `threads`, each thread cycling
+        // through its own keys; `pool_parallel_put` in the micro bench times it
+        let run = |threads: usize| {
+            let handles: Vec<_> = (0..threads)
+                .map(|t| {
+                    let server = server.clone();
+                    std::thread::spawn(move || {
+                        for i in 0..PUTS / threads {
+                            let key = format!("t{}-k{}", t, i % KEYS_PER_THREAD).into_bytes();
+                            let fill = (t * 31 + i) as u8;
+                            server.put_value(key, vec![fill; SIZE], 0).unwrap();
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        };
+
+        run(1);
+        run(4);
+
+        // Every key holds its thread's last write, in the map and in the pool
+        for t in 0..4 {
+            for k in 0..KEYS_PER_THREAD {
+                let last = (PUTS / 4 - KEYS_PER_THREAD..PUTS / 4)
+                    .find(|i| i % KEYS_PER_THREAD == k)
+                    .unwrap();
+                let fill = (t * 31 + last) as u8;
+                let entry = server
+                    .core
+                    .cache
+                    .get(format!("t{}-k{}", t, k).as_bytes())
+                    .unwrap();
+                assert!(entry.data.iter().all(|&b| b == fill));
+                let pool = server.core.memory_pool.read();
+                let stored = pool.read(entry.allocation.offset, SIZE).unwrap();
+                assert!(
+                    stored.iter().all(|&b| b == fill),
+                    "pool bytes of t{}-k{}",
+                    t,
+                    k
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_count_tracks_puts_deletes_and_expiry() {
+        let server = KvCacheServer::new(ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        })
+        .unwrap();
+
+        server.put_value(b"a".to_vec(), vec![1; 10], 0).unwrap();
+        server.put_value(b"b".to_vec(), vec![2; 20], 0).unwrap();
+        server.put_value(b"a".to_vec(), vec![3; 5], 0).unwrap();
+        assert_eq!(server.count(), (2, 25));
+
+        assert!(server.delete_value(b"b").unwrap());
+        assert!(!server.delete_value(b"b").unwrap());
+        assert!(server.rename_value(b"a", b"c").unwrap());
+        assert!(server.copy_value(b"c", b"d", None).unwrap());
+        assert!(!server
+            .put_versioned(b"d".to_vec(), vec![4; 100], 0, None, true, None)
+            .unwrap());
+        assert_eq!(server.count(), (2, 10));
+
+        // An expired entry stops counting once a read removes it
+        let expire = |key: &[u8]| {
+            server.put_value(key.to_vec(), vec![5; 7], 1000).unwrap();
+            server.core.cache.get_mut(key).unwrap().created_at -= Duration::from_secs(2);
+        };
+        expire(b"e");
+        assert!(matches!(server.touch_live(b"e"), Lookup::Expired { .. }));
+        assert_eq!(server.count(), (2, 10));
+
+        // ... or an EXISTS of its key
+        expire(b"f");
+        assert!(!server.contains(b"f"));
+        assert_eq!(server.count(), (2, 10));
+
+        // ... or, with nobody asking, the TTL sweep
+        expire(b"g");
+        expire(b"h");
+        assert_eq!(server.count(), (4, 24));
+        assert_eq!(server.sweep_expired(), 2);
+
+        let service = KvCacheServiceImpl {
+            inner: Arc::new(server),
+        };
+        let count = service
+            .count(Request::new(CountRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!((count.entries, count.stored_bytes), (2, 10));
+    }
+
+    #[test]
+    fn test_rename_keeps_pool_region_and_copy_takes_new_one() {
+        let server = KvCacheServer::new(ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            ..Default::default()
+        })
+        .unwrap();
+        server
+            .put_value(b"src".to_vec(), b"value".to_vec(), 600_000)
+            .unwrap();
+        let offset = server.core.cache.get(&b"src"[..]).unwrap().offset();
+
+        assert!(server.rename_value(b"src", b"dst").unwrap());
+        assert!(server.core.cache.get(&b"src"[..]).is_none());
+        let renamed = server.core.cache.get(&b"dst"[..]).unwrap();
+        assert_eq!(renamed.offset(), offset);
+        assert_eq!(renamed.ttl_millis, 600_000);
+        drop(renamed);
+
+        assert!(server.copy_value(b"dst", b"copy", Some(0)).unwrap());
+        let copy = server.core.cache.get(&b"copy"[..]).unwrap();
+        assert_ne!(copy.offset(), offset);
+        assert_eq!(copy.ttl_millis, 0);
+        assert_eq!(copy.data, b"value");
+    }
+
+    #[tokio::test]
+    async fn test_put_if_absent_stores_exactly_once() {
+        const KEYS: usize = 200;
+
+        let server = Arc::new(
+            KvCacheServer::new(ServerConfig {
+                memory_pool_size: 16 * 1024 * 1024,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+
+        // Two writers race for every key
+        let handles: Vec<_> = (0..2u8)
+            .map(|writer| {
+                let server = server.clone();
+                std::thread::spawn(move || {
+                    (0..KEYS)
+                        .map(|k| {
+                            let key = format!("lock-{}", k).into_bytes();
+                            server
+                                .put_versioned(key, vec![writer; 64], 0, None, true, None)
+                                .unwrap()
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let won: Vec<Vec<bool>> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        for (k, (&first, &second)) in won[0].iter().zip(&won[1]).enumerate() {
+            assert!(first != second, "lock-{}: {} and {}", k, first, second);
+            let winner = if first { 0 } else { 1 };
+            let entry = server
+                .core
+                .cache
+                .get(format!("lock-{}", k).as_bytes())
+                .unwrap();
+            assert!(entry.data.iter().all(|&b| b == winner));
+        }
+
+        // Through the RPC, a held key reports that it exists rather than stale
+        let service = KvCacheServiceImpl { inner: server };
+        let put = |key: &[u8]| {
+            Request::new(PutRequest {
+                key: key.to_vec(),
+                value_source: Some(crate::pb::put_request::ValueSource::InlineValue(
+                    b"v".to_vec(),
+                )),
+                put_if_absent: true,
+                ..Default::default()
+            })
+        };
+        let held = service.put(put(b"lock-0")).await.unwrap().into_inner();
+        assert!(held.success && held.key_existed && !held.stale);
+        let fresh = service.put(put(b"lock-new")).await.unwrap().into_inner();
+        assert!(fresh.success && !fresh.key_existed);
+    }
+
+    #[test]
+    fn test_deferred_deletes_return_promptly_and_free_eventually() {
+        let config = ServerConfig {
+            memory_pool_size: 1024 * 1024,
+            deferred_free: true,
+            ..Default::default()
+        };
+        let server = Arc::new(KvCacheServer::new(config).unwrap());
+        for i in 0..100 {
+            server
+                .put_value(format!("key{}", i).into_bytes(), vec![0u8; 1000], 0)
+                .unwrap();
+        }
+        assert_eq!(server.core.memory_pool.read().stats().used, 100 * 1000);
+
+        // A long-lived reader (e.g. a zero-copy read guard) would block eager
+        // deletes, which need the write lock
+        let reader = server.core.memory_pool.read();
+        let (done_tx, done_rx) = mpsc::channel();
+        let deleter = server.clone();
+        std::thread::spawn(move || {
+            for i in 0..100 {
+                assert!(deleter
+                    .delete_value(format!("key{}", i).as_bytes())
+                    .unwrap());
+            }
+            done_tx.send(()).unwrap();
+        });
+        done_rx
+            .recv_timeout(Duration::from_secs(5))
+            .expect("deferred deletes blocked behind a reader");
+        drop(reader);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while server.core.memory_pool.read().stats().used > 0 {
+            assert!(
+                Instant::now() < deadline,
+                "freed bytes never returned to the pool"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
     }
 }
@@ -0,0 +1,126 @@
+//! Client-side sharding across multiple KV cache servers
+//!
+//! Keys are mapped to servers with a consistent hash ring, so every client that
+//! is configured with the same server list routes a key to the same server, and
+//! adding a server only moves a fraction of the keys.
+
+use crate::client::{ClientConfig, KvCacheClient};
+use crate::hashring::HashRing;
+use anyhow::{anyhow, Result};
+
+/// A set of clients, one per server, with keys routed by consistent hashing
+pub struct ShardedClient {
+    shards: Vec<KvCacheClient>,
+    /// Server addresses, in the same order as `shards`
+    ring: HashRing<String>,
+}
+
+impl ShardedClient {
+    /// Create one client per config; each config's `server_addr` identifies its shard
+    pub fn new(configs: Vec<ClientConfig>) -> Result<Self> {
+        if configs.is_empty() {
+            return Err(anyhow!("ShardedClient needs at least one server"));
+        }
+
+        let mut ring = HashRing::default();
+        for config in &configs {
+            if ring.add_node(config.server_addr.clone()).is_some() {
+                return Err(anyhow!(
+                    "Server {} is listed more than once",
+                    config.server_addr
+                ));
+            }
+        }
+
+        let shards = configs
+            .into_iter()
+            .map(KvCacheClient::new)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { shards, ring })
+    }
+
+    /// Connect every shard
+    pub async fn connect(&self) -> Result<()> {
+        for shard in &self.shards {
+            shard.connect().await?;
+        }
+        Ok(())
+    }
+
+    /// Connect every shard, giving each up to `timeout` to become reachable
+    pub async fn connect_with_retry(&self, timeout: std::time::Duration) -> Result<()> {
+        for shard in &self.shards {
+            shard.connect_with_retry(timeout).await?;
+        }
+        Ok(())
+    }
+
+    /// Index of the shard that owns the key
+    pub fn shard_index(&self, key: &[u8]) -> usize {
+        // Never empty: `new` adds every shard
+        self.ring.node_index(key).unwrap()
+    }
+
+    /// Client for the shard that owns the key
+    pub fn shard(&self, key: &[u8]) -> &KvCacheClient {
+        &self.shards[self.shard_index(key)]
+    }
+
+    /// Shut down every shard's client, returning the first error
+    pub async fn shutdown(self) -> Result<()> {
+        let mut result = Ok(());
+        for shard in self.shards {
+            let shut_down = shard.shutdown().await;
+            if result.is_ok() {
+                result = shut_down;
+            }
+        }
+        result
+    }
+
+    /// All shard clients, in configuration order
+    pub fn shards(&self) -> &[KvCacheClient] {
+        &self.shards
+    }
+
+    /// Get a value from the owning shard
+    pub async fn get(&self, key: &[u8]) -> Result<Vec<u8>> {
+        self.shard(key).get(key).await
+    }
+
+    /// Put a value on the owning shard
+    pub async fn put(&self, key: &[u8], value: &[u8], ttl_seconds: u64) -> Result<()> {
+        self.shard(key).put(key, value, ttl_seconds).await
+    }
+
+    /// Delete a value from the owning shard
+    pub async fn delete(&self, key: &[u8]) -> Result<bool> {
+        self.shard(key).delete(key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_spread_across_shards() {
+        let configs = (0..3)
+            .map(|i| ClientConfig {
+                server_addr: format!("http://10.0.0.{}:50051", i),
+                receive_buffer_size: 1024 * 1024,
+                ..Default::default()
+            })
+            .collect();
+        let client = ShardedClient::new(configs).unwrap();
+
+        let mut counts = [0usize; 3];
+        for i in 0..3000 {
+            counts[client.shard_index(format!("key_{}", i).as_bytes())] += 1;
+        }
+        for count in counts {
+            assert!(count > 500, "uneven distribution: {:?}", counts);
+        }
+    }
+}
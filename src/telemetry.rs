@@ -0,0 +1,131 @@
+//! OpenTelemetry tracing (`otel` feature)
+//!
+//! Trace context crosses the gRPC boundary as a W3C `traceparent` in request
+//! metadata: the client injects its current span's context and server handlers
+//! parent their spans on it, so a GET's server handler and RDMA transfer show
+//! up as children of the client's span. Spans are exported over OTLP when
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+
+use anyhow::Result;
+use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Tracer, TracerProvider};
+use tonic::metadata::{KeyRef, MetadataKey, MetadataMap, MetadataValue};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Shuts the tracer provider down (flushing pending spans) when dropped
+pub struct TelemetryGuard {
+    provider: Option<TracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                tracing::warn!("Failed to shut down OpenTelemetry exporter: {}", e);
+            }
+        }
+    }
+}
+
+/// Install the global subscriber: fmt logging plus, when an OTLP endpoint is
+/// configured, span export (and the tokio-console layer with `console`)
+///
+/// `RUST_LOG` (or `default_filter`) applies to logging and export, not to the
+/// console layer. Must be called from within a Tokio runtime. Keep the guard alive for the
+/// life of the process.
+pub fn init_tracing(default_filter: &str, service_name: &'static str) -> Result<TelemetryGuard> {
+    let provider = match std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT") {
+        Some(_) => {
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_tonic()
+                .build()?;
+            Some(
+                TracerProvider::builder()
+                    .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+                    .build(),
+            )
+        }
+        None => None,
+    };
+
+    let filter =
+        || EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    #[cfg(feature = "console")]
+    let console = Some(crate::console::layer());
+    #[cfg(not(feature = "console"))]
+    let console = None::<tracing_subscriber::layer::Identity>;
+
+    tracing_subscriber::registry()
+        .with(console)
+        .with(tracing_subscriber::fmt::layer().with_filter(filter()))
+        .with(
+            provider
+                .as_ref()
+                .map(|provider| layer(provider, service_name).with_filter(filter())),
+        )
+        .init();
+
+    Ok(TelemetryGuard { provider })
+}
+
+/// `tracing` layer recording spans into `provider`
+pub fn layer<S>(
+    provider: &TracerProvider,
+    service_name: &'static str,
+) -> OpenTelemetryLayer<S, Tracer>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name))
+}
+
+/// Write the current span's trace context into outgoing request metadata
+pub fn inject_context(metadata: &mut MetadataMap) {
+    let context = tracing::Span::current().context();
+    TraceContextPropagator::new().inject_context(&context, &mut MetadataInjector(metadata));
+}
+
+/// Parent `span` on the trace context carried by incoming request metadata
+///
+/// Without a `traceparent` the span starts a new trace.
+pub fn set_parent_from(span: &tracing::Span, metadata: &MetadataMap) {
+    let context = TraceContextPropagator::new().extract(&MetadataExtractor(metadata));
+    span.set_parent(context);
+}
+
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+impl Injector for MetadataInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(key), Ok(value)) = (
+            MetadataKey::from_bytes(key.as_bytes()),
+            MetadataValue::try_from(value),
+        ) {
+            self.0.insert(key, value);
+        }
+    }
+}
+
+struct MetadataExtractor<'a>(&'a MetadataMap);
+
+impl Extractor for MetadataExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .keys()
+            .filter_map(|key| match key {
+                KeyRef::Ascii(key) => Some(key.as_str()),
+                KeyRef::Binary(_) => None,
+            })
+            .collect()
+    }
+}
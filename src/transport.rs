@@ -3,14 +3,33 @@
 //! This module provides an abstraction over RDMA operations, with both
 //! a mock implementation for testing and a real implementation using fabric-lib.
 
-use crate::protocol::{DomainAddress, MemoryRegionDescriptor, MemoryRegionHandle};
+use crate::memory::ValueDevice;
+use crate::protocol::{
+    DomainAddress, MemoryRegionDescriptor, MemoryRegionHandle, MemoryRegionRemoteKey,
+};
 use anyhow::{anyhow, Result};
-use std::sync::Arc;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+use std::collections::BTreeMap;
 use std::ffi::c_void;
+use std::future::Future;
+use std::pin::Pin;
 use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Transfer sizes `RdmaTransport::self_test` checks on every domain
+pub const SELF_TEST_SIZES: [usize; 3] = [4 * 1024, 64 * 1024, 1024 * 1024];
+
+/// Transfers of each size per domain in a self-test, averaged for its timings
+const SELF_TEST_ITERATIONS: u32 = 8;
 
 /// Configuration for the RDMA transport
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct TransportConfig {
     /// Node ID for this transport instance
     pub node_id: u32,
@@ -18,6 +37,38 @@ pub struct TransportConfig {
     pub num_domains: usize,
     /// Whether to use mock transport (for testing without RDMA hardware)
     pub use_mock: bool,
+    /// Mock only: check every transfer against the registered regions, and that its
+    /// destination descriptor is routable, before copying
+    pub validate_regions: bool,
+    /// Mock only: simulated latency of each async transfer
+    #[serde(with = "humantime_serde")]
+    pub mock_transfer_delay: Duration,
+    /// Copy locally instead of going through the NIC when the destination region
+    /// was registered in this process on the same node (matching domain addresses)
+    pub loopback_bypass: bool,
+    /// Use the mock transport, with a warning, if real RDMA was requested but is
+    /// unavailable; otherwise that is a startup error
+    pub fallback_to_mock: bool,
+    /// Split transfers longer than this into chunks of at most this many bytes,
+    /// so one large write doesn't hold a queue pair while small ones wait
+    /// (0 = never split)
+    pub max_chunk_size: usize,
+    /// Most memory regions this transport may register; each one pins memory and
+    /// uses NIC resources (0 = no limit)
+    pub max_registrations: usize,
+    /// Most bytes this transport may register in total (0 = no limit)
+    pub max_registered_bytes: usize,
+    /// Mock only: fail this many `register_memory` calls before succeeding, to
+    /// exercise registration error handling
+    pub mock_registration_failures: u32,
+    /// Consecutive failed transfers after which a domain is marked unhealthy and
+    /// round-robin transfers avoid it (0 = never)
+    pub domain_failure_threshold: u32,
+    /// How often the server probes unhealthy domains to bring them back (0 = never)
+    #[serde(with = "humantime_serde")]
+    pub domain_probe_interval: Duration,
+    /// Mock only: domains whose transfers and probes fail, to exercise health tracking
+    pub mock_failing_domains: Vec<u8>,
 }
 
 impl Default for TransportConfig {
@@ -26,15 +77,33 @@ impl Default for TransportConfig {
             node_id: 0,
             num_domains: 1,
             use_mock: true,
+            validate_regions: false,
+            mock_transfer_delay: Duration::from_micros(10),
+            loopback_bypass: false,
+            fallback_to_mock: false,
+            max_chunk_size: 0,
+            max_registrations: 0,
+            max_registered_bytes: 0,
+            mock_registration_failures: 0,
+            domain_failure_threshold: 3,
+            domain_probe_interval: Duration::from_secs(1),
+            mock_failing_domains: Vec::new(),
         }
     }
 }
 
 /// Routing strategy for domain selection
-#[derive(Clone, Debug)]
+///
+/// Serialized with a `mode` tag, e.g. `{ mode = "pinned", domain_idx = 1 }`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
 pub enum DomainRouting {
     /// Round-robin across domains, sharding by transfer size
-    RoundRobinSharded { num_shards: u8 },
+    RoundRobinSharded {
+        /// Becomes fabric-lib's `NonZeroU8`, so zero is rejected when deserializing
+        #[serde(deserialize_with = "deserialize_num_shards")]
+        num_shards: u8,
+    },
     /// Use a specific domain
     Pinned { domain_idx: u8 },
 }
@@ -45,6 +114,140 @@ impl Default for DomainRouting {
     }
 }
 
+/// Picks the domain for each transfer, in place of the request's `routing`
+///
+/// Installed with `RdmaTransport::set_router` for policies the built-in
+/// variants can't express, such as pinning by key hash or sending each
+/// transfer to the least-loaded domain.
+pub trait DomainRouter: Send + Sync {
+    /// Domain index for `request`, given the bytes each domain has
+    /// transferred so far (empty if the backend doesn't track them)
+    fn route(&self, request: &TransferRequest, domain_bytes: &[u64]) -> u8;
+}
+
+impl<F: Fn(&TransferRequest, &[u64]) -> u8 + Send + Sync> DomainRouter for F {
+    fn route(&self, request: &TransferRequest, domain_bytes: &[u64]) -> u8 {
+        self(request, domain_bytes)
+    }
+}
+
+/// Health of each domain, shared between `RdmaTransport` and its backend
+///
+/// Both backends stripe round-robin transfers across domains themselves and
+/// record the outcome of each stripe against the domain that carried it.
+/// After `threshold` failures in a row the domain is marked unhealthy and
+/// round-robin transfers skip it until `RdmaTransport::probe_domains` finds
+/// it working again.
+pub struct DomainHealth {
+    domains: Vec<DomainState>,
+    threshold: u32,
+}
+
+#[derive(Default)]
+struct DomainState {
+    consecutive_failures: AtomicU32,
+    failures: AtomicU64,
+    unhealthy: AtomicBool,
+}
+
+impl DomainHealth {
+    pub fn new(num_domains: usize, threshold: u32) -> Self {
+        Self {
+            domains: (0..num_domains.max(1))
+                .map(|_| DomainState::default())
+                .collect(),
+            threshold,
+        }
+    }
+
+    pub fn record_success(&self, domain_idx: usize) {
+        if let Some(state) = self.domains.get(domain_idx) {
+            state.consecutive_failures.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_failure(&self, domain_idx: usize) {
+        let Some(state) = self.domains.get(domain_idx) else {
+            return;
+        };
+        state.failures.fetch_add(1, Ordering::Relaxed);
+        let in_a_row = state.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        let tripped = self.threshold > 0 && in_a_row >= self.threshold;
+        if tripped && !state.unhealthy.swap(true, Ordering::Relaxed) {
+            tracing::warn!(
+                "Domain {} marked unhealthy after {} consecutive transfer failures",
+                domain_idx,
+                in_a_row
+            );
+        }
+    }
+
+    pub fn is_healthy(&self, domain_idx: usize) -> bool {
+        self.domains
+            .get(domain_idx)
+            .is_some_and(|state| !state.unhealthy.load(Ordering::Relaxed))
+    }
+
+    /// Put a domain back into rotation, e.g. after a successful probe
+    pub fn mark_healthy(&self, domain_idx: usize) {
+        if let Some(state) = self.domains.get(domain_idx) {
+            state.consecutive_failures.store(0, Ordering::Relaxed);
+            if state.unhealthy.swap(false, Ordering::Relaxed) {
+                tracing::info!("Domain {} recovered", domain_idx);
+            }
+        }
+    }
+
+    /// Domains round-robin transfers may use: the healthy ones, or all of them
+    /// if none are, so transfers still have somewhere to go
+    pub fn usable_domains(&self) -> Vec<usize> {
+        let healthy: Vec<_> = (0..self.domains.len())
+            .filter(|&idx| self.is_healthy(idx))
+            .collect();
+        if healthy.is_empty() {
+            (0..self.domains.len()).collect()
+        } else {
+            healthy
+        }
+    }
+}
+
+/// A domain's health and traffic, as reported by `RdmaTransport::domain_stats`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DomainStats {
+    pub domain_idx: usize,
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    /// Failed transfers over the transport's lifetime
+    pub failures: u64,
+    pub bytes_transferred: u64,
+}
+
+/// Timing of one domain and transfer size in `RdmaTransport::self_test`
+#[derive(Clone, Debug)]
+pub struct SelfTestResult {
+    pub domain_idx: usize,
+    pub size: usize,
+    /// Mean time per transfer
+    pub latency: Duration,
+}
+
+impl SelfTestResult {
+    /// Bytes per second achieved by back-to-back transfers of this size
+    pub fn bandwidth(&self) -> f64 {
+        self.size as f64 / self.latency.as_secs_f64()
+    }
+}
+
+fn deserialize_num_shards<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u8, D::Error> {
+    match u8::deserialize(deserializer)? {
+        0 => Err(serde::de::Error::custom("num_shards must be at least 1")),
+        n => Ok(n),
+    }
+}
+
 /// Request for a single RDMA transfer
 #[derive(Clone, Debug)]
 pub struct TransferRequest {
@@ -64,6 +267,21 @@ pub struct TransferRequest {
     pub routing: DomainRouting,
 }
 
+/// Request for a single RDMA read, pulling remote memory into a local region
+#[derive(Clone, Debug)]
+pub struct ReadRequest {
+    /// Source memory region descriptor (remote)
+    pub src_descriptor: MemoryRegionDescriptor,
+    /// Source offset within the memory region
+    pub src_offset: u64,
+    /// Read length in bytes
+    pub length: u64,
+    /// Destination memory region handle (local)
+    pub dst_handle: MemoryRegionHandle,
+    /// Destination offset within the memory region
+    pub dst_offset: u64,
+}
+
 /// Result of a transfer operation
 #[derive(Clone, Debug)]
 pub struct TransferResult {
@@ -72,6 +290,80 @@ pub struct TransferResult {
     pub error: Option<String>,
 }
 
+/// Completion of a transfer submitted with `RdmaTransport::submit_with_handle`
+///
+/// The transfer makes progress whether or not the handle is polled; awaiting it
+/// yields the result. Dropping it detaches the transfer.
+pub struct CompletionHandle {
+    imm_data: Option<u32>,
+    state: CompletionState,
+}
+
+enum CompletionState {
+    /// Finished during submission (loopback copy)
+    Ready(Option<TransferResult>),
+    Running(tokio::task::JoinHandle<Result<TransferResult>>),
+}
+
+impl CompletionHandle {
+    /// The request's immediate data, for matching against remote notifications
+    pub fn imm_data(&self) -> Option<u32> {
+        self.imm_data
+    }
+}
+
+impl Future for CompletionHandle {
+    type Output = Result<TransferResult>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &mut self.state {
+            CompletionState::Ready(result) => Poll::Ready(
+                result
+                    .take()
+                    .ok_or_else(|| anyhow!("Transfer completion polled after it resolved")),
+            ),
+            CompletionState::Running(task) => Pin::new(task)
+                .poll(cx)
+                .map(|joined| joined.map_err(|e| anyhow!("Transfer task failed: {}", e))?),
+        }
+    }
+}
+
+/// Run a transfer's chunks concurrently, combining their results
+///
+/// When the last chunk carries immediate data it is held back until the
+/// others complete, so the receiver's notification still means "all here".
+async fn submit_chunks(
+    inner: &dyn RdmaTransportTrait,
+    mut chunks: Vec<TransferRequest>,
+) -> Result<TransferResult> {
+    if chunks.len() == 1 {
+        return inner.submit_transfer_async(chunks.pop().unwrap()).await;
+    }
+
+    let last = match chunks.last() {
+        Some(chunk) if chunk.imm_data.is_some() => chunks.pop(),
+        _ => None,
+    };
+    let mut results = futures::future::try_join_all(
+        chunks
+            .into_iter()
+            .map(|chunk| inner.submit_transfer_async(chunk)),
+    )
+    .await?;
+    if let Some(last) = last {
+        if results.iter().all(|result| result.success) {
+            results.push(inner.submit_transfer_async(last).await?);
+        }
+    }
+
+    Ok(TransferResult {
+        success: results.iter().all(|result| result.success),
+        bytes_transferred: results.iter().map(|result| result.bytes_transferred).sum(),
+        error: results.into_iter().find_map(|result| result.error),
+    })
+}
+
 /// Trait for RDMA transport implementations
 pub trait RdmaTransportTrait: Send + Sync {
     /// Get the domain addresses for this transport
@@ -84,6 +376,23 @@ pub trait RdmaTransportTrait: Send + Sync {
         len: usize,
     ) -> Result<(MemoryRegionHandle, MemoryRegionDescriptor)>;
 
+    /// Register memory on a CUDA device for GPU-direct RDMA
+    ///
+    /// Backends that only reach host memory keep the default, which fails.
+    fn register_device_memory(
+        &self,
+        _ptr: *mut u8,
+        len: usize,
+        device_id: u32,
+    ) -> Result<(MemoryRegionHandle, MemoryRegionDescriptor)> {
+        Err(anyhow!(
+            "Cannot register {} bytes on CUDA device {}: this transport only reaches host \
+             memory (the mock copies with host memcpy); use the fabric transport",
+            len,
+            device_id
+        ))
+    }
+
     /// Submit a transfer request
     fn submit_transfer(&self, request: TransferRequest) -> Result<()>;
 
@@ -93,8 +402,44 @@ pub trait RdmaTransportTrait: Send + Sync {
         request: TransferRequest,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<TransferResult>> + Send + '_>>;
 
+    /// Whether `submit_read_async` is implemented
+    fn supports_read(&self) -> bool {
+        false
+    }
+
+    /// Submit a read and wait for completion (async)
+    ///
+    /// Backends without one-sided reads keep the default, which fails.
+    fn submit_read_async(
+        &self,
+        _request: ReadRequest,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<TransferResult>> + Send + '_>>
+    {
+        Box::pin(async { Err(anyhow!("RDMA read is not supported by this transport")) })
+    }
+
     /// Poll for completion (non-blocking)
     fn poll_completion(&self) -> Option<TransferResult>;
+
+    /// Release a registration made by `register_memory`
+    ///
+    /// The mock registers nothing, so it keeps the default, which succeeds.
+    fn deregister_memory(&self, _handle: &MemoryRegionHandle) -> Result<()> {
+        Ok(())
+    }
+
+    /// Bytes transferred per domain so far, if the implementation tracks it
+    fn domain_bytes_transferred(&self) -> Vec<u64> {
+        Vec::new()
+    }
+
+    /// Check whether an unhealthy domain can carry transfers again
+    ///
+    /// Backends that can't probe keep the default, which succeeds and so
+    /// leaves live traffic to find out.
+    fn probe_domain(&self, _domain_idx: usize) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// RDMA Transport implementation
@@ -103,158 +448,954 @@ pub trait RdmaTransportTrait: Send + Sync {
 pub struct RdmaTransport {
     inner: Arc<dyn RdmaTransportTrait>,
     config: TransportConfig,
+    /// Cached at construction; compared against registered regions for loopback
+    domain_addresses: Vec<DomainAddress>,
+    loopback_transfers: AtomicU64,
+    chunk_transfers: AtomicU64,
+    /// Regions currently registered: base ptr -> len
+    registrations: Mutex<BTreeMap<u64, usize>>,
+    router: RwLock<Option<Arc<dyn DomainRouter>>>,
+    health: Arc<DomainHealth>,
 }
 
 impl RdmaTransport {
     /// Create a new RDMA transport with the given configuration
-    pub fn new(config: TransportConfig) -> Result<Self> {
-        let inner: Arc<dyn RdmaTransportTrait> = if config.use_mock {
-            Arc::new(MockTransport::new(config.clone()))
+    pub fn new(mut config: TransportConfig) -> Result<Self> {
+        let (inner, health) = if config.use_mock {
+            Self::new_mock(&config)
         } else {
-            #[cfg(feature = "rdma")]
-            {
-                Arc::new(FabricTransport::new(config.clone())?)
-            }
-            #[cfg(not(feature = "rdma"))]
-            {
-                tracing::error!("Real RDMA requested but binary was not compiled with 'rdma' feature");
-                return Err(anyhow!(
-                    "Real RDMA not available. Rebuild with '--features rdma' or use --mock true"
-                ));
+            match Self::new_real(&mut config) {
+                Ok(backend) => backend,
+                Err(e) if config.fallback_to_mock => {
+                    tracing::warn!("!!! {} !!!", e);
+                    tracing::warn!(
+                        "!!! Falling back to MOCK transport: transfers only work within this process !!!"
+                    );
+                    config.use_mock = true;
+                    Self::new_mock(&config)
+                }
+                Err(e) => return Err(e),
             }
         };
+        Ok(Self::with_backend(inner, config, health))
+    }
+
+    fn new_mock(config: &TransportConfig) -> (Arc<dyn RdmaTransportTrait>, Arc<DomainHealth>) {
+        let health = Arc::new(DomainHealth::new(
+            config.num_domains,
+            config.domain_failure_threshold,
+        ));
+        let mock = MockTransport::new(config.clone(), health.clone());
+        (Arc::new(mock), health)
+    }
+
+    /// Wrap a backend that records into `health`
+    fn with_backend(
+        inner: Arc<dyn RdmaTransportTrait>,
+        config: TransportConfig,
+        health: Arc<DomainHealth>,
+    ) -> Self {
+        let domain_addresses = inner.domain_addresses();
+        Self {
+            inner,
+            config,
+            domain_addresses,
+            loopback_transfers: AtomicU64::new(0),
+            chunk_transfers: AtomicU64::new(0),
+            registrations: Mutex::new(BTreeMap::new()),
+            router: RwLock::new(None),
+            health,
+        }
+    }
+
+    /// The fabric-lib backend; `config.num_domains` becomes the number of
+    /// domains the engine actually opened
+    #[cfg(feature = "rdma")]
+    fn new_real(
+        config: &mut TransportConfig,
+    ) -> Result<(Arc<dyn RdmaTransportTrait>, Arc<DomainHealth>)> {
+        let fabric = FabricTransport::new(config)?;
+        let health = fabric.health.clone();
+        Ok((Arc::new(fabric), health))
+    }
+
+    #[cfg(not(feature = "rdma"))]
+    fn new_real(
+        _config: &mut TransportConfig,
+    ) -> Result<(Arc<dyn RdmaTransportTrait>, Arc<DomainHealth>)> {
+        tracing::error!("Real RDMA requested but binary was not compiled with 'rdma' feature");
+        Err(anyhow!(
+            "Real RDMA not available. Rebuild with '--features rdma' or use --mock true"
+        ))
+    }
 
-        Ok(Self { inner, config })
+    /// Whether transfers go through the mock transport (requested or fallen back to)
+    pub fn is_mock(&self) -> bool {
+        self.config.use_mock
     }
 
     /// Get the domain addresses for this transport
     pub fn domain_addresses(&self) -> Vec<DomainAddress> {
-        self.inner.domain_addresses()
+        self.domain_addresses.clone()
     }
 
     /// Submit a transfer request
     pub fn submit_transfer(&self, request: TransferRequest) -> Result<()> {
-        self.inner.submit_transfer(request)
+        if !self.config.use_mock {
+            check_routable(&request.dst_descriptor)?;
+        }
+        if self.try_loopback(&request)? {
+            return Ok(());
+        }
+        // Submitted in order, so the chunk carrying the immediate data goes last
+        for chunk in self.chunks(request) {
+            self.inner.submit_transfer(chunk)?;
+        }
+        Ok(())
     }
 
     /// Submit a transfer and wait for completion
     pub async fn submit_transfer_async(&self, request: TransferRequest) -> Result<TransferResult> {
-        self.inner.submit_transfer_async(request).await
+        if !self.config.use_mock {
+            check_routable(&request.dst_descriptor)?;
+        }
+        if self.try_loopback(&request)? {
+            return Ok(TransferResult {
+                success: true,
+                bytes_transferred: request.length,
+                error: None,
+            });
+        }
+        submit_chunks(&*self.inner, self.chunks(request)).await
     }
 
-    /// Get the node ID
-    pub fn node_id(&self) -> u32 {
-        self.config.node_id
+    /// Whether this transport can RDMA read; only the mock can so far
+    pub fn supports_read(&self) -> bool {
+        self.inner.supports_read()
     }
 
-    /// Register memory for RDMA access
-    /// Returns (handle, descriptor) that can be used for transfers
-    pub fn register_memory(
+    /// Read from a remote region into a local one and wait for completion
+    pub async fn submit_read_async(&self, request: ReadRequest) -> Result<TransferResult> {
+        if !self.config.use_mock {
+            check_routable(&request.src_descriptor)?;
+        }
+        self.inner.submit_read_async(request).await
+    }
+
+    /// Submit a transfer without waiting for it, returning a handle to its completion
+    ///
+    /// Lets a caller overlap the transfer with other work (e.g. answering the
+    /// RPC) and reconcile completion later. Must be called within a Tokio runtime.
+    pub fn submit_with_handle(&self, request: TransferRequest) -> Result<CompletionHandle> {
+        if !self.config.use_mock {
+            check_routable(&request.dst_descriptor)?;
+        }
+        let imm_data = request.imm_data;
+        if self.try_loopback(&request)? {
+            return Ok(CompletionHandle {
+                imm_data,
+                state: CompletionState::Ready(Some(TransferResult {
+                    success: true,
+                    bytes_transferred: request.length,
+                    error: None,
+                })),
+            });
+        }
+
+        let inner = self.inner.clone();
+        let chunks = self.chunks(request);
+        let task = tokio::spawn(async move { submit_chunks(&*inner, chunks).await });
+        Ok(CompletionHandle {
+            imm_data,
+            state: CompletionState::Running(task),
+        })
+    }
+
+    /// Submit several transfers together and wait for all of them
+    ///
+    /// Results are in request order; fails if any submission fails.
+    pub async fn submit_batch_async(
         &self,
-        ptr: *mut u8,
-        len: usize,
-    ) -> Result<(MemoryRegionHandle, MemoryRegionDescriptor)> {
-        self.inner.register_memory(ptr, len)
+        requests: Vec<TransferRequest>,
+    ) -> Result<Vec<TransferResult>> {
+        futures::future::try_join_all(
+            requests
+                .into_iter()
+                .map(|request| self.submit_transfer_async(request)),
+        )
+        .await
     }
-}
 
-/// Mock transport for testing without RDMA hardware
-struct MockTransport {
-    config: TransportConfig,
-    domain_addresses: Vec<DomainAddress>,
-}
+    /// Submit several transfers together, yielding results as they complete
+    ///
+    /// Results arrive in completion order, not request order. A transfer that
+    /// fails to submit yields an unsuccessful result carrying the error.
+    pub fn submit_transfer_stream(
+        &self,
+        requests: Vec<TransferRequest>,
+    ) -> impl futures::Stream<Item = TransferResult> + '_ {
+        requests
+            .into_iter()
+            .map(|request| async move {
+                self.submit_transfer_async(request)
+                    .await
+                    .unwrap_or_else(|e| TransferResult {
+                        success: false,
+                        bytes_transferred: 0,
+                        error: Some(e.to_string()),
+                    })
+            })
+            .collect::<futures::stream::FuturesUnordered<_>>()
+    }
 
-impl MockTransport {
-    fn new(config: TransportConfig) -> Self {
-        // Generate mock domain addresses
-        let domain_addresses = (0..config.num_domains)
-            .map(|i| {
-                DomainAddress::new(format!("mock://node{}/domain{}", config.node_id, i).into_bytes())
+    /// Route every later transfer through `router`, or back through each
+    /// request's own `routing` with `None`
+    pub fn set_router(&self, router: Option<Arc<dyn DomainRouter>>) {
+        *self.router.write() = router;
+    }
+
+    /// Bytes transferred per domain so far (empty if the backend doesn't track it)
+    pub fn domain_bytes_transferred(&self) -> Vec<u64> {
+        self.inner.domain_bytes_transferred()
+    }
+
+    /// Health and traffic of each domain
+    pub fn domain_stats(&self) -> Vec<DomainStats> {
+        let bytes = self.domain_bytes_transferred();
+        self.health
+            .domains
+            .iter()
+            .enumerate()
+            .map(|(domain_idx, state)| DomainStats {
+                domain_idx,
+                healthy: !state.unhealthy.load(Ordering::Relaxed),
+                consecutive_failures: state.consecutive_failures.load(Ordering::Relaxed),
+                failures: state.failures.load(Ordering::Relaxed),
+                bytes_transferred: bytes.get(domain_idx).copied().unwrap_or(0),
             })
-            .collect();
+            .collect()
+    }
 
-        Self {
-            config,
-            domain_addresses,
+    /// Probe every unhealthy domain, returning how many recovered
+    ///
+    /// The fabric backend blocks on each probe's write, so call this off the
+    /// async runtime's workers.
+    pub fn probe_domains(&self) -> usize {
+        let mut recovered = 0;
+        for domain_idx in 0..self.health.domains.len() {
+            if self.health.is_healthy(domain_idx) {
+                continue;
+            }
+            match self.inner.probe_domain(domain_idx) {
+                Ok(()) => {
+                    self.health.mark_healthy(domain_idx);
+                    recovered += 1;
+                }
+                Err(e) => tracing::debug!("Domain {} still unhealthy: {}", domain_idx, e),
+            }
         }
+        recovered
     }
-}
 
-impl RdmaTransportTrait for MockTransport {
-    fn domain_addresses(&self) -> Vec<DomainAddress> {
-        self.domain_addresses.clone()
+    /// Check that every domain moves data correctly, timing a few transfer sizes
+    ///
+    /// Registers a source and a destination buffer and writes from one to the
+    /// other with each of `SELF_TEST_SIZES` pinned to each domain, comparing the
+    /// bytes after every transfer. Loopback bypass and any installed router are
+    /// skipped so the backend carries every transfer. Fails on the first
+    /// transfer that errors or delivers the wrong bytes.
+    pub async fn self_test(&self) -> Result<Vec<SelfTestResult>> {
+        let len = SELF_TEST_SIZES[SELF_TEST_SIZES.len() - 1];
+        let mut src: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        let mut dst = vec![0u8; len];
+        let (src_handle, _) = self.register_memory(src.as_mut_ptr(), len)?;
+        let (dst_handle, dst_descriptor) = match self.register_memory(dst.as_mut_ptr(), len) {
+            Ok(registered) => registered,
+            Err(e) => {
+                self.deregister_memory(&src_handle)?;
+                return Err(e);
+            }
+        };
+
+        let result = self
+            .run_self_test(src_handle, &src, dst_descriptor, dst.as_mut_ptr())
+            .await;
+        self.deregister_memory(&dst_handle)?;
+        self.deregister_memory(&src_handle)?;
+        result
     }
 
-    fn register_memory(
+    async fn run_self_test(
         &self,
-        ptr: *mut u8,
-        len: usize,
-    ) -> Result<(MemoryRegionHandle, MemoryRegionDescriptor)> {
-        // Mock implementation: just create fake registration
-        let handle = MemoryRegionHandle::new(ptr as u64, len);
+        src_handle: MemoryRegionHandle,
+        src: &[u8],
+        dst_descriptor: MemoryRegionDescriptor,
+        dst: *mut u8,
+    ) -> Result<Vec<SelfTestResult>> {
+        let mut results = Vec::new();
+        for domain_idx in 0..self.config.num_domains {
+            for size in SELF_TEST_SIZES {
+                let mut elapsed = Duration::ZERO;
+                for _ in 0..SELF_TEST_ITERATIONS {
+                    // SAFETY: `dst` is the start of a registered buffer at least
+                    // `size` bytes long, and no transfer into it is in flight
+                    unsafe { std::ptr::write_bytes(dst, 0, size) };
+                    let request = TransferRequest {
+                        src_handle,
+                        src_offset: 0,
+                        length: size as u64,
+                        imm_data: None,
+                        dst_descriptor: dst_descriptor.clone(),
+                        dst_offset: 0,
+                        routing: DomainRouting::Pinned {
+                            domain_idx: domain_idx as u8,
+                        },
+                    };
+                    let start = Instant::now();
+                    let result = submit_chunks(&*self.inner, self.split(request)).await?;
+                    elapsed += start.elapsed();
+                    if !result.success {
+                        return Err(anyhow!(
+                            "Self-test transfer of {} bytes on domain {} failed: {}",
+                            size,
+                            domain_idx,
+                            result.error.unwrap_or_default()
+                        ));
+                    }
+
+                    // SAFETY: as above; the transfer into it has completed
+                    let received = unsafe { std::slice::from_raw_parts(dst, size) };
+                    if let Some(at) = received.iter().zip(src).position(|(got, sent)| got != sent) {
+                        return Err(anyhow!(
+                            "Self-test transfer of {} bytes on domain {} delivered wrong data at byte {}",
+                            size,
+                            domain_idx,
+                            at
+                        ));
+                    }
+                }
+                results.push(SelfTestResult {
+                    domain_idx,
+                    size,
+                    latency: elapsed / SELF_TEST_ITERATIONS,
+                });
+            }
+        }
+        Ok(results)
+    }
 
-        let addr_rkey_list: Vec<_> = self.domain_addresses
-            .iter()
-            .enumerate()
-            .map(|(i, addr)| {
-                (addr.clone(), crate::protocol::MemoryRegionRemoteKey(i as u64))
-            })
-            .collect();
+    /// Number of transfers served by the local loopback path
+    pub fn loopback_transfers(&self) -> u64 {
+        self.loopback_transfers.load(Ordering::Relaxed)
+    }
 
-        let descriptor = MemoryRegionDescriptor::new(ptr as u64, addr_rkey_list);
+    /// Number of sub-transfers submitted for transfers split by `max_chunk_size`
+    pub fn chunk_transfers(&self) -> u64 {
+        self.chunk_transfers.load(Ordering::Relaxed)
+    }
 
-        Ok((handle, descriptor))
+    /// Split a transfer into `max_chunk_size` pieces, or leave it whole
+    ///
+    /// Only the last piece carries the immediate data, since the receiver takes
+    /// it to mean the whole value has landed. With a router installed, every
+    /// piece goes to the domain it picks for the whole transfer.
+    fn chunks(&self, mut request: TransferRequest) -> Vec<TransferRequest> {
+        if let Some(router) = self.router.read().as_ref() {
+            let domain_idx = router.route(&request, &self.domain_bytes_transferred());
+            request.routing = DomainRouting::Pinned { domain_idx };
+        }
+        self.split(request)
     }
 
-    fn submit_transfer(&self, request: TransferRequest) -> Result<()> {
-        // In the mock implementation, we simulate the transfer by copying memory
-        // In a real RDMA implementation, this would initiate the RDMA write
+    /// The splitting half of `chunks`, leaving the request's routing alone
+    fn split(&self, request: TransferRequest) -> Vec<TransferRequest> {
+        let max = self.config.max_chunk_size as u64;
+        if max == 0 || request.length <= max {
+            return vec![request];
+        }
 
-        tracing::debug!(
-            "Mock transfer: src_offset={}, dst_offset={}, length={}",
-            request.src_offset,
-            request.dst_offset,
-            request.length
-        );
+        let chunks: Vec<_> = (0..request.length)
+            .step_by(max as usize)
+            .map(|start| TransferRequest {
+                src_offset: request.src_offset + start,
+                length: max.min(request.length - start),
+                imm_data: (start + max >= request.length)
+                    .then_some(request.imm_data)
+                    .flatten(),
+                dst_offset: request.dst_offset + start,
+                ..request.clone()
+            })
+            .collect();
+        self.chunk_transfers
+            .fetch_add(chunks.len() as u64, Ordering::Relaxed);
+        chunks
+    }
 
-        // Validate pointers are non-null
-        let src_ptr = (request.src_handle.ptr + request.src_offset) as *const u8;
-        let dst_ptr = (request.dst_descriptor.ptr + request.dst_offset) as *mut u8;
+    /// Perform the transfer as a local copy if loopback bypass applies
+    ///
+    /// Returns `Ok(false)` when the transfer must go through the NIC: bypass is
+    /// disabled, the destination descriptor doesn't match a registration in this
+    /// process (base pointer and rkeys), it was registered by a transport on
+    /// another node, or the source isn't a host region registered here (device
+    /// memory never is). A remote address that merely falls inside a local
+    /// region is not enough. Ranges that overrun their regions or overlap are
+    /// errors.
+    fn try_loopback(&self, request: &TransferRequest) -> Result<bool> {
+        if !self.config.loopback_bypass {
+            return Ok(false);
+        }
 
-        if src_ptr.is_null() || dst_ptr.is_null() {
-            return Err(anyhow!(
-                "Mock transfer failed: null pointer (src={:p}, dst={:p})",
-                src_ptr, dst_ptr
-            ));
+        let src_start = request.src_handle.ptr + request.src_offset;
+        let dst_start = request.dst_descriptor.ptr + request.dst_offset;
+        let len = request.length;
+
+        {
+            let regions = LOCAL_REGIONS.lock();
+            let same_node = regions
+                .get(&request.dst_descriptor.ptr)
+                .is_some_and(|region| {
+                    region.addr_rkey_list == request.dst_descriptor.addr_rkey_list
+                        && region
+                            .domain_addresses
+                            .iter()
+                            .any(|addr| self.domain_addresses.contains(addr))
+                });
+            if !same_node || !regions.contains_key(&request.src_handle.ptr) {
+                return Ok(false);
+            }
+
+            if !check_registered(&regions, src_start, len)
+                || !check_registered(&regions, dst_start, len)
+            {
+                return Err(anyhow!(
+                    "Loopback transfer rejected: src [{:#x}, +{}) or dst [{:#x}, +{}) \
+                     is outside registered regions",
+                    src_start,
+                    len,
+                    dst_start,
+                    len
+                ));
+            }
+            if src_start < dst_start + len && dst_start < src_start + len {
+                return Err(anyhow!(
+                    "Loopback transfer rejected: src [{:#x}, +{}) and dst [{:#x}, +{}) overlap",
+                    src_start,
+                    len,
+                    dst_start,
+                    len
+                ));
+            }
         }
 
-        // SAFETY: The mock transport only works when client and server are in the
-        // same process (e.g., integration tests). When running as separate processes,
-        // the destination pointer from the remote client is NOT valid in this address
-        // space. Use real RDMA (use_mock: false) for cross-process transfers.
-        //
-        // We cannot easily validate the pointers are in our address space, so this
-        // will segfault if used incorrectly across processes.
-        tracing::warn!(
-            "Mock transport performing memory copy - this only works when client \
-             and server are in the SAME process. For separate processes, use real RDMA."
-        );
+        tracing::debug!("Loopback transfer: length={}", len);
 
+        // SAFETY: both ranges were registered in this process, lie within their
+        // regions, and do not overlap
         unsafe {
-            std::ptr::copy_nonoverlapping(src_ptr, dst_ptr, request.length as usize);
+            std::ptr::copy_nonoverlapping(
+                src_start as *const u8,
+                dst_start as *mut u8,
+                len as usize,
+            );
         }
 
-        Ok(())
+        self.loopback_transfers.fetch_add(1, Ordering::Relaxed);
+        Ok(true)
     }
 
-    fn submit_transfer_async(
-        &self,
-        request: TransferRequest,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<TransferResult>> + Send + '_>>
-    {
-        Box::pin(async move {
+    /// Get the node ID
+    pub fn node_id(&self) -> u32 {
+        self.config.node_id
+    }
+
+    /// Number of memory regions registered with this transport
+    pub fn registration_count(&self) -> usize {
+        self.registrations.lock().len()
+    }
+
+    /// Total bytes registered with this transport
+    pub fn registered_bytes(&self) -> usize {
+        self.registrations.lock().values().sum()
+    }
+
+    /// Whether this transport's registrations go in `LOCAL_REGIONS`
+    ///
+    /// The mock bounds-checks every transfer against it and loopback bypass
+    /// matches destinations in it; a real transport without bypass never
+    /// looks, so it doesn't leave entries there.
+    fn tracks_local_regions(&self) -> bool {
+        self.config.use_mock || self.config.loopback_bypass
+    }
+
+    /// Register memory for RDMA access
+    /// Returns (handle, descriptor) that can be used for transfers
+    ///
+    /// Fails without registering if it would take the transport past
+    /// `max_registrations` or `max_registered_bytes`.
+    pub fn register_memory(
+        &self,
+        ptr: *mut u8,
+        len: usize,
+    ) -> Result<(MemoryRegionHandle, MemoryRegionDescriptor)> {
+        self.register_memory_on(ptr, len, ValueDevice::Host)
+    }
+
+    /// Register memory that lives on `device` for RDMA access
+    ///
+    /// Device memory is left out of `LOCAL_REGIONS`: loopback can't copy it
+    /// with host memcpy, so transfers from it always go through the NIC.
+    pub fn register_memory_on(
+        &self,
+        ptr: *mut u8,
+        len: usize,
+        device: ValueDevice,
+    ) -> Result<(MemoryRegionHandle, MemoryRegionDescriptor)> {
+        // Held across the registration so concurrent ones can't both squeeze in
+        let mut registrations = self.registrations.lock();
+        let count = registrations.len();
+        let bytes: usize = registrations.values().sum();
+        let max_count = self.config.max_registrations;
+        let max_bytes = self.config.max_registered_bytes;
+        if max_count > 0 && count >= max_count {
+            return Err(anyhow!(
+                "Cannot register {} byte memory region: transport already has {} registrations \
+                 (max_registrations = {}). Each registration uses NIC resources; share buffers \
+                 between requests (fewer clients, or get_many instead of dedicated buffers) \
+                 or raise max_registrations",
+                len,
+                count,
+                max_count
+            ));
+        }
+        if max_bytes > 0 && bytes.saturating_add(len) > max_bytes {
+            return Err(anyhow!(
+                "Cannot register {} byte memory region: transport already has {} bytes registered \
+                 (max_registered_bytes = {}). Registered memory is pinned and uses NIC resources; \
+                 use smaller or fewer buffers or raise max_registered_bytes",
+                len,
+                bytes,
+                max_bytes
+            ));
+        }
+
+        let registered = match device {
+            ValueDevice::Host => self.inner.register_memory(ptr, len)?,
+            ValueDevice::Cuda { device_id } => {
+                self.inner.register_device_memory(ptr, len, device_id)?
+            }
+        };
+        if !self.config.use_mock {
+            check_routable(&registered.1)?;
+        }
+        registrations.insert(ptr as u64, len);
+        drop(registrations);
+
+        if self.tracks_local_regions() && device == ValueDevice::Host {
+            LOCAL_REGIONS.lock().insert(
+                ptr as u64,
+                LocalRegion {
+                    len,
+                    domain_addresses: self.domain_addresses.clone(),
+                    addr_rkey_list: registered.1.addr_rkey_list.clone(),
+                },
+            );
+        }
+        Ok(registered)
+    }
+
+    /// Release a region registered with `register_memory`
+    ///
+    /// The memory must not be the target of any further transfer.
+    pub fn deregister_memory(&self, handle: &MemoryRegionHandle) -> Result<()> {
+        self.inner.deregister_memory(handle)?;
+        if self.registrations.lock().remove(&handle.ptr).is_some() && self.tracks_local_regions() {
+            LOCAL_REGIONS.lock().remove(&handle.ptr);
+        }
+        Ok(())
+    }
+}
+
+/// A region registered with some transport in this process
+struct LocalRegion {
+    len: usize,
+    /// Domain addresses of the transport that registered it
+    domain_addresses: Vec<DomainAddress>,
+    /// The (domain address, rkey) pairs handed out in its descriptor
+    addr_rkey_list: SmallVec<[(DomainAddress, MemoryRegionRemoteKey); 4]>,
+}
+
+/// Regions registered with any transport in this process, keyed by base ptr
+///
+/// The mock copies raw pointers, so a client's descriptor is only meaningful to a
+/// server in the same process. Keeping the registry process-wide lets the server's
+/// transport validate destinations that were registered by the client's transport,
+/// and lets loopback bypass recognise same-node destinations. Entries are
+/// removed on deregistration, and only recorded by transports that consult them.
+static LOCAL_REGIONS: Mutex<BTreeMap<u64, LocalRegion>> = parking_lot::const_mutex(BTreeMap::new());
+
+/// Check that a descriptor names at least one domain a transfer can be routed to
+fn check_routable(descriptor: &MemoryRegionDescriptor) -> Result<()> {
+    if descriptor.addr_rkey_list.is_empty() {
+        return Err(anyhow!(
+            "Memory region descriptor at {:#x} has no (domain address, rkey) pairs; \
+             it was not registered with an RDMA transport, so transfers to it cannot be routed",
+            descriptor.ptr
+        ));
+    }
+    Ok(())
+}
+
+/// Check that a transfer stays within its source region and its destination's
+/// registered region
+///
+/// The destination's length comes from the process-wide registry, so the
+/// destination must have been registered with a transport in this process.
+fn check_bounds(request: &TransferRequest) -> Result<()> {
+    let len = request.length;
+    let src_len = request.src_handle.len as u64;
+    if request
+        .src_offset
+        .checked_add(len)
+        .is_none_or(|end| end > src_len)
+    {
+        return Err(anyhow!(
+            "Transfer out of bounds: src_offset {} + length {} exceeds the {}-byte source region",
+            request.src_offset,
+            len,
+            src_len
+        ));
+    }
+
+    let dst_ptr = request.dst_descriptor.ptr;
+    let dst_len = LOCAL_REGIONS
+        .lock()
+        .get(&dst_ptr)
+        .map(|region| region.len as u64)
+        .ok_or_else(|| {
+            anyhow!(
+                "Transfer rejected: destination region {:#x} was not registered in this process",
+                dst_ptr
+            )
+        })?;
+    if request
+        .dst_offset
+        .checked_add(len)
+        .is_none_or(|end| end > dst_len)
+    {
+        return Err(anyhow!(
+            "Transfer out of bounds: dst_offset {} + length {} exceeds the {}-byte destination region",
+            request.dst_offset,
+            len,
+            dst_len
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check that a read stays within its destination region and its source's
+/// registered region
+fn check_read_bounds(request: &ReadRequest) -> Result<()> {
+    let len = request.length;
+    let src_ptr = request.src_descriptor.ptr;
+    let src_len = LOCAL_REGIONS
+        .lock()
+        .get(&src_ptr)
+        .map(|region| region.len as u64)
+        .ok_or_else(|| {
+            anyhow!(
+                "Read rejected: source region {:#x} was not registered in this process",
+                src_ptr
+            )
+        })?;
+    if request
+        .src_offset
+        .checked_add(len)
+        .is_none_or(|end| end > src_len)
+    {
+        return Err(anyhow!(
+            "Read out of bounds: src_offset {} + length {} exceeds the {}-byte source region",
+            request.src_offset,
+            len,
+            src_len
+        ));
+    }
+
+    let dst_len = request.dst_handle.len as u64;
+    if request
+        .dst_offset
+        .checked_add(len)
+        .is_none_or(|end| end > dst_len)
+    {
+        return Err(anyhow!(
+            "Read out of bounds: dst_offset {} + length {} exceeds the {}-byte destination region",
+            request.dst_offset,
+            len,
+            dst_len
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check that `[start, start + len)` lies within a single registered region
+fn check_registered(regions: &BTreeMap<u64, LocalRegion>, start: u64, len: u64) -> bool {
+    let end = match start.checked_add(len) {
+        Some(end) => end,
+        None => return false,
+    };
+    match regions.range(..=start).next_back() {
+        Some((&base, region)) => end <= base + region.len as u64,
+        None => false,
+    }
+}
+
+/// Split a transfer into per-domain stripes: (domain, offset, len)
+///
+/// Mirrors fabric-lib's `RoundRobinSharded`: the transfer is cut into up to
+/// `num_shards` contiguous pieces, assigned to consecutive domains starting
+/// from `next_domain`'s rotating position. Unhealthy domains are left out of
+/// the rotation.
+fn stripes(
+    routing: &DomainRouting,
+    length: u64,
+    health: &DomainHealth,
+    next_domain: &AtomicU64,
+) -> Vec<(usize, u64, u64)> {
+    let num_domains = health.domains.len();
+    match *routing {
+        DomainRouting::Pinned { domain_idx } => {
+            vec![(domain_idx as usize % num_domains, 0, length)]
+        }
+        DomainRouting::RoundRobinSharded { num_shards } => {
+            let domains = health.usable_domains();
+            let shards = (num_shards.max(1) as u64)
+                .min(domains.len() as u64)
+                .min(length.max(1));
+            let first = next_domain.fetch_add(1, Ordering::Relaxed) as usize;
+            let chunk = length.div_ceil(shards);
+            (0..shards)
+                .map(|i| {
+                    let offset = i * chunk;
+                    let len = chunk.min(length - offset);
+                    (domains[(first + i as usize) % domains.len()], offset, len)
+                })
+                .collect()
+        }
+    }
+}
+
+/// Mock transport for testing without RDMA hardware
+struct MockTransport {
+    config: TransportConfig,
+    domain_addresses: Vec<DomainAddress>,
+    /// Bytes copied on behalf of each simulated domain
+    domain_bytes: Vec<AtomicU64>,
+    /// Domain that takes the next transfer's first stripe
+    next_domain: AtomicU64,
+    /// Injected registration failures still to come
+    registration_failures: AtomicU64,
+    /// Domains whose transfers fail (injected)
+    failing_domains: Vec<AtomicBool>,
+    health: Arc<DomainHealth>,
+}
+
+impl MockTransport {
+    fn new(config: TransportConfig, health: Arc<DomainHealth>) -> Self {
+        // Generate mock domain addresses
+        let domain_addresses = (0..config.num_domains)
+            .map(|i| {
+                DomainAddress::new(
+                    format!("mock://node{}/domain{}", config.node_id, i).into_bytes(),
+                )
+            })
+            .collect();
+
+        let domain_bytes = (0..config.num_domains).map(|_| AtomicU64::new(0)).collect();
+        let failing_domains = (0..config.num_domains)
+            .map(|i| AtomicBool::new(config.mock_failing_domains.contains(&(i as u8))))
+            .collect();
+
+        Self {
+            registration_failures: AtomicU64::new(config.mock_registration_failures as u64),
+            config,
+            domain_addresses,
+            domain_bytes,
+            next_domain: AtomicU64::new(0),
+            failing_domains,
+            health,
+        }
+    }
+
+    fn is_failing(&self, domain: usize) -> bool {
+        self.failing_domains
+            .get(domain)
+            .is_some_and(|failing| failing.load(Ordering::Relaxed))
+    }
+
+    /// Validate that src and dst ranges are registered and don't overlap
+    fn validate_transfer(&self, request: &TransferRequest) -> Result<()> {
+        let src_start = request.src_handle.ptr + request.src_offset;
+        let dst_start = request.dst_descriptor.ptr + request.dst_offset;
+        let len = request.length;
+
+        let regions = LOCAL_REGIONS.lock();
+        if !check_registered(&regions, src_start, len) {
+            return Err(anyhow!(
+                "Mock transfer rejected: src range [{:#x}, +{}) is outside registered regions",
+                src_start,
+                len
+            ));
+        }
+        if !check_registered(&regions, dst_start, len) {
+            return Err(anyhow!(
+                "Mock transfer rejected: dst range [{:#x}, +{}) is outside registered regions",
+                dst_start,
+                len
+            ));
+        }
+        if src_start < dst_start + len && dst_start < src_start + len {
+            return Err(anyhow!(
+                "Mock transfer rejected: src [{:#x}, +{}) and dst [{:#x}, +{}) overlap",
+                src_start,
+                len,
+                dst_start,
+                len
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl RdmaTransportTrait for MockTransport {
+    fn domain_addresses(&self) -> Vec<DomainAddress> {
+        self.domain_addresses.clone()
+    }
+
+    fn register_memory(
+        &self,
+        ptr: *mut u8,
+        len: usize,
+    ) -> Result<(MemoryRegionHandle, MemoryRegionDescriptor)> {
+        let injected =
+            self.registration_failures
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                    left.checked_sub(1)
+                });
+        if injected.is_ok() {
+            return Err(anyhow!(
+                "Mock registration of {} bytes failed (injected)",
+                len
+            ));
+        }
+
+        // Mock implementation: just create fake registration
+        let handle = MemoryRegionHandle::new(ptr as u64, len);
+
+        let addr_rkey_list: Vec<_> = self
+            .domain_addresses
+            .iter()
+            .enumerate()
+            .map(|(i, addr)| {
+                (
+                    addr.clone(),
+                    crate::protocol::MemoryRegionRemoteKey(i as u64),
+                )
+            })
+            .collect();
+
+        let descriptor = MemoryRegionDescriptor::new(ptr as u64, addr_rkey_list);
+
+        Ok((handle, descriptor))
+    }
+
+    fn submit_transfer(&self, request: TransferRequest) -> Result<()> {
+        // In the mock implementation, we simulate the transfer by copying memory
+        // In a real RDMA implementation, this would initiate the RDMA write
+
+        tracing::debug!(
+            "Mock transfer: src_offset={}, dst_offset={}, length={}",
+            request.src_offset,
+            request.dst_offset,
+            request.length
+        );
+
+        // Validate pointers are non-null
+        let src_ptr = (request.src_handle.ptr + request.src_offset) as *const u8;
+        let dst_ptr = (request.dst_descriptor.ptr + request.dst_offset) as *mut u8;
+
+        if src_ptr.is_null() || dst_ptr.is_null() {
+            return Err(anyhow!(
+                "Mock transfer failed: null pointer (src={:p}, dst={:p})",
+                src_ptr,
+                dst_ptr
+            ));
+        }
+
+        if self.config.validate_regions {
+            // An unroutable destination is a setup error, not an aliasing bug
+            check_routable(&request.dst_descriptor)?;
+            if let Err(e) = self.validate_transfer(&request) {
+                // Aliasing bugs are programming errors; fail loudly while developing
+                if cfg!(debug_assertions) {
+                    panic!("{}", e);
+                }
+                return Err(e);
+            }
+        }
+        check_bounds(&request)?;
+
+        // SAFETY: The mock transport only works when client and server are in the
+        // same process (e.g., integration tests). When running as separate processes,
+        // the destination pointer from the remote client is NOT valid in this address
+        // space. Use real RDMA (use_mock: false) for cross-process transfers.
+        //
+        // We cannot easily validate the pointers are in our address space, so this
+        // will segfault if used incorrectly across processes.
+        tracing::warn!(
+            "Mock transport performing memory copy - this only works when client \
+             and server are in the SAME process. For separate processes, use real RDMA."
+        );
+
+        let stripes = stripes(
+            &request.routing,
+            request.length,
+            &self.health,
+            &self.next_domain,
+        );
+        for (domain, offset, len) in stripes {
+            if self.is_failing(domain) {
+                self.health.record_failure(domain);
+                return Err(anyhow!(
+                    "Mock transfer on domain {} failed (injected)",
+                    domain
+                ));
+            }
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    src_ptr.add(offset as usize),
+                    dst_ptr.add(offset as usize),
+                    len as usize,
+                );
+            }
+            if let Some(bytes) = self.domain_bytes.get(domain) {
+                bytes.fetch_add(len, Ordering::Relaxed);
+            }
+            self.health.record_success(domain);
+        }
+
+        Ok(())
+    }
+
+    fn submit_transfer_async(
+        &self,
+        request: TransferRequest,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<TransferResult>> + Send + '_>>
+    {
+        Box::pin(async move {
             // Simulate async transfer with a small delay
-            tokio::time::sleep(tokio::time::Duration::from_micros(10)).await;
+            tokio::time::sleep(self.config.mock_transfer_delay).await;
 
             self.submit_transfer(request.clone())?;
 
@@ -266,68 +1407,177 @@ impl RdmaTransportTrait for MockTransport {
         })
     }
 
+    fn supports_read(&self) -> bool {
+        true
+    }
+
+    fn submit_read_async(
+        &self,
+        request: ReadRequest,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<TransferResult>> + Send + '_>>
+    {
+        Box::pin(async move {
+            tokio::time::sleep(self.config.mock_transfer_delay).await;
+
+            tracing::debug!(
+                "Mock read: src_offset={}, dst_offset={}, length={}",
+                request.src_offset,
+                request.dst_offset,
+                request.length
+            );
+            check_read_bounds(&request)?;
+
+            // SAFETY: as for writes, the source was registered in this process;
+            // both ranges were bounds-checked above
+            unsafe {
+                std::ptr::copy(
+                    (request.src_descriptor.ptr + request.src_offset) as *const u8,
+                    (request.dst_handle.ptr + request.dst_offset) as *mut u8,
+                    request.length as usize,
+                );
+            }
+
+            Ok(TransferResult {
+                success: true,
+                bytes_transferred: request.length,
+                error: None,
+            })
+        })
+    }
+
     fn poll_completion(&self) -> Option<TransferResult> {
         // Mock always completes immediately
         None
     }
+
+    fn domain_bytes_transferred(&self) -> Vec<u64> {
+        self.domain_bytes
+            .iter()
+            .map(|bytes| bytes.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    fn probe_domain(&self, domain_idx: usize) -> Result<()> {
+        if self.is_failing(domain_idx) {
+            return Err(anyhow!(
+                "Mock probe of domain {} failed (injected)",
+                domain_idx
+            ));
+        }
+        Ok(())
+    }
 }
 
+/// How long `FabricTransport::probe_domain` waits for its write to complete
+#[cfg(feature = "rdma")]
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Bytes `FabricTransport::probe_domain` writes from one half of its buffer
+/// to the other
+#[cfg(feature = "rdma")]
+const PROBE_LEN: usize = 64;
+
 /// Real fabric-lib RDMA transport implementation
+///
+/// Round-robin transfers are striped across domains here rather than by
+/// fabric-lib, each stripe pinned to its domain, so a failing domain can be
+/// charged with its failures and left out of the rotation.
 #[cfg(feature = "rdma")]
 struct FabricTransport {
     config: TransportConfig,
     engine: Arc<fabric_lib::TransferEngine>,
     domain_addresses: Vec<DomainAddress>,
+    health: Arc<DomainHealth>,
+    /// Domain that takes the next transfer's first stripe
+    next_domain: AtomicU64,
+    /// Bytes written through each domain; shared with completion callbacks
+    domain_bytes: Arc<Vec<AtomicU64>>,
+    /// Registered buffer each probe writes across, one probe at a time
+    probe: Mutex<ProbeBuffer>,
+}
+
+/// `FabricTransport`'s probe buffer and its registration
+#[cfg(feature = "rdma")]
+struct ProbeBuffer {
+    buf: Box<[u8]>,
+    handle: MemoryRegionHandle,
+    descriptor: MemoryRegionDescriptor,
+    /// Varies the probe's bytes so a stale buffer can't pass for a write
+    round: u8,
 }
 
 #[cfg(feature = "rdma")]
 impl FabricTransport {
-    fn new(config: TransportConfig) -> Result<Self> {
-        use fabric_lib::{TransferEngine, RdmaEngine, Worker};
+    fn new(config: &mut TransportConfig) -> Result<Self> {
+        use fabric_lib::{RdmaEngine, TransferEngine, Worker};
 
         tracing::info!("Initializing fabric-lib RDMA transport");
 
         // Try to get EFA domains directly (works for host memory without GPU affinity)
-        let efa_domains = fabric_lib::detect_topology()
-            .ok()
-            .and_then(|topo| {
-                if topo.is_empty() {
-                    None
-                } else {
-                    Some(topo)
-                }
-            });
+        let efa_domains = fabric_lib::detect_topology().ok().and_then(|topo| {
+            if topo.is_empty() {
+                None
+            } else {
+                Some(topo)
+            }
+        });
 
-        let engine = if let Some(topology) = efa_domains {
+        let (engine, num_domains) = if let Some(topology) = efa_domains {
             // GPU-aware topology found - use it
             tracing::info!("Using GPU-aware topology with {} groups", topology.len());
             Self::build_with_topology(config.num_domains, &topology[0])?
         } else {
             // No GPU topology - build for host memory only with EFA domains
             tracing::info!("No GPU topology found, building for host memory with EFA domains");
-            Self::build_host_only(config.num_domains)?
+            (
+                Self::build_host_only(config.num_domains)?,
+                config.num_domains,
+            )
         };
+        if num_domains < config.num_domains {
+            tracing::warn!(
+                "Only {} of the {} requested RDMA domains are available",
+                num_domains,
+                config.num_domains
+            );
+            config.num_domains = num_domains;
+        }
 
         let engine = Arc::new(engine);
 
         // Get domain addresses from the engine
-        let domain_addresses = vec![
-            DomainAddress(engine.main_address().0.to_vec())
-        ];
+        let domain_addresses = vec![DomainAddress(engine.main_address().0.to_vec())];
+
+        let mut buf = vec![0u8; 2 * PROBE_LEN].into_boxed_slice();
+        let (handle, descriptor) =
+            Self::register_on(&engine, buf.as_mut_ptr(), buf.len(), cuda_lib::Device::Host)?;
 
         tracing::info!("Fabric-lib RDMA transport initialized successfully");
 
         Ok(Self {
-            config,
+            health: Arc::new(DomainHealth::new(
+                num_domains,
+                config.domain_failure_threshold,
+            )),
+            next_domain: AtomicU64::new(0),
+            domain_bytes: Arc::new((0..num_domains).map(|_| AtomicU64::new(0)).collect()),
+            probe: Mutex::new(ProbeBuffer {
+                buf,
+                handle,
+                descriptor,
+                round: 0,
+            }),
+            config: config.clone(),
             engine,
             domain_addresses,
         })
     }
 
+    /// The engine and how many domains it opened
     fn build_with_topology(
         num_domains: usize,
-        topo_group: &fabric_lib::TopologyGroup
-    ) -> Result<fabric_lib::TransferEngine> {
+        topo_group: &fabric_lib::TopologyGroup,
+    ) -> Result<(fabric_lib::TransferEngine, usize)> {
         use fabric_lib::TransferEngineBuilder;
 
         tracing::info!(
@@ -339,7 +1589,12 @@ impl FabricTransport {
         );
 
         let num_domains = num_domains.min(topo_group.domains.len());
-        let domains: Vec<_> = topo_group.domains.iter().take(num_domains).cloned().collect();
+        let domains: Vec<_> = topo_group
+            .domains
+            .iter()
+            .take(num_domains)
+            .cloned()
+            .collect();
 
         if domains.is_empty() {
             return Err(anyhow!("No RDMA domains available"));
@@ -353,15 +1608,12 @@ impl FabricTransport {
         let pin_uvm_cpu = topo_group.cpus[1];
 
         let mut builder = TransferEngineBuilder::default();
-        builder.add_gpu_domains(
-            topo_group.cuda_device,
-            domains,
-            pin_worker_cpu,
-            pin_uvm_cpu,
-        );
+        builder.add_gpu_domains(topo_group.cuda_device, domains, pin_worker_cpu, pin_uvm_cpu);
 
-        builder.build()
-            .map_err(|e| anyhow!("Failed to build TransferEngine: {}", e))
+        let engine = builder
+            .build()
+            .map_err(|e| anyhow!("Failed to build TransferEngine: {}", e))?;
+        Ok((engine, num_domains))
     }
 
     fn build_host_only(num_domains: usize) -> Result<fabric_lib::TransferEngine> {
@@ -369,7 +1621,10 @@ impl FabricTransport {
 
         // Use fabric-lib's new_host_only() method for host memory transfers
         // without requiring GPU-NIC affinity
-        tracing::info!("Building host-only TransferEngine with {} domains", num_domains);
+        tracing::info!(
+            "Building host-only TransferEngine with {} domains",
+            num_domains
+        );
 
         // Get CPUs from NUMA node 0 for pinning
         let cpus = Self::get_numa_cpus(0)?;
@@ -402,13 +1657,16 @@ impl FabricTransport {
         let mut cpus = Vec::new();
         for part in cpulist.trim().split(',') {
             if let Some((start, end)) = part.split_once('-') {
-                let start: u16 = start.parse()
+                let start: u16 = start
+                    .parse()
                     .map_err(|e| anyhow!("Failed to parse CPU range start: {}", e))?;
-                let end: u16 = end.parse()
+                let end: u16 = end
+                    .parse()
                     .map_err(|e| anyhow!("Failed to parse CPU range end: {}", e))?;
                 cpus.extend(start..=end);
             } else {
-                let cpu: u16 = part.parse()
+                let cpu: u16 = part
+                    .parse()
                     .map_err(|e| anyhow!("Failed to parse CPU: {}", e))?;
                 cpus.push(cpu);
             }
@@ -417,21 +1675,26 @@ impl FabricTransport {
         Ok(cpus)
     }
 
-
     /// Convert our MemoryRegionDescriptor to fabric-lib's format
-    fn convert_mr_descriptor(desc: &MemoryRegionDescriptor) -> fabric_lib::api::MemoryRegionDescriptor {
-        use fabric_lib::api::{MemoryRegionDescriptor as FabricMRD, SmallVec};
+    fn convert_mr_descriptor(
+        desc: &MemoryRegionDescriptor,
+    ) -> fabric_lib::api::MemoryRegionDescriptor {
         use bytes::Bytes;
+        use fabric_lib::api::{MemoryRegionDescriptor as FabricMRD, SmallVec};
 
-        let addr_rkey_list: SmallVec<(fabric_lib::api::DomainAddress, fabric_lib::api::MemoryRegionRemoteKey)> =
-            desc.addr_rkey_list.iter()
-                .map(|(addr, rkey)| {
-                    (
-                        fabric_lib::api::DomainAddress(Bytes::copy_from_slice(&addr.0)),
-                        fabric_lib::api::MemoryRegionRemoteKey(rkey.0)
-                    )
-                })
-                .collect();
+        let addr_rkey_list: SmallVec<(
+            fabric_lib::api::DomainAddress,
+            fabric_lib::api::MemoryRegionRemoteKey,
+        )> = desc
+            .addr_rkey_list
+            .iter()
+            .map(|(addr, rkey)| {
+                (
+                    fabric_lib::api::DomainAddress(Bytes::copy_from_slice(&addr.0)),
+                    fabric_lib::api::MemoryRegionRemoteKey(rkey.0),
+                )
+            })
+            .collect();
 
         FabricMRD {
             ptr: desc.ptr,
@@ -442,136 +1705,227 @@ impl FabricTransport {
     /// Convert our MemoryRegionHandle to fabric-lib's format
     fn convert_mr_handle(handle: &MemoryRegionHandle) -> fabric_lib::api::MemoryRegionHandle {
         fabric_lib::api::MemoryRegionHandle::new(
-            NonNull::new(handle.ptr as *mut c_void)
-                .expect("Invalid memory region handle pointer")
+            NonNull::new(handle.ptr as *mut c_void).expect("Invalid memory region handle pointer"),
         )
     }
 
-    /// Convert our DomainRouting to fabric-lib's format
-    fn convert_routing(routing: &DomainRouting) -> fabric_lib::api::DomainGroupRouting {
-        use fabric_lib::api::DomainGroupRouting;
-        use std::num::NonZeroU8;
+    /// The part of `request` one stripe carries, pinned to `domain`
+    fn stripe_request(
+        request: &TransferRequest,
+        domain: usize,
+        offset: u64,
+        length: u64,
+        imm_data: Option<u32>,
+    ) -> fabric_lib::api::TransferRequest {
+        use fabric_lib::api::{
+            DomainGroupRouting, SingleTransferRequest, TransferRequest as FabricTR,
+        };
+
+        FabricTR::Single(SingleTransferRequest {
+            src_mr: Self::convert_mr_handle(&request.src_handle),
+            src_offset: request.src_offset + offset,
+            length,
+            imm_data,
+            dst_mr: Self::convert_mr_descriptor(&request.dst_descriptor),
+            dst_offset: request.dst_offset + offset,
+            domain: DomainGroupRouting::Pinned {
+                domain_idx: domain as u8,
+            },
+        })
+    }
+
+    /// `request`'s stripes; only the last carries its immediate data
+    fn stripes(&self, request: &TransferRequest) -> Vec<(usize, u64, u64, Option<u32>)> {
+        let stripes = stripes(
+            &request.routing,
+            request.length,
+            &self.health,
+            &self.next_domain,
+        );
+        let last = stripes.len() - 1;
+        stripes
+            .into_iter()
+            .enumerate()
+            .map(|(i, (domain, offset, len))| {
+                (domain, offset, len, request.imm_data.filter(|_| i == last))
+            })
+            .collect()
+    }
+
+    /// Write one stripe and record its outcome against `domain`
+    async fn write_stripe(
+        &self,
+        request: &TransferRequest,
+        (domain, offset, length, imm_data): (usize, u64, u64, Option<u32>),
+    ) -> TransferResult {
+        use fabric_lib::AsyncTransferEngine;
 
-        match routing {
-            DomainRouting::RoundRobinSharded { num_shards } => {
-                DomainGroupRouting::RoundRobinSharded {
-                    num_shards: NonZeroU8::new(*num_shards).unwrap_or(NonZeroU8::new(1).unwrap())
+        let stripe = Self::stripe_request(request, domain, offset, length, imm_data);
+        match self.engine.submit_transfer_async(stripe).await {
+            Ok(()) => {
+                self.health.record_success(domain);
+                self.domain_bytes[domain].fetch_add(length, Ordering::Relaxed);
+                TransferResult {
+                    success: true,
+                    bytes_transferred: length,
+                    error: None,
                 }
             }
-            DomainRouting::Pinned { domain_idx } => {
-                DomainGroupRouting::Pinned { domain_idx: *domain_idx }
+            Err(e) => {
+                self.health.record_failure(domain);
+                TransferResult {
+                    success: false,
+                    bytes_transferred: 0,
+                    error: Some(format!("Transfer on domain {} failed: {}", domain, e)),
+                }
             }
         }
     }
-}
 
-#[cfg(feature = "rdma")]
-impl RdmaTransportTrait for FabricTransport {
-    fn domain_addresses(&self) -> Vec<DomainAddress> {
-        self.domain_addresses.clone()
+    /// Register `len` bytes at `ptr` on `device` with the engine
+    fn register(
+        &self,
+        ptr: *mut u8,
+        len: usize,
+        device: cuda_lib::Device,
+    ) -> Result<(MemoryRegionHandle, MemoryRegionDescriptor)> {
+        Self::register_on(&self.engine, ptr, len, device)
     }
 
-    fn register_memory(
-        &self,
+    fn register_on(
+        engine: &fabric_lib::TransferEngine,
         ptr: *mut u8,
         len: usize,
+        device: cuda_lib::Device,
     ) -> Result<(MemoryRegionHandle, MemoryRegionDescriptor)> {
         use fabric_lib::RdmaEngine;
-        use cuda_lib::Device;
 
-        let ptr_nonnull = NonNull::new(ptr as *mut c_void)
-            .ok_or_else(|| anyhow!("Invalid memory pointer"))?;
+        let ptr_nonnull =
+            NonNull::new(ptr as *mut c_void).ok_or_else(|| anyhow!("Invalid memory pointer"))?;
 
-        // Register memory with fabric-lib (Host memory, not GPU)
-        let (fabric_handle, fabric_descriptor) = self.engine
-            .register_memory_allow_remote(ptr_nonnull, len, Device::Host)
+        let (fabric_handle, fabric_descriptor) = engine
+            .register_memory_allow_remote(ptr_nonnull, len, device)
             .map_err(|e| anyhow!("Failed to register memory: {}", e))?;
 
         // Convert fabric-lib handle to our handle
         let handle = MemoryRegionHandle::new(fabric_handle.ptr.as_ptr() as u64, len);
 
         // Convert fabric-lib descriptor to our descriptor
-        let addr_rkey_list: Vec<_> = fabric_descriptor.addr_rkey_list.iter()
+        let addr_rkey_list: Vec<_> = fabric_descriptor
+            .addr_rkey_list
+            .iter()
             .map(|(addr, rkey)| {
                 (
                     DomainAddress(addr.0.to_vec()),
-                    crate::protocol::MemoryRegionRemoteKey(rkey.0)
+                    crate::protocol::MemoryRegionRemoteKey(rkey.0),
                 )
             })
             .collect();
 
-        let descriptor = MemoryRegionDescriptor::new(
-            fabric_descriptor.ptr,
-            addr_rkey_list
-        );
+        let descriptor = MemoryRegionDescriptor::new(fabric_descriptor.ptr, addr_rkey_list);
 
         Ok((handle, descriptor))
     }
+}
 
-    fn submit_transfer(&self, request: TransferRequest) -> Result<()> {
-        use fabric_lib::api::{TransferRequest as FabricTR, SingleTransferRequest};
-
-        let src_mr = Self::convert_mr_handle(&request.src_handle);
-        let dst_mr = Self::convert_mr_descriptor(&request.dst_descriptor);
-        let domain = Self::convert_routing(&request.routing);
-
-        let fabric_request = FabricTR::Single(SingleTransferRequest {
-            src_mr,
-            src_offset: request.src_offset,
-            length: request.length,
-            imm_data: request.imm_data,
-            dst_mr,
-            dst_offset: request.dst_offset,
-            domain,
-        });
+#[cfg(feature = "rdma")]
+impl RdmaTransportTrait for FabricTransport {
+    fn domain_addresses(&self) -> Vec<DomainAddress> {
+        self.domain_addresses.clone()
+    }
 
-        let callback = fabric_lib::TransferCallback {
-            on_done: Box::new(|| Ok(())),
-            on_error: Box::new(|e| {
-                tracing::error!("Transfer error: {}", e);
-                Err(format!("Transfer error: {}", e))
-            }),
+    fn register_memory(
+        &self,
+        ptr: *mut u8,
+        len: usize,
+    ) -> Result<(MemoryRegionHandle, MemoryRegionDescriptor)> {
+        self.register(ptr, len, cuda_lib::Device::Host)
+    }
+
+    fn register_device_memory(
+        &self,
+        ptr: *mut u8,
+        len: usize,
+        device_id: u32,
+    ) -> Result<(MemoryRegionHandle, MemoryRegionDescriptor)> {
+        let device_id =
+            u8::try_from(device_id).map_err(|_| anyhow!("Invalid CUDA device id {}", device_id))?;
+        self.register(
+            ptr,
+            len,
+            cuda_lib::Device::Cuda(cuda_lib::CudaDeviceId(device_id)),
+        )
+    }
+
+    fn submit_transfer(&self, request: TransferRequest) -> Result<()> {
+        // Stripes complete in no particular order, so one carrying immediate
+        // data could be noticed before the rest have landed
+        let stripes = match request.imm_data {
+            Some(_) => {
+                let (domain, ..) = self.stripes(&request)[0];
+                vec![(domain, 0, request.length, request.imm_data)]
+            }
+            None => self.stripes(&request),
         };
 
-        self.engine.submit_transfer(fabric_request, callback)
-            .map_err(|e| anyhow!("Failed to submit transfer: {}", e))
+        for (domain, offset, length, imm_data) in stripes {
+            let stripe = Self::stripe_request(&request, domain, offset, length, imm_data);
+            let (health, bytes) = (self.health.clone(), self.domain_bytes.clone());
+            let failed = self.health.clone();
+            let callback = fabric_lib::TransferCallback {
+                on_done: Box::new(move || {
+                    health.record_success(domain);
+                    bytes[domain].fetch_add(length, Ordering::Relaxed);
+                    Ok(())
+                }),
+                on_error: Box::new(move |e| {
+                    tracing::error!("Transfer error on domain {}: {}", domain, e);
+                    failed.record_failure(domain);
+                    Err(format!("Transfer error: {}", e))
+                }),
+            };
+
+            if let Err(e) = self.engine.submit_transfer(stripe, callback) {
+                self.health.record_failure(domain);
+                return Err(anyhow!(
+                    "Failed to submit transfer on domain {}: {}",
+                    domain,
+                    e
+                ));
+            }
+        }
+        Ok(())
     }
 
     fn submit_transfer_async(
         &self,
         request: TransferRequest,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<TransferResult>> + Send + '_>> {
-        use fabric_lib::api::{TransferRequest as FabricTR, SingleTransferRequest};
-        use fabric_lib::AsyncTransferEngine;
-
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<TransferResult>> + Send + '_>>
+    {
         Box::pin(async move {
-            let src_mr = Self::convert_mr_handle(&request.src_handle);
-            let dst_mr = Self::convert_mr_descriptor(&request.dst_descriptor);
-            let domain = Self::convert_routing(&request.routing);
-            let length = request.length;
-
-            let fabric_request = FabricTR::Single(SingleTransferRequest {
-                src_mr,
-                src_offset: request.src_offset,
-                length: request.length,
-                imm_data: request.imm_data,
-                dst_mr,
-                dst_offset: request.dst_offset,
-                domain,
-            });
-
-            match self.engine.submit_transfer_async(fabric_request).await {
-                Ok(()) => Ok(TransferResult {
-                    success: true,
-                    bytes_transferred: length,
-                    error: None,
-                }),
-                Err(e) => Ok(TransferResult {
-                    success: false,
-                    bytes_transferred: 0,
-                    error: Some(format!("{}", e)),
-                }),
+            let mut stripes = self.stripes(&request);
+            // As with chunks, the stripe carrying the immediate data waits for the rest
+            let last = match stripes.last() {
+                Some(stripe) if stripe.3.is_some() => stripes.pop(),
+                _ => None,
+            };
+            let mut results = futures::future::join_all(
+                stripes
+                    .into_iter()
+                    .map(|stripe| self.write_stripe(&request, stripe)),
+            )
+            .await;
+            if let Some(last) = last {
+                if results.iter().all(|result| result.success) {
+                    results.push(self.write_stripe(&request, last).await);
+                }
             }
+
+            Ok(TransferResult {
+                success: results.iter().all(|result| result.success),
+                bytes_transferred: results.iter().map(|result| result.bytes_transferred).sum(),
+                error: results.into_iter().find_map(|result| result.error),
+            })
         })
     }
 
@@ -579,6 +1933,86 @@ impl RdmaTransportTrait for FabricTransport {
         // fabric-lib handles completions internally via callbacks
         None
     }
+
+    fn deregister_memory(&self, handle: &MemoryRegionHandle) -> Result<()> {
+        use fabric_lib::RdmaEngine;
+
+        let ptr = NonNull::new(handle.ptr as *mut c_void)
+            .ok_or_else(|| anyhow!("Invalid memory region handle pointer"))?;
+        self.engine.unregister_memory(ptr).map_err(|e| {
+            anyhow!(
+                "Failed to deregister {} bytes at {:#x}: {}",
+                handle.len,
+                handle.ptr,
+                e
+            )
+        })
+    }
+
+    fn domain_bytes_transferred(&self) -> Vec<u64> {
+        self.domain_bytes
+            .iter()
+            .map(|bytes| bytes.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// Write a fresh pattern through `domain_idx` from one half of the probe
+    /// buffer to the other and check it arrived
+    fn probe_domain(&self, domain_idx: usize) -> Result<()> {
+        let mut probe = self.probe.lock();
+        probe.round = probe.round.wrapping_add(1);
+        let pattern = probe.round ^ domain_idx as u8 ^ 0xa5;
+        probe.buf[..PROBE_LEN].fill(pattern);
+        probe.buf[PROBE_LEN..].fill(!pattern);
+
+        let request = TransferRequest {
+            src_handle: probe.handle,
+            src_offset: 0,
+            length: PROBE_LEN as u64,
+            imm_data: None,
+            dst_descriptor: probe.descriptor.clone(),
+            dst_offset: PROBE_LEN as u64,
+            routing: DomainRouting::Pinned {
+                domain_idx: domain_idx as u8,
+            },
+        };
+        let stripe = Self::stripe_request(&request, domain_idx, 0, PROBE_LEN as u64, None);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let failed = tx.clone();
+        let callback = fabric_lib::TransferCallback {
+            on_done: Box::new(move || {
+                let _ = tx.send(Ok(()));
+                Ok(())
+            }),
+            on_error: Box::new(move |e| {
+                let _ = failed.send(Err(e.to_string()));
+                Err(format!("Probe error: {}", e))
+            }),
+        };
+        self.engine
+            .submit_transfer(stripe, callback)
+            .map_err(|e| anyhow!("Failed to submit probe of domain {}: {}", domain_idx, e))?;
+
+        match rx.recv_timeout(PROBE_TIMEOUT) {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return Err(anyhow!("Probe of domain {} failed: {}", domain_idx, e)),
+            Err(_) => {
+                // The write may still land; the next probe starts from a new pattern
+                return Err(anyhow!(
+                    "Probe of domain {} timed out after {:?}",
+                    domain_idx,
+                    PROBE_TIMEOUT
+                ));
+            }
+        }
+        if probe.buf[PROBE_LEN..].iter().any(|&byte| byte != pattern) {
+            return Err(anyhow!(
+                "Probe of domain {} completed but delivered wrong data",
+                domain_idx
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -591,6 +2025,7 @@ mod tests {
             node_id: 1,
             num_domains: 2,
             use_mock: true,
+            ..Default::default()
         };
         let transport = RdmaTransport::new(config).unwrap();
 
@@ -599,11 +2034,15 @@ mod tests {
         assert_eq!(addrs.len(), 2);
 
         // Allocate source and destination buffers
-        let src_data = vec![1u8, 2, 3, 4, 5];
+        let mut src_data = vec![1u8, 2, 3, 4, 5];
         let mut dst_data = vec![0u8; 5];
 
-        let src_handle = MemoryRegionHandle::new(src_data.as_ptr() as u64, src_data.len());
-        let dst_descriptor = MemoryRegionDescriptor::new(dst_data.as_mut_ptr() as u64, vec![]);
+        let (src_handle, _) = transport
+            .register_memory(src_data.as_mut_ptr(), src_data.len())
+            .unwrap();
+        let (_, dst_descriptor) = transport
+            .register_memory(dst_data.as_mut_ptr(), dst_data.len())
+            .unwrap();
 
         let request = TransferRequest {
             src_handle,
@@ -622,4 +2061,659 @@ mod tests {
         // Verify data was copied
         assert_eq!(dst_data, src_data);
     }
+
+    #[tokio::test]
+    async fn test_self_test_verifies_every_domain() {
+        let transport = RdmaTransport::new(TransportConfig {
+            num_domains: 2,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let results = transport.self_test().await.unwrap();
+        assert_eq!(results.len(), 2 * SELF_TEST_SIZES.len());
+        for domain_idx in 0..2 {
+            let sizes: Vec<_> = results
+                .iter()
+                .filter(|result| result.domain_idx == domain_idx)
+                .map(|result| result.size)
+                .collect();
+            assert_eq!(sizes, SELF_TEST_SIZES);
+        }
+        assert!(
+            results.iter().all(|result| result.bandwidth() > 0.0),
+            "{:?}",
+            results
+        );
+        assert_eq!(
+            transport
+                .domain_bytes_transferred()
+                .iter()
+                .filter(|&&bytes| bytes > 0)
+                .count(),
+            2
+        );
+        assert_eq!(transport.registration_count(), 0);
+    }
+
+    #[test]
+    fn test_registrations_beyond_cap_fail_with_remediation() {
+        let transport = RdmaTransport::new(TransportConfig {
+            max_registrations: 2,
+            max_registered_bytes: 4096,
+            ..Default::default()
+        })
+        .unwrap();
+        let mut buffers = vec![vec![0u8; 1024]; 3];
+
+        let err = transport
+            .register_memory(buffers[0].as_mut_ptr(), 8192)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("max_registered_bytes = 4096"), "{}", err);
+
+        for buf in &mut buffers[..2] {
+            transport
+                .register_memory(buf.as_mut_ptr(), buf.len())
+                .unwrap();
+        }
+        assert_eq!(transport.registration_count(), 2);
+        assert_eq!(transport.registered_bytes(), 2048);
+
+        let err = transport
+            .register_memory(buffers[2].as_mut_ptr(), buffers[2].len())
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("already has 2 registrations (max_registrations = 2)"),
+            "{}",
+            err
+        );
+        assert!(err.contains("raise max_registrations"), "{}", err);
+        assert_eq!(transport.registration_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_custom_router_picks_domain_per_transfer() {
+        let transport = RdmaTransport::new(TransportConfig {
+            num_domains: 2,
+            ..Default::default()
+        })
+        .unwrap();
+        let seen_load = Arc::new(Mutex::new(Vec::new()));
+        let router_load = Arc::clone(&seen_load);
+        transport.set_router(Some(Arc::new(
+            move |request: &TransferRequest, domain_bytes: &[u64]| {
+                router_load.lock().push(domain_bytes.to_vec());
+                (request.length % 2) as u8
+            },
+        )));
+
+        let mut src = vec![3u8; 64];
+        let mut dst = vec![0u8; 64];
+        let (src_handle, _) = transport
+            .register_memory(src.as_mut_ptr(), src.len())
+            .unwrap();
+        let (_, dst_descriptor) = transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+
+        // Even lengths 10 + 20, odd 7 + 5
+        for length in [10, 7, 20, 5] {
+            let result = transport
+                .submit_transfer_async(TransferRequest {
+                    src_handle,
+                    src_offset: 0,
+                    length,
+                    imm_data: None,
+                    dst_descriptor: dst_descriptor.clone(),
+                    dst_offset: 0,
+                    routing: DomainRouting::RoundRobinSharded { num_shards: 2 },
+                })
+                .await
+                .unwrap();
+            assert!(result.success);
+        }
+
+        assert_eq!(transport.domain_bytes_transferred(), vec![30, 12]);
+        assert_eq!(seen_load.lock()[3], vec![30, 7]);
+
+        transport.set_router(None);
+        let result = transport
+            .submit_transfer_async(TransferRequest {
+                src_handle,
+                src_offset: 0,
+                length: 2,
+                imm_data: None,
+                dst_descriptor,
+                dst_offset: 0,
+                routing: DomainRouting::Pinned { domain_idx: 1 },
+            })
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(transport.domain_bytes_transferred(), vec![30, 14]);
+    }
+
+    #[tokio::test]
+    async fn test_failing_domain_is_avoided_then_recovered() {
+        let config = TransportConfig {
+            num_domains: 2,
+            domain_failure_threshold: 2,
+            mock_failing_domains: vec![1],
+            ..Default::default()
+        };
+        let health = Arc::new(DomainHealth::new(2, 2));
+        let mock = Arc::new(MockTransport::new(config.clone(), health.clone()));
+        let transport = RdmaTransport::with_backend(mock.clone(), config, health);
+
+        let mut src = vec![5u8; 64];
+        let mut dst = vec![0u8; 64];
+        let (src_handle, _) = transport
+            .register_memory(src.as_mut_ptr(), src.len())
+            .unwrap();
+        let (_, dst_descriptor) = transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+        let request = TransferRequest {
+            src_handle,
+            src_offset: 0,
+            length: 8,
+            imm_data: None,
+            dst_descriptor,
+            dst_offset: 0,
+            routing: DomainRouting::RoundRobinSharded { num_shards: 1 },
+        };
+
+        // Rotation alternates domains until the second failure on domain 1
+        let mut failed = 0;
+        for _ in 0..4 {
+            if transport
+                .submit_transfer_async(request.clone())
+                .await
+                .is_err()
+            {
+                failed += 1;
+            }
+        }
+        assert_eq!(failed, 2);
+        let stats = transport.domain_stats();
+        assert!(stats[0].healthy);
+        assert!(!stats[1].healthy);
+        assert_eq!(stats[1].failures, 2);
+
+        // Now every transfer goes to domain 0, and probing a dead domain fails
+        for _ in 0..4 {
+            transport
+                .submit_transfer_async(request.clone())
+                .await
+                .unwrap();
+        }
+        assert_eq!(transport.domain_bytes_transferred(), vec![48, 0]);
+        assert_eq!(transport.probe_domains(), 0);
+
+        mock.failing_domains[1].store(false, Ordering::Relaxed);
+        assert_eq!(transport.probe_domains(), 1);
+        assert!(transport.domain_stats()[1].healthy);
+        for _ in 0..2 {
+            transport
+                .submit_transfer_async(request.clone())
+                .await
+                .unwrap();
+        }
+        assert_eq!(transport.domain_bytes_transferred(), vec![56, 8]);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_stream_yields_every_result() {
+        use futures::StreamExt;
+
+        let transport = RdmaTransport::new(TransportConfig::default()).unwrap();
+        let mut src = vec![7u8; 20 * 16];
+        let mut dst = vec![0u8; 20 * 16];
+        let (src_handle, _) = transport
+            .register_memory(src.as_mut_ptr(), src.len())
+            .unwrap();
+        let (_, dst_descriptor) = transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+
+        let requests = (0..20)
+            .map(|i| TransferRequest {
+                src_handle,
+                src_offset: i * 16,
+                length: 16,
+                imm_data: None,
+                dst_descriptor: dst_descriptor.clone(),
+                dst_offset: i * 16,
+                routing: DomainRouting::default(),
+            })
+            .collect();
+
+        let results: Vec<_> = transport.submit_transfer_stream(requests).collect().await;
+        assert_eq!(results.len(), 20);
+        assert!(results
+            .iter()
+            .all(|result| result.success && result.bytes_transferred == 16));
+        assert_eq!(dst, src);
+    }
+
+    #[tokio::test]
+    async fn test_large_transfer_is_split_into_chunks() {
+        const SIZE: usize = 4 * 1024 * 1024;
+        const CHUNK: usize = 256 * 1024;
+
+        let transport = RdmaTransport::new(TransportConfig {
+            max_chunk_size: CHUNK,
+            ..Default::default()
+        })
+        .unwrap();
+        let mut src: Vec<u8> = (0..SIZE).map(|i| (i % 251) as u8).collect();
+        let mut dst = vec![0u8; SIZE];
+        let (src_handle, _) = transport
+            .register_memory(src.as_mut_ptr(), src.len())
+            .unwrap();
+        let (_, dst_descriptor) = transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+
+        let result = transport
+            .submit_transfer_async(TransferRequest {
+                src_handle,
+                src_offset: 0,
+                length: SIZE as u64,
+                imm_data: Some(42),
+                dst_descriptor,
+                dst_offset: 0,
+                routing: DomainRouting::default(),
+            })
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.bytes_transferred, SIZE as u64);
+        assert_eq!(transport.chunk_transfers(), (SIZE / CHUNK) as u64);
+        assert!(dst == src);
+    }
+
+    #[test]
+    #[cfg_attr(
+        debug_assertions,
+        should_panic(expected = "outside registered regions")
+    )]
+    fn test_mock_validation_rejects_out_of_bounds_dst() {
+        let config = TransportConfig {
+            validate_regions: true,
+            ..Default::default()
+        };
+        let transport = RdmaTransport::new(config).unwrap();
+
+        let mut src_data = vec![7u8; 64];
+        let mut dst_data = vec![0u8; 64];
+        let (src_handle, _) = transport
+            .register_memory(src_data.as_mut_ptr(), src_data.len())
+            .unwrap();
+        let (_, dst_descriptor) = transport
+            .register_memory(dst_data.as_mut_ptr(), dst_data.len())
+            .unwrap();
+
+        // dst_offset pushes the write past the end of the registered region
+        let request = TransferRequest {
+            src_handle,
+            src_offset: 0,
+            length: 32,
+            imm_data: None,
+            dst_descriptor,
+            dst_offset: 48,
+            routing: DomainRouting::default(),
+        };
+
+        let result = transport.submit_transfer(request);
+        assert!(result.is_err());
+        assert_eq!(dst_data, vec![0u8; 64]);
+    }
+
+    #[tokio::test]
+    async fn test_completion_handle_delivers_result_when_awaited_later() {
+        let config = TransportConfig {
+            mock_transfer_delay: Duration::from_millis(20),
+            ..Default::default()
+        };
+        let transport = RdmaTransport::new(config).unwrap();
+
+        let mut src_data: Vec<u8> = (0..128).collect();
+        let mut dst_data = vec![0u8; 128];
+        let (src_handle, _) = transport
+            .register_memory(src_data.as_mut_ptr(), src_data.len())
+            .unwrap();
+        let (_, dst_descriptor) = transport
+            .register_memory(dst_data.as_mut_ptr(), dst_data.len())
+            .unwrap();
+
+        let request = TransferRequest {
+            src_handle,
+            src_offset: 0,
+            length: 128,
+            imm_data: Some(42),
+            dst_descriptor,
+            dst_offset: 0,
+            routing: DomainRouting::default(),
+        };
+
+        // Submission returns before the (delayed) copy happens
+        let completion = transport.submit_with_handle(request).unwrap();
+        assert_eq!(completion.imm_data(), Some(42));
+        assert_eq!(dst_data, vec![0u8; 128]);
+
+        let result = completion.await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.bytes_transferred, 128);
+        assert_eq!(dst_data, src_data);
+    }
+
+    #[test]
+    fn test_mock_rejects_transfer_longer_than_source() {
+        let transport = RdmaTransport::new(TransportConfig::default()).unwrap();
+
+        let mut src_data = vec![7u8; 16];
+        let mut dst_data = vec![0u8; 64];
+        let (src_handle, _) = transport
+            .register_memory(src_data.as_mut_ptr(), src_data.len())
+            .unwrap();
+        let (_, dst_descriptor) = transport
+            .register_memory(dst_data.as_mut_ptr(), dst_data.len())
+            .unwrap();
+
+        let request = TransferRequest {
+            src_handle,
+            src_offset: 0,
+            length: 32,
+            imm_data: None,
+            dst_descriptor,
+            dst_offset: 0,
+            routing: DomainRouting::default(),
+        };
+
+        let err = transport.submit_transfer(request).unwrap_err().to_string();
+        assert!(err.contains("exceeds the 16-byte source region"), "{}", err);
+        assert_eq!(dst_data, vec![0u8; 64]);
+    }
+
+    /// Needs a CUDA GPU and an RDMA NIC that can write to its own node (EFA
+    /// can't; see fabric-lib-host-only-implementation.md)
+    #[cfg(feature = "cuda")]
+    #[tokio::test]
+    async fn test_transfer_from_device_pool() {
+        // With loopback bypass, same-node transfers from device memory still
+        // go through the NIC rather than failing
+        for loopback_bypass in [false, true] {
+            transfer_from_device_pool(loopback_bypass).await;
+        }
+    }
+
+    #[cfg(feature = "cuda")]
+    async fn transfer_from_device_pool(loopback_bypass: bool) {
+        use crate::memory::{MemoryPool, MemoryPoolConfig};
+
+        let config = TransportConfig {
+            use_mock: false,
+            loopback_bypass,
+            ..Default::default()
+        };
+        let server_transport = Arc::new(RdmaTransport::new(config.clone()).unwrap());
+        let client_transport = Arc::new(RdmaTransport::new(config).unwrap());
+
+        let pool_config = MemoryPoolConfig {
+            size: 1024 * 1024,
+            device: ValueDevice::Cuda { device_id: 0 },
+            ..Default::default()
+        };
+        let pool = MemoryPool::new(pool_config, 0, Some(&server_transport)).unwrap();
+        let src = pool.allocate(4096).unwrap();
+        let value: Vec<u8> = (0..4096).map(|i| i as u8).collect();
+        pool.write_allocation(&src, &value).unwrap();
+        assert_eq!(pool.read_chunks(std::slice::from_ref(&src)).unwrap(), value);
+        assert!(pool.read(src.offset, value.len()).is_err());
+
+        let mut dst = vec![0u8; value.len()];
+        let (dst_handle, dst_descriptor) = client_transport
+            .register_memory(dst.as_mut_ptr(), dst.len())
+            .unwrap();
+        let transfer = server_transport.submit_transfer_async(TransferRequest {
+            src_handle: pool.handle(),
+            src_offset: src.offset as u64,
+            length: value.len() as u64,
+            imm_data: None,
+            dst_descriptor,
+            dst_offset: 0,
+            routing: DomainRouting::default(),
+        });
+        let result = tokio::time::timeout(Duration::from_secs(10), transfer)
+            .await
+            .expect("transfer from device memory never completed")
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.bytes_transferred, value.len() as u64);
+        assert_eq!(dst, value);
+        assert_eq!(server_transport.loopback_transfers(), 0);
+        client_transport.deregister_memory(&dst_handle).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_loopback_bypass_between_same_node_pools() {
+        use crate::memory::{MemoryPool, MemoryPoolConfig};
+
+        let config = TransportConfig {
+            node_id: 7,
+            loopback_bypass: true,
+            mock_transfer_delay: Duration::from_secs(3600),
+            ..Default::default()
+        };
+        let server_transport = Arc::new(RdmaTransport::new(config.clone()).unwrap());
+        let client_transport = Arc::new(RdmaTransport::new(config).unwrap());
+
+        let pool_config = MemoryPoolConfig {
+            size: 64 * 1024,
+            ..Default::default()
+        };
+        let mut src_pool =
+            MemoryPool::new(pool_config.clone(), 7, Some(&server_transport)).unwrap();
+        let dst_pool = MemoryPool::new(pool_config, 7, Some(&client_transport)).unwrap();
+
+        let src = src_pool.allocate(16).unwrap();
+        let dst = dst_pool.allocate(16).unwrap();
+        src_pool.write(src.offset, b"loopback payload").unwrap();
+
+        let request = TransferRequest {
+            src_handle: src_pool.handle(),
+            src_offset: src.offset as u64,
+            length: 16,
+            imm_data: None,
+            dst_descriptor: dst_pool.descriptor().clone(),
+            dst_offset: dst.offset as u64,
+            routing: DomainRouting::default(),
+        };
+
+        // The hour-long mock delay means only the bypass can finish in time
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            server_transport.submit_transfer_async(request),
+        )
+        .await
+        .expect("transfer did not take the loopback path")
+        .unwrap();
+        assert!(result.success);
+        assert_eq!(server_transport.loopback_transfers(), 1);
+        assert_eq!(dst_pool.read(dst.offset, 16).unwrap(), b"loopback payload");
+    }
+
+    #[test]
+    fn test_loopback_needs_an_exactly_matching_registration() {
+        let config = TransportConfig {
+            node_id: 7,
+            loopback_bypass: true,
+            ..Default::default()
+        };
+        let transport = RdmaTransport::new(config).unwrap();
+
+        let mut src_data = vec![7u8; 64];
+        let mut dst_data = vec![0u8; 256];
+        let (src_handle, _) = transport
+            .register_memory(src_data.as_mut_ptr(), src_data.len())
+            .unwrap();
+        let (_, dst_descriptor) = transport
+            .register_memory(dst_data.as_mut_ptr(), dst_data.len())
+            .unwrap();
+        let request = |dst_descriptor| TransferRequest {
+            src_handle,
+            src_offset: 0,
+            length: 32,
+            imm_data: None,
+            dst_descriptor,
+            dst_offset: 0,
+            routing: DomainRouting::default(),
+        };
+
+        // A remote region whose address happens to land inside a local one
+        let inside = MemoryRegionDescriptor {
+            ptr: dst_descriptor.ptr + 64,
+            ..dst_descriptor.clone()
+        };
+        assert!(!transport.try_loopback(&request(inside)).unwrap());
+
+        // The local base address, but another registration's rkeys
+        let mut foreign = dst_descriptor.clone();
+        for (_, rkey) in &mut foreign.addr_rkey_list {
+            rkey.0 ^= 0xffff;
+        }
+        assert!(!transport.try_loopback(&request(foreign)).unwrap());
+        assert_eq!(dst_data, vec![0u8; 256]);
+
+        assert!(transport.try_loopback(&request(dst_descriptor)).unwrap());
+        assert_eq!(dst_data[..32], [7u8; 32]);
+        assert_eq!(transport.loopback_transfers(), 1);
+    }
+
+    #[test]
+    fn test_loopback_leaves_unlisted_sources_to_the_nic() {
+        let config = TransportConfig {
+            node_id: 7,
+            loopback_bypass: true,
+            ..Default::default()
+        };
+        let transport = RdmaTransport::new(config).unwrap();
+
+        let mut dst_data = vec![0u8; 64];
+        let (_, dst_descriptor) = transport
+            .register_memory(dst_data.as_mut_ptr(), dst_data.len())
+            .unwrap();
+        // Stands in for a device pool, which is registered but kept out of
+        // LOCAL_REGIONS since host memcpy can't reach it
+        let src_data = vec![7u8; 64];
+        let request = TransferRequest {
+            src_handle: MemoryRegionHandle::new(src_data.as_ptr() as u64, src_data.len()),
+            src_offset: 0,
+            length: 32,
+            imm_data: None,
+            dst_descriptor,
+            dst_offset: 0,
+            routing: DomainRouting::default(),
+        };
+
+        assert!(!transport.try_loopback(&request).unwrap());
+        assert_eq!(dst_data, vec![0u8; 64]);
+        assert_eq!(transport.loopback_transfers(), 0);
+    }
+
+    #[test]
+    fn test_transfer_to_empty_descriptor_is_rejected() {
+        let config = TransportConfig {
+            validate_regions: true,
+            ..Default::default()
+        };
+        let transport = RdmaTransport::new(config).unwrap();
+
+        let mut src_data = vec![7u8; 64];
+        let mut dst_data = vec![0u8; 64];
+        let (src_handle, _) = transport
+            .register_memory(src_data.as_mut_ptr(), src_data.len())
+            .unwrap();
+        transport
+            .register_memory(dst_data.as_mut_ptr(), dst_data.len())
+            .unwrap();
+
+        // Right pointer, but a placeholder descriptor without any domain addresses
+        let request = TransferRequest {
+            src_handle,
+            src_offset: 0,
+            length: 32,
+            imm_data: None,
+            dst_descriptor: MemoryRegionDescriptor::new(dst_data.as_mut_ptr() as u64, vec![]),
+            dst_offset: 0,
+            routing: DomainRouting::default(),
+        };
+
+        let err = transport.submit_transfer(request).unwrap_err().to_string();
+        assert!(err.contains("no (domain address, rkey) pairs"), "{}", err);
+        assert_eq!(dst_data, vec![0u8; 64]);
+    }
+
+    #[cfg(not(feature = "rdma"))]
+    #[test]
+    fn test_fallback_to_mock_without_rdma_feature() {
+        let strict = TransportConfig {
+            use_mock: false,
+            ..Default::default()
+        };
+        assert!(RdmaTransport::new(strict.clone()).is_err());
+
+        let transport = RdmaTransport::new(TransportConfig {
+            fallback_to_mock: true,
+            ..strict
+        })
+        .unwrap();
+        assert!(transport.is_mock());
+        assert_eq!(transport.domain_addresses().len(), 1);
+    }
+
+    #[test]
+    fn test_transport_config_and_routing_round_trip_through_serde() {
+        let config = TransportConfig {
+            node_id: 7,
+            num_domains: 4,
+            use_mock: false,
+            mock_transfer_delay: Duration::from_millis(3),
+            loopback_bypass: true,
+            ..Default::default()
+        };
+        let text = toml::to_string(&config).unwrap();
+        let decoded: TransportConfig = toml::from_str(&text).unwrap();
+        assert_eq!(decoded.node_id, 7);
+        assert_eq!(decoded.num_domains, 4);
+        assert!(!decoded.use_mock);
+        assert_eq!(decoded.mock_transfer_delay, Duration::from_millis(3));
+        assert!(decoded.loopback_bypass);
+
+        for routing in [
+            DomainRouting::RoundRobinSharded { num_shards: 3 },
+            DomainRouting::Pinned { domain_idx: 2 },
+        ] {
+            let json = serde_json::to_string(&routing).unwrap();
+            assert_eq!(
+                serde_json::from_str::<DomainRouting>(&json).unwrap(),
+                routing
+            );
+        }
+        assert_eq!(
+            serde_json::from_str::<DomainRouting>(r#"{"mode":"pinned","domain_idx":1}"#).unwrap(),
+            DomainRouting::Pinned { domain_idx: 1 }
+        );
+
+        let err = serde_json::from_str::<DomainRouting>(
+            r#"{"mode":"round_robin_sharded","num_shards":0}"#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("num_shards must be at least 1"), "{}", err);
+    }
 }
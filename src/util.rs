@@ -0,0 +1,84 @@
+//! Small helpers shared by the binaries
+
+use anyhow::{anyhow, bail, Result};
+
+/// Parse a byte size such as "512", "1000KB" or "1.5MiB"
+///
+/// KB/MB/GB/TB are decimal (powers of 1000) and KiB/MiB/GiB/TiB binary
+/// (powers of 1024); a bare number or a "B" suffix means bytes. Units are
+/// case-insensitive and may be separated from the number by spaces.
+/// Fractional values are allowed as long as they come to a whole number of
+/// bytes.
+pub fn parse_size(s: &str) -> Result<usize> {
+    let trimmed = s.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000 * 1000,
+        "gb" => 1000 * 1000 * 1000,
+        "tb" => 1000 * 1000 * 1000 * 1000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        other => bail!(
+            "Invalid size {:?}: unknown unit {:?} (expected B, KB, MB, GB, TB, KiB, MiB, GiB or TiB)",
+            s,
+            other
+        ),
+    };
+
+    if number.is_empty() {
+        bail!(
+            "Invalid size {:?}: expected a number, e.g. 512, 64KiB or 1.5MB",
+            s
+        );
+    }
+
+    let bytes = match number.parse::<u64>() {
+        Ok(whole) => whole
+            .checked_mul(multiplier)
+            .ok_or_else(|| anyhow!("Invalid size {:?}: too large", s))?,
+        Err(_) => {
+            let value: f64 = number
+                .parse()
+                .map_err(|_| anyhow!("Invalid size {:?}: {:?} is not a number", s, number))?;
+            let bytes = value * multiplier as f64;
+            if bytes.fract() != 0.0 {
+                bail!("Invalid size {:?}: not a whole number of bytes", s);
+            }
+            if bytes >= u64::MAX as f64 {
+                bail!("Invalid size {:?}: too large", s);
+            }
+            bytes as u64
+        }
+    };
+
+    usize::try_from(bytes).map_err(|_| anyhow!("Invalid size {:?}: too large", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("1.5MiB").unwrap(), 1536 * 1024);
+        assert_eq!(parse_size("1000KB").unwrap(), 1_000_000);
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("1.5MB").unwrap(), 1_500_000);
+        assert_eq!(parse_size("64 kib").unwrap(), 64 * 1024);
+        assert_eq!(parse_size("16B").unwrap(), 16);
+
+        let err = parse_size("abc").unwrap_err().to_string();
+        assert!(err.contains("\"abc\""), "{}", err);
+        assert!(parse_size("1.5").is_err());
+        assert!(parse_size("1.2.3MB").is_err());
+        assert!(parse_size("10XB").is_err());
+    }
+}
@@ -0,0 +1,319 @@
+//! Write-ahead log for PUT/DELETE durability
+//!
+//! Records are appended as a little-endian `u32` length followed by a bincode
+//! payload. Appends are buffered; `flush` makes everything appended so far
+//! durable. On startup the server replays the log to rebuild its cache. A torn
+//! record at the tail (a crash mid-append) ends replay rather than failing it,
+//! and is cut off when the log is reopened so new records follow the last
+//! complete one.
+//!
+//! `compact` bounds the log: it rewrites it as a snapshot of the live entries
+//! followed by whatever was appended while the snapshot was taken, then swaps
+//! the new file in. Replaying that gives the same state as the full history.
+
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, ErrorKind, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// A logged mutation, as read back by `replay`
+///
+/// Variants are encoded by position, so new ones go at the end.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum WalRecord {
+    /// A PUT logged by an older server, with its TTL in whole seconds
+    Put {
+        key: Vec<u8>,
+        value: Vec<u8>,
+        ttl_seconds: u64,
+    },
+    Delete {
+        key: Vec<u8>,
+    },
+    PutMillis {
+        key: Vec<u8>,
+        value: Vec<u8>,
+        ttl_millis: u64,
+    },
+}
+
+/// Borrowed twin of `WalRecord` so appends don't copy values; encodes identically
+#[derive(Serialize)]
+enum WalRecordRef<'a> {
+    #[allow(dead_code)] // only read back from older logs
+    Put {
+        key: &'a [u8],
+        value: &'a [u8],
+        ttl_seconds: u64,
+    },
+    Delete {
+        key: &'a [u8],
+    },
+    PutMillis {
+        key: &'a [u8],
+        value: &'a [u8],
+        ttl_millis: u64,
+    },
+}
+
+struct WalState {
+    writer: BufWriter<File>,
+    /// Sequence number of the last appended record
+    appended: u64,
+    /// Sequence number up to which the log is known durable
+    synced: u64,
+    /// Bytes in the log file, buffered appends included
+    len: u64,
+    /// Size of the log right after the last compaction
+    compacted_len: u64,
+    /// Records appended while a compaction is taking its snapshot, to be
+    /// copied after it into the new log
+    compacting: Option<Vec<u8>>,
+}
+
+/// Writer for the snapshot a `compact` call starts the new log with
+pub struct WalSnapshot {
+    writer: BufWriter<File>,
+    len: u64,
+}
+
+impl WalSnapshot {
+    /// Add a live entry to the snapshot
+    pub fn put(&mut self, key: &[u8], value: &[u8], ttl_millis: u64) -> Result<()> {
+        let record = encode(&WalRecordRef::PutMillis {
+            key,
+            value,
+            ttl_millis,
+        })?;
+        self.writer.write_all(&record)?;
+        self.len += record.len() as u64;
+        Ok(())
+    }
+}
+
+/// Append-only log file
+pub struct Wal {
+    path: PathBuf,
+    state: Mutex<WalState>,
+}
+
+impl Wal {
+    /// Open (creating if needed) the log for appending
+    ///
+    /// A torn record at the tail is truncated away first.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| anyhow!("Failed to open WAL {}: {}", path.display(), e))?;
+
+        // Only a regular file can hold a torn record (and be read to its end)
+        let mut len = 0;
+        if file.metadata()?.is_file() {
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            let complete = complete_len(&bytes);
+            if complete < bytes.len() {
+                tracing::warn!(
+                    "WAL {}: truncating {}-byte torn record at tail",
+                    path.display(),
+                    bytes.len() - complete
+                );
+                file.set_len(complete as u64)?;
+                file.seek(std::io::SeekFrom::End(0))?;
+            }
+            len = complete as u64;
+        }
+
+        Ok(Self {
+            path,
+            state: Mutex::new(WalState {
+                writer: BufWriter::new(file),
+                appended: 0,
+                synced: 0,
+                len,
+                compacted_len: 0,
+                compacting: None,
+            }),
+        })
+    }
+
+    /// Read every complete record from a log file; a missing file is an empty log
+    pub fn replay(path: impl AsRef<Path>) -> Result<Vec<WalRecord>> {
+        let path = path.as_ref();
+        let mut bytes = Vec::new();
+        match File::open(path) {
+            Ok(mut file) => file.read_to_end(&mut bytes)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(anyhow!("Failed to read WAL {}: {}", path.display(), e)),
+        };
+
+        let complete = complete_len(&bytes);
+        if complete < bytes.len() {
+            tracing::warn!("WAL {}: ignoring torn record at tail", path.display());
+        }
+        let mut records = Vec::new();
+        let mut rest = &bytes[..complete];
+        while !rest.is_empty() {
+            let len = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+            records.push(bincode::deserialize(&rest[4..4 + len])?);
+            rest = &rest[4 + len..];
+        }
+
+        Ok(records)
+    }
+
+    /// Append a PUT, returning its sequence number
+    ///
+    /// The record is buffered; it is only durable after `flush`.
+    pub fn append_put(&self, key: &[u8], value: &[u8], ttl_millis: u64) -> Result<u64> {
+        self.append(&WalRecordRef::PutMillis {
+            key,
+            value,
+            ttl_millis,
+        })
+    }
+
+    /// Append a DELETE, returning its sequence number
+    pub fn append_delete(&self, key: &[u8]) -> Result<u64> {
+        self.append(&WalRecordRef::Delete { key })
+    }
+
+    fn append(&self, record: &WalRecordRef<'_>) -> Result<u64> {
+        let record = encode(record)?;
+
+        let mut state = self.state.lock();
+        state.writer.write_all(&record)?;
+        if let Some(pending) = &mut state.compacting {
+            pending.extend_from_slice(&record);
+        }
+        state.len += record.len() as u64;
+        state.appended += 1;
+        Ok(state.appended)
+    }
+
+    /// Rewrite the log as what `snapshot` writes, followed by every record
+    /// appended meanwhile, and make it durable
+    ///
+    /// `snapshot` runs without the log locked, so appends carry on while it
+    /// walks the cache. A record appended during it may or may not be
+    /// reflected in the snapshot; replaying it again afterwards gives the
+    /// same final state either way.
+    pub fn compact(&self, snapshot: impl FnOnce(&mut WalSnapshot) -> Result<()>) -> Result<()> {
+        {
+            let mut state = self.state.lock();
+            if state.compacting.is_some() {
+                return Err(anyhow!(
+                    "WAL {} is already being compacted",
+                    self.path.display()
+                ));
+            }
+            state.compacting = Some(Vec::new());
+        }
+
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".compact");
+        let tmp_path = PathBuf::from(tmp_path);
+        let result = self.write_compacted(&tmp_path, snapshot);
+        if result.is_err() {
+            self.state.lock().compacting = None;
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    fn write_compacted(
+        &self,
+        tmp_path: &Path,
+        snapshot: impl FnOnce(&mut WalSnapshot) -> Result<()>,
+    ) -> Result<()> {
+        let file = File::create(tmp_path)
+            .map_err(|e| anyhow!("Failed to create {}: {}", tmp_path.display(), e))?;
+        let mut compacted = WalSnapshot {
+            writer: BufWriter::new(file),
+            len: 0,
+        };
+        snapshot(&mut compacted)?;
+
+        let mut state = self.state.lock();
+        let pending = state.compacting.take().unwrap_or_default();
+        compacted.writer.write_all(&pending)?;
+        compacted.writer.flush()?;
+        compacted.writer.get_ref().sync_data()?;
+        std::fs::rename(tmp_path, &self.path)?;
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            File::open(dir)?.sync_all()?;
+        }
+
+        let len = compacted.len + pending.len() as u64;
+        let file = compacted
+            .writer
+            .into_inner()
+            .map_err(|e| anyhow!("Failed to flush {}: {}", tmp_path.display(), e))?;
+        state.writer = BufWriter::new(file);
+        state.len = len;
+        state.compacted_len = len;
+        state.synced = state.appended;
+        tracing::info!("Compacted WAL {} to {} bytes", self.path.display(), len);
+        Ok(())
+    }
+
+    /// Bytes in the log file, including appends not yet flushed
+    pub fn len(&self) -> u64 {
+        self.state.lock().len
+    }
+
+    /// Whether the log holds no records
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Size of the log right after its last compaction (0 before any)
+    pub fn compacted_len(&self) -> u64 {
+        self.state.lock().compacted_len
+    }
+
+    /// Make every record appended so far durable; returns the synced sequence
+    pub fn flush(&self) -> Result<u64> {
+        let mut state = self.state.lock();
+        if state.synced < state.appended {
+            state.writer.flush()?;
+            state.writer.get_ref().sync_data()?;
+            state.synced = state.appended;
+        }
+        Ok(state.synced)
+    }
+
+    /// Path of the log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Frame a record as its little-endian `u32` length followed by the payload
+fn encode(record: &WalRecordRef<'_>) -> Result<Vec<u8>> {
+    let payload = bincode::serialize(record)?;
+    let len = u32::try_from(payload.len()).map_err(|_| anyhow!("WAL record too large"))?;
+    let mut framed = Vec::with_capacity(4 + payload.len());
+    framed.extend_from_slice(&len.to_le_bytes());
+    framed.extend_from_slice(&payload);
+    Ok(framed)
+}
+
+/// Length of the prefix of `bytes` made of complete records
+fn complete_len(bytes: &[u8]) -> usize {
+    let mut complete = 0;
+    while let Some(header) = bytes.get(complete..complete + 4) {
+        let len = u32::from_le_bytes(header.try_into().unwrap()) as usize;
+        if bytes.len() - complete - 4 < len {
+            break;
+        }
+        complete += 4 + len;
+    }
+    complete
+}
@@ -0,0 +1,112 @@
+//! In-process server harness shared by the integration tests
+
+use kv_rdma_poc::client::{ClientConfig, KvCacheClient};
+use kv_rdma_poc::server::{KvCacheServer, ServerConfig};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::TcpListenerStream;
+
+/// How long `TestServer::start` waits for the server to accept connections
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A KV cache server serving on a free `[::1]` port until stopped or dropped
+pub struct TestServer {
+    addr: SocketAddr,
+    stop: Option<oneshot::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl TestServer {
+    /// Serve `config` (its `listen_addr` is replaced by the port picked)
+    pub async fn start(config: ServerConfig) -> Self {
+        Self::start_with(config, |server| server).await
+    }
+
+    /// Like `start`, letting `build` finish the server, e.g. to add a loader
+    pub async fn start_with(
+        mut config: ServerConfig,
+        build: impl FnOnce(KvCacheServer) -> KvCacheServer,
+    ) -> Self {
+        // Binding here, rather than picking a port for the server to bind
+        // later, means no other test can take the port in between
+        let listener = TcpListener::bind("[::1]:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        config.listen_addr = addr.to_string();
+        let service = build(KvCacheServer::new(config).unwrap()).into_service();
+
+        let (stop, stopped) = oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                    let _ = stopped.await;
+                })
+                .await
+                .unwrap();
+        });
+
+        let server = Self {
+            addr,
+            stop: Some(stop),
+            handle: Some(handle),
+        };
+        wait_until_reachable(|| tokio::net::TcpStream::connect(addr)).await;
+        server
+    }
+
+    /// The gRPC endpoint clients connect to
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Client settings for this server with a 4MB receive buffer
+    pub fn client_config(&self) -> ClientConfig {
+        ClientConfig {
+            server_addr: self.url(),
+            receive_buffer_size: 4 * 1024 * 1024,
+            ..Default::default()
+        }
+    }
+
+    /// A client connected with `client_config`
+    pub async fn client(&self) -> KvCacheClient {
+        let client = KvCacheClient::new(self.client_config()).unwrap();
+        client.connect().await.unwrap();
+        client
+    }
+
+    /// Shut down gracefully, closing open connections, and wait for it
+    ///
+    /// Dropping aborts the serve task instead, which leaves connections that
+    /// are already open running.
+    pub async fn stop(mut self) {
+        self.stop.take().unwrap().send(()).unwrap();
+        self.handle.take().unwrap().await.unwrap();
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = &self.handle {
+            handle.abort();
+        }
+    }
+}
+
+/// Retry `connect` until it succeeds, panicking after `START_TIMEOUT`
+pub async fn wait_until_reachable<F, T>(mut connect: impl FnMut() -> F)
+where
+    F: std::future::Future<Output = std::io::Result<T>>,
+{
+    let deadline = Instant::now() + START_TIMEOUT;
+    loop {
+        match connect().await {
+            Ok(_) => return,
+            Err(e) if Instant::now() >= deadline => panic!("server never became reachable: {}", e),
+            Err(_) => tokio::time::sleep(Duration::from_millis(5)).await,
+        }
+    }
+}
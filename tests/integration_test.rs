@@ -1,16 +1,18 @@
 //! Integration tests for KV Cache with RDMA
 
-use kv_rdma_poc::client::{ClientConfig, KvCacheClient};
-use kv_rdma_poc::server::{KvCacheServer, ServerConfig};
+mod common;
+
+use common::TestServer;
+use futures::StreamExt;
+use kv_rdma_poc::client::{
+    AdaptiveBufferConfig, ClientConfig, KeyNotFound, KvCacheClient, KvEntry, RetryPolicy,
+};
+use kv_rdma_poc::pb::{GetSource, KeyspaceEventKind};
+use kv_rdma_poc::server::{EvictionPolicy, ServerConfig};
 use kv_rdma_poc::transport::TransportConfig;
+use std::sync::Arc;
 use std::time::Duration;
 
-/// Find an available port for testing
-fn find_available_port() -> u16 {
-    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
-    listener.local_addr().unwrap().port()
-}
-
 #[tokio::test]
 async fn test_server_client_integration() {
     // Initialize tracing for debugging
@@ -18,52 +20,46 @@ async fn test_server_client_integration() {
         .with_env_filter("kv_rdma_poc=debug")
         .try_init();
 
-    // Find an available port
-    let port = find_available_port();
-    let server_addr = format!("[::1]:{}", port);
-    let client_addr = format!("http://[::1]:{}", port);
-
     // Start server in background
-    let server_config = ServerConfig {
+    let server = TestServer::start(ServerConfig {
         node_id: 0,
-        listen_addr: server_addr.clone(),
         memory_pool_size: 16 * 1024 * 1024, // 16MB for testing
         transport: TransportConfig {
             node_id: 0,
             num_domains: 1,
             use_mock: true,
+            ..Default::default()
         },
-    };
-
-    let server = KvCacheServer::new(server_config.clone()).unwrap();
-    let service = server.into_service();
-
-    let server_handle = tokio::spawn(async move {
-        tonic::transport::Server::builder()
-            .add_service(service)
-            .serve(server_addr.parse().unwrap())
-            .await
-            .unwrap();
-    });
-
-    // Wait for server to start
-    tokio::time::sleep(Duration::from_millis(100)).await;
+        ..Default::default()
+    })
+    .await;
 
     // Create and connect client
     let client_config = ClientConfig {
         client_id: 1,
-        server_addr: client_addr,
         receive_buffer_size: 4 * 1024 * 1024, // 4MB
         transport: TransportConfig {
             node_id: 1,
             num_domains: 1,
             use_mock: true,
+            ..Default::default()
         },
+        ..server.client_config()
     };
 
     let client = KvCacheClient::new(client_config).unwrap();
+    assert!(client.server_info().is_none());
     client.connect().await.unwrap();
 
+    // Registration reports what we're talking to
+    let info = client.server_info().unwrap();
+    assert_eq!(info.server_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(
+        info.protocol_version,
+        kv_rdma_poc::protocol::PROTOCOL_VERSION
+    );
+    assert!(info.uptime_seconds < 60, "uptime {}s", info.uptime_seconds);
+
     // Test PUT
     client.put(b"key1", b"value1", 0).await.unwrap();
     client.put(b"key2", b"hello world", 0).await.unwrap();
@@ -94,49 +90,28 @@ async fn test_server_client_integration() {
     // Test heartbeat
     let alive = client.heartbeat().await.unwrap();
     assert!(alive);
-
-    // Clean up
-    server_handle.abort();
 }
 
 #[tokio::test]
 async fn test_large_values() {
     // Initialize tracing
-    let _ = tracing_subscriber::fmt()
-        .with_env_filter("warn")
-        .try_init();
-
-    let port = find_available_port();
-    let server_addr = format!("[::1]:{}", port);
-    let client_addr = format!("http://[::1]:{}", port);
+    let _ = tracing_subscriber::fmt().with_env_filter("warn").try_init();
 
     // Start server
-    let server_config = ServerConfig {
+    let server = TestServer::start(ServerConfig {
         node_id: 0,
-        listen_addr: server_addr.clone(),
         memory_pool_size: 64 * 1024 * 1024,
         transport: TransportConfig::default(),
-    };
-
-    let server = KvCacheServer::new(server_config).unwrap();
-    let service = server.into_service();
-
-    let server_handle = tokio::spawn(async move {
-        tonic::transport::Server::builder()
-            .add_service(service)
-            .serve(server_addr.parse().unwrap())
-            .await
-            .unwrap();
-    });
-
-    tokio::time::sleep(Duration::from_millis(100)).await;
+        ..Default::default()
+    })
+    .await;
 
     // Create client
     let client_config = ClientConfig {
         client_id: 1,
-        server_addr: client_addr,
         receive_buffer_size: 16 * 1024 * 1024,
         transport: TransportConfig::default(),
+        ..server.client_config()
     };
 
     let client = KvCacheClient::new(client_config).unwrap();
@@ -153,53 +128,33 @@ async fn test_large_values() {
         assert_eq!(retrieved.len(), size);
         assert_eq!(retrieved, value);
     }
-
-    server_handle.abort();
 }
 
 #[tokio::test]
 async fn test_multiple_clients() {
-    let _ = tracing_subscriber::fmt()
-        .with_env_filter("warn")
-        .try_init();
-
-    let port = find_available_port();
-    let server_addr = format!("[::1]:{}", port);
-    let client_addr = format!("http://[::1]:{}", port);
+    let _ = tracing_subscriber::fmt().with_env_filter("warn").try_init();
 
     // Start server
-    let server_config = ServerConfig {
+    let server = TestServer::start(ServerConfig {
         node_id: 0,
-        listen_addr: server_addr.clone(),
         memory_pool_size: 32 * 1024 * 1024,
         transport: TransportConfig::default(),
-    };
-
-    let server = KvCacheServer::new(server_config).unwrap();
-    let service = server.into_service();
-
-    let server_handle = tokio::spawn(async move {
-        tonic::transport::Server::builder()
-            .add_service(service)
-            .serve(server_addr.parse().unwrap())
-            .await
-            .unwrap();
-    });
-
-    tokio::time::sleep(Duration::from_millis(100)).await;
+        ..Default::default()
+    })
+    .await;
 
     // Create multiple clients
     let mut clients = Vec::new();
     for i in 1..=3 {
         let client_config = ClientConfig {
             client_id: i,
-            server_addr: client_addr.clone(),
-            receive_buffer_size: 4 * 1024 * 1024,
             transport: TransportConfig {
                 node_id: i,
                 num_domains: 1,
                 use_mock: true,
+                ..Default::default()
             },
+            ..server.client_config()
         };
 
         let client = KvCacheClient::new(client_config).unwrap();
@@ -208,7 +163,10 @@ async fn test_multiple_clients() {
     }
 
     // Client 1 writes, all clients read
-    clients[0].put(b"shared_key", b"shared_value", 0).await.unwrap();
+    clients[0]
+        .put(b"shared_key", b"shared_value", 0)
+        .await
+        .unwrap();
 
     for client in &clients {
         let value = client.get(b"shared_key").await.unwrap();
@@ -219,7 +177,10 @@ async fn test_multiple_clients() {
     for (i, client) in clients.iter().enumerate() {
         let key = format!("client_{}_key", i);
         let value = format!("client_{}_value", i);
-        client.put(key.as_bytes(), value.as_bytes(), 0).await.unwrap();
+        client
+            .put(key.as_bytes(), value.as_bytes(), 0)
+            .await
+            .unwrap();
     }
 
     // All clients can read all keys
@@ -231,6 +192,1158 @@ async fn test_multiple_clients() {
             assert_eq!(value, expected_value.as_bytes());
         }
     }
+}
+
+#[tokio::test]
+async fn test_max_pending_bounds_in_flight_gets() {
+    let _ = tracing_subscriber::fmt().with_env_filter("warn").try_init();
+
+    // Slow transfers keep GETs in flight long enough to pile up
+    let server = TestServer::start(ServerConfig {
+        node_id: 0,
+        memory_pool_size: 16 * 1024 * 1024,
+        transport: TransportConfig {
+            mock_transfer_delay: Duration::from_millis(50),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .await;
+
+    let max_pending = 2;
+    let client_config = ClientConfig {
+        client_id: 1,
+        receive_buffer_size: 16 * 1024 * 1024,
+        transport: TransportConfig::default(),
+        max_pending,
+        ..server.client_config()
+    };
+
+    let client = std::sync::Arc::new(KvCacheClient::new(client_config).unwrap());
+    client.connect().await.unwrap();
+    client.put(b"slow_key", b"slow_value", 0).await.unwrap();
+
+    let mut gets = Vec::new();
+    for _ in 0..8 {
+        let client = std::sync::Arc::clone(&client);
+        gets.push(tokio::spawn(async move { client.get(b"slow_key").await }));
+    }
+
+    // Sample the pending map while the GETs drain
+    let mut max_seen = 0;
+    while !gets.iter().all(|g| g.is_finished()) {
+        max_seen = max_seen.max(client.pending_requests());
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    for get in gets {
+        assert_eq!(get.await.unwrap().unwrap(), b"slow_value");
+    }
+    assert!(max_seen > 0);
+    assert!(
+        max_seen <= max_pending,
+        "saw {} pending, limit {}",
+        max_seen,
+        max_pending
+    );
+}
+
+#[tokio::test]
+async fn test_get_if_newer() {
+    let _ = tracing_subscriber::fmt().with_env_filter("warn").try_init();
+
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    let client = server.client().await;
+
+    client.put(b"versioned", b"v1", 0).await.unwrap();
+    let (value, version) = client.get_if_newer(b"versioned", 0).await.unwrap().unwrap();
+    assert_eq!(value, b"v1");
+
+    // Our copy is current: nothing comes back
+    assert!(client
+        .get_if_newer(b"versioned", version)
+        .await
+        .unwrap()
+        .is_none());
+
+    // A new write makes our copy stale
+    client.put(b"versioned", b"v2", 0).await.unwrap();
+    let (value, newer) = client
+        .get_if_newer(b"versioned", version)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(value, b"v2");
+    assert!(newer > version);
+}
+
+#[tokio::test]
+async fn test_dump_and_load_into_second_server() {
+    let _ = tracing_subscriber::fmt().with_env_filter("warn").try_init();
+
+    let mut servers = Vec::new();
+    let mut clients = Vec::new();
+    for _ in 0..2 {
+        let server = TestServer::start(ServerConfig {
+            memory_pool_size: 16 * 1024 * 1024,
+            ..Default::default()
+        })
+        .await;
+        clients.push(server.client().await);
+        servers.push(server);
+    }
+    let (source, target) = (&clients[0], &clients[1]);
+
+    // Mix of small (inline) and large (fetched over RDMA) values
+    for i in 0..300u32 {
+        let size = if i % 50 == 0 { 256 * 1024 } else { 100 };
+        let value = vec![(i % 251) as u8; size];
+        let ttl = if i % 2 == 0 { 0 } else { 3600 };
+        source
+            .put(format!("key_{}", i).as_bytes(), &value, ttl)
+            .await
+            .unwrap();
+    }
+
+    let loaded = target
+        .load_from_dump(source.dump().await.unwrap())
+        .await
+        .unwrap();
+    assert_eq!(loaded, 300);
+
+    for i in 0..300u32 {
+        let key = format!("key_{}", i);
+        assert_eq!(
+            target.get(key.as_bytes()).await.unwrap(),
+            source.get(key.as_bytes()).await.unwrap(),
+            "mismatch for {}",
+            key
+        );
+    }
+}
+
+/// Loader backed by a fixed map, counting how often it is consulted
+struct MapLoader {
+    values: std::collections::HashMap<Vec<u8>, Vec<u8>>,
+    loads: std::sync::atomic::AtomicUsize,
+}
+
+impl kv_rdma_poc::loader::ValueLoader for MapLoader {
+    fn load<'a>(&'a self, key: &'a [u8]) -> kv_rdma_poc::loader::LoadFuture<'a> {
+        Box::pin(async move {
+            self.loads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(self.values.get(key).cloned())
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_warm_populates_through_loader_without_transfer() {
+    let _ = tracing_subscriber::fmt().with_env_filter("warn").try_init();
+
+    let loader = std::sync::Arc::new(MapLoader {
+        values: [
+            (b"cold1".to_vec(), vec![1u8; 4096]),
+            (b"cold2".to_vec(), vec![2u8; 64]),
+        ]
+        .into_iter()
+        .collect(),
+        loads: Default::default(),
+    });
+    let server = TestServer::start_with(
+        ServerConfig {
+            memory_pool_size: 16 * 1024 * 1024,
+            ..Default::default()
+        },
+        |server| server.with_loader(loader.clone()),
+    )
+    .await;
+
+    let client = server.client().await;
+
+    let resident = client
+        .warm(&[&b"cold1"[..], b"cold2", b"absent"])
+        .await
+        .unwrap();
+    assert_eq!(resident, 2);
+    assert_eq!(loader.loads.load(std::sync::atomic::Ordering::SeqCst), 3);
+    // Warming never touches the receive buffer
+    assert_eq!(client.memory_stats().used, 0);
+
+    // Now resident: served from the cache without asking the loader again
+    assert_eq!(client.get(b"cold1").await.unwrap(), vec![1u8; 4096]);
+    assert_eq!(client.warm(&[b"cold2"]).await.unwrap(), 1);
+    assert_eq!(loader.loads.load(std::sync::atomic::Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_get_detailed_reports_source_version_and_ttl() {
+    let loader = std::sync::Arc::new(MapLoader {
+        values: [(b"backed".to_vec(), vec![7u8; 2048])]
+            .into_iter()
+            .collect(),
+        loads: Default::default(),
+    });
+    let server = TestServer::start_with(
+        ServerConfig {
+            memory_pool_size: 16 * 1024 * 1024,
+            ..Default::default()
+        },
+        |server| server.with_loader(loader),
+    )
+    .await;
+
+    let client = server.client().await;
+
+    // First read goes through the loader, later ones hit
+    let loaded = client.get_detailed(b"backed").await.unwrap();
+    assert_eq!(loaded.value, vec![7u8; 2048]);
+    assert_eq!(loaded.source, GetSource::Loaded);
+    assert_eq!(loaded.remaining_ttl, 0);
+    let hit = client.get_detailed(b"backed").await.unwrap();
+    assert_eq!(hit.source, GetSource::Hit);
+    assert_eq!(hit.version, loaded.version);
+
+    client.put(b"session", b"fresh", 60).await.unwrap();
+    let fresh = client.get_detailed(b"session").await.unwrap();
+    assert_eq!(fresh.value, b"fresh");
+    assert_eq!(fresh.source, GetSource::Hit);
+    assert!(
+        (59..=60).contains(&fresh.remaining_ttl),
+        "{}",
+        fresh.remaining_ttl
+    );
+    assert!(fresh.version > hit.version);
+}
+
+#[tokio::test]
+async fn test_millisecond_ttl_expires_on_time() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    let client = server.client().await;
+
+    client.put_ms(b"short", b"lived", 200).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(client.get(b"short").await.unwrap(), b"lived");
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(
+        client.get(b"short").await.is_err(),
+        "200ms TTL outlived 300ms"
+    );
+}
+
+#[tokio::test]
+async fn test_expired_get_is_repaired_through_loader() {
+    let _ = tracing_subscriber::fmt().with_env_filter("warn").try_init();
+
+    let loader = std::sync::Arc::new(MapLoader {
+        values: [(b"session".to_vec(), b"fresh".to_vec())]
+            .into_iter()
+            .collect(),
+        loads: Default::default(),
+    });
+    let server = TestServer::start_with(
+        ServerConfig {
+            memory_pool_size: 16 * 1024 * 1024,
+            repair_on_expiry: true,
+            ..Default::default()
+        },
+        |server| server.with_loader(loader.clone()),
+    )
+    .await;
+
+    let client = server.client().await;
+
+    client.put(b"session", b"stale", 1).await.unwrap();
+    assert_eq!(client.get(b"session").await.unwrap(), b"stale");
+    assert_eq!(loader.loads.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(client.get(b"session").await.unwrap(), b"fresh");
+    assert_eq!(loader.loads.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_get_many_into_packs_values_at_offsets() {
+    let _ = tracing_subscriber::fmt().with_env_filter("warn").try_init();
+
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    let client = server.client().await;
+
+    let tensors: [(&[u8], Vec<u8>); 3] = [
+        (b"t0", vec![0xa0; 100]),
+        (b"t1", vec![0xa1; 4096]),
+        (b"t2", vec![0xa2; 7]),
+    ];
+    for (key, value) in &tensors {
+        client.put(key, value, 0).await.unwrap();
+    }
+
+    let mut buf = client.register_buffer(8192).unwrap();
+    let keys = [b"t1".as_slice(), b"t0", b"missing", b"t2"];
+    let offsets = [128, 0, 6000, 4224];
+    let lengths = client
+        .get_many_into(&keys, &mut buf, &offsets)
+        .await
+        .unwrap();
+    assert_eq!(lengths, vec![Some(4096), Some(100), None, Some(7)]);
+
+    assert_eq!(&buf[0..100], &tensors[0].1[..]);
+    assert_eq!(&buf[128..128 + 4096], &tensors[1].1[..]);
+    assert_eq!(&buf[4224..4224 + 7], &tensors[2].1[..]);
+    assert!(buf[100..128].iter().all(|&b| b == 0));
+
+    // A value that would spill into the next slot is refused
+    let err = client
+        .get_many_into(&[b"t1", b"t0"], &mut buf, &[0, 1000])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("doesn't fit"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_single_buffer_mode_gets_skip_the_allocator() {
+    let _ = tracing_subscriber::fmt().with_env_filter("warn").try_init();
+
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    let client_config = ClientConfig {
+        single_buffer_mode: true,
+        ..server.client_config()
+    };
+
+    let client = KvCacheClient::new(client_config).unwrap();
+    client.connect().await.unwrap();
+
+    let values: Vec<Vec<u8>> = (0..10u8).map(|i| vec![i; 100 + i as usize * 37]).collect();
+    for (i, value) in values.iter().enumerate() {
+        client
+            .put(format!("key{}", i).as_bytes(), value, 0)
+            .await
+            .unwrap();
+    }
+
+    let allocations = client.memory_stats().allocations;
+    for i in 0..1000 {
+        let view = client
+            .get_reuse(format!("key{}", i % 10).as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(&*view, &values[i % 10][..]);
+    }
+    assert_eq!(client.memory_stats().allocations, allocations);
+}
+
+#[tokio::test]
+async fn test_watch_events_reports_sets_and_expirations() {
+    let _ = tracing_subscriber::fmt().with_env_filter("warn").try_init();
+
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    let client = server.client().await;
+
+    let events = client.watch_events().await.unwrap();
+    let mut events = std::pin::pin!(events);
+
+    client.put(b"session", b"value", 1).await.unwrap();
+    // Expiry is noticed when the key is next looked up
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert!(client.get(b"session").await.is_err());
+
+    for expected in [KeyspaceEventKind::Set, KeyspaceEventKind::Expired] {
+        let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+            .await
+            .expect("timed out waiting for event")
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.kind(), expected);
+        assert_eq!(event.key, b"session");
+        assert_eq!(event.missed, 0);
+    }
+}
+
+#[tokio::test]
+async fn test_put_from_registered_buffer() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    let client = server.client().await;
+
+    // Larger than the client's 1MB GET buffer, so read it back with get_many_into
+    const SIZE: usize = 2 * 1024 * 1024;
+    let mut src = client.register_buffer(SIZE).unwrap();
+    for (i, byte) in src.iter_mut().enumerate() {
+        *byte = (i % 251) as u8;
+    }
+    client
+        .put_from_buffer(b"pushed", &src, SIZE, 0)
+        .await
+        .unwrap();
+
+    let mut dst = client.register_buffer(SIZE).unwrap();
+    let lengths = client
+        .get_many_into(&[b"pushed"], &mut dst, &[0])
+        .await
+        .unwrap();
+    assert_eq!(lengths, vec![Some(SIZE)]);
+    assert!(dst[..] == src[..]);
+
+    // A length beyond the buffer is refused before anything is sent
+    assert!(client
+        .put_from_buffer(b"pushed", &src, SIZE + 1, 0)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_rename_and_copy() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    let client = server.client().await;
+
+    // Rename: the value moves, replacing whatever the destination held
+    client.put(b"old", b"moved value", 0).await.unwrap();
+    client.put(b"new", b"overwritten", 0).await.unwrap();
+    assert!(client.rename(b"old", b"new").await.unwrap());
+    assert!(client.get(b"old").await.is_err());
+    assert_eq!(client.get(b"new").await.unwrap(), b"moved value");
+    assert!(!client.rename(b"old", b"elsewhere").await.unwrap());
+
+    // Copy: both keys hold the value, and changing one leaves the other alone
+    assert!(client.copy(b"new", b"copy", None).await.unwrap());
+    assert_eq!(client.get(b"copy").await.unwrap(), b"moved value");
+    client.put(b"new", b"changed", 0).await.unwrap();
+    assert_eq!(client.get(b"copy").await.unwrap(), b"moved value");
+    assert!(client.delete(b"copy").await.unwrap());
+    assert_eq!(client.get(b"new").await.unwrap(), b"changed");
+    assert!(!client.copy(b"missing", b"copy", Some(60)).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_large_value_is_stored_across_fragmented_pool() {
+    const BLOCK: usize = 128 * 1024;
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 8 * BLOCK,
+        ..Default::default()
+    })
+    .await;
+
+    let client = server.client().await;
+
+    // Fill the pool, then free every other block: half the pool is free, but
+    // no free block is larger than one value
+    for i in 0..8u8 {
+        client.put(&[b'k', i], &vec![i; BLOCK], 0).await.unwrap();
+    }
+    for i in (1..8u8).step_by(2) {
+        assert!(client.delete(&[b'k', i]).await.unwrap());
+    }
+
+    let large: Vec<u8> = (0..3 * BLOCK + BLOCK / 2)
+        .map(|i| (i % 251) as u8)
+        .collect();
+    client.put(b"large", &large, 0).await.unwrap();
+    assert_eq!(client.get(b"large").await.unwrap(), large);
+    for i in (0..8u8).step_by(2) {
+        assert_eq!(client.get(&[b'k', i]).await.unwrap(), vec![i; BLOCK]);
+    }
+
+    // Its chunks go back to the pool when it's deleted
+    assert!(client.delete(b"large").await.unwrap());
+    for i in (1..8u8).step_by(2) {
+        client.put(&[b'k', i], &vec![i; BLOCK], 0).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_adaptive_get_buffers_converge_on_value_size() {
+    const VALUE: usize = 100 * 1024;
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    let client = KvCacheClient::new(ClientConfig {
+        adaptive_buffer: Some(AdaptiveBufferConfig::default()),
+        ..server.client_config()
+    })
+    .unwrap();
+    client.connect().await.unwrap();
+
+    for i in 0..10u8 {
+        let key = format!("session:{:04}", i);
+        client
+            .put(key.as_bytes(), &vec![i; VALUE + i as usize * 100], 0)
+            .await
+            .unwrap();
+    }
+
+    // The first GET under the prefix reserves the maximum; later ones settle
+    // near the value size without retries
+    let mut last_buffer = 0;
+    for i in 0..10u8 {
+        let before = client.get_buffer_stats();
+        let key = format!("session:{:04}", i);
+        let value = client.get(key.as_bytes()).await.unwrap();
+        assert_eq!(value, vec![i; VALUE + i as usize * 100]);
+        let after = client.get_buffer_stats();
+        assert_eq!(after.allocations, before.allocations + 1);
+        last_buffer = after.allocated_bytes - before.allocated_bytes;
+        if i == 0 {
+            assert_eq!(last_buffer, 1024 * 1024);
+        }
+    }
+    assert!(
+        (VALUE as u64..=VALUE as u64 * 5 / 4).contains(&last_buffer),
+        "last GET reserved {} bytes",
+        last_buffer
+    );
+    assert_eq!(client.get_buffer_stats().retries, 0);
+
+    // A much larger value under the same prefix costs exactly one retry
+    let large = vec![0xAB; 4 * VALUE];
+    client.put(b"session:big", &large, 0).await.unwrap();
+    assert_eq!(client.get(b"session:big").await.unwrap(), large);
+    assert_eq!(client.get_buffer_stats().retries, 1);
+}
+
+#[tokio::test]
+async fn test_import_streams_many_records() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 64 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+    let client = server.client().await;
+
+    let records = (0..10_000).map(|i| KvEntry {
+        key: format!("import:{}", i).into_bytes(),
+        value: format!("value {}", i).into_bytes(),
+        ttl_seconds: 0,
+    });
+    let response = client.import(futures::stream::iter(records)).await.unwrap();
+    assert_eq!((response.stored, response.failed), (10_000, 0));
+    assert_eq!(client.count().await.unwrap().entries, 10_000);
+    for i in [0, 4_321, 9_999] {
+        let value = client
+            .get(format!("import:{}", i).as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(value, format!("value {}", i).into_bytes());
+    }
+
+    // A pool of 16 4KB pages fills up partway; the rest are counted as failed
+    let small = TestServer::start(ServerConfig {
+        memory_pool_size: 64 * 1024,
+        eviction: EvictionPolicy::None,
+        ..Default::default()
+    })
+    .await;
+    let client = small.client().await;
+    let records = (0..100).map(|i| KvEntry {
+        key: format!("import:{}", i).into_bytes(),
+        value: vec![0u8; 100],
+        ttl_seconds: 0,
+    });
+    let response = client.import(futures::stream::iter(records)).await.unwrap();
+    assert!(response.stored > 0 && response.failed > 0, "{:?}", response);
+    assert_eq!(response.stored + response.failed, 100);
+    assert!(!response.first_error.is_empty());
+    assert_eq!(client.count().await.unwrap().entries, response.stored);
+}
+
+#[tokio::test]
+async fn test_compare_and_swap() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+    // Room for every racing task's 1MB GET buffer at once
+    let client = KvCacheClient::new(ClientConfig {
+        receive_buffer_size: 16 * 1024 * 1024,
+        ..server.client_config()
+    })
+    .unwrap();
+    client.connect().await.unwrap();
+    let client = Arc::new(client);
+
+    // Absent key: only a swap expecting no value applies
+    assert!(!client
+        .compare_and_swap(b"lock", Some(b"free"), b"held")
+        .await
+        .unwrap());
+    assert!(client.get(b"lock").await.is_err());
+    assert!(client
+        .compare_and_swap(b"lock", None, b"held")
+        .await
+        .unwrap());
+    assert!(!client
+        .compare_and_swap(b"lock", None, b"taken")
+        .await
+        .unwrap());
+
+    // Mismatch leaves the value alone; a match replaces it
+    assert!(!client
+        .compare_and_swap(b"lock", Some(b"free"), b"taken")
+        .await
+        .unwrap());
+    assert_eq!(client.get(b"lock").await.unwrap(), b"held");
+    assert!(client
+        .compare_and_swap(b"lock", Some(b"held"), b"free")
+        .await
+        .unwrap());
+    assert_eq!(client.get(b"lock").await.unwrap(), b"free");
+
+    // Racing increments each land exactly once
+    client.put(b"counter", b"0", 0).await.unwrap();
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move {
+                for _ in 0..20 {
+                    loop {
+                        let current = client.get(b"counter").await.unwrap();
+                        let next = (std::str::from_utf8(&current)
+                            .unwrap()
+                            .parse::<u64>()
+                            .unwrap()
+                            + 1)
+                        .to_string();
+                        if client
+                            .compare_and_swap(b"counter", Some(&current), next.as_bytes())
+                            .await
+                            .unwrap()
+                        {
+                            break;
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+    assert_eq!(client.get(b"counter").await.unwrap(), b"160");
+}
+
+#[tokio::test]
+async fn test_mget_mixes_hits_and_misses() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+    let client = server.client().await;
+    for i in (0..10).filter(|i| i % 3 != 1) {
+        client
+            .put(
+                format!("mget:{}", i).as_bytes(),
+                &vec![i as u8; 1000 * i],
+                0,
+            )
+            .await
+            .unwrap();
+    }
+    let gets_before = client.stats().await.unwrap().gets;
+
+    // The 4MB receive pool only fits four 1MB regions per batch
+    let keys: Vec<Vec<u8>> = (0..10)
+        .map(|i| format!("mget:{}", i).into_bytes())
+        .collect();
+    let keys: Vec<&[u8]> = keys.iter().map(Vec::as_slice).collect();
+    let values = client.mget(&keys).await.unwrap();
+    assert_eq!(values.len(), 10);
+    for (i, value) in values.into_iter().enumerate() {
+        if i % 3 == 1 {
+            assert_eq!(value, None, "key {}", i);
+        } else {
+            assert_eq!(value, Some(vec![i as u8; 1000 * i]), "key {}", i);
+        }
+    }
+    assert_eq!(client.stats().await.unwrap().gets - gets_before, 7);
+    assert_eq!(client.metrics().get.count, 0, "fell back to single GETs");
+    assert_eq!(
+        client.mget(&[]).await.unwrap(),
+        Vec::<Option<Vec<u8>>>::new()
+    );
+}
+
+#[tokio::test]
+async fn test_mset_reports_each_entry() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 64 * 1024,
+        ..Default::default()
+    })
+    .await;
+    let client = server.client().await;
+
+    // The middle value is larger than the whole pool
+    let big = vec![0u8; 128 * 1024];
+    let results = client
+        .mset(&[(b"first", b"1", 0), (b"big", &big, 0), (b"last", b"3", 60)])
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 3);
+    assert!(results[0].is_ok() && results[2].is_ok(), "{:?}", results);
+    let err = results[1].as_ref().unwrap_err().to_string();
+    assert!(err.contains("Memory pool exhausted"), "{}", err);
+
+    assert_eq!(client.get(b"first").await.unwrap(), b"1");
+    assert_eq!(client.get(b"last").await.unwrap(), b"3");
+    assert!(client.get(b"big").await.is_err());
+    assert_eq!(client.count().await.unwrap().entries, 2);
+}
+
+#[tokio::test]
+async fn test_value_len_reads_size_without_fetching() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+    let client = server.client().await;
+    client.put(b"sized", &vec![7u8; 10_000], 0).await.unwrap();
+    let used = client.server_memory_stats().await.unwrap().used;
+
+    assert_eq!(client.value_len(b"sized").await.unwrap(), Some(10_000));
+    assert_eq!(client.value_len(b"absent").await.unwrap(), None);
+    assert_eq!(client.server_memory_stats().await.unwrap().used, used);
+    assert_eq!(client.metrics().get.count, 0);
+}
+
+#[tokio::test]
+async fn test_values_over_get_buffer_size_are_refetched() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+    let client = server.client().await;
+    let value: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    client.put(b"fits", &value, 0).await.unwrap();
+    assert_eq!(client.get(b"fits").await.unwrap(), value);
+    assert_eq!(client.get_buffer_stats().retries, 1);
+
+    // Larger than the client's whole 4MB receive pool
+    client
+        .put(b"oversized", &vec![1u8; 5 * 1024 * 1024], 0)
+        .await
+        .unwrap();
+    let err = client.get(b"oversized").await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("value of 5242880 bytes exceeds the 4194304 bytes free"),
+        "{}",
+        err
+    );
+    assert_eq!(client.memory_stats().used, 0);
+}
+
+#[tokio::test]
+async fn test_size_gets_first_skips_the_resize_retry() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+    let value: Vec<u8> = (0..2 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    server
+        .client()
+        .await
+        .put(b"large", &value, 0)
+        .await
+        .unwrap();
+
+    let client = KvCacheClient::new(ClientConfig {
+        size_gets_first: true,
+        ..server.client_config()
+    })
+    .unwrap();
+    client.connect().await.unwrap();
+    assert_eq!(client.get(b"large").await.unwrap(), value);
+    assert!(client.get(b"absent").await.unwrap_err().is::<KeyNotFound>());
+    let stats = client.get_buffer_stats();
+    assert_eq!(
+        (stats.allocations, stats.allocated_bytes),
+        (2, value.len() as u64 + 1024 * 1024)
+    );
+    assert_eq!(stats.retries, 0);
+}
+
+#[tokio::test]
+async fn test_exists_skips_the_pools() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+    let client = server.client().await;
+    client.put(b"present", b"value", 0).await.unwrap();
+    client.put(b"expiring", b"value", 1).await.unwrap();
+    let client_allocations = client.memory_stats().allocations;
+    let server_allocations = client.server_memory_stats().await.unwrap().allocations;
+
+    assert!(client.exists(b"present").await.unwrap());
+    assert!(client.exists(b"expiring").await.unwrap());
+    assert!(!client.exists(b"absent").await.unwrap());
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert!(!client.exists(b"expiring").await.unwrap());
+
+    assert_eq!(client.memory_stats().allocations, client_allocations);
+    assert_eq!(
+        client.server_memory_stats().await.unwrap().allocations,
+        server_allocations
+    );
+}
+
+#[tokio::test]
+async fn test_client_metrics_count_operations() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    let client = server.client().await;
+    for i in 0..3 {
+        client
+            .put(format!("key{}", i).as_bytes(), b"value", 0)
+            .await
+            .unwrap();
+    }
+    for i in 0..4 {
+        client.get(format!("key{}", i).as_bytes()).await.ok();
+    }
+    client.delete(b"key0").await.unwrap();
+
+    let metrics = client.metrics();
+    assert_eq!((metrics.put.count, metrics.put.errors), (3, 0));
+    // key3 was never written
+    assert_eq!((metrics.get.count, metrics.get.errors), (4, 1));
+    assert_eq!((metrics.delete.count, metrics.delete.errors), (1, 0));
+    for op in [&metrics.get, &metrics.put, &metrics.delete] {
+        assert!(op.mean > Duration::ZERO);
+        assert!(op.p50.unwrap() <= op.p99.unwrap());
+    }
+
+    let quiet = KvCacheClient::new(ClientConfig {
+        record_metrics: false,
+        ..server.client_config()
+    })
+    .unwrap();
+    quiet.connect().await.unwrap();
+    quiet.get(b"key1").await.unwrap();
+    assert_eq!(quiet.metrics(), Default::default());
+}
+
+#[tokio::test]
+async fn test_get_with_retry_waits_for_late_write() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    let new_client = |client_id| {
+        KvCacheClient::new(ClientConfig {
+            client_id,
+            ..server.client_config()
+        })
+        .unwrap()
+    };
+    let reader = new_client(1);
+    reader.connect().await.unwrap();
+    let writer = new_client(2);
+    writer.connect().await.unwrap();
+
+    // The write lands a little after the reads start
+    let write = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        writer.put(b"late", b"eventually", 0).await.unwrap();
+    });
+
+    let err = reader.get(b"late").await.unwrap_err();
+    assert!(err.is::<KeyNotFound>(), "{}", err);
+
+    let policy = RetryPolicy {
+        max_attempts: 20,
+        initial_backoff: Duration::from_millis(20),
+        max_backoff: Duration::from_millis(100),
+    };
+    assert_eq!(
+        reader.get_with_retry(b"late", &policy).await.unwrap(),
+        b"eventually"
+    );
+    write.await.unwrap();
+
+    // A key that never shows up still misses once the attempts run out
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(1),
+        ..Default::default()
+    };
+    let err = reader.get_with_retry(b"never", &policy).await.unwrap_err();
+    assert!(err.is::<KeyNotFound>(), "{}", err);
+}
+
+#[tokio::test]
+async fn test_client_fails_over_to_next_seed() {
+    let _ = tracing_subscriber::fmt().with_env_filter("warn").try_init();
+
+    let mut servers = Vec::new();
+    for _ in 0..2 {
+        servers.push(
+            TestServer::start(ServerConfig {
+                memory_pool_size: 16 * 1024 * 1024,
+                ..Default::default()
+            })
+            .await,
+        );
+    }
+    let addrs: Vec<_> = servers.iter().map(TestServer::url).collect();
+
+    let client = KvCacheClient::new(ClientConfig {
+        server_addr: addrs[0].clone(),
+        seed_addrs: vec![addrs[1].clone()],
+        receive_buffer_size: 4 * 1024 * 1024,
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(client.current_server(), None);
+    client.connect().await.unwrap();
+    assert_eq!(client.current_server().as_deref(), Some(addrs[0].as_str()));
+    client.put(b"before", b"first", 0).await.unwrap();
+
+    // Stop the first server, closing its connections; the next RPC moves to
+    // the second and re-registers
+    servers.remove(0).stop().await;
+
+    client.put(b"after", b"second", 0).await.unwrap();
+    assert_eq!(client.current_server().as_deref(), Some(addrs[1].as_str()));
+    assert_eq!(client.get(b"after").await.unwrap(), b"second");
+    assert!(client.heartbeat().await.unwrap());
+    // The second server never saw the first one's data
+    assert!(client.get(b"before").await.is_err());
+}
+
+#[tokio::test]
+async fn test_connect_with_retry_waits_for_server_to_start() {
+    let port = std::net::TcpListener::bind("[::1]:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let client = KvCacheClient::new(ClientConfig {
+        server_addr: format!("http://[::1]:{}", port),
+        receive_buffer_size: 4 * 1024 * 1024,
+        ..Default::default()
+    })
+    .unwrap();
+    assert!(client.connect().await.is_err());
+
+    let connecting = tokio::spawn(async move {
+        client
+            .connect_with_retry(Duration::from_secs(5))
+            .await
+            .map(|_| client)
+    });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!connecting.is_finished());
+
+    let server_handle = tokio::spawn(kv_rdma_poc::server::run_server(ServerConfig {
+        listen_addr: format!("[::1]:{}", port),
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    }));
+    let client = connecting.await.unwrap().unwrap();
+    client.put(b"key", b"value", 0).await.unwrap();
+    assert_eq!(client.get(b"key").await.unwrap(), b"value");
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_connect_retries_until_server_starts() {
+    let port = std::net::TcpListener::bind("[::1]:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let client = KvCacheClient::new(ClientConfig {
+        server_addr: format!("http://[::1]:{}", port),
+        receive_buffer_size: 4 * 1024 * 1024,
+        connect_retries: 10,
+        connect_backoff: Duration::from_millis(20),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let connecting = tokio::spawn(async move { client.connect().await.map(|_| client) });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!connecting.is_finished());
+
+    let server_handle = tokio::spawn(kv_rdma_poc::server::run_server(ServerConfig {
+        listen_addr: format!("[::1]:{}", port),
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    }));
+    let client = connecting.await.unwrap().unwrap();
+    client.put(b"key", b"value", 0).await.unwrap();
+    assert_eq!(client.get(b"key").await.unwrap(), b"value");
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_server_is_reachable_when_start_returns() {
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    // No sleep: the first connection and RPC must succeed straight away
+    let addr = server.url().trim_start_matches("http://").to_string();
+    tokio::net::TcpStream::connect(&addr).await.unwrap();
+    let client = server.client().await;
+    client.heartbeat().await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unix_socket_round_trip() {
+    let path = std::env::temp_dir().join(format!("kv-rdma-poc-{}.sock", std::process::id()));
+    let server_handle = tokio::spawn(kv_rdma_poc::server::run_server(ServerConfig {
+        listen_addr: format!("unix:{}", path.display()),
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    }));
+    common::wait_until_reachable(|| tokio::net::UnixStream::connect(&path)).await;
+
+    let client = KvCacheClient::new(ClientConfig {
+        server_addr: format!("unix:{}", path.display()),
+        receive_buffer_size: 4 * 1024 * 1024,
+        ..Default::default()
+    })
+    .unwrap();
+    client.connect().await.unwrap();
+
+    // Both a value returned inline and one RDMA written to the client
+    client
+        .put(b"small", b"over a unix socket", 0)
+        .await
+        .unwrap();
+    assert_eq!(client.get(b"small").await.unwrap(), b"over a unix socket");
+    let large = vec![9u8; 256 * 1024];
+    client.put(b"large", &large, 0).await.unwrap();
+    assert_eq!(client.get(b"large").await.unwrap(), large);
 
     server_handle.abort();
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(feature = "otel")]
+#[tokio::test]
+async fn test_get_trace_spans_client_and_server() {
+    use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    // A current-thread runtime keeps server and client tasks under this thread's subscriber
+    let exporter = InMemorySpanExporter::default();
+    let provider = TracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let subscriber = tracing_subscriber::registry()
+        .with(kv_rdma_poc::telemetry::layer(&provider, "integration-test"));
+    let _subscriber = tracing::subscriber::set_default(subscriber);
+
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    let client = server.client().await;
+    client.put(b"traced", b"value", 0).await.unwrap();
+    assert_eq!(client.get(b"traced").await.unwrap(), b"value");
+
+    provider.force_flush();
+    let spans = exporter.get_finished_spans().unwrap();
+    let find = |name: &str| {
+        spans
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap_or_else(|| panic!("no {} span", name))
+    };
+    let client_span = find("kv.client.get");
+    let server_span = find("kv.server.get");
+    let transfer_span = find("rdma.transfer");
+
+    assert_eq!(
+        server_span.span_context.trace_id(),
+        client_span.span_context.trace_id()
+    );
+    assert_eq!(
+        server_span.parent_span_id,
+        client_span.span_context.span_id()
+    );
+    assert_eq!(
+        transfer_span.parent_span_id,
+        server_span.span_context.span_id()
+    );
+}
+
+#[cfg(feature = "console")]
+#[tokio::test]
+async fn test_server_serves_with_console_layer() {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let subscriber = tracing_subscriber::registry().with(kv_rdma_poc::console::layer());
+    let _subscriber = tracing::subscriber::set_default(subscriber);
+
+    let server = TestServer::start(ServerConfig {
+        memory_pool_size: 16 * 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+
+    let client = server.client().await;
+    client.put(b"console", b"value", 0).await.unwrap();
+    assert_eq!(client.get(b"console").await.unwrap(), b"value");
+
+    let stats = client.stats().await.unwrap();
+    assert!(stats.runtime.unwrap().workers > 0);
 }
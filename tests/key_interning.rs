@@ -0,0 +1,69 @@
+//! Heap cost of interned keys
+//!
+//! Counting allocations needs a global allocator, which would apply to every
+//! test in the library's own test binary, so this lives in a binary of its own.
+
+use dashmap::DashMap;
+use kv_rdma_poc::keys::{CacheKey, KeyArena};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts live allocations made by the current thread, so concurrently
+/// running tests don't disturb the numbers
+struct CountingAlloc;
+
+thread_local! {
+    static LIVE_ALLOCATIONS: Cell<i64> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE_ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_ALLOCATIONS.with(|n| n.set(n.get() - 1));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn live_allocations() -> i64 {
+    LIVE_ALLOCATIONS.with(Cell::get)
+}
+
+/// Heap allocations left behind by inserting `keys` fixed-format keys,
+/// arriving as `Vec<u8>` like gRPC keys, into a presized map
+fn count_inserts(arena: Option<&KeyArena>, keys: usize) -> i64 {
+    let map: DashMap<CacheKey, u64> = DashMap::with_capacity(keys);
+    let mut buf = *b"session-00000000";
+    let before = live_allocations();
+    for i in 0..keys {
+        let mut n = i;
+        for digit in buf[8..].iter_mut().rev() {
+            *digit = b'0' + (n % 10) as u8;
+            n /= 10;
+        }
+        map.insert(KeyArena::key(arena, buf.to_vec()), i as u64);
+    }
+    let after = live_allocations();
+
+    buf[8..].copy_from_slice(b"00000042");
+    assert_eq!(*map.get(&buf[..]).unwrap(), 42);
+    after - before
+}
+
+#[test]
+fn test_interned_keys_allocate_far_less_than_vec_keys() {
+    const KEYS: usize = 10_000;
+    let owned = count_inserts(None, KEYS);
+    let arena = KeyArena::default();
+    let interned = count_inserts(Some(&arena), KEYS);
+
+    // One per key against a handful of arena chunks
+    assert!(owned >= KEYS as i64, "owned path: {} allocations", owned);
+    assert!(interned <= 100, "interned path: {} allocations", interned);
+}